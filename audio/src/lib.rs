@@ -18,24 +18,35 @@ use crate::musictable::MusicTable;
 thread_local!(static AUDIO_PLAYER: RefCell<Option<AudioPlayer>> = RefCell::new(None));
 
 pub struct AudioContext {
-    _mixer_context: sdl2::mixer::Sdl2MixerContext,
+    _mixer_context: Option<sdl2::mixer::Sdl2MixerContext>,
 }
 
-/// Initialize AudioPlayer
-pub fn init<P: AsRef<Path>>(data_dirs: &[P]) -> AudioContext {
-    let mixer_context = init_device();
+/// Initialize AudioPlayer.
+/// Volumes are given in percent.
+/// If the audio device is unavailable, the game continues without audio.
+pub fn init<P: AsRef<Path>>(data_dirs: &[P], music_volume: u32, sound_volume: u32) -> AudioContext {
+    let mixer_context = match init_device() {
+        Ok(mixer_context) => mixer_context,
+        Err(e) => {
+            warn!("Failed to initialize audio device: {}", e);
+            return AudioContext { _mixer_context: None };
+        }
+    };
+    set_volume(music_volume, sound_volume);
     
     AUDIO_PLAYER.with(|a| {
         assert!(a.borrow().is_none());
         *a.borrow_mut() = Some(AudioPlayer::new(data_dirs));
     });
-    AudioContext { _mixer_context: mixer_context }
+    AudioContext { _mixer_context: Some(mixer_context) }
 }
 
+/// Call the function if the audio player is available
 pub fn with_audio_player<F: FnOnce(&AudioPlayer)>(f: F) {
     AUDIO_PLAYER.with(|a| {
-        assert!(a.borrow().is_some());
-        f(a.borrow().as_ref().unwrap());
+        if let Some(a) = a.borrow().as_ref() {
+            f(a);
+        }
     });
 }
 
@@ -46,16 +57,31 @@ pub fn play_sound(name: &str) {
     });
 }
 
-/// Play an music (ogg file)
+/// Play an music (ogg file).
+/// The music continues without restarting if it is already playing.
 pub fn play_music(name: &str) {
     with_audio_player(|a| {
         a.play_music(name);
     });
 }
 
+/// Stop the music if it is playing
+pub fn stop_music() {
+    with_audio_player(|a| {
+        a.stop_music();
+    });
+}
+
+/// Set music and sound effect volumes in percent
+pub fn set_volume(music_volume: u32, sound_volume: u32) {
+    use sdl2::mixer::{Channel, Music, MAX_VOLUME};
+    let to_mixer_volume = |v: u32| std::cmp::min(v, 100) as i32 * MAX_VOLUME / 100;
+    Music::set_volume(to_mixer_volume(music_volume));
+    Channel::all().set_volume(to_mixer_volume(sound_volume));
+}
+
 fn finalize() {
     AUDIO_PLAYER.with(|a| {
-        assert!(a.borrow().is_some());
         *a.borrow_mut() = None;
     });
 }
@@ -69,6 +95,7 @@ impl Drop for AudioContext {
 pub struct AudioPlayer {
     wavtable: WavTable,
     musictable: MusicTable,
+    current_music: RefCell<Option<String>>,
 }
 
 impl AudioPlayer {
@@ -77,6 +104,7 @@ impl AudioPlayer {
         let musictable = MusicTable::new(data_dirs);
         AudioPlayer {
             wavtable, musictable,
+            current_music: RefCell::new(None),
         }
     }
 
@@ -87,13 +115,22 @@ impl AudioPlayer {
     }
 
     pub fn play_music(&self, name: &str) {
-        if let Err(e) = self.musictable.play(name) {
-            warn!("{}", e);
+        if self.current_music.borrow().as_ref().map(|s| s.as_str()) == Some(name) {
+            return;
         }
+        match self.musictable.play(name) {
+            Ok(()) => { *self.current_music.borrow_mut() = Some(name.to_owned()); }
+            Err(e) => { warn!("{}", e); }
+        }
+    }
+
+    pub fn stop_music(&self) {
+        sdl2::mixer::Music::halt();
+        *self.current_music.borrow_mut() = None;
     }
 }
 
-fn init_device() -> sdl2::mixer::Sdl2MixerContext {
+fn init_device() -> Result<sdl2::mixer::Sdl2MixerContext, String> {
     use sdl2::mixer::{InitFlag, DEFAULT_CHANNELS, AUDIO_S16LSB};    
 
     // Initialization for sound
@@ -101,12 +138,12 @@ fn init_device() -> sdl2::mixer::Sdl2MixerContext {
     let format = AUDIO_S16LSB; // signed 16 bit samples, in little-endian byte order
     let channels = DEFAULT_CHANNELS; // Stereo
     let chunk_size = 1024;
-    sdl2::mixer::open_audio(frequency, format, channels, chunk_size).unwrap();
-    let mixer_context = sdl2::mixer::init(InitFlag::OGG).unwrap();
+    sdl2::mixer::open_audio(frequency, format, channels, chunk_size)?;
+    let mixer_context = sdl2::mixer::init(InitFlag::OGG)?;
     
     sdl2::mixer::allocate_channels(1);
 
-    mixer_context
+    Ok(mixer_context)
 }
 
 #[cfg(test)]
@@ -127,7 +164,7 @@ mod tests {
             }
         };
 
-        let _audio_context = init(&[app_dir]);
+        let _audio_context = init(&[app_dir], 100, 100);
 
         play_music("test");
         std::thread::sleep(std::time::Duration::from_millis(3000));
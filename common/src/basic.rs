@@ -41,6 +41,8 @@ pub const TALK_TXT_DIR: &'static str = "talk";
 pub const MISC_TXT_DIR: &'static str = "misc";
 pub const SAVE_DIR_NAME: &'static str = "save";
 pub const SAVE_EXTENSION: &'static str = "rrsve";
+/// Version of save file format. Increase this when the format is changed incompatibly.
+pub const SAVE_FORMAT_VERSION: u32 = 2;
 
 /// Id table
 pub const ID_TABLE_SECTION_TAG: &'static str = "§";
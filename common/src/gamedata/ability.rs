@@ -0,0 +1,30 @@
+
+use super::defs::Element;
+
+/// Effect applied to the target when an ability is used
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum AbilityEffect {
+    /// Damage the target by the element
+    Damage,
+    /// Restore the target's hp
+    Heal,
+    /// Give hasted status to the target for turns of the power
+    Haste,
+}
+
+/// Ability that characters use by consuming mp
+#[derive(Serialize, Deserialize)]
+pub struct AbilityObject {
+    pub id: String,
+    /// Mp consumed by using this ability
+    pub cost_mp: i32,
+    /// Maximum distance to the target. If zero, the user is the target.
+    pub range: u32,
+    pub effect: AbilityEffect,
+    /// Element of damage
+    pub element: Element,
+    /// The power of the effect is decided by dice
+    pub dice_n: u16,
+    pub dice_x: u16,
+}
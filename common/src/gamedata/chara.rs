@@ -1,10 +1,12 @@
 
 use std::collections::HashMap;
-use crate::objholder::CharaTemplateIdx;
-use super::item::{ItemList, EquipItemList};
+use array2d::{Vec2d, Direction};
+use crate::objholder::{AbilityIdx, CharaTemplateIdx};
+use super::item::{Item, ItemList, EquipItemList};
 use super::map::MapId;
 use super::site::SiteId;
 use super::skill::SkillList;
+use super::faction::Faction;
 use super::unknown_id_err;
 
 /// Character's races
@@ -51,6 +53,21 @@ pub enum Relationship {
     HOSTILE,
 }
 
+impl std::str::FromStr for Relationship {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::Relationship::*;
+        match s {
+            "ally" => Ok(ALLY),
+            "friendly" => Ok(FRIENDLY),
+            "neutral" => Ok(NEUTRAL),
+            "hostile" => Ok(HOSTILE),
+            _ => Err(()),
+        }
+    }
+}
+
 impl Relationship {
     pub fn relative(&self, other: Relationship) -> Relationship {
         use self::Relationship::*;
@@ -83,8 +100,23 @@ pub struct Chara {
     pub skills: SkillList,
     /// Relationship to player character
     pub rel: Relationship,
+    pub faction: Faction,
     /// When talked, execute this script
     pub trigger_talk: Option<String>,
+    #[serde(default)]
+    pub mp: i32,
+    /// Abilities this character can use
+    #[serde(default)]
+    pub abilities: Vec<AbilityIdx>,
+    /// Attribute points allocated at character creation
+    #[serde(default)]
+    pub bonus_attr: CharaAttrRevision,
+    /// The item this character is riding
+    #[serde(default)]
+    pub mount: Option<Item>,
+    /// The direction this character faced last
+    #[serde(default)]
+    pub facing: Direction,
 }
 
 /// Character attributes
@@ -110,6 +142,9 @@ pub struct CharaAttributes {
     pub spd: u16,
     /// Range of view in tile
     pub view_range: i32,
+    /// Max MP
+    #[serde(default)]
+    pub max_mp: i32,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -124,7 +159,7 @@ pub struct CharaBaseAttr {
     pub spd: i16,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct CharaAttrRevision {
     pub hp: i32,
     pub str: i16,
@@ -162,6 +197,8 @@ pub enum CharaStatus {
     Starving,
     Asleep { turn_left: u16 },
     Poisoned,
+    /// Speed is increased while this status lasts
+    Hasted { turn_left: u16 },
 }
 
 impl Default for Chara {
@@ -181,7 +218,13 @@ impl Default for Chara {
             status: Vec::new(),
             skills: SkillList::default(),
             rel: Relationship::NEUTRAL,
+            faction: Faction::default(),
             trigger_talk: None,
+            mp: 0,
+            abilities: Vec::new(),
+            bonus_attr: CharaAttrRevision::default(),
+            mount: None,
+            facing: Direction::none(),
         }
     }
 }
@@ -208,12 +251,104 @@ pub enum CharaId {
     /// Indexed for a map. This character don't appear on other maps.
     /// Randomly generated characters use this id.
     OnMap { mid: MapId, n: u32 },
+    /// Companions in the player's party. They move between maps with the player.
+    Party { n: u32 },
 }
 
 /// Data to determine NPC character's actions
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct CharaAI {
     pub kind: NpcAIKind,
+    /// Needs of this npc. Characters without needs act by their AI kind only.
+    #[serde(default)]
+    pub needs: Option<CharaNeeds>,
+    /// Combat tactics of companions. Other characters don't have this.
+    #[serde(default)]
+    pub tactics: Option<Tactics>,
+    /// Current behavior state chosen by the AI kind
+    #[serde(default)]
+    pub state: AiState,
+    /// The position that shopkeepers stay near. Set when the npc acts first time.
+    #[serde(default)]
+    pub home: Option<Vec2d>,
+    /// Daily schedule of town npcs sorted by the start hour
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
+    /// The position where the npc noticed an enemy last. Npcs go there after losing sight.
+    #[serde(default)]
+    pub last_seen: Option<Vec2d>,
+    /// The order given to party members by the player
+    #[serde(default)]
+    pub order: PartyOrder,
+}
+
+/// Npcs follow the entry from its start hour until the next entry starts
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    pub hour: u16,
+    /// The position to stay near. Npcs wander freely if None.
+    pub pos: Option<Vec2d>,
+}
+
+/// Behavior state of npcs
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum AiState {
+    /// Stay on the current tile
+    Idle,
+    /// Walk around at random
+    Wander,
+    /// Move to the nearest enemy and attack it
+    Chase,
+    /// Run away from the nearest enemy
+    Flee,
+}
+
+impl Default for AiState {
+    fn default() -> AiState {
+        AiState::Idle
+    }
+}
+
+/// Needs of npcs living in towns.
+/// Each value increases as time passes, and the most pressing one determines the npc's goal.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, Serialize, Deserialize)]
+pub struct CharaNeeds {
+    pub hunger: u16,
+    pub fatigue: u16,
+    pub social: u16,
+}
+
+/// Combat tactics presets of companions
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum Tactics {
+    /// Pursue the nearest enemy
+    Aggressive,
+    /// Stay near the player, and fight enemies approaching the player
+    Defensive,
+    /// Prioritize healing wounded allies
+    Support,
+    /// Hold position. Attack only adjacent enemies.
+    Hold,
+}
+
+/// Orders given to party members from the party window
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum PartyOrder {
+    /// Act by its tactics, and move to other maps with the player
+    Follow,
+    /// Hold position, and stay on the map when the player leaves
+    Wait,
+    /// Attack the character until it is defeated
+    Attack(CharaId),
+}
+
+impl Default for PartyOrder {
+    fn default() -> PartyOrder {
+        PartyOrder::Follow
+    }
 }
 
 /// Rough kind of NPC AI
@@ -226,12 +361,25 @@ pub enum NpcAIKind {
     NoMove,
     /// This npc will chase near enemies, and try melee atacks
     Melee,
+    /// This npc walks around peacefully, and never attacks
+    Villager,
+    /// This npc stays near its shop
+    Shopkeeper,
+    /// This npc attacks near enemies, but flees when it is wounded
+    Coward,
 }
 
 impl Default for CharaAI {
     fn default() -> CharaAI {
         CharaAI {
             kind: NpcAIKind::None,
+            needs: None,
+            tactics: None,
+            state: AiState::default(),
+            home: None,
+            schedule: Vec::new(),
+            last_seen: None,
+            order: PartyOrder::default(),
         }
     }
 }
@@ -271,11 +419,34 @@ impl CharaHolder {
         }.get_mut(&cid).unwrap_or_else(|| unknown_id_err(cid))
     }
 
-    pub(crate) fn remove_chara(&mut self, cid: CharaId) {
+    pub(crate) fn remove_chara(&mut self, cid: CharaId) -> Option<Chara> {
         match cid {
             CharaId::OnMap { .. } => &mut self.on_map,
             _ => &mut self.c,
-        }.remove(&cid);
+        }.remove(&cid)
+    }
+
+    /// Ids of party members in the order they joined
+    pub fn party(&self) -> Vec<CharaId> {
+        let mut party: Vec<(u32, CharaId)> = self.c.keys().filter_map(|&cid| match cid {
+            CharaId::Party { n } => Some((n, cid)),
+            _ => None,
+        }).collect();
+        party.sort_by_key(|&(n, _)| n);
+        party.into_iter().map(|(_, cid)| cid).collect()
+    }
+
+    fn new_party_id(&self) -> CharaId {
+        let n = (0..).find(|&n| !self.c.contains_key(&CharaId::Party { n })).unwrap();
+        CharaId::Party { n }
+    }
+
+    /// Move the character data to a new party id
+    pub(crate) fn change_to_party_id(&mut self, cid: CharaId) -> Option<CharaId> {
+        let chara = self.remove_chara(cid)?;
+        let new_cid = self.new_party_id();
+        self.c.insert(new_cid, chara);
+        Some(new_cid)
     }
 
     pub(crate) fn replace_on_map_chara(&mut self, next: HashMap<CharaId, Chara>)
@@ -0,0 +1,296 @@
+
+//! Two dimensional array stored in fixed-size chunks for large maps.
+//! Chunks that have the same elements are not allocated until they are changed.
+//! Each chunk records the revision of its last change, so users like the renderer
+//! can update their data only for chunks changed after the revision they saw.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use array2d::*;
+
+/// Width and height of chunks
+pub const CHUNK_SIZE: u32 = 16;
+const CHUNK_LEN: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// Shared by all arrays to keep revisions increasing even if an array is reloaded
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Serialize, Deserialize)]
+enum Chunk<T> {
+    /// All elements in this chunk are the same
+    Uniform(T),
+    Filled(Vec<T>),
+}
+
+/// Has the same indexing interface as Array2d
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChunkedArray2d<T> {
+    w: u32,
+    h: u32,
+    /// The number of chunks in a row
+    nx: u32,
+    chunks: Vec<Chunk<T>>,
+    /// The revision of the last change of each chunk. Empty after loading, and all chunks are dirty then.
+    #[serde(skip)]
+    changed_at: Vec<u64>,
+}
+
+impl<T> ChunkedArray2d<T> {
+    pub fn size(&self) -> (u32, u32) {
+        (self.w, self.h)
+    }
+
+    /// If pos is out of range, returns None.
+    pub fn get<P: Into<Vec2d>>(&self, p: P) -> Option<&T> {
+        let p = p.into();
+        if self.in_range(p) {
+            Some(&self[p])
+        } else {
+            None
+        }
+    }
+
+    pub fn in_range<P: Into<Vec2d>>(&self, p: P) -> bool {
+        let p = p.into();
+        p.0 >= 0 && p.1 >= 0 && (p.0 as u32) < self.w && (p.1 as u32) < self.h
+    }
+
+    pub fn iter_idx(&self) -> RectIter {
+        RectIter::new((0, 0), (self.w as i32 - 1, self.h as i32 - 1))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter_idx().map(move |p| &self[p])
+    }
+
+    pub fn iter_with_idx(&self) -> impl Iterator<Item = (Vec2d, &T)> + '_ {
+        self.iter_idx().map(move |p| (p, &self[p]))
+    }
+
+    /// Position of the chunk that contains the position
+    pub fn chunk_pos(p: Vec2d) -> Vec2d {
+        Vec2d(p.0 / CHUNK_SIZE as i32, p.1 / CHUNK_SIZE as i32)
+    }
+
+    /// Positions of elements in the chunk. Elements out of this array are not included.
+    pub fn iter_chunk_idx(&self, chunk_pos: Vec2d) -> RectIter {
+        let top_left = chunk_pos * CHUNK_SIZE as i32;
+        let right_bottom = Vec2d(
+            std::cmp::min(top_left.0 + CHUNK_SIZE as i32, self.w as i32) - 1,
+            std::cmp::min(top_left.1 + CHUNK_SIZE as i32, self.h as i32) - 1);
+        RectIter::new(top_left, right_bottom)
+    }
+
+    /// The current revision. Pass it to is_chunk_dirty() or dirty_chunks() later
+    /// to know which chunks have been changed since now.
+    pub fn revision(&self) -> u64 {
+        REVISION.load(Ordering::Relaxed)
+    }
+
+    /// The chunk has been changed after the revision
+    pub fn is_chunk_dirty(&self, chunk_pos: Vec2d, since: u64) -> bool {
+        let c = (chunk_pos.1 as u32 * self.nx + chunk_pos.0 as u32) as usize;
+        self.changed_at.get(c).map_or(true, |&r| r > since)
+    }
+
+    /// Positions of the chunks changed after the revision
+    pub fn dirty_chunks(&self, since: u64) -> Vec<Vec2d> {
+        let ny = self.chunks.len() as u32 / std::cmp::max(self.nx, 1);
+        RectIter::new((0, 0), (self.nx as i32 - 1, ny as i32 - 1))
+            .filter(|&c| self.is_chunk_dirty(c, since))
+            .collect()
+    }
+
+    /// Index of the chunk and index in the chunk
+    #[inline]
+    fn locate(&self, x: u32, y: u32) -> (usize, usize) {
+        debug_assert!(x < self.w && y < self.h, "Array access out of bounds");
+        let c = (y / CHUNK_SIZE) * self.nx + x / CHUNK_SIZE;
+        let i = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        (c as usize, i as usize)
+    }
+
+    #[inline]
+    fn get_ref(&self, x: u32, y: u32) -> &T {
+        let (c, i) = self.locate(x, y);
+        match &self.chunks[c] {
+            Chunk::Uniform(v) => v,
+            Chunk::Filled(v) => &v[i],
+        }
+    }
+}
+
+impl<T> ChunkedArray2d<T> where T: Clone {
+    pub fn new(w: u32, h: u32, v: T) -> ChunkedArray2d<T> {
+        let nx = (w + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let ny = (h + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let n = (nx * ny) as usize;
+        ChunkedArray2d {
+            w, h, nx,
+            chunks: vec![Chunk::Uniform(v); n],
+            changed_at: vec![REVISION.load(Ordering::Relaxed); n],
+        }
+    }
+
+    /// Allocate the chunk if it is uniform, and mark it as changed
+    fn get_mut(&mut self, x: u32, y: u32) -> &mut T {
+        let (c, i) = self.locate(x, y);
+        let revision = REVISION.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.changed_at.len() != self.chunks.len() {
+            self.changed_at = vec![revision; self.chunks.len()];
+        }
+        self.changed_at[c] = revision;
+
+        let chunk = &mut self.chunks[c];
+        let filled = if let Chunk::Uniform(v) = &*chunk {
+            Some(Chunk::Filled(vec![v.clone(); CHUNK_LEN]))
+        } else {
+            None
+        };
+        if let Some(filled) = filled {
+            *chunk = filled;
+        }
+        match chunk {
+            Chunk::Filled(v) => &mut v[i],
+            Chunk::Uniform(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T> ChunkedArray2d<T> where T: Clone + PartialEq {
+    /// Free chunks whose elements became the same.
+    /// Elements out of the array in edge chunks are ignored.
+    pub fn compact(&mut self) {
+        let (w, h, nx) = (self.w, self.h, self.nx);
+        for (c, chunk) in self.chunks.iter_mut().enumerate() {
+            let x0 = (c as u32 % nx) * CHUNK_SIZE;
+            let y0 = (c as u32 / nx) * CHUNK_SIZE;
+            let cw = std::cmp::min(CHUNK_SIZE, w - x0);
+            let ch = std::cmp::min(CHUNK_SIZE, h - y0);
+            let uniform = match &*chunk {
+                Chunk::Filled(v) if (0..ch).all(|y| (0..cw).all(|x| {
+                    v[(y * CHUNK_SIZE + x) as usize] == v[0]
+                })) => Some(Chunk::Uniform(v[0].clone())),
+                _ => None,
+            };
+            if let Some(uniform) = uniform {
+                *chunk = uniform;
+            }
+        }
+    }
+}
+
+impl<T> Index<(u32, u32)> for ChunkedArray2d<T> {
+    type Output = T;
+    #[inline]
+    fn index(&self, index: (u32, u32)) -> &T {
+        self.get_ref(index.0, index.1)
+    }
+}
+
+impl<T: Clone> IndexMut<(u32, u32)> for ChunkedArray2d<T> {
+    #[inline]
+    fn index_mut(&mut self, index: (u32, u32)) -> &mut T {
+        self.get_mut(index.0, index.1)
+    }
+}
+
+impl<T> Index<(i32, i32)> for ChunkedArray2d<T> {
+    type Output = T;
+    #[inline]
+    fn index(&self, index: (i32, i32)) -> &T {
+        debug_assert!(index.0 >= 0 && index.1 >= 0, "Array access out of bounds");
+        self.get_ref(index.0 as u32, index.1 as u32)
+    }
+}
+
+impl<T: Clone> IndexMut<(i32, i32)> for ChunkedArray2d<T> {
+    #[inline]
+    fn index_mut(&mut self, index: (i32, i32)) -> &mut T {
+        debug_assert!(index.0 >= 0 && index.1 >= 0, "Array access out of bounds");
+        self.get_mut(index.0 as u32, index.1 as u32)
+    }
+}
+
+impl<T> Index<Vec2d> for ChunkedArray2d<T> {
+    type Output = T;
+    #[inline]
+    fn index(&self, index: Vec2d) -> &T {
+        &self[(index.0, index.1)]
+    }
+}
+
+impl<T: Clone> IndexMut<Vec2d> for ChunkedArray2d<T> {
+    #[inline]
+    fn index_mut(&mut self, index: Vec2d) -> &mut T {
+        &mut self[(index.0, index.1)]
+    }
+}
+
+impl<T> fmt::Debug for ChunkedArray2d<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n_filled = self.chunks.iter().filter(|c| if let Chunk::Filled(_) = c { true } else { false }).count();
+        write!(f, "ChunkedArray2d {{ size: {}x{}, filled chunks: {}/{} }}",
+               self.w, self.h, n_filled, self.chunks.len())
+    }
+}
+
+#[test]
+fn chunked_array_test() {
+    // Edges are not multiples of CHUNK_SIZE
+    let mut a = ChunkedArray2d::new(20, 35, 0u32);
+    assert_eq!(a.nx, 2);
+    assert_eq!(a.chunks.len(), 6);
+    assert_eq!(a.locate(0, 0), (0, 0));
+    assert_eq!(a.locate(17, 0), (1, 1));
+    assert_eq!(a.locate(19, 34), (5, 2 * 16 + 3));
+    assert_eq!(a.locate(3, 16), (2, 3));
+
+    a[(19u32, 34u32)] = 5;
+    a[Vec2d(0, 0)] = 1;
+    assert_eq!(a[(19i32, 34i32)], 5);
+    assert_eq!(a.get((0, 0)), Some(&1));
+    assert_eq!(a.get((1, 0)), Some(&0));
+    assert_eq!(a.get((20, 0)), None);
+    assert_eq!(a.get((0, -1)), None);
+    assert!(a.in_range((19, 34)));
+    assert!(!a.in_range((19, 35)));
+    assert_eq!(a.iter().count(), 20 * 35);
+
+    let b: ChunkedArray2d<u32> = serde_json::from_str(&serde_json::to_string(&a).unwrap()).unwrap();
+    assert!(b.iter_with_idx().all(|(p, v)| *v == a[p]));
+
+    // The edge chunk is compacted though its padding keeps the initial value
+    for p in a.iter_idx() {
+        a[p] = 5;
+    }
+    a[(0u32, 0u32)] = 1;
+    a.compact();
+    let is_uniform = |c: &Chunk<u32>| if let Chunk::Uniform(_) = c { true } else { false };
+    assert!(!is_uniform(&a.chunks[0]));
+    assert!(a.chunks[1..].iter().all(is_uniform));
+    assert_eq!(a[(19u32, 34u32)], 5);
+    assert_eq!(a[(0u32, 0u32)], 1);
+}
+
+#[test]
+fn dirty_chunks_test() {
+    let mut a = ChunkedArray2d::new(20, 35, 0u32);
+    assert_eq!(ChunkedArray2d::<u32>::chunk_pos(Vec2d(17, 33)), Vec2d(1, 2));
+    assert_eq!(a.iter_chunk_idx(Vec2d(1, 2)).count(), 4 * 3);
+    let rev = a.revision();
+    assert!(a.dirty_chunks(rev).is_empty());
+
+    a[(17u32, 33u32)] = 1;
+    assert_eq!(a.dirty_chunks(rev), vec![Vec2d(1, 2)]);
+    let rev2 = a.revision();
+    assert!(a.dirty_chunks(rev2).is_empty());
+    a[(0u32, 0u32)] = 1;
+    assert_eq!(a.dirty_chunks(rev), vec![Vec2d(0, 0), Vec2d(1, 2)]);
+    assert_eq!(a.dirty_chunks(rev2), vec![Vec2d(0, 0)]);
+
+    // All chunks are dirty after loading
+    let b: ChunkedArray2d<u32> = serde_json::from_str(&serde_json::to_string(&a).unwrap()).unwrap();
+    assert_eq!(b.dirty_chunks(b.revision()).len(), 6);
+}
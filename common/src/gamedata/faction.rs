@@ -0,0 +1,87 @@
+
+use crate::hashmap::HashMap;
+use super::chara::Relationship;
+
+/// Faction of characters
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum Faction {
+    Player,
+    Citizen,
+    Monster,
+    /// Characters that don't belong to any faction
+    Unknown,
+}
+
+impl Default for Faction {
+    fn default() -> Faction {
+        Faction::Unknown
+    }
+}
+
+impl std::str::FromStr for Faction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Faction::*;
+        match s {
+            "player" => Ok(Player),
+            "citizen" => Ok(Citizen),
+            "monster" => Ok(Monster),
+            "unknown" => Ok(Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Faction {
+    /// Relationship to the player faction at the start of the game
+    pub fn default_player_relation(self) -> Relationship {
+        match self {
+            Faction::Player => Relationship::ALLY,
+            Faction::Citizen => Relationship::FRIENDLY,
+            Faction::Monster => Relationship::HOSTILE,
+            Faction::Unknown => Relationship::NEUTRAL,
+        }
+    }
+}
+
+/// Relationships between factions.
+/// Relationships to the player faction can be changed during the game.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct FactionRelations {
+    player: HashMap<Faction, Relationship>,
+}
+
+impl FactionRelations {
+    pub fn new() -> FactionRelations {
+        FactionRelations::default()
+    }
+
+    /// Relationship between the player faction and given faction
+    pub fn player_relation(&self, faction: Faction) -> Relationship {
+        if faction == Faction::Player {
+            return Relationship::ALLY;
+        }
+        self.player.get(&faction).cloned().unwrap_or_else(|| faction.default_player_relation())
+    }
+
+    pub fn set_player_relation(&mut self, faction: Faction, rel: Relationship) {
+        if faction == Faction::Player {
+            return;
+        }
+        self.player.insert(faction, rel);
+    }
+
+    /// Relationship between two factions. The result is symmetric.
+    pub fn relation(&self, a: Faction, b: Faction) -> Relationship {
+        use self::Faction::*;
+        match (a, b) {
+            (Player, other) | (other, Player) => self.player_relation(other),
+            (Unknown, _) | (_, Unknown) => Relationship::NEUTRAL,
+            (a, b) if a == b => Relationship::FRIENDLY,
+            (Citizen, Monster) | (Monster, Citizen) => Relationship::HOSTILE,
+            _ => Relationship::NEUTRAL,
+        }
+    }
+}
@@ -44,6 +44,89 @@ pub struct ItemObject {
     pub medical_effect: MedicalEffect,
     /// Character's nutrition will be increased by this value after eating this item
     pub nutrition: u16,
+    /// Radius of the light this item gives when it is equipped or on the ground
+    pub light: u8,
+    /// Capacity if this item can hold other items
+    pub container: Option<ContainerParams>,
+    /// Range of initial charges for wands and tools
+    pub charge: Option<(u32, u32)>,
+    /// Quality is chosen randomly when generated
+    pub has_quality: bool,
+    /// One of these materials is chosen randomly when generated
+    pub materials: Vec<MaterialKind>,
+    /// Parameters for horses and boats if this item can be ridden on region maps
+    pub mount: Option<MountParams>,
+    /// Power for digging walls if this item is a pickaxe or shovel. 0 if this item cannot dig.
+    pub dig_power: u16,
+    /// Id of the anim image drawn over characters equipping this item.
+    /// It has the same frame arrangement as character images.
+    pub overlay: Option<String>,
+    /// Skill learned by reading this item
+    pub learn_skill: Option<super::skill::SkillKind>,
+}
+
+/// Travel parameters of mounts and vehicles
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct MountParams {
+    /// Percentage of the time passing per turn on region maps
+    pub travel_time: u16,
+    /// Movements while riding. They replace the rider's own movements.
+    pub movements: Vec<crate::obj::Movement>,
+    /// Id of the character template drawn as the rider
+    pub rider_img: String,
+}
+
+/// Capacity limits of container items
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ContainerParams {
+    /// Max total weight of the contents (gram)
+    pub max_w: u32,
+    /// Max number of the contents
+    pub max_n: u32,
+}
+
+impl Item {
+    /// Items in this container. Returns None if this item is not a container.
+    pub fn contents(&self) -> Option<&ItemList> {
+        self.attributes.iter().filter_map(|attr| match attr {
+            ItemAttribute::Container(list) => Some(list),
+            _ => None,
+        }).next()
+    }
+
+    /// Mutable version for contents
+    pub fn contents_mut(&mut self) -> Option<&mut ItemList> {
+        self.attributes.iter_mut().filter_map(|attr| match attr {
+            ItemAttribute::Container(list) => Some(list),
+            _ => None,
+        }).next()
+    }
+
+    pub fn is_container(&self) -> bool {
+        self.contents().is_some()
+    }
+
+    /// Remaining charges. Returns None if this item doesn't have charges.
+    pub fn charge(&self) -> Option<u32> {
+        self.attributes.iter().filter_map(|attr| match attr {
+            ItemAttribute::Charge { n } => Some(*n),
+            _ => None,
+        }).next()
+    }
+
+    pub fn quality(&self) -> ItemQuality {
+        self.attributes.iter().filter_map(|attr| match attr {
+            ItemAttribute::Quality(quality) => Some(*quality),
+            _ => None,
+        }).next().unwrap_or(ItemQuality::Normal)
+    }
+
+    pub fn material(&self) -> Option<MaterialKind> {
+        self.attributes.iter().filter_map(|attr| match attr {
+            ItemAttribute::Material(material) => Some(*material),
+            _ => None,
+        }).next()
+    }
 }
 
 impl Ord for Item {
@@ -80,6 +163,7 @@ bitflags! {
     pub struct ItemFlags: u64 {
         const EATABLE   = 1 << 0;
         const DRINKABLE = 1 << 1;
+        const READABLE  = 1 << 2;
     }
 }
 
@@ -117,12 +201,33 @@ pub enum ItemAttribute {
     /// Data to generate the contents.
     /// Used to fix generated contents when this item is opened.
     ContentGen { level: u32, seed: u32 },
+    /// Items held by this container
+    Container(ItemList),
+    /// Remaining charges of wands and tools
+    Charge { n: u32 },
+    /// Items without this attribute have normal quality
+    Quality(ItemQuality),
+    Material(MaterialKind),
+}
+
+/// Quality changes the price and effectiveness of items
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum ItemQuality {
+    Poor, Normal, Good, Excellent,
+}
+
+/// Material changes the price and effectiveness of items
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum MaterialKind {
+    Wood, Bronze, Iron, Steel, Silver, Mithril,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 #[serde(rename_all="snake_case")]
 pub enum MedicalEffect {
-    None, Heal, Sleep, Poison,
+    None, Heal, Sleep, Poison, Haste,
 }
 
 impl Default for MedicalEffect {
@@ -156,12 +261,32 @@ pub enum ItemListLocation {
     Chara { cid: super::chara::CharaId },
     Equip { cid: super::chara::CharaId },
     Shop { cid: super::CharaId },
+    /// Contents of the container item at the index of the list
+    Container { parent: ContainerLocation, i: u32 },
+}
+
+/// Item lists that can have containers. Containers cannot be nested.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ContainerLocation {
+    OnMap { mid: super::map::MapId, pos: Vec2d },
+    Chara { cid: super::chara::CharaId },
+}
+
+impl From<ContainerLocation> for ItemListLocation {
+    fn from(l: ContainerLocation) -> ItemListLocation {
+        match l {
+            ContainerLocation::OnMap { mid, pos } => ItemListLocation::OnMap { mid, pos },
+            ContainerLocation::Chara { cid } => ItemListLocation::Chara { cid },
+        }
+    }
 }
 
 pub type ItemLocation = (ItemListLocation, u32);
 
-/// Item list that records all items owned by one character or one tile
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Item list that records all items owned by one character or one tile.
+/// Containers are placed at the front of the list and never stacked,
+/// so moving other items doesn't change their indices.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub struct ItemList {
     pub items: Vec<(Item, u32)>,
 }
@@ -185,13 +310,21 @@ impl ItemList {
 
     /// Append item
     pub fn append(&mut self, item: Item, n: u32) {
-        
+        let n_container = self.items.iter().take_while(|(item, _)| item.is_container()).count();
+
+        if item.is_container() {
+            for _ in 0..n {
+                self.items.insert(n_container, (item.clone(), 1));
+            }
+            return;
+        }
+
         if self.items.is_empty() {
             self.items.push((item, n));
             return;
         }
 
-        for i in 0..self.items.len() {
+        for i in n_container..self.items.len() {
             match item.cmp(&self.items[i].0) { 
                 Ordering::Equal => { // If this list has the same item, increases the number
                     self.items[i].1 += n;
@@ -426,6 +559,21 @@ impl EquipItemList {
         None
     }
 
+    /// Remove the item from specified slot, and returns removed item
+    pub fn remove(&mut self, esk: EquipSlotKind, n: usize) -> Option<Item> {
+        let i = self.list_idx(esk, n)?;
+        let item = self.item_list.items.remove(i).0;
+
+        for slot in self.slots.iter_mut() {
+            match slot.list_idx {
+                Some(list_idx) if list_idx as usize == i => { slot.list_idx = None; }
+                Some(list_idx) if list_idx as usize > i => { slot.list_idx = Some(list_idx - 1); }
+                _ => (),
+            }
+        }
+        Some(item)
+    }
+
     fn list_idx(&self, esk: EquipSlotKind, n: usize) -> Option<usize> {
         if let Some(slot) = self.slots.iter().find(|slot| slot.esk == esk && slot.n as usize == n) {
             if let Some(list_idx) = slot.list_idx {
@@ -9,6 +9,8 @@ use crate::gamedata::item::{Item, ItemList};
 use crate::gamedata::chara::{CharaId, Chara};
 use crate::gamedata::site::SiteId;
 use crate::gamedata::region::RegionId;
+use crate::gamedata::time::Time;
+use crate::gamedata::chunk::ChunkedArray2d;
 
 pub use crate::piece_pattern::*;
 
@@ -16,8 +18,9 @@ pub use crate::piece_pattern::*;
 pub struct Map {
     pub w: u32,
     pub h: u32,
-    pub tile: Array2d<TileInfo>,
-    pub observed_tile: Array2d<ObservedTileInfo>,
+    /// Stored in chunks so that large maps do not allocate unchanged areas
+    pub tile: ChunkedArray2d<TileInfo>,
+    pub observed_tile: ChunkedArray2d<ObservedTileInfo>,
     pub player_pos: Vec2d,
     pub entrance: Vec2d,
     /// Characters on this map
@@ -29,6 +32,15 @@ pub struct Map {
     /// If this is None, nearest tile's infomation will be used
     pub outside_tile: Option<OutsideTileInfo>,
     pub boundary: MapBoundary,
+    /// Music id played on this map. No music is played if empty.
+    #[serde(default)]
+    pub music: String,
+    /// Ambient darkness of this map. 0 is fully lit and 255 is pitch dark.
+    #[serde(default)]
+    pub darkness: u8,
+    /// Regions that start scripts when the player enters them
+    #[serde(default)]
+    pub events: Vec<MapEvent>,
 }
 
 pub type TileArray = ArrayVec<[TileIdxPP; N_TILE_IMG_LAYER]>;
@@ -98,7 +110,27 @@ pub enum SpecialTileKind {
     /// Site symbol on region map
     SiteSymbol {
         kind: SiteSymbolKind,
-    }
+    },
+    /// Harvestable resource such as trees and ore veins
+    Resource {
+        idx: SpecialTileIdx,
+        /// The remaining number of harvesting
+        remaining: u32,
+        /// An exhausted resource regrows after this time
+        regrow_at: Time,
+    },
+    Trap {
+        idx: SpecialTileIdx,
+        /// Hidden traps are not drawn
+        detected: bool,
+    },
+    Door {
+        state: DoorState,
+        /// Item that unlocks this door
+        key: Option<ItemIdx>,
+        /// Difficulty for lockpicking
+        lock_level: u32,
+    },
 }
 
 impl SpecialTileKind {
@@ -108,6 +140,23 @@ impl SpecialTileKind {
             _ => false,
         }
     }
+
+    /// Traps that have not been detected
+    pub fn is_hidden_trap(&self) -> bool {
+        match *self {
+            SpecialTileKind::Trap { detected, .. } => !detected,
+            _ => false,
+        }
+    }
+
+    /// Closed and locked doors block sight.
+    /// Characters open closed doors by moving into them, and only locked doors block movement.
+    pub fn is_closed_door(&self) -> bool {
+        match *self {
+            SpecialTileKind::Door { state, .. } => state != DoorState::Open,
+            _ => false,
+        }
+    }
 }
 
 impl Default for SpecialTileKind {
@@ -122,13 +171,28 @@ pub enum StairsKind {
     UpStairs, DownStairs,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DoorState {
+    Open, Closed, Locked,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum SiteSymbolKind {
     Cave, Ruin, Tower, Town, Village,
 }
 
+#[cfg(feature="global_state_obj")]
 impl SpecialTileKind {
-    /// Convert to id of SpecialTileObject
+    /// Convert to index of SpecialTileObject.
+    /// Resources and traps have indices, and other kinds are searched by ids.
+    pub fn obj_idx(&self) -> Option<SpecialTileIdx> {
+        match *self {
+            SpecialTileKind::Resource { idx, .. } | SpecialTileKind::Trap { idx, .. } => Some(idx),
+            _ => crate::gobj::id_to_idx_checked(self.obj_id()?),
+        }
+    }
+
+    /// Convert to id of SpecialTileObject. The object may not exist in paks, e.g. for doors.
     pub fn obj_id(&self) -> Option<&'static str> {
         Some(match *self {
             SpecialTileKind::None => { return None; },
@@ -147,6 +211,14 @@ impl SpecialTileKind {
                     SiteSymbolKind::Village => "!rm-village",
                 }
             }
+            SpecialTileKind::Resource { idx, .. } => crate::gobj::idx_to_id(idx),
+            SpecialTileKind::Trap { idx, .. } => crate::gobj::idx_to_id(idx),
+            SpecialTileKind::Door { state, .. } => {
+                match state {
+                    DoorState::Open => "!door-open",
+                    DoorState::Closed | DoorState::Locked => "!door-closed",
+                }
+            }
         })
     }
 }
@@ -154,7 +226,7 @@ impl SpecialTileKind {
 /// If stairs or boundaries have this value, they are connected to region map
 pub const FLOOR_OUTSIDE: u32 = 0xFFFFFFFF;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct TileInfo {
     /// Base tile
     pub tile: TileArray,
@@ -170,7 +242,7 @@ pub struct TileInfo {
 
 /// The data for map drawing
 /// These data will be updated every player turn based on player's view
-#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ObservedTileInfo {
     /// Tile is observed or not
     pub tile: bool,
@@ -186,6 +258,45 @@ pub struct OutsideTileInfo {
     pub wall: Option<WallIdx>,
 }
 
+/// A rectangle region that starts a script when it is triggered
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct MapEvent {
+    pub top_left: Vec2d,
+    /// Included in the region
+    pub bottom_right: Vec2d,
+    pub script_id: String,
+    /// Removed from the map after the first trigger
+    pub once: bool,
+    #[serde(default)]
+    pub trigger: MapEventTrigger,
+}
+
+/// Events triggered by conditions are removed after the first trigger regardless of `once`
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MapEventTrigger {
+    /// The player enters the region
+    Enter,
+    /// The player examines a tile in the region
+    Examine,
+    /// The global variable becomes true
+    GVar(String),
+    /// No characters of the template remain on the map, e.g. the boss is defeated
+    Defeated(String),
+}
+
+impl Default for MapEventTrigger {
+    fn default() -> MapEventTrigger {
+        MapEventTrigger::Enter
+    }
+}
+
+impl MapEvent {
+    pub fn contains(&self, pos: Vec2d) -> bool {
+        self.top_left.0 <= pos.0 && pos.0 <= self.bottom_right.0 &&
+            self.top_left.1 <= pos.1 && pos.1 <= self.bottom_right.1
+    }
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct MapBoundary {
     pub n: BoundaryBehavior,
@@ -234,13 +345,16 @@ impl Map {
     pub fn new(w: u32, h: u32) -> Map {
         Map {
             w: w, h: h,
-            tile: Array2d::new(w, h, TileInfo::default()),
-            observed_tile: Array2d::new(w, h, ObservedTileInfo::default()),
+            tile: ChunkedArray2d::new(w, h, TileInfo::default()),
+            observed_tile: ChunkedArray2d::new(w, h, ObservedTileInfo::default()),
             player_pos: Vec2d(0, 0), entrance: Vec2d(0, 0),
             charaid: Vec::new(),
             charas: Some(HashMap::new()),
             outside_tile: None,
             boundary: MapBoundary::default(),
+            music: String::new(),
+            darkness: 0,
+            events: Vec::new(),
         }
     }
 
@@ -299,6 +413,30 @@ impl Map {
         true
     }
 
+    /// Returns the script id of the event region entered by moving from `from` to `to`.
+    /// Events triggered only once are removed.
+    pub fn enter_event(&mut self, from: Vec2d, to: Vec2d) -> Option<String> {
+        self.trigger_event(|e| {
+            e.trigger == MapEventTrigger::Enter && e.contains(to) && !e.contains(from)
+        })
+    }
+
+    /// Returns the script id of the event region examined at `pos`
+    pub fn examine_event(&mut self, pos: Vec2d) -> Option<String> {
+        self.trigger_event(|e| e.trigger == MapEventTrigger::Examine && e.contains(pos))
+    }
+
+    /// Returns the script id of the first event satisfying `f`.
+    /// Events triggered only once are removed.
+    pub fn trigger_event<F: FnMut(&MapEvent) -> bool>(&mut self, f: F) -> Option<String> {
+        let i = self.events.iter().position(f)?;
+        if self.events[i].once {
+            Some(self.events.remove(i).script_id)
+        } else {
+            Some(self.events[i].script_id.clone())
+        }
+    }
+
     /// Locate a character at given position.
     /// If the new position is not empty, this function will fail and return false
     pub fn locate_chara(&mut self, cid: CharaId, pos: Vec2d) -> bool {
@@ -344,7 +482,7 @@ impl Map {
         let pos = self.chara_pos(cid).unwrap();
         self.tile[pos].chara = None;
 
-        if let CharaId::OnMap { .. } = cid {
+        if cid != CharaId::Player {
             let i = self.charaid.iter().enumerate().find(|&(_, cid_o)| *cid_o == cid).unwrap().0;
             let removed_cid = self.charaid.swap_remove(i);
             assert!(removed_cid == cid);
@@ -1,10 +1,17 @@
 
+use crate::basic::SAVE_FORMAT_VERSION;
 
 /// Meta data
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct MetaData {
     /// Save directory name
     save_name: String,
+    /// Save file format version. Old save files without this field are version 0.
+    #[serde(default)]
+    format_version: u32,
+    /// Written by the player when saving
+    #[serde(default)]
+    description: String,
 }
 
 impl MetaData {
@@ -15,12 +22,27 @@ impl MetaData {
     pub fn set_save_name(&mut self, s: &str) {
         self.save_name = s.to_owned();
     }
+
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Leading and trailing whitespaces are removed
+    pub fn set_description(&mut self, s: &str) {
+        self.description = s.trim().to_owned();
+    }
 }
 
 impl Default for MetaData {
     fn default() -> MetaData {
         MetaData {
             save_name: "uninit".to_owned(),
+            format_version: SAVE_FORMAT_VERSION,
+            description: String::new(),
         }
     }
 }
@@ -1,35 +1,47 @@
 
 pub mod meta;
+pub mod ability;
 pub mod chara;
+pub mod chunk;
 pub mod defs;
+pub mod faction;
 pub mod item;
 pub mod map;
 pub mod player;
 pub mod quest;
+pub mod recipe;
 pub mod region;
 pub mod shop;
 pub mod site;
 pub mod skill;
+pub mod spawn;
 pub mod time;
 pub mod town;
 pub mod variables;
+pub mod weather;
 
 use array2d::Vec2d;
 
 pub use self::meta::*;
+pub use self::ability::*;
 pub use self::chara::*;
+pub use self::chunk::*;
 pub use self::defs::*;
+pub use self::faction::*;
 pub use self::item::*;
 pub use self::map::*;
 pub use self::player::*;
 pub use self::quest::*;
+pub use self::recipe::*;
 pub use self::region::*;
 pub use self::shop::*;
 pub use self::site::*;
 pub use self::skill::*;
+pub use self::spawn::*;
 pub use self::time::*;
 pub use self::town::*;
 pub use self::variables::*;
+pub use self::weather::*;
 
 /// Includes all data for one game world.
 /// This can be a snapshot of the current game, so it must implement Serialize and Deserialize.
@@ -43,6 +55,10 @@ pub struct GameData {
     pub player: Player,
     pub quest: QuestHolder,
     pub vars: Variables,
+    pub faction: FactionRelations,
+    /// The random number generator state when saved, to continue the same random sequence
+    #[serde(default)]
+    pub rng_state: Option<rng::RngState>,
     current_mapid: MapId,
 }
 
@@ -56,6 +72,8 @@ impl GameData {
             player: Player::default(),
             quest: QuestHolder::new(),
             vars: Variables::new(),
+            faction: FactionRelations::new(),
+            rng_state: None,
             current_mapid: MapId::default(),
         }
     }
@@ -143,6 +161,27 @@ impl GameData {
         self.chara.remove_chara(cid);
     }
 
+    /// The character joins the player's party, and gets a new party id.
+    /// The position on the current map is not changed.
+    pub fn join_party(&mut self, cid: CharaId) -> Option<CharaId> {
+        match cid {
+            CharaId::Player | CharaId::Party { .. } => { return None; }
+            _ => (),
+        }
+        let pos = self.get_current_map().chara_pos(cid)?;
+        self.get_current_map_mut().remove_chara(cid);
+        let new_cid = self.chara.change_to_party_id(cid)?;
+        self.get_current_map_mut().locate_chara(new_cid, pos);
+        Some(new_cid)
+    }
+
+    /// Remove the party member from the current map to move it with the player.
+    /// The character data is kept.
+    pub fn remove_party_member_from_map(&mut self, cid: CharaId) {
+        assert!(if let CharaId::Party { .. } = cid { true } else { false });
+        self.get_current_map_mut().remove_chara(cid);
+    }
+
     pub fn add_site(&mut self, site: Site, kind: SiteKind, rid: RegionId, pos: Vec2d) -> Option<SiteId> {
         let region = self.region.get_mut(rid);
         region.add_site(site, kind, pos)
@@ -189,6 +228,10 @@ impl GameData {
             ItemListLocation::Shop { cid } => {
                 &self.get_shop(cid).items
             }
+            ItemListLocation::Container { parent, i } => {
+                self.get_item_list(parent.into()).items[i as usize].0.contents()
+                    .expect("Get contents of an item that is not a container")
+            }
         }
     }
 
@@ -208,6 +251,10 @@ impl GameData {
             ItemListLocation::Shop { cid } => {
                 &mut self.get_shop_mut(cid).items
             }
+            ItemListLocation::Container { parent, i } => {
+                self.get_item_list_mut(parent.into()).items[i as usize].0.contents_mut()
+                    .expect("Get contents of an item that is not a container")
+            }
         }
     }
 
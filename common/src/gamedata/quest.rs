@@ -1,25 +1,143 @@
 
 use std::slice::{Iter, IterMut};
-use crate::objholder::CharaTemplateIdx;
+use crate::hashmap::HashMap;
+use crate::objholder::{CharaTemplateIdx, ItemIdx};
+use super::site::SiteId;
 use super::defs::Reward;
+use super::time::Time;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum QuestState {
     Active, Completed, RewardReceived
 }
 
+/// State of quests started and completed by scripts
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum ScriptQuestState {
+    Inactive, Active, Complete, Failed,
+}
+
+impl std::str::FromStr for ScriptQuestState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inactive" => Ok(ScriptQuestState::Inactive),
+            "active" => Ok(ScriptQuestState::Active),
+            "complete" => Ok(ScriptQuestState::Complete),
+            "failed" => Ok(ScriptQuestState::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ScriptQuest {
+    pub state: ScriptQuestState,
+    /// The quest fails if it is not completed until this time
+    pub deadline: Option<Time>,
+    /// Script executed when the quest fails
+    pub on_fail: Option<String>,
+    /// Script executed when the quest is completed. Rewards are given by this script.
+    pub on_complete: Option<String>,
+    /// Progress of the quest set by scripts
+    #[serde(default)]
+    pub stage: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QuestHolder {
     quests: Vec<(QuestState, Quest)>,
+    /// Script quest states by quest id. Quests not in this table are inactive.
+    script_quests: HashMap<String, ScriptQuest>,
 }
 
 impl QuestHolder {
     pub fn new() -> QuestHolder {
         QuestHolder {
             quests: Vec::new(),
+            script_quests: HashMap::default(),
+        }
+    }
+
+    pub fn script_quest_state(&self, id: &str) -> ScriptQuestState {
+        self.script_quests.get(id).map(|q| q.state).unwrap_or(ScriptQuestState::Inactive)
+    }
+
+    pub fn script_quest(&self, id: &str) -> Option<&ScriptQuest> {
+        self.script_quests.get(id)
+    }
+
+    /// Start the script quest. Returns false if it has already been started.
+    pub fn start_script_quest(
+        &mut self, id: &str, deadline: Option<Time>,
+        on_fail: Option<String>, on_complete: Option<String>) -> bool {
+
+        if self.script_quest_state(id) != ScriptQuestState::Inactive {
+            return false;
+        }
+        let quest = ScriptQuest {
+            state: ScriptQuestState::Active,
+            deadline,
+            on_fail,
+            on_complete,
+            stage: 0,
+        };
+        self.script_quests.insert(id.to_owned(), quest);
+        true
+    }
+
+    /// Complete the script quest. Returns false if it is not active.
+    pub fn complete_script_quest(&mut self, id: &str) -> bool {
+        match self.script_quests.get_mut(id) {
+            Some(quest) if quest.state == ScriptQuestState::Active => {
+                quest.state = ScriptQuestState::Complete;
+                true
+            }
+            _ => false,
         }
     }
 
+    pub fn script_quest_stage(&self, id: &str) -> u32 {
+        self.script_quests.get(id).map(|q| q.stage).unwrap_or(0)
+    }
+
+    /// Set the stage of the script quest. Returns false if it is not active.
+    pub fn set_script_quest_stage(&mut self, id: &str, stage: u32) -> bool {
+        match self.script_quests.get_mut(id) {
+            Some(quest) if quest.state == ScriptQuestState::Active => {
+                quest.stage = stage;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Started script quests sorted by id
+    pub fn script_quests(&self) -> Vec<(&str, &ScriptQuest)> {
+        let mut quests: Vec<(&str, &ScriptQuest)> = self.script_quests.iter()
+            .map(|(id, quest)| (id.as_str(), quest))
+            .collect();
+        quests.sort_by_key(|&(id, _)| id);
+        quests
+    }
+
+    /// The active script quest whose deadline has passed becomes failed.
+    /// If there are multiple overdue quests, the one with the smallest id is failed.
+    /// Returns the id and on-fail script of the failed quest.
+    pub fn fail_overdue_quest(&mut self, now: Time) -> Option<(String, Option<String>)> {
+        let (id, quest) = self.script_quests
+            .iter_mut()
+            .filter(|(_, quest)| {
+                quest.state == ScriptQuestState::Active &&
+                    quest.deadline.map_or(false, |deadline| deadline < now)
+            })
+            .min_by(|a, b| a.0.cmp(b.0))?;
+        quest.state = ScriptQuestState::Failed;
+        Some((id.clone(), quest.on_fail.clone()))
+    }
+
     pub fn iter(&self) -> Iter<(QuestState, Quest)> {
         self.quests.iter()
     }
@@ -45,15 +163,40 @@ pub enum Quest {
         goal: u32,
         killed: u32,
     },
+    CollectItems {
+        reward: Reward,
+        idx: ItemIdx,
+        goal: u32,
+        collected: u32,
+    },
+    ReachSite {
+        reward: Reward,
+        sid: SiteId,
+        reached: bool,
+    },
 }
 
 impl Quest {
     pub fn reward(&self) -> &Reward {
         match self {
-            Quest::SlayMonsters { reward, .. } => {
-                reward
-            }
+            Quest::SlayMonsters { reward, .. } => reward,
+            Quest::CollectItems { reward, .. } => reward,
+            Quest::ReachSite { reward, .. } => reward,
         }
     }
+
+    /// Returns (current, goal) of the objective progress
+    pub fn progress(&self) -> (u32, u32) {
+        match self {
+            Quest::SlayMonsters { goal, killed, .. } => (*killed, *goal),
+            Quest::CollectItems { goal, collected, .. } => (*collected, *goal),
+            Quest::ReachSite { reached, .. } => (*reached as u32, 1),
+        }
+    }
+
+    pub fn is_objective_met(&self) -> bool {
+        let (current, goal) = self.progress();
+        current >= goal
+    }
 }
 
@@ -0,0 +1,26 @@
+
+use super::skill::SkillKind;
+
+/// An item and its number consumed by crafting
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct RecipeInput {
+    /// Item id
+    pub id: String,
+    pub n: u32,
+}
+
+/// Recipe to craft an item from other items
+#[derive(Serialize, Deserialize)]
+pub struct RecipeObject {
+    pub id: String,
+    /// Items consumed by crafting
+    pub inputs: Vec<RecipeInput>,
+    /// Skill used for crafting. It gains exp when crafting succeeds.
+    pub skill: SkillKind,
+    /// Required level of the skill
+    pub skill_level: u32,
+    /// Item id of the result
+    pub output: String,
+    /// The number of crafted items
+    pub n_output: u32,
+}
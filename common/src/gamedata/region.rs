@@ -5,6 +5,8 @@ use array2d::*;
 use filebox::FileBox;
 use super::site::*;
 use super::map::*;
+use super::weather::Weather;
+use super::time::Time;
 use super::unknown_id_err;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
@@ -18,6 +20,11 @@ pub struct Region {
     pub(crate) sites: HashMap<SiteId, SiteInfo>,
     /// An map to represents this region
     pub(crate) map: BoxedMap,
+    /// Current weather of this region
+    pub weather: Weather,
+    /// The weather changes after this time
+    #[serde(default)]
+    pub next_weather_change: Time,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -140,7 +147,7 @@ impl RegionHolder {
         }
     }
 
-    /// Preload map from file
+    /// Preload map from file. Maps already loaded keep their changes in memory.
     pub fn preload_map<P: AsRef<Path>>(&mut self, mid: MapId, map_dir_path: P) {
         info!("preload map {:?}", mid);
         let boxed_map = self.get_boxed_map_mut(mid);
@@ -201,6 +208,8 @@ impl Region {
             id: RegionId(0),
             sites: HashMap::new(),
             map: FileBox::new(map_random_id, map),
+            weather: Weather::default(),
+            next_weather_change: Time::default(),
         }
     }
 
@@ -3,7 +3,10 @@ use filebox::FileBox;
 use super::map::Map;
 use super::region::RegionId;
 use super::town::Town;
+use super::weather::Weather;
 
+/// Maps are kept as whole snapshots. Changes of tiles and items on the map are written to
+/// the file of the map by the next save, and the map is read from it when loaded again.
 pub type BoxedMap = FileBox<Map>;
 
 /// Site represents a dungeon, town, or other facility
@@ -16,6 +19,9 @@ pub struct Site {
     max_floor: u32,
     /// Site kind specific data
     pub content: SiteContent,
+    /// Weather fixed in this site regardless of the region's weather
+    #[serde(default)]
+    pub weather: Option<Weather>,
 }
 
 /// Site kind specific data
@@ -43,6 +49,7 @@ impl Site {
             map: Vec::new(),
             max_floor,
             content: SiteContent::Other,
+            weather: None,
         }
     }
     
@@ -119,6 +126,19 @@ pub enum SiteKind {
     AutoGenDungeon, Town, Other
 }
 
+impl std::str::FromStr for SiteKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto_gen_dungeon" => Ok(SiteKind::AutoGenDungeon),
+            "town" => Ok(SiteKind::Town),
+            "other" => Ok(SiteKind::Other),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct SiteId {
     pub rid: RegionId,
@@ -11,6 +11,11 @@ pub enum SkillKind {
     Evasion,
     MartialArts,
     Weapon(WeaponKind),
+    Crafting,
+    Gathering,
+    Lockpicking,
+    Perception,
+    Stealth,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
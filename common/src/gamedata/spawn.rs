@@ -0,0 +1,21 @@
+
+/// Npcs appearing together on a dungeon floor
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpawnGroup {
+    /// Character template ids of the members.
+    /// They are chosen for each member by generation levels and weights.
+    pub chara: Vec<String>,
+    /// The frequency of this group
+    pub weight: f32,
+    /// The range of floors where this group appears
+    pub floor_range: [u32; 2],
+    /// The range of the number of members
+    pub n: [u32; 2],
+}
+
+/// Npc groups generated on the floors of dungeons
+#[derive(Serialize, Deserialize)]
+pub struct SpawnTableObject {
+    pub id: String,
+    pub groups: Vec<SpawnGroup>,
+}
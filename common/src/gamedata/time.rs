@@ -131,3 +131,13 @@ pub struct Date {
     pub year: u32,
 }
 
+/// Night is from NIGHT_START_HOUR to NIGHT_END_HOUR - 1
+pub const NIGHT_START_HOUR: u16 = 19;
+pub const NIGHT_END_HOUR: u16 = 6;
+
+impl Date {
+    pub fn is_night(&self) -> bool {
+        self.hour >= NIGHT_START_HOUR || self.hour < NIGHT_END_HOUR
+    }
+}
+
@@ -1,13 +1,18 @@
 
 use crate::gamedata::shop::*;
 use crate::gamedata::quest::Quest;
+use crate::gamedata::time::Time;
 use fnv::FnvHashMap;
 use std::collections::hash_map::{Values, ValuesMut};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Town {
     id: String,
+    name: Option<String>,
     shops: FnvHashMap<u32, Shop>,
+    /// The last time when items on shops are updated
+    #[serde(default)]
+    shops_updated: Time,
     pub quests: Vec<Quest>,
 }
 
@@ -15,15 +20,27 @@ impl Town {
     pub fn new(id: &str) -> Town {
         Town {
             id: id.to_owned(),
+            name: None,
             shops: FnvHashMap::default(),
+            shops_updated: Time::default(),
             quests: Vec::new(),
         }
     }
 
+    /// Set the name of this town. If not set, the name is given from its id.
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Town {
+        self.name = Some(name.into());
+        self
+    }
+
     pub fn id(&self) -> &str {
         &self.id
     }
 
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| name.as_str())
+    }
+
     pub fn get_shop(&self, n: u32) -> Option<&Shop> {
         self.shops.get(&n)
     }
@@ -43,5 +60,13 @@ impl Town {
     pub fn add_shop(&mut self, shop: Shop, n: u32) {
         self.shops.insert(n, shop);
     }
+
+    pub fn shops_updated(&self) -> Time {
+        self.shops_updated
+    }
+
+    pub fn set_shops_updated(&mut self, time: Time) {
+        self.shops_updated = time;
+    }
 }
 
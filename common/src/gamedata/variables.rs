@@ -6,12 +6,16 @@ use crate::hashmap::HashMap;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Variables {
     global: HashMap<String, Value>,
+    /// Variables for the running script
+    #[serde(skip)]
+    local: HashMap<String, Value>,
 }
 
 impl Variables {
     pub fn new() -> Variables {
         Variables {
             global: HashMap::default(),
+            local: HashMap::default(),
         }
     }
 
@@ -30,6 +34,20 @@ impl Variables {
         self.global.insert(name.to_string(), v);
     }
 
+    /// Get local variable of the running script
+    pub fn local_var(&self, name: &str) -> Option<&Value> {
+        self.local.get(name)
+    }
+
+    pub fn set_local_var<S: ToString>(&mut self, name: S, v: Value) {
+        self.local.insert(name.to_string(), v);
+    }
+
+    /// Discard all local variables. Called when scripts start and end.
+    pub fn clear_local_vars(&mut self) {
+        self.local.clear();
+    }
+
     /// Set special named variable "$?".
     /// This special variable is used for the result of the last instruction.
     pub fn set_last_result(&mut self, v: Value) {
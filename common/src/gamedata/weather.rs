@@ -0,0 +1,29 @@
+
+/// Weather of a region
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum Weather {
+    Fine, Cloudy, Rain, Snow, Fog,
+}
+
+impl Default for Weather {
+    fn default() -> Weather {
+        Weather::Fine
+    }
+}
+
+impl std::str::FromStr for Weather {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Weather::*;
+        match s {
+            "fine" => Ok(Fine),
+            "cloudy" => Ok(Cloudy),
+            "rain" => Ok(Rain),
+            "snow" => Ok(Snow),
+            "fog" => Ok(Fog),
+            _ => Err(()),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 //! This module provides global state objholder
 
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock};
 use lazy_static::lazy_static;
 use crate::objholder::*;
 
@@ -12,42 +12,85 @@ pub fn init(pak_dirs: Vec<PathBuf>) {
     ::lazy_static::initialize(&OBJ_HOLDER);
 }
 
+/// Initialize lazy static by already loaded objects instead of pak files.
+/// This is used by unit tests that need objects.
+#[cfg(feature="test_objholder")]
+pub fn init_with_objholder(mut objholder: ObjectHolder) {
+    objholder.build_idx_table();
+    *PRELOADED_OBJ_HOLDER.lock().unwrap() = Some(objholder);
+    ::lazy_static::initialize(&OBJ_HOLDER);
+}
+
 lazy_static! {
     static ref PAK_DIRS: Mutex<Option<Vec<PathBuf>>> = Mutex::new(None);
-    static ref OBJ_HOLDER: ObjectHolder = {
+    // Replaced object holders by reloading are leaked because references to them may remain
+    static ref OBJ_HOLDER: RwLock<&'static ObjectHolder> = RwLock::new(Box::leak(Box::new(load_objholder())));
+    pub static ref OBJ_HOLDER_HASH: u64 = objholder_hash(get_objholder());
+}
+
+#[cfg(feature="test_objholder")]
+lazy_static! {
+    static ref PRELOADED_OBJ_HOLDER: Mutex<Option<ObjectHolder>> = Mutex::new(None);
+}
+
+fn load_objholder() -> ObjectHolder {
+    #[cfg(feature="test_objholder")]
+    {
+        if let Some(objholder) = PRELOADED_OBJ_HOLDER.lock().unwrap().take() {
+            return objholder;
+        }
+    }
+    let pak_dirs = PAK_DIRS.lock().unwrap();
+    ObjectHolder::load(pak_dirs.as_ref().unwrap())
+}
+
+/// Hash of object ids. Saved games can be loaded only if it is the same.
+fn objholder_hash(objholder: &ObjectHolder) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = fnv::FnvHasher::default();
+    objholder.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Reload pak files for development.
+/// Objects are replaced only if the object ids are unchanged, because indices of objects
+/// in game data must not be changed. Returns true if objects are replaced.
+pub fn reload() -> bool {
+    let objholder = {
         let pak_dirs = PAK_DIRS.lock().unwrap();
-        ObjectHolder::load(pak_dirs.as_ref().unwrap())
-    };
-    pub static ref OBJ_HOLDER_HASH: u64 = {
-        use std::hash::{Hash, Hasher};
-        let mut hasher = fnv::FnvHasher::default();
-        get_objholder().hash(&mut hasher);
-        hasher.finish()
+        let pak_dirs = if let Some(pak_dirs) = pak_dirs.as_ref() { pak_dirs } else { return false; };
+        ObjectHolder::load(pak_dirs)
     };
+    if objholder_hash(&objholder) != *OBJ_HOLDER_HASH {
+        warn!("Paks are not reloaded because object ids are changed. Restart is needed");
+        return false;
+    }
+    *OBJ_HOLDER.write().unwrap() = Box::leak(Box::new(objholder));
+    true
 }
 
 pub fn get_objholder() -> &'static ObjectHolder {
-    &*OBJ_HOLDER
+    *OBJ_HOLDER.read().unwrap()
 }
 
 pub fn get_obj<T: ObjectIndex>(idx: T) -> &'static T::ObjectType {
-    idx.get_obj_from_objholder(&OBJ_HOLDER)
+    idx.get_obj_from_objholder(get_objholder())
 }
 
 pub fn id_to_idx<T: ObjectIndex + Default>(id: &str) -> T {
-    T::search_idx(id, &OBJ_HOLDER).unwrap_or_default()
+    T::search_idx(id, get_objholder()).unwrap_or_default()
 }
 
 pub fn id_to_idx_checked<T: ObjectIndex>(id: &str) -> Option<T> {
-    T::search_idx(id, &OBJ_HOLDER)
+    T::search_idx(id, get_objholder())
 }
 
 pub fn idx_to_id<T: ObjectIndex>(idx: T) -> &'static str {
-    idx.to_id(&OBJ_HOLDER)
+    idx.to_id(get_objholder())
 }
 
 pub fn get_by_id<T: FromId>(id: &str) -> &'static T {
-    if let Some(s) = T::get_obj_from_objholder_by_id(id, &OBJ_HOLDER) {
+    if let Some(s) = T::get_obj_from_objholder_by_id(id, get_objholder()) {
         s
     } else {
         eprintln!("Object \"{}\" is not found", id);
@@ -56,6 +99,6 @@ pub fn get_by_id<T: FromId>(id: &str) -> &'static T {
 }
 
 pub fn get_by_id_checked<T: FromId>(id: &str) -> Option<&'static T> {
-    T::get_obj_from_objholder_by_id(id, &OBJ_HOLDER)
+    T::get_obj_from_objholder_by_id(id, get_objholder())
 }
 
@@ -4,6 +4,7 @@ extern crate serde_derive;
 #[macro_use]
 extern crate log;
 extern crate rusted_ruins_array2d as array2d;
+extern crate rusted_ruins_rng as rng;
 
 pub mod basic;
 pub mod hashmap;
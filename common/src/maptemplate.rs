@@ -3,7 +3,7 @@ use std::ops::{Index, IndexMut};
 use array2d::*;
 use crate::basic::N_TILE_IMG_LAYER;
 use crate::piece_pattern::*;
-use crate::gamedata::ItemGen;
+use crate::gamedata::{ItemGen, MapEvent};
 #[cfg(feature="global_state_obj")]
 use crate::gamedata::map::TileLayers;
 #[cfg(feature="global_state_obj")]
@@ -26,6 +26,43 @@ pub struct MapTemplateObject {
     pub deco: Array2d<Option<u32>>,
     pub boundary: MapTemplateBoundary,
     pub items: Vec<(Vec2d, ItemGen)>,
+    /// Music id played on this map
+    #[serde(default)]
+    pub music: String,
+    /// Ambient darkness of this map
+    #[serde(default)]
+    pub darkness: u8,
+    #[serde(default)]
+    pub doors: Vec<(Vec2d, DoorGen)>,
+    /// Special tile ids of traps
+    #[serde(default)]
+    pub traps: Vec<(Vec2d, String)>,
+    #[serde(default)]
+    pub charas: Vec<(Vec2d, CharaGen)>,
+    #[serde(default)]
+    pub events: Vec<MapEvent>,
+}
+
+/// A door placed by map templates
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+pub struct DoorGen {
+    pub locked: bool,
+    /// Item id of the key
+    pub key: Option<String>,
+    /// Difficulty for lockpicking
+    pub lock_level: u32,
+}
+
+/// A character placed by map templates
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+pub struct CharaGen {
+    /// Chara template id
+    pub id: String,
+    /// The generation level of the template is used if 0
+    pub level: u32,
+    pub talk_script_id: Option<String>,
+    /// Hostile to the player. Otherwise friendly.
+    pub hostile: bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
@@ -23,6 +23,9 @@ pub enum Object {
     RegionGen(RegionGenObject),
     Script(ScriptObject),
     SiteGen(SiteGenObject),
+    Ability(AbilityObject),
+    Recipe(RecipeObject),
+    SpawnTable(SpawnTableObject),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -45,12 +48,29 @@ pub struct CharaTemplateObject {
     /// Default AI kind for this character
     pub default_ai_kind: gamedata::NpcAIKind,
     pub base_attr: CharaBaseAttr,
+    /// Skill levels that differ from the defaults for generated characters
+    pub skill_levels: Vec<(gamedata::SkillKind, u32)>,
+    /// Ways of moving. Decides which kinds of tiles this character can enter.
+    #[serde(default = "default_movements")]
+    pub movements: Vec<Movement>,
+}
+
+fn default_movements() -> Vec<Movement> {
+    vec![Movement::Walk]
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum Movement {
+    Walk, Swim, Fly,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct DecoObject {
     pub id: String,
     pub img: Img,
+    /// Radius of the light for torches and braziers. Zero if this deco doesn't shine.
+    pub light: u8,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -64,6 +84,53 @@ pub struct SpecialTileObject {
     pub id: String,
     pub always_background: bool,
     pub img: Img,
+    /// Special tiles that have this can be harvested
+    pub resource: Option<ResourceParams>,
+    /// Special tiles that have this are hidden traps
+    pub trap: Option<TrapParams>,
+}
+
+/// Parameters of harvestable special tiles such as trees and ore veins
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceParams {
+    /// Item id that characters need to have for harvesting
+    pub tool: Option<String>,
+    pub skill: gamedata::SkillKind,
+    /// Required level of the skill
+    pub skill_level: u32,
+    /// The number of harvesting until the resource is exhausted
+    pub max_uses: u32,
+    /// Hours until an exhausted resource regrows
+    pub regrowth_hours: u32,
+    pub yields: Vec<ResourceYield>,
+}
+
+/// Parameters of traps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrapParams {
+    pub effect: TrapEffect,
+    /// Difficulty for detection and disarming
+    pub difficulty: u32,
+}
+
+/// Effects of traps triggered when a character steps on them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum TrapEffect {
+    Damage { dice_n: u16, dice_x: u16 },
+    /// Move the character to a random tile on the map
+    Teleport,
+    /// Call new monsters to the map
+    Alarm { n_npc: u32 },
+}
+
+/// An item harvested from a resource and its weight in choosing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourceYield {
+    /// Item id
+    pub id: String,
+    pub weight: f32,
+    pub n: u32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -74,10 +141,36 @@ pub struct TileObject {
     pub symbol_color: (u8, u8, u8),
 }
 
+/// Movement class of the tile
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[serde(rename_all="snake_case")]
 pub enum TileKind {
-    Ground, Water,
+    Ground, Water, Lava, Chasm,
+}
+
+impl TileKind {
+    /// Characters that have one of the movements can enter tiles of this kind
+    pub fn passable_by(self, movements: &[Movement]) -> bool {
+        movements.iter().any(|&m| match (self, m) {
+            (_, Movement::Fly) => true,
+            (TileKind::Ground, Movement::Walk) => true,
+            (TileKind::Water, Movement::Swim) => true,
+            _ => false,
+        })
+    }
+}
+
+#[test]
+fn tile_kind_passable_by_test() {
+    use Movement::*;
+    assert!(TileKind::Ground.passable_by(&[Walk]));
+    assert!(!TileKind::Water.passable_by(&[Walk]));
+    assert!(TileKind::Water.passable_by(&[Walk, Swim]));
+    assert!(!TileKind::Ground.passable_by(&[Swim]));
+    assert!(TileKind::Lava.passable_by(&[Fly]));
+    assert!(TileKind::Chasm.passable_by(&[Fly]));
+    assert!(!TileKind::Lava.passable_by(&[Walk, Swim]));
+    assert!(!TileKind::Chasm.passable_by(&[]));
 }
 
 #[derive(Serialize, Deserialize)]
@@ -93,6 +186,8 @@ pub struct WallObject {
     pub base_draw: bool,
     pub img: Img,
     pub symbol_color: (u8, u8, u8),
+    /// Digging this wall needs this much power. None if this wall cannot be dug.
+    pub hardness: Option<u16>,
 }
 
 pub use crate::gamedata::item::ItemObject;
@@ -108,6 +203,10 @@ pub struct Img {
     pub n_pattern: u32,
     pub n_anim_frame: u32,
     pub duration: u32,
+    /// The number of facing directions. Images for 4 directions are arranged as south, west, east and north,
+    /// and each direction has `n_pattern * n_anim_frame` frames.
+    /// Legacy images have only 1 direction.
+    pub n_direction: u32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -118,6 +217,10 @@ pub struct Icon {
 
 // No image objects
 
+pub use crate::gamedata::ability::AbilityObject;
+pub use crate::gamedata::recipe::RecipeObject;
+pub use crate::gamedata::spawn::SpawnTableObject;
+
 pub use crate::maptemplate::MapTemplateObject;
 pub use crate::regiongen::RegionGenObject;
 pub use crate::script::ScriptObject;
@@ -136,8 +239,8 @@ macro_rules! impl_object {
 }
 
 impl_object!(
-    AnimImgObject, CharaTemplateObject, DecoObject, EffectObject,
-    ItemObject, SpecialTileObject, TileObject,
+    AbilityObject, AnimImgObject, CharaTemplateObject, DecoObject, EffectObject,
+    ItemObject, RecipeObject, SpawnTableObject, SpecialTileObject, TileObject,
     UIImgObject, WallObject,
     MapTemplateObject, RegionGenObject, SiteGenObject, ScriptObject
 );
@@ -145,11 +248,14 @@ impl_object!(
 impl Object {
     pub fn get_id(&self) -> &str {
         match *self {
+            Object::Ability(ref o) => &o.id,
             Object::AnimImg(ref o) => &o.id,
             Object::CharaTemplate(ref o) => &o.id,
             Object::Deco(ref o) => &o.id,
             Object::Effect(ref o) => &o.id,
             Object::Item(ref o) => &o.id,
+            Object::Recipe(ref o) => &o.id,
+            Object::SpawnTable(ref o) => &o.id,
             Object::SpecialTile(ref o) => &o.id,
             Object::Tile(ref o) => &o.id,
             Object::UIImg(ref o) => &o.id,
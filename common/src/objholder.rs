@@ -3,6 +3,7 @@ use crate::obj::*;
 use crate::pakutil::load_objs_dir;
 use std::num::NonZeroU32;
 use std::path::Path;
+use crate::hashmap::HashMap;
 
 const NON_ZERO_U32_1: NonZeroU32 = unsafe { NonZeroU32::new_unchecked(1) };
 
@@ -19,6 +20,11 @@ macro_rules! impl_idx {
             }
 
             fn search_idx(id: &str, objholder: &ObjectHolder) -> Option<$idx> {
+                let table = &objholder.idx_table.$mem;
+                if table.len() == objholder.$mem.len() {
+                    return table.get(id).map(|&i| $idx::from_usize(i as usize));
+                }
+                // Objects are pushed after building the table
                 for (i, ref o) in (&objholder.$mem).into_iter().enumerate() {
                     if o.id == id {
                         return Some($idx::from_usize(i));
@@ -40,12 +46,7 @@ macro_rules! impl_idx {
             fn get_obj_from_objholder_by_id<'a>(
                 id: &str, objholder: &'a ObjectHolder) -> Option<&'a $obj> {
 
-                for ref o in (&objholder.$mem).into_iter() {
-                    if o.id == id {
-                        return Some(o);
-                    }
-                }
-                None
+                $idx::search_idx(id, objholder).map(|idx| &objholder.$mem[idx.as_usize()])
             }
         }
         
@@ -87,28 +88,61 @@ macro_rules! impl_idx {
 macro_rules! impl_objholder {
     ($({$a:ident, $obj:ty, $mem:ident, $idx:ident}),*) => {
         pub struct ObjectHolder {
-            $(pub $mem: Vec<$obj>),*
+            $(pub $mem: Vec<$obj>,)*
+            idx_table: IdxTable,
+        }
+
+        /// Indices of objects by their ids, to search objects without scanning them
+        #[derive(Default)]
+        struct IdxTable {
+            $($mem: HashMap<String, u32>),*
         }
 
         impl ObjectHolder {
             pub fn new() -> ObjectHolder {
                 ObjectHolder {
-                    $($mem: Vec::new()),*
+                    $($mem: Vec::new(),)*
+                    idx_table: IdxTable::default(),
                 }
             }
 
+            /// Intern object ids into indices. Must be called after objects are changed.
+            pub fn build_idx_table(&mut self) {
+                $(
+                    self.idx_table.$mem = self.$mem.iter().enumerate()
+                        .map(|(i, o)| (o.id.clone(), i as u32))
+                        .collect();
+                )*
+            }
+
+            /// Load objects from pak files in the directories.
+            /// Objects in later directories override earlier objects that have the same id.
             pub fn load<P: AsRef<Path>>(dirs: &[P]) -> ObjectHolder {
                 let mut objholder = ObjectHolder::new();
+                // (object type, id) -> index in the vec
+                let mut loaded: HashMap<(&'static str, String), usize> = HashMap::default();
 
                 for dir in dirs {
-                    load_objs_dir(dir.as_ref(), |object| {
+                    let dir = dir.as_ref();
+                    load_objs_dir(dir, |object| {
                         match object {
-                            $(Object::$a(o) => { objholder.$mem.push(o); }),*
+                            $(Object::$a(o) => {
+                                let key = (stringify!($mem), o.id.clone());
+                                if let Some(&i) = loaded.get(&key) {
+                                    warn!("{} \"{}\" is overridden by \"{}\"",
+                                          stringify!($mem), o.id, dir.to_string_lossy());
+                                    objholder.$mem[i] = o;
+                                } else {
+                                    loaded.insert(key, objholder.$mem.len());
+                                    objholder.$mem.push(o);
+                                }
+                            }),*
                         }
                     });
                 }
 
                 objholder.sort();
+                objholder.build_idx_table();
                 objholder
             }
 
@@ -161,11 +195,14 @@ macro_rules! impl_objholder {
 }
 
 impl_objholder! {
+    {Ability, AbilityObject, ability, AbilityIdx},
     {AnimImg, AnimImgObject, anim_img, AnimImgIdx},
     {CharaTemplate, CharaTemplateObject, chara_template, CharaTemplateIdx},
     {Deco, DecoObject, deco, DecoIdx},
     {Effect, EffectObject, effect, EffectIdx},
     {Item, ItemObject, item, ItemIdx},
+    {Recipe, RecipeObject, recipe, RecipeIdx},
+    {SpawnTable, SpawnTableObject, spawn_table, SpawnTableIdx},
     {SpecialTile, SpecialTileObject, special_tile, SpecialTileIdx},
     {Tile, TileObject, tile, TileIdx},
     {UIImg, UIImgObject, ui_img, UIImgIdx},
@@ -1,10 +1,10 @@
 
 use std::path::{Path, PathBuf};
-use std::fs::{File, create_dir_all};
+use std::fs::{File, copy, create_dir_all, read_dir, remove_dir_all};
 use std::io::{Write, BufReader, BufWriter};
 use serde_cbor::ser::to_writer_packed;
 use serde_cbor::from_reader;
-use crate::basic::SAVE_EXTENSION;
+use crate::basic::{SAVE_EXTENSION, SAVE_FORMAT_VERSION};
 use crate::gamedata::*;
 use crate::impl_filebox::MapLoadError;
 
@@ -17,30 +17,52 @@ impl GameData {
         }
 
         let save_dir = path.as_ref();
+        self.save_data(save_dir)?;
 
-        // Create directory
-        create_dir_all(&save_dir)?;
+        // Write maps
+        let map_dir = save_dir.join("maps");
+        create_dir_all(&map_dir)?;
+        
+        let mut errors: Vec<MapLoadError> = Vec::new();
+        self.region.visit_all_maps(|_mid, map| {
+            match BoxedMap::write(map, &map_dir) {
+                Ok(_) => (),
+                Err(e) => errors.push(e),
+            }
+        });
 
-        // Write id table file
-        let mut file = BufWriter::new(File::create(save_dir.join("idtable"))?);
-        writeln!(file, "{:016x}", *crate::gobj::OBJ_HOLDER_HASH)?;
-        crate::gobj::get_objholder().write_table(&mut file)?;
+        if !errors.is_empty() {
+            return Err(errors.into_iter().next().unwrap().into());
+        }
 
-        // Write metadata file
-        let mut file = BufWriter::new(File::create(save_dir.join("metadata"))?);
-        serde_json::to_writer_pretty(&mut file, &self.meta)?;
+        Ok(())
+    }
 
-        // Write GameData
-        let mut file = BufWriter::new(File::create(save_dir.join("gamedata"))?);
-        to_writer_packed(&mut file, &self)?;
+    /// Save a copy of game data to another directory, like auto-saves.
+    /// Maps not loaded are copied from map_src_dir. Changed maps are still written by the next save().
+    pub fn save_copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, path: P, map_src_dir: Q)
+                                                     -> Result<(), Box<std::error::Error>> {
+        let save_dir = path.as_ref();
+        self.save_data(save_dir)?;
 
-        // Write maps
+        // Maps of the previous copy are removed
         let map_dir = save_dir.join("maps");
+        if map_dir.exists() {
+            remove_dir_all(&map_dir)?;
+        }
         create_dir_all(&map_dir)?;
-        
+        if map_src_dir.as_ref().exists() {
+            for entry in read_dir(map_src_dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_file() {
+                    copy(entry.path(), map_dir.join(entry.file_name()))?;
+                }
+            }
+        }
+
         let mut errors: Vec<MapLoadError> = Vec::new();
         self.region.visit_all_maps(|_mid, map| {
-            match BoxedMap::write(map, &map_dir) {
+            match BoxedMap::write_copy(map, &map_dir) {
                 Ok(_) => (),
                 Err(e) => errors.push(e),
             }
@@ -53,13 +75,41 @@ impl GameData {
         Ok(())
     }
 
+    /// Write the id table, metadata, and game data except maps
+    fn save_data(&self, save_dir: &Path) -> Result<(), Box<std::error::Error>> {
+        // Create directory
+        create_dir_all(&save_dir)?;
+
+        // Write id table file
+        let mut file = BufWriter::new(File::create(save_dir.join("idtable"))?);
+        writeln!(file, "{:016x}", *crate::gobj::OBJ_HOLDER_HASH)?;
+        crate::gobj::get_objholder().write_table(&mut file)?;
+
+        // Write metadata file
+        let mut file = BufWriter::new(File::create(save_dir.join("metadata"))?);
+        serde_json::to_writer_pretty(&mut file, &self.meta)?;
+
+        // Write GameData
+        let mut file = BufWriter::new(File::create(save_dir.join("gamedata"))?);
+        to_writer_packed(&mut file, &self)?;
+
+        Ok(())
+    }
+
+    /// Read only the metadata file in the save directory
+    pub fn load_metadata<P: AsRef<Path>>(path: P) -> Result<MetaData, Box<std::error::Error>> {
+        let mut file = BufReader::new(File::open(path.as_ref().join("metadata"))?);
+        Ok(serde_json::from_reader(&mut file)?)
+    }
+
     /// Load game data from specified directory
     pub fn load<P: AsRef<Path>>(path: P) -> Result<GameData, Box<std::error::Error>> {
         let save_dir = path.as_ref();
         
-        // Read metadata file
-        let mut file = BufReader::new(File::open(save_dir.join("metadata"))?);
-        let meta: MetaData = serde_json::from_reader(&mut file)?;
+        let meta = GameData::load_metadata(save_dir)?;
+        if meta.format_version() != SAVE_FORMAT_VERSION {
+            return Err(Box::new(SaveVersionError { found: meta.format_version() }));
+        }
 
         // Read index conversion table
         let mut file = BufReader::new(File::open(save_dir.join("idtable"))?);
@@ -98,6 +148,24 @@ impl GameData {
     }
 }
 
+/// The save file was written in an unsupported format version
+#[derive(Debug)]
+pub struct SaveVersionError {
+    pub found: u32,
+}
+
+impl std::fmt::Display for SaveVersionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unsupported save format version {} (expected {})", self.found, SAVE_FORMAT_VERSION)
+    }
+}
+
+impl std::error::Error for SaveVersionError {
+    fn description(&self) -> &str {
+        "unsupported save format version"
+    }
+}
+
 /// Print save data size
 #[cfg(debug_assertions)]
 fn print_save_data_size(gd: &GameData) {
@@ -2,7 +2,7 @@
 use std::ops::Index;
 use std::fmt;
 use crate::hashmap::HashMap;
-use crate::gamedata::Time;
+use crate::gamedata::{Time, Weather, ScriptQuestState, Faction, Relationship, SiteKind};
 
 /// Instructions are executed in Game.
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -13,12 +13,46 @@ pub enum Instruction {
     JumpIf(String, Expr),
     /// Talk instruction (textid, Vec<choice's textid, destination section>)
     Talk(String, Vec<(String, String)>),
+    /// Talk one text randomly chosen from the list
+    TalkRandom(Vec<String>),
     /// Set global variable
     GSet(String, Expr),
+    /// Set local variable. Local variables are discarded when the script ends.
+    LSet(String, Expr),
     /// Player receive money
     ReceiveMoney(Expr),
-    /// Remove item form player's inventory
-    RemoveItem(String),
+    /// Player receive items (item id, the number of items)
+    ReceiveItem(String, Expr),
+    /// Remove items form player's inventory (item id, the number of items).
+    /// Nothing is removed if the player doesn't have enough items, and the result is set to "$?".
+    RemoveItem(String, Expr),
+    /// Add the value to player's stat. Only the player is affected, even in talks with other characters.
+    /// Hp is clamped between 1 and max hp.
+    ModifyStat(StatKind, Expr),
+    /// Start the quest
+    /// (quest id, deadline in hours, script id executed on failure, script id executed on completion)
+    QuestStart(String, Option<Expr>, Option<String>, Option<String>),
+    /// Complete the quest of given id if it is active, and execute its on-complete script
+    QuestComplete(String),
+    /// Set the stage of the active quest to record its progress (quest id, stage)
+    QuestSet(String, Expr),
+    /// Move the player to the position on given floor of the current site (floor, x, y)
+    MovePlayer(Expr, Expr, Expr),
+    /// Characters of given template id on the current map become hostile to the player
+    MakeHostile(String),
+    /// Set the relationship between the player faction and given faction
+    SetFactionRelation(Faction, Relationship),
+    /// Play the sound effect
+    PlaySound(String),
+    /// Set the weather of the current region for given hours
+    SetWeather(Weather, Expr),
+    /// The talking character joins the player's party
+    JoinParty,
+    /// Generate a hostile character of given template id near the player
+    SpawnChara(String),
+    /// Move the player to the floor of the site in the current region (site kind, site number, floor).
+    /// Move to the region map if None.
+    Teleport(Option<(SiteKind, u32, u32)>),
     /// Special Instruction
     Special(SpecialInstruction),
 }
@@ -36,6 +70,8 @@ pub enum SpecialInstruction {
     QuestWindow,
     /// Special instruction to receive quest rewards
     ReceiveQuestRewards,
+    /// Special instruction to make all tiles of the current map observed
+    RevealMap,
 }
 
 impl std::str::FromStr for SpecialInstruction {
@@ -49,6 +85,25 @@ impl std::str::FromStr for SpecialInstruction {
             "get_dungeon_location" => Ok(GetDungeonLocation),
             "quest_window" => Ok(QuestWindow),
             "receive_quest_rewards" => Ok(ReceiveQuestRewards),
+            "reveal_map" => Ok(RevealMap),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Stats that can be modified by scripts
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum StatKind {
+    Hp, Sp,
+}
+
+impl std::str::FromStr for StatKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hp" => Ok(StatKind::Hp),
+            "sp" => Ok(StatKind::Sp),
             _ => Err(()),
         }
     }
@@ -62,9 +117,26 @@ pub enum Expr {
     /// Reference to global variable
     GVar(String),
     IsGVarEmpty(String),
+    /// Reference to local variable
+    LVar(String),
     CurrentTime,
     DurationHour(Box<Expr>, Box<Expr>),
     HasItem(String),
+    /// The number of the item in player's inventory
+    ItemCount(String),
+    /// Player has the item whose rank is the given tier or higher
+    HasItemQuality(String, Box<Expr>),
+    /// Player's current money
+    HaveMoney,
+    /// Hour of current time
+    CurrentHour,
+    IsNight,
+    /// Current region's weather is the given one
+    IsWeather(Weather),
+    /// State of the quest started by scripts
+    QuestState(String),
+    /// Stage of the quest started by scripts. Inactive quests are stage 0.
+    QuestStage(String),
 }
 
 /// Value is the result of evaluation of Expr.
@@ -73,6 +145,7 @@ pub enum Value {
     Bool(bool),
     Int(i32),
     Time(Time),
+    QuestState(ScriptQuestState),
     /// Referenced for unknown variable. This can be changed to 0 or false.
     RefUnknownVar,
     Error(ExprErrorKind),
@@ -96,16 +169,48 @@ pub enum ExprErrorKind {
 
 /// Script consists of one or more sections.
 /// One section includes one or more instructions.
+/// Inline texts written in the script are registered as talk text ids.
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
-pub struct Script(HashMap<String, Vec<Instruction>>);
+pub struct Script {
+    sections: HashMap<String, Vec<Instruction>>,
+    texts: HashMap<String, String>,
+}
 
 impl Script {
     pub fn from_map(map: HashMap<String, Vec<Instruction>>) -> Script {
-        Script(map)
+        Script {
+            sections: map,
+            texts: HashMap::default(),
+        }
+    }
+
+    pub fn with_texts(mut self, texts: HashMap<String, String>) -> Script {
+        self.texts = texts;
+        self
+    }
+
+    /// Inline text by text id
+    pub fn text(&self, text_id: &str) -> Option<&str> {
+        self.texts.get(text_id).map(|s| s.as_str())
+    }
+
+    /// Iterate over inline texts with their ids
+    pub fn iter_texts(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.texts.iter().map(|(id, text)| (id.as_str(), text.as_str()))
     }
     
+    /// Iterate over sections with their names
+    pub fn iter_sections(&self) -> impl Iterator<Item = (&str, &[Instruction])> {
+        self.sections.iter().map(|(name, v)| (name.as_str(), v.as_slice()))
+    }
+
+    /// Returns true if the script has the section
+    pub fn has_section(&self, s: &str) -> bool {
+        self.sections.contains_key(s)
+    }
+
     pub fn get(&self, pos: &ScriptPos) -> Option<&Instruction> {
-        if let Some(v) = self.0.get(&pos.section) {
+        if let Some(v) = self.sections.get(&pos.section) {
             v.get(pos.i)
         } else {
             warn!("script error: unknown section {}", pos.section);
@@ -114,7 +219,7 @@ impl Script {
     }
     
     pub fn section(&self, s: &str) -> &[Instruction] {
-        self.0[s].as_ref()
+        self.sections[s].as_ref()
     }
 }
 
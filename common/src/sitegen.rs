@@ -2,6 +2,8 @@
 use array2d::Vec2d;
 use crate::gamedata::site::SiteKind;
 use crate::gamedata::shop::ShopKind;
+use crate::gamedata::chara::ScheduleEntry;
+use crate::gamedata::weather::Weather;
 
 /// Hold data for site generation
 #[derive(Clone, Serialize, Deserialize)]
@@ -11,6 +13,8 @@ pub struct SiteGenObject {
     pub map_template_id: Vec<String>,
     pub unique_citizens: Vec<UniqueCitizenGenData>,
     pub shops: Vec<ShopGenData>,
+    /// Weather fixed in the site, like fog in swamps
+    pub weather: Option<Weather>,
 }
 
 /// Data to generate a unique citizen
@@ -23,6 +27,9 @@ pub struct UniqueCitizenGenData {
     pub n: u32,
     pub chara_template_id: String,
     pub talk_script_id: Option<String>,
+    /// Daily schedule. The citizen acts by its AI kind all day if empty.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
 }
 
 /// Data to generate a shop on the site
@@ -74,10 +74,17 @@ impl<T: WithId> FileBox<T> {
     }
 
     pub fn write_force<P: AsRef<Path>>(s: &Self, p: P) -> Result<(), T::Error> {
+        Self::write_copy(s, p)?;
+        s.changed.set(false);
+        Ok(())
+    }
+
+    /// Write to another directory than the original.
+    /// The changed flag is kept because the original file is not updated.
+    pub fn write_copy<P: AsRef<Path>>(s: &Self, p: P) -> Result<(), T::Error> {
         if let Some(a) = &s.inner {
             let mut file = GzEncoder::new(BufWriter::new(File::create(s.path(p))?), Compression::fast());
             T::write(&mut file, &a)?;
-            s.changed.set(false);
         }
 
         Ok(())
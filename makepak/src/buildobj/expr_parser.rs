@@ -92,6 +92,15 @@ named!(is_gvar_empty<CompleteStr, Expr>,
     )
 );
 
+named!(lvar<CompleteStr, Expr>,
+    do_parse!(
+        tag!("lvar") >>
+        multispace0 >>
+        var_name: delimited!(char!('('), ws!(id), char!(')')) >>
+        (Expr::LVar(var_name))
+    )
+);
+
 named!(current_time<CompleteStr, Expr>,
     do_parse!(
         tag!("current_time") >>
@@ -125,6 +134,96 @@ named!(has_item<CompleteStr, Expr>,
     )
 );
 
+named!(item_count<CompleteStr, Expr>,
+    do_parse!(
+        tag!("item_count") >>
+        multispace0 >>
+        s: delimited!(char!('('), ws!(id), char!(')')) >>
+        (Expr::ItemCount(s))
+    )
+);
+
+named!(has_item_quality<CompleteStr, Expr>,
+    do_parse!(
+        tag!("has_item_quality") >>
+        multispace0 >>
+        char!('(') >>
+        s: ws!(id) >>
+        char!(',') >>
+        tier: expr >>
+        char!(')') >>
+        (Expr::HasItemQuality(s, Box::new(tier)))
+    )
+);
+
+named!(have_money<CompleteStr, Expr>,
+    do_parse!(
+        tag!("have_money") >>
+        multispace0 >>
+        char!('(') >>
+        multispace0 >>
+        char!(')') >>
+        (Expr::HaveMoney)
+    )
+);
+
+named!(current_hour<CompleteStr, Expr>,
+    do_parse!(
+        tag!("current_hour") >>
+        multispace0 >>
+        char!('(') >>
+        multispace0 >>
+        char!(')') >>
+        (Expr::CurrentHour)
+    )
+);
+
+named!(is_night<CompleteStr, Expr>,
+    do_parse!(
+        tag!("is_night") >>
+        multispace0 >>
+        char!('(') >>
+        multispace0 >>
+        char!(')') >>
+        (Expr::IsNight)
+    )
+);
+
+named!(is_weather<CompleteStr, Expr>,
+    do_parse!(
+        tag!("is_weather") >>
+        multispace0 >>
+        weather: delimited!(char!('('), ws!(map_res!(symbol, str::parse)), char!(')')) >>
+        (Expr::IsWeather(weather))
+    )
+);
+
+named!(quest_state<CompleteStr, Expr>,
+    do_parse!(
+        tag!("quest_state") >>
+        multispace0 >>
+        s: delimited!(char!('('), ws!(id), char!(')')) >>
+        (Expr::QuestState(s))
+    )
+);
+
+named!(quest_stage<CompleteStr, Expr>,
+    do_parse!(
+        tag!("quest_stage") >>
+        multispace0 >>
+        s: delimited!(char!('('), ws!(id), char!(')')) >>
+        (Expr::QuestStage(s))
+    )
+);
+
+named!(quest_state_literal<CompleteStr, Expr>,
+    do_parse!(
+        state: map_res!(alt!(tag!("inactive") | tag!("active") | tag!("complete") | tag!("failed")),
+                        |s: CompleteStr| s.parse()) >>
+        (Expr::Value(Value::QuestState(state)))
+    )
+);
+
 named!(factor<CompleteStr, Expr>,
     ws!(alt_complete!(
         true_literal |
@@ -133,9 +232,19 @@ named!(factor<CompleteStr, Expr>,
         gvar |
         gvar_special |
         is_gvar_empty |
+        lvar |
         current_time |
         duration_hours |
+        has_item_quality |
         has_item |
+        item_count |
+        have_money |
+        current_hour |
+        is_night |
+        is_weather |
+        quest_state |
+        quest_stage |
+        quest_state_literal |
         parens
     ))
 );
@@ -259,14 +368,43 @@ named!(pub expr<CompleteStr, Expr>,
 
 #[test]
 fn expr_test() {
+    use common::gamedata::{Weather, ScriptQuestState};
+
     assert_eq!(expr(CompleteStr("true")), Ok((CompleteStr(""), Expr::Value(Value::Bool(true)))));
     assert_eq!(expr(CompleteStr("false")), Ok((CompleteStr(""), Expr::Value(Value::Bool(false)))));
     assert_eq!(expr(CompleteStr("1234")), Ok((CompleteStr(""), Expr::Value(Value::Int(1234)))));
     assert_eq!(expr(CompleteStr("$(aa)")), Ok((CompleteStr(""), Expr::GVar("aa".to_owned()))));
     assert_eq!(expr(CompleteStr("is_gvar_empty(bb)")),
                Ok((CompleteStr(""), Expr::IsGVarEmpty("bb".to_owned()))));
+    assert_eq!(expr(CompleteStr("lvar(tmp)")), Ok((CompleteStr(""), Expr::LVar("tmp".to_owned()))));
     let a = Expr::HasItem("box".to_owned());
     assert_eq!(expr(CompleteStr("has_item(box)")), Ok((CompleteStr(""), a)));
+    let a = Expr::Term(vec![
+        (Operator::None, Expr::ItemCount("potion".to_owned())),
+        (Operator::GreaterEq, Expr::Value(Value::Int(3)))]);
+    assert_eq!(expr(CompleteStr("item_count(potion) >= 3")), Ok((CompleteStr(""), a)));
+    let a = Expr::HasItemQuality("sword".to_owned(), Box::new(Expr::Value(Value::Int(2))));
+    assert_eq!(expr(CompleteStr("has_item_quality(sword, 2)")), Ok((CompleteStr(""), a)));
+    let a = Expr::Term(vec![
+        (Operator::None, Expr::QuestState("main".to_owned())),
+        (Operator::Eq, Expr::Value(Value::QuestState(ScriptQuestState::Complete)))]);
+    assert_eq!(expr(CompleteStr("quest_state(main) == complete")), Ok((CompleteStr(""), a)));
+    let a = Expr::Term(vec![
+        (Operator::None, Expr::QuestState("escort".to_owned())),
+        (Operator::Eq, Expr::Value(Value::QuestState(ScriptQuestState::Failed)))]);
+    assert_eq!(expr(CompleteStr("quest_state(escort) == failed")), Ok((CompleteStr(""), a)));
+    let a = Expr::Term(vec![
+        (Operator::None, Expr::QuestStage("main".to_owned())),
+        (Operator::Greater, Expr::Value(Value::Int(1)))]);
+    assert_eq!(expr(CompleteStr("quest_stage(main) > 1")), Ok((CompleteStr(""), a)));
+    let a = Expr::Term(vec![
+        (Operator::None, Expr::HaveMoney),
+        (Operator::GreaterEq, Expr::Value(Value::Int(500)))]);
+    assert_eq!(expr(CompleteStr("have_money() >= 500")), Ok((CompleteStr(""), a)));
+    assert_eq!(expr(CompleteStr("is_night()")), Ok((CompleteStr(""), Expr::IsNight)));
+    assert_eq!(expr(CompleteStr("is_weather(rain)")),
+               Ok((CompleteStr(""), Expr::IsWeather(Weather::Rain))));
+    assert!(expr(CompleteStr("is_weather(sunny)")).is_err());
     assert_eq!(
         expr(CompleteStr("1 * 2 + 3")),
         Ok((CompleteStr(""),
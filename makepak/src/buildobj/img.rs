@@ -22,7 +22,8 @@ pub fn build_img(input: ImgInput) -> Result<(Img, ImgData), Error> {
     let grid_ny = input.grid_ny.unwrap_or(1);
     let n_pattern = input.n_pattern.unwrap_or(1);
     let n_anim_frame = input.n_anim_frame.unwrap_or(1);
-    let n_frame = input.n_frame.unwrap_or(n_pattern * n_anim_frame);
+    let n_direction = input.n_direction.unwrap_or(1);
+    let n_frame = input.n_frame.unwrap_or(n_pattern * n_anim_frame * n_direction);
     let duration = input.duration.unwrap_or(0);
     
     ensure!(
@@ -33,10 +34,11 @@ pub fn build_img(input: ImgInput) -> Result<(Img, ImgData), Error> {
             image_x: imgdata.dimensions.0,
             image_y: imgdata.dimensions.1,
         });
-    assert!(n_frame == n_pattern * n_anim_frame); // TODO: Make these asserts ensure!()
+    assert!(n_frame == n_pattern * n_anim_frame * n_direction); // TODO: Make these asserts ensure!()
     assert!(n_frame > 0);
     assert!(n_pattern > 0);
     assert!(n_anim_frame > 0);
+    assert!(n_direction == 1 || n_direction == 4);
 
     Ok((
         Img {
@@ -49,6 +51,7 @@ pub fn build_img(input: ImgInput) -> Result<(Img, ImgData), Error> {
             n_pattern: n_pattern,
             n_anim_frame: n_anim_frame,
             duration: duration,
+            n_direction: n_direction,
         },
         imgdata))
 }
@@ -44,6 +44,10 @@ pub fn build_item_object(tomlinput: TomlInput) -> Result<ItemObject, Error> {
         },
     };
 
+    if item.learn_skill.is_some() {
+        flags |= ItemFlags::READABLE;
+    }
+
     Ok(ItemObject {
         id: tomlinput.id,
         img: build_img(img)?.0,
@@ -60,6 +64,15 @@ pub fn build_item_object(tomlinput: TomlInput) -> Result<ItemObject, Error> {
         eff: item.eff.unwrap_or(0),
         medical_effect: item.medical_effect.unwrap_or_default(),
         nutrition: item.nutrition.unwrap_or(0),
+        light: item.light.unwrap_or(0),
+        container: item.container,
+        charge: item.charge,
+        has_quality: item.has_quality.unwrap_or(false),
+        materials: item.materials.unwrap_or_default(),
+        mount: item.mount,
+        dig_power: item.dig_power.unwrap_or(0),
+        overlay: item.overlay,
+        learn_skill: item.learn_skill,
     })
 }
 
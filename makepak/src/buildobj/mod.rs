@@ -13,10 +13,15 @@ use crate::error::*;
 use self::img::*;
 use self::item::build_item_object;
 pub use script_parser::parse as script_parse;
+pub use script_parser::parse_from_line as script_parse_from_line;
+pub use script_parser::included_files as script_included_files;
 
 pub fn build_object(tomlinput: TomlInput) -> Result<Object, Error> {
     let object_type = tomlinput.object_type.clone();
     match object_type.as_ref() {
+        "ability" => {
+            return build_ability_object(tomlinput).map(|o| Object::Ability(o));
+        }
         "anim_img" => {
             return build_anim_img_object(tomlinput).map(|o| Object::AnimImg(o));
         }
@@ -32,6 +37,12 @@ pub fn build_object(tomlinput: TomlInput) -> Result<Object, Error> {
         "item" => {
             return build_item_object(tomlinput).map(|o| Object::Item(o));
         }
+        "recipe" => {
+            return build_recipe_object(tomlinput).map(|o| Object::Recipe(o));
+        }
+        "spawn_table" => {
+            return build_spawn_table_object(tomlinput).map(|o| Object::SpawnTable(o));
+        }
         "special_tile" => {
             return build_special_tile_object(tomlinput).map(|o| Object::SpecialTile(o));
         }
@@ -59,12 +70,50 @@ pub fn build_object(tomlinput: TomlInput) -> Result<Object, Error> {
     }
 }
 
+fn build_ability_object(tomlinput: TomlInput) -> Result<AbilityObject, Error> {
+    let ability = get_optional_field!(tomlinput, ability);
+
+    Ok(AbilityObject {
+        id: tomlinput.id,
+        cost_mp: ability.cost_mp,
+        range: ability.range,
+        effect: ability.effect,
+        element: ability.element.unwrap_or(gamedata::Element::Physical),
+        dice_n: ability.dice_n,
+        dice_x: ability.dice_x,
+    })
+}
+
+fn build_recipe_object(tomlinput: TomlInput) -> Result<RecipeObject, Error> {
+    let recipe = get_optional_field!(tomlinput, recipe);
+
+    Ok(RecipeObject {
+        id: tomlinput.id,
+        inputs: recipe.inputs,
+        skill: recipe.skill.unwrap_or(gamedata::SkillKind::Crafting),
+        skill_level: recipe.skill_level.unwrap_or(0),
+        output: recipe.output,
+        n_output: recipe.n_output.unwrap_or(1),
+    })
+}
+
+fn build_spawn_table_object(tomlinput: TomlInput) -> Result<SpawnTableObject, Error> {
+    let spawn_table = get_optional_field!(tomlinput, spawn_table);
+
+    Ok(SpawnTableObject {
+        id: tomlinput.id,
+        groups: spawn_table.groups,
+    })
+}
+
 fn build_deco_object(tomlinput: TomlInput) -> Result<DecoObject, Error> {
     let img = get_optional_field!(tomlinput, image);
+    let light = tomlinput.deco.and_then(|deco| deco.light).unwrap_or(0);
     
     Ok(DecoObject {
         id: tomlinput.id,
         img: build_img(img)?.0,
+        light,
     })
 }
 
@@ -79,16 +128,18 @@ fn build_effect_object(tomlinput: TomlInput) -> Result<EffectObject, Error> {
 
 fn build_special_tile_object(tomlinput: TomlInput) -> Result<SpecialTileObject, Error> {
     let img = get_optional_field!(tomlinput, image);
-    let always_background = if let Some(special_tile) = tomlinput.special_tile {
-        special_tile.always_background.unwrap_or(false)
+    let (always_background, resource, trap) = if let Some(special_tile) = tomlinput.special_tile {
+        (special_tile.always_background.unwrap_or(false), special_tile.resource, special_tile.trap)
     } else {
-        false
+        (false, None, None)
     };
     
     Ok(SpecialTileObject {
         id: tomlinput.id,
         always_background: always_background,
         img: build_img(img)?.0,
+        resource,
+        trap,
     })
 }
 
@@ -117,10 +168,10 @@ fn build_ui_img_object(tomlinput: TomlInput) -> Result<UIImgObject, Error> {
 fn build_wall_object(tomlinput: TomlInput) -> Result<WallObject, Error> {
     let img = get_optional_field!(tomlinput, image);
     let (img, imgdata) = build_img(img)?;
-    let base_draw = if let Some(wall) = tomlinput.wall {
-        wall.base_draw.unwrap_or(false)
+    let (base_draw, hardness) = if let Some(wall) = tomlinput.wall {
+        (wall.base_draw.unwrap_or(false), wall.hardness)
     } else {
-        true
+        (true, None)
     };
     
     Ok(WallObject {
@@ -128,6 +179,7 @@ fn build_wall_object(tomlinput: TomlInput) -> Result<WallObject, Error> {
         base_draw: base_draw,
         img: img,
         symbol_color: imgdata.calc_average_color(),
+        hardness: hardness,
     })
 }
 
@@ -154,6 +206,10 @@ fn build_chara_template_object(tomlinput: TomlInput) -> Result<CharaTemplateObje
         gen_level: chara_dep_input.gen_level,
         default_ai_kind: chara_dep_input.default_ai_kind.unwrap_or(gamedata::NpcAIKind::None),
         base_attr,
+        skill_levels: chara_dep_input.skills.unwrap_or_default().into_iter()
+            .map(|skill| (skill.kind, skill.lv))
+            .collect(),
+        movements: chara_dep_input.movements.unwrap_or_else(|| vec![Movement::Walk]),
     })
 }
 
@@ -201,6 +257,7 @@ fn build_site_gen_object(tomlinput: TomlInput) -> Result<SiteGenObject, Error> {
         map_template_id: sg.map_template_id,
         unique_citizens: sg.unique_citizens.unwrap_or(vec![]),
         shops: sg.shops.unwrap_or(vec![]),
+        weather: sg.weather,
     })
 }
 
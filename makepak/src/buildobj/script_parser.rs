@@ -1,16 +1,37 @@
 
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use nom::{space, line_ending};
+use nom::{space, multispace, line_ending, not_line_ending, digit1};
 use nom::types::CompleteStr;
 use common::hashmap::HashMap;
 
+use common::gamedata::SiteKind;
 use common::script::*;
 use crate::error::PakCompileError;
 use super::expr_parser::*;
 
+named!(comment<CompleteStr, ()>,
+    do_parse!(
+        char!('#') >>
+        opt!(not_line_ending) >>
+        (())
+    )
+);
+
 named!(end_line<CompleteStr, ()>,
     do_parse!(
         opt!(space) >>
+        opt!(comment) >>
+        line_ending >>
+        (())
+    )
+);
+
+// A line that has only a comment
+named!(comment_line<CompleteStr, ()>,
+    do_parse!(
+        opt!(multispace) >>
+        comment >>
         line_ending >>
         (())
     )
@@ -19,6 +40,8 @@ named!(end_line<CompleteStr, ()>,
 #[test]
 fn end_line_test() {
     assert_eq!(end_line(CompleteStr("   \naabb")), Ok((CompleteStr("aabb"), ())));
+    assert_eq!(end_line(CompleteStr(" # comment\naabb")), Ok((CompleteStr("aabb"), ())));
+    assert_eq!(comment_line(CompleteStr("\n  # comment\naabb")), Ok((CompleteStr("aabb"), ())));
 }
 
 macro_rules! array(
@@ -31,7 +54,7 @@ macro_rules! array(
             char!(']')))
     });
     ($i:expr, $f:expr) => (
-        array!($i, call!($f));
+        array!($i, call!($f))
     );
 );
 
@@ -82,6 +105,12 @@ fn jump_instruction_test() {
         jump_if_instruction(CompleteStr("jump_if(has-key, has_item(key))\n")),
         Ok((CompleteStr(""), Instruction::JumpIf(
             "has-key".to_owned(), Expr::HasItem("key".to_owned())))));
+    let e = Expr::Term(vec![
+        (Operator::None, Expr::ItemCount("potion".to_owned())),
+        (Operator::GreaterEq, Expr::Value(Value::Int(3)))]);
+    assert_eq!(
+        jump_if_instruction(CompleteStr("jump_if(enough, item_count(potion) >= 3)\n")),
+        Ok((CompleteStr(""), Instruction::JumpIf("enough".to_owned(), e))));
 }
 
 named!(special_instruction<CompleteStr, Instruction>,
@@ -126,6 +155,17 @@ named!(talk_instruction_with_choices<CompleteStr, Instruction>,
     )
 );
 
+named!(talk_random_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("talk_random")) >>
+        char!('(') >>
+        text_ids: array!(id) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::TalkRandom(text_ids))
+    )
+);
+
 named!(gset_instruction<CompleteStr, Instruction>,
     do_parse!(
         ws!(tag!("gset")) >>
@@ -139,6 +179,32 @@ named!(gset_instruction<CompleteStr, Instruction>,
     )
 );
 
+named!(lset_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("lset")) >>
+        char!('(') >>
+        var_name: ws!(id) >>
+        char!(',') >>
+        value: ws!(expr) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::LSet(var_name, value))
+    )
+);
+
+#[test]
+fn lset_instruction_test() {
+    assert_eq!(
+        instruction(CompleteStr("lset(tmp, 1)\n")),
+        Ok((CompleteStr(""), Instruction::LSet("tmp".to_owned(), Expr::Value(Value::Int(1))))));
+    let e = Expr::Term(vec![
+        (Operator::None, Expr::LVar("tmp".to_owned())),
+        (Operator::Add, Expr::Value(Value::Int(1)))]);
+    assert_eq!(
+        instruction(CompleteStr("lset(tmp, lvar(tmp) + 1)\n")),
+        Ok((CompleteStr(""), Instruction::LSet("tmp".to_owned(), e))));
+}
+
 named!(receive_money_instruction<CompleteStr, Instruction>,
     do_parse!(
         ws!(tag!("receive_money")) >>
@@ -148,15 +214,352 @@ named!(receive_money_instruction<CompleteStr, Instruction>,
     )
 );
 
+named!(receive_item_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(alt!(tag!("receive_item") | tag!("give_item"))) >>
+        char!('(') >>
+        item_id: ws!(id) >>
+        char!(',') >>
+        n: ws!(expr) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::ReceiveItem(item_id, n))
+    )
+);
+
 named!(remove_item_instruction<CompleteStr, Instruction>,
     do_parse!(
         ws!(tag!("remove_item")) >>
-        item_id: delimited!(char!('('), ws!(id), char!(')')) >>
+        char!('(') >>
+        item_id: ws!(id) >>
+        n: opt!(preceded!(char!(','), ws!(expr))) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::RemoveItem(item_id, n.unwrap_or(Expr::Value(Value::Int(1)))))
+    )
+);
+
+named!(modify_stat_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(alt!(tag!("heal") | tag!("modify_stat"))) >>
+        char!('(') >>
+        kind: ws!(map_res!(symbol, FromStr::from_str)) >>
+        char!(',') >>
+        value: ws!(expr) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::ModifyStat(kind, value))
+    )
+);
+
+#[test]
+fn receive_item_instruction_test() {
+    assert_eq!(
+        receive_item_instruction(CompleteStr("receive_item(healing-potion, 3)\n")),
+        Ok((CompleteStr(""), Instruction::ReceiveItem(
+            "healing-potion".to_owned(), Expr::Value(Value::Int(3))))));
+    assert_eq!(
+        instruction(CompleteStr("give_item(healing-potion, 3)\n")),
+        Ok((CompleteStr(""), Instruction::ReceiveItem(
+            "healing-potion".to_owned(), Expr::Value(Value::Int(3))))));
+}
+
+#[test]
+fn remove_item_instruction_test() {
+    assert_eq!(
+        remove_item_instruction(CompleteStr("remove_item(healing-potion)\n")),
+        Ok((CompleteStr(""), Instruction::RemoveItem(
+            "healing-potion".to_owned(), Expr::Value(Value::Int(1))))));
+    assert_eq!(
+        remove_item_instruction(CompleteStr("remove_item(healing-potion, 3)\n")),
+        Ok((CompleteStr(""), Instruction::RemoveItem(
+            "healing-potion".to_owned(), Expr::Value(Value::Int(3))))));
+}
+
+#[test]
+fn modify_stat_instruction_test() {
+    assert_eq!(
+        modify_stat_instruction(CompleteStr("heal(hp, 20)\n")),
+        Ok((CompleteStr(""), Instruction::ModifyStat(StatKind::Hp, Expr::Value(Value::Int(20))))));
+    let e = Expr::Term(vec![
+        (Operator::None, Expr::Value(Value::Int(0))),
+        (Operator::Sub, Expr::GVar("damage".to_owned()))]);
+    assert_eq!(
+        modify_stat_instruction(CompleteStr("modify_stat(sp, 0 - $(damage))\n")),
+        Ok((CompleteStr(""), Instruction::ModifyStat(StatKind::Sp, e))));
+    assert!(modify_stat_instruction(CompleteStr("heal(str, 20)\n")).is_err());
+}
+
+// Inline text block surrounded by triple quotes.
+// Lines are trimmed, and leading and trailing empty lines are removed.
+named!(inline_text<CompleteStr, String>,
+    do_parse!(
+        tag!("\"\"\"") >>
+        s: take_until!("\"\"\"") >>
+        tag!("\"\"\"") >>
+        (s.lines().map(|line| line.trim()).collect::<Vec<&str>>().join("\n").trim().to_owned())
+    )
+);
+
+// Talk instruction with an inline text block.
+// Returns the instruction and (text id, text) pair.
+named!(talk_instruction_inline<CompleteStr, (Instruction, (String, String))>,
+    do_parse!(
+        ws!(tag!("talk")) >>
+        char!('(') >>
+        text: ws!(inline_text) >>
+        choices: opt!(preceded!(
+            char!(','),
+            array!(delimited!(
+                char!('('), separated_pair!(ws!(id), char!(','), ws!(id)), char!(')') )))) >>
+        char!(')') >>
+        end_line >>
+        ({
+            let text_id = inline_text_id(&text);
+            (Instruction::Talk(text_id.clone(), choices.unwrap_or_default()), (text_id, text))
+        })
+    )
+);
+
+/// Text ids of inline texts are generated from the hash of texts
+fn inline_text_id(text: &str) -> String {
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in text.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("inline-text-{:016x}", hash)
+}
+
+#[test]
+fn talk_instruction_inline_test() {
+    let text = "Hello.\nWelcome to our town.".to_owned();
+    let text_id = inline_text_id(&text);
+    let result = Instruction::Talk(text_id.clone(), vec![("a".to_owned(), "b".to_owned())]);
+    assert_eq!(
+        talk_instruction_inline(CompleteStr("talk(\"\"\"\n  Hello.\n  Welcome to our town.\n\"\"\", [(a, b)])\n")),
+        Ok((CompleteStr(""), (result, (text_id, text)))));
+}
+
+named!(quest_start_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("quest_start")) >>
+        char!('(') >>
+        quest_id: ws!(id) >>
+        deadline: opt!(preceded!(char!(','), ws!(expr))) >>
+        on_fail: opt!(preceded!(char!(','), ws!(id))) >>
+        on_complete: opt!(preceded!(char!(','), ws!(id))) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::QuestStart(quest_id, deadline, on_fail, on_complete))
+    )
+);
+
+named!(quest_complete_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("quest_complete")) >>
+        quest_id: delimited!(char!('('), ws!(id), char!(')')) >>
+        end_line >>
+        (Instruction::QuestComplete(quest_id))
+    )
+);
+
+named!(quest_set_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("quest_set")) >>
+        char!('(') >>
+        quest_id: ws!(id) >>
+        char!(',') >>
+        stage: ws!(expr) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::QuestSet(quest_id, stage))
+    )
+);
+
+named!(move_player_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(alt!(tag!("move_player") | tag!("move_chara"))) >>
+        char!('(') >>
+        floor: ws!(expr) >>
+        char!(',') >>
+        x: ws!(expr) >>
+        char!(',') >>
+        y: ws!(expr) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::MovePlayer(floor, x, y))
+    )
+);
+
+named!(make_hostile_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("make_hostile")) >>
+        chara_id: delimited!(char!('('), ws!(id), char!(')')) >>
+        end_line >>
+        (Instruction::MakeHostile(chara_id))
+    )
+);
+
+named!(set_faction_relation_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("set_faction_relation")) >>
+        char!('(') >>
+        faction: ws!(map_res!(symbol, FromStr::from_str)) >>
+        char!(',') >>
+        rel: ws!(map_res!(symbol, FromStr::from_str)) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::SetFactionRelation(faction, rel))
+    )
+);
+
+named!(set_weather_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("set_weather")) >>
+        char!('(') >>
+        weather: ws!(map_res!(symbol, FromStr::from_str)) >>
+        char!(',') >>
+        hours: ws!(expr) >>
+        char!(')') >>
         end_line >>
-        (Instruction::RemoveItem(item_id))
+        (Instruction::SetWeather(weather, hours))
     )
 );
 
+named!(join_party_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("join_party")) >>
+        tag!("()") >>
+        end_line >>
+        (Instruction::JoinParty)
+    )
+);
+
+named!(spawn_chara_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("spawn_chara")) >>
+        chara_id: delimited!(char!('('), ws!(id), char!(')')) >>
+        end_line >>
+        (Instruction::SpawnChara(chara_id))
+    )
+);
+
+named!(uint<CompleteStr, u32>,
+    map_res!(digit1, |s: CompleteStr| u32::from_str(&s))
+);
+
+named!(teleport_site<CompleteStr, Option<(SiteKind, u32, u32)>>,
+    do_parse!(
+        kind: ws!(map_res!(symbol, FromStr::from_str)) >>
+        char!(',') >>
+        n: ws!(uint) >>
+        char!(',') >>
+        floor: ws!(uint) >>
+        (Some((kind, n, floor)))
+    )
+);
+
+named!(teleport_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("teleport")) >>
+        char!('(') >>
+        dest: alt!(ws!(tag!("region")) => { |_| None } | teleport_site) >>
+        char!(')') >>
+        end_line >>
+        (Instruction::Teleport(dest))
+    )
+);
+
+#[test]
+fn event_instruction_test() {
+    use common::gamedata::{Faction, Relationship, Weather};
+    assert_eq!(
+        instruction(CompleteStr("quest_set(rescue, 2)\n")),
+        Ok((CompleteStr(""), Instruction::QuestSet(
+            "rescue".to_owned(), Expr::Value(Value::Int(2))))));
+    assert_eq!(
+        instruction(CompleteStr("move_player(1, 10, 12)\n")),
+        Ok((CompleteStr(""), Instruction::MovePlayer(
+            Expr::Value(Value::Int(1)), Expr::Value(Value::Int(10)), Expr::Value(Value::Int(12))))));
+    assert_eq!(
+        instruction(CompleteStr("make_hostile(bandit)\n")),
+        Ok((CompleteStr(""), Instruction::MakeHostile("bandit".to_owned()))));
+    assert_eq!(
+        instruction(CompleteStr("set_faction_relation(citizen, hostile)\n")),
+        Ok((CompleteStr(""), Instruction::SetFactionRelation(
+            Faction::Citizen, Relationship::HOSTILE))));
+    assert_eq!(
+        instruction(CompleteStr("set_weather(fog, 6)\n")),
+        Ok((CompleteStr(""), Instruction::SetWeather(
+            Weather::Fog, Expr::Value(Value::Int(6))))));
+    assert_eq!(
+        instruction(CompleteStr("join_party()\n")),
+        Ok((CompleteStr(""), Instruction::JoinParty)));
+    assert_eq!(
+        instruction(CompleteStr("spawn_chara(slime)\n")),
+        Ok((CompleteStr(""), Instruction::SpawnChara("slime".to_owned()))));
+    assert_eq!(
+        instruction(CompleteStr("teleport(region)\n")),
+        Ok((CompleteStr(""), Instruction::Teleport(None))));
+    assert_eq!(
+        instruction(CompleteStr("teleport(town, 0, 1)\n")),
+        Ok((CompleteStr(""), Instruction::Teleport(Some((SiteKind::Town, 0, 1))))));
+}
+
+named!(play_sound_instruction<CompleteStr, Instruction>,
+    do_parse!(
+        ws!(tag!("play_sound")) >>
+        sound_id: delimited!(char!('('), ws!(id), char!(')')) >>
+        end_line >>
+        (Instruction::PlaySound(sound_id))
+    )
+);
+
+#[test]
+fn play_sound_instruction_test() {
+    assert_eq!(
+        play_sound_instruction(CompleteStr("play_sound(door_open)\n")),
+        Ok((CompleteStr(""), Instruction::PlaySound("door_open".to_owned()))));
+    assert_eq!(
+        instruction(CompleteStr("play_sound( floor-change )\n")),
+        Ok((CompleteStr(""), Instruction::PlaySound("floor-change".to_owned()))));
+}
+
+#[test]
+fn quest_instruction_test() {
+    assert_eq!(
+        quest_start_instruction(CompleteStr("quest_start(main)\n")),
+        Ok((CompleteStr(""), Instruction::QuestStart("main".to_owned(), None, None, None))));
+    assert_eq!(
+        quest_start_instruction(CompleteStr("quest_start(rescue, 48, rescue-failed)\n")),
+        Ok((CompleteStr(""), Instruction::QuestStart(
+            "rescue".to_owned(),
+            Some(Expr::Value(Value::Int(48))),
+            Some("rescue-failed".to_owned()),
+            None))));
+    assert_eq!(
+        quest_start_instruction(CompleteStr("quest_start(rescue, 48, rescue-failed, rescue-reward)\n")),
+        Ok((CompleteStr(""), Instruction::QuestStart(
+            "rescue".to_owned(),
+            Some(Expr::Value(Value::Int(48))),
+            Some("rescue-failed".to_owned()),
+            Some("rescue-reward".to_owned())))));
+    assert_eq!(
+        quest_complete_instruction(CompleteStr("quest_complete( main )\n")),
+        Ok((CompleteStr(""), Instruction::QuestComplete("main".to_owned()))));
+}
+
+#[test]
+fn talk_random_instruction_test() {
+    let result = Instruction::TalkRandom(
+        vec!["greet_a".to_owned(), "greet-b".to_owned(), "greet_c".to_owned()]);
+    assert_eq!(
+        instruction(CompleteStr("talk_random([greet_a, greet-b, greet_c])\n")),
+        Ok((CompleteStr(""), result)));
+}
+
 #[test]
 fn talk_instruction_test() {
     let result = Instruction::Talk(
@@ -173,35 +576,84 @@ named!(instruction<CompleteStr, Instruction>,
         jump_if_instruction |
         talk_instruction_with_choices |
         talk_instruction |
+        talk_random_instruction |
         gset_instruction |
+        lset_instruction |
         receive_money_instruction |
+        receive_item_instruction |
         remove_item_instruction |
+        modify_stat_instruction |
+        quest_start_instruction |
+        quest_complete_instruction |
+        quest_set_instruction |
+        move_player_instruction |
+        make_hostile_instruction |
+        set_faction_relation_instruction |
+        play_sound_instruction |
+        set_weather_instruction |
+        join_party_instruction |
+        spawn_chara_instruction |
+        teleport_instruction |
         special_instruction
     )
 );
 
-named!(section<CompleteStr, (String, Vec<Instruction>)>,
+named!(section_item<CompleteStr, (Instruction, Option<(String, String)>)>,
+    alt!(
+        map!(talk_instruction_inline, |(i, text)| (i, Some(text))) |
+        map!(instruction, |i| (i, None))
+    )
+);
+
+type Sections = (HashMap<String, Vec<Instruction>>, HashMap<String, String>);
+
+named!(section<CompleteStr, (String, Vec<(Instruction, Option<(String, String)>)>)>,
     do_parse!(
         section: section_start >>
-        instructions: many0!(instruction) >>
+        many0!(comment_line) >>
+        instructions: many0!(terminated!(section_item, many0!(comment_line))) >>
         (section.to_string(), instructions)
     )
 );
 
-named!(sections<CompleteStr, HashMap<String, Vec<Instruction>>>,
-    exact!(fold_many0!(
+named!(sections<CompleteStr, Sections>,
+    exact!(delimited!(many0!(comment_line), fold_many0!(
         section,
-        HashMap::default(),
-        | mut s: HashMap<String, Vec<Instruction>>, section: (String, Vec<Instruction>) | {
-            s.insert(section.0, section.1);
-            s
-        }))
+        (HashMap::default(), HashMap::default()),
+        | (mut s, mut texts): Sections,
+          section: (String, Vec<(Instruction, Option<(String, String)>)>) | {
+            let instructions = section.1.into_iter().map(|(i, text)| {
+                if let Some((text_id, text)) = text {
+                    texts.insert(text_id, text);
+                }
+                i
+            }).collect();
+            s.insert(section.0, instructions);
+            (s, texts)
+        }), opt!(multispace)))
 );
 
 pub fn parse(input: &str) -> Result<Script, PakCompileError> {
-    match sections(CompleteStr(input)) {
+    parse_from_line(input, 1)
+}
+
+/// Parse the script that starts at the given line of its file.
+/// Line numbers in error messages are counted from it.
+pub fn parse_from_line(input: &str, first_line: usize) -> Result<Script, PakCompileError> {
+    let mut expanded = Expanded::default();
+    expand_includes(input, None, &crate::dir::get_src_dir(), first_line, 0, &mut expanded)?;
+    let Expanded { text, lines, .. } = expanded;
+
+    match sections(CompleteStr(&text)) {
         Ok(o) => {
-            Ok(Script::from_map(o.1))
+            let (sections, texts) = o.1;
+            Ok(Script::from_map(sections).with_texts(texts))
+        }
+        Err(nom::Err::Error(nom::Context::Code(rest, _))) |
+        Err(nom::Err::Failure(nom::Context::Code(rest, _))) => {
+            Err(PakCompileError::ScriptParseError {
+                description: error_location(&text, &rest, &lines),
+            })
         }
         Err(e) => {
             Err(PakCompileError::ScriptParseError {
@@ -211,6 +663,83 @@ pub fn parse(input: &str) -> Result<Script, PakCompileError> {
     }
 }
 
+/// Resolved paths of the files included by the script, including nested includes
+pub fn included_files(input: &str) -> Result<Vec<PathBuf>, PakCompileError> {
+    let mut expanded = Expanded::default();
+    expand_includes(input, None, &crate::dir::get_src_dir(), 1, 0, &mut expanded)?;
+    Ok(expanded.files)
+}
+
+/// Source file and line number of each line of the script text after including files
+type SourceLines = Vec<(Option<String>, usize)>;
+
+/// Script text after including files
+#[derive(Default)]
+struct Expanded {
+    text: String,
+    lines: SourceLines,
+    /// Resolved paths of included files
+    files: Vec<PathBuf>,
+}
+
+const MAX_INCLUDE_DEPTH: u32 = 8;
+
+/// Replace `include(file)` lines with the contents of the files.
+/// Relative paths are searched from `dir`, the directory of the file that includes them.
+fn expand_includes(
+    input: &str, file: Option<&str>, dir: &Path, first_line: usize, depth: u32,
+    out: &mut Expanded) -> Result<(), PakCompileError> {
+
+    for (i, line) in input.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("include(") && trimmed.ends_with(')') {
+            let path = trimmed["include(".len()..trimmed.len() - 1].trim();
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(PakCompileError::ScriptParseError {
+                    description: format!("too deep include of \"{}\"", path),
+                });
+            }
+            let full_path = if Path::new(path).is_relative() {
+                dir.join(path)
+            } else {
+                Path::new(path).to_owned()
+            };
+            let included = std::fs::read_to_string(&full_path).map_err(|e| {
+                PakCompileError::ScriptParseError {
+                    description: format!("cannot include \"{}\": {}", path, e),
+                }
+            })?;
+            let included_dir = full_path.parent().unwrap_or(dir).to_owned();
+            out.files.push(full_path);
+            expand_includes(&included, Some(path), &included_dir, 1, depth + 1, out)?;
+        } else {
+            out.text.push_str(line);
+            out.text.push('\n');
+            out.lines.push((file.map(|f| f.to_owned()), first_line + i));
+        }
+    }
+    Ok(())
+}
+
+/// Error message with the line and column of the position where parsing failed
+fn error_location(text: &str, rest: &str, lines: &SourceLines) -> String {
+    let pos = text.len() - rest.trim_start().len();
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let n = text[..line_start].matches('\n').count();
+    let column = text[line_start..pos].chars().count() + 1;
+    let line_text = text[line_start..].lines().next().unwrap_or("").trim();
+
+    match lines.get(n) {
+        Some((Some(file), line)) => {
+            format!("{}:{}:{}: cannot parse \"{}\"", file, line, column, line_text)
+        }
+        Some((None, line)) => {
+            format!("line {}, column {}: cannot parse \"{}\"", line, column, line_text)
+        }
+        None => "unexpected end of the script".to_owned(),
+    }
+}
+
 #[test]
 fn parse_test() {
     let script = r#"--- test_section0
@@ -238,6 +767,72 @@ talk(textid1,
                 vec![("aaa".to_owned(), "bbb".to_owned()), ("ccc".to_owned(), "ddd".to_owned())]),
         ]);
 
-    assert_eq!(sections(CompleteStr(script)), Ok((CompleteStr(""), result)))
+    assert_eq!(sections(CompleteStr(script)), Ok((CompleteStr(""), (result, HashMap::default()))))
+}
+
+#[test]
+fn parse_inline_text_test() {
+    let script = r#"--- start
+talk("""
+  The gate is closed at night.
+  Come back in the morning.
+""")
+"#;
+    let script = parse(script).unwrap();
+    let text_id = match script.section("start")[0] {
+        Instruction::Talk(ref text_id, _) => text_id.clone(),
+        _ => panic!("not a talk instruction"),
+    };
+    assert_eq!(script.text(&text_id),
+               Some("The gate is closed at night.\nCome back in the morning."));
+}
+
+
+#[test]
+fn parse_comment_test() {
+    let script = r#"# Comment before sections
+--- start
+# Comment in a section
+talk(textid0) # Comment after an instruction
+
+jump(end)
+--- end
+talk(textid1)
+"#;
+    let script = parse(script).unwrap();
+    assert_eq!(script.section("start"),
+               &[Instruction::Talk("textid0".to_owned(), vec![]), Instruction::Jump("end".to_owned())]);
+    assert_eq!(script.section("end"), &[Instruction::Talk("textid1".to_owned(), vec![])]);
 }
 
+#[test]
+fn parse_error_location_test() {
+    let script = "--- start\ntalk(textid0)\n  unknown(a)\n";
+    match parse_from_line(script, 2) {
+        Err(PakCompileError::ScriptParseError { description }) => {
+            assert_eq!(description, "line 4, column 3: cannot parse \"unknown(a)\"");
+        }
+        _ => panic!("parse error expected"),
+    }
+}
+
+#[test]
+fn parse_include_test() {
+    let dir = std::env::temp_dir().join("rusted-ruins-makepak-include-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let included = dir.join("included.rrscript");
+    std::fs::write(&included, "--- sub\ntalk(textid1)\n").unwrap();
+
+    std::fs::create_dir_all(dir.join("lib")).unwrap();
+    let nested = dir.join("lib").join("nested.rrscript");
+    std::fs::write(&nested, "--- sub\ntalk(textid1)\n").unwrap();
+    // Nested includes are resolved relative to the included file
+    std::fs::write(&included, "include(lib/nested.rrscript)\n").unwrap();
+
+    let script = format!("--- start\njump(sub)\ninclude({})\n", included.display());
+    assert_eq!(included_files(&script).unwrap(), vec![included.clone(), dir.join("lib/nested.rrscript")]);
+    let script = parse(&script).unwrap();
+    assert_eq!(script.section("sub"), &[Instruction::Talk("textid1".to_owned(), vec![])]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
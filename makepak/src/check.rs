@@ -0,0 +1,309 @@
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use common::gamedata::MapEventTrigger;
+use common::obj::Object;
+use common::script::{Instruction, Script, QUIT_SECTION, CONTINUE_SECTION};
+use rayon::prelude::*;
+use crate::compile::read_object;
+use crate::error::*;
+
+/// Kinds of objects that can be referenced by other objects
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum RefKind {
+    AnimImg,
+    CharaTemplate,
+    Deco,
+    Item,
+    MapTemplate,
+    Script,
+    SiteGen,
+    SpecialTile,
+    Text,
+    Tile,
+    Wall,
+}
+
+impl RefKind {
+    fn name(self) -> &'static str {
+        match self {
+            RefKind::AnimImg => "anim img",
+            RefKind::CharaTemplate => "chara template",
+            RefKind::Deco => "deco",
+            RefKind::Item => "item",
+            RefKind::MapTemplate => "map template",
+            RefKind::Script => "script",
+            RefKind::SiteGen => "site gen",
+            RefKind::SpecialTile => "special tile",
+            RefKind::Text => "text",
+            RefKind::Tile => "tile",
+            RefKind::Wall => "wall",
+        }
+    }
+}
+
+/// Check cross references between objects in the input files without writing a pak file.
+/// Talk text files (*.txt) can be given to resolve text ids.
+/// Returns the number of found problems.
+pub fn check(files: &[&str]) -> usize {
+    let (text_files, obj_files): (Vec<&str>, Vec<&str>) =
+        files.iter().partition(|f| Path::new(f).extension().map_or(false, |e| e == "txt"));
+
+    let results: Vec<Result<Object, Error>> =
+        obj_files.par_iter().map(|f| read_object(Path::new(f))).collect();
+    let mut n_problem = 0;
+
+    let mut index: HashSet<(RefKind, String)> = HashSet::new();
+    for result in &results {
+        if let Ok(obj) = result {
+            if let Some(kind) = object_kind(obj) {
+                index.insert((kind, obj.get_id().to_owned()));
+            }
+            if let Object::Script(ref o) = *obj {
+                for (text_id, _) in o.script.iter_texts() {
+                    index.insert((RefKind::Text, text_id.to_owned()));
+                }
+            }
+        }
+    }
+    for f in &text_files {
+        match read_text_ids(f) {
+            Ok(ids) => index.extend(ids.into_iter().map(|id| (RefKind::Text, id))),
+            Err(e) => {
+                eprintln!("{}: {}", f, e);
+                n_problem += 1;
+            }
+        }
+    }
+    // Text ids cannot be resolved without text files
+    let check_texts = !text_files.is_empty();
+
+    for (f, result) in obj_files.iter().zip(results.iter()) {
+        let obj = match result {
+            Ok(obj) => obj,
+            Err(echain) => {
+                eprintln!("Cannot process \"{}\"", f);
+                for e in echain.iter_chain() {
+                    eprintln!("{}", e);
+                }
+                n_problem += 1;
+                continue;
+            }
+        };
+
+        let mut messages = Vec::new();
+        for (kind, id) in references(obj) {
+            if kind == RefKind::Text && !check_texts {
+                continue;
+            }
+            if !index.contains(&(kind, id.clone())) {
+                messages.push((id.clone(), format!("unknown {} \"{}\"", kind.name(), id)));
+            }
+        }
+        if let Object::Script(ref o) = *obj {
+            for section in missing_sections(&o.script) {
+                messages.push((section.clone(), format!("unknown section \"{}\"", section)));
+            }
+        }
+
+        for (id, message) in messages {
+            if let Some(line) = find_line(f, &id) {
+                eprintln!("{}:{}: {}", f, line, message);
+            } else {
+                eprintln!("{}: {}", f, message);
+            }
+            n_problem += 1;
+        }
+    }
+
+    n_problem
+}
+
+fn object_kind(obj: &Object) -> Option<RefKind> {
+    Some(match *obj {
+        Object::AnimImg(_) => RefKind::AnimImg,
+        Object::CharaTemplate(_) => RefKind::CharaTemplate,
+        Object::Deco(_) => RefKind::Deco,
+        Object::Item(_) => RefKind::Item,
+        Object::MapTemplate(_) => RefKind::MapTemplate,
+        Object::Script(_) => RefKind::Script,
+        Object::SiteGen(_) => RefKind::SiteGen,
+        Object::SpecialTile(_) => RefKind::SpecialTile,
+        Object::Tile(_) => RefKind::Tile,
+        Object::Wall(_) => RefKind::Wall,
+        _ => { return None; }
+    })
+}
+
+/// Ids of other objects referenced by the object
+fn references(obj: &Object) -> Vec<(RefKind, String)> {
+    let mut refs = Vec::new();
+    match *obj {
+        Object::Item(ref o) => {
+            refs.extend(o.mount.iter().map(|mount| (RefKind::CharaTemplate, mount.rider_img.clone())));
+            refs.extend(o.overlay.iter().map(|overlay| (RefKind::AnimImg, overlay.clone())));
+        }
+        Object::Recipe(ref o) => {
+            refs.extend(o.inputs.iter().map(|input| (RefKind::Item, input.id.clone())));
+            refs.push((RefKind::Item, o.output.clone()));
+        }
+        Object::SpawnTable(ref o) => {
+            for group in &o.groups {
+                refs.extend(group.chara.iter().map(|id| (RefKind::CharaTemplate, id.clone())));
+            }
+        }
+        Object::SpecialTile(ref o) => {
+            if let Some(ref resource) = o.resource {
+                refs.extend(resource.tool.iter().map(|tool| (RefKind::Item, tool.clone())));
+                refs.extend(resource.yields.iter().map(|y| (RefKind::Item, y.id.clone())));
+            }
+        }
+        Object::MapTemplate(ref o) => {
+            refs.extend(o.tile_table.iter().map(|id| (RefKind::Tile, id.clone())));
+            refs.extend(o.wall_table.iter().map(|id| (RefKind::Wall, id.clone())));
+            refs.extend(o.deco_table.iter().map(|id| (RefKind::Deco, id.clone())));
+            refs.extend(o.items.iter().map(|(_, item)| (RefKind::Item, item.id.clone())));
+            refs.extend(o.doors.iter().filter_map(|(_, door)| door.key.clone())
+                        .map(|key| (RefKind::Item, key)));
+            refs.extend(o.traps.iter().map(|(_, id)| (RefKind::SpecialTile, id.clone())));
+            for (_, chara) in &o.charas {
+                refs.push((RefKind::CharaTemplate, chara.id.clone()));
+                refs.extend(chara.talk_script_id.iter().map(|id| (RefKind::Script, id.clone())));
+            }
+            for e in &o.events {
+                refs.push((RefKind::Script, e.script_id.clone()));
+                if let MapEventTrigger::Defeated(ref id) = e.trigger {
+                    refs.push((RefKind::CharaTemplate, id.clone()));
+                }
+            }
+        }
+        Object::RegionGen(ref o) => {
+            refs.push((RefKind::MapTemplate, o.map_template_id.clone()));
+            refs.extend(o.towns.iter().map(|(id, _)| (RefKind::SiteGen, id.clone())));
+        }
+        Object::SiteGen(ref o) => {
+            refs.extend(o.map_template_id.iter().map(|id| (RefKind::MapTemplate, id.clone())));
+            for citizen in &o.unique_citizens {
+                refs.push((RefKind::CharaTemplate, citizen.chara_template_id.clone()));
+                refs.extend(citizen.talk_script_id.iter().map(|id| (RefKind::Script, id.clone())));
+            }
+        }
+        Object::Script(ref o) => {
+            for (_, section) in o.script.iter_sections() {
+                for instruction in section {
+                    script_references(instruction, &mut refs);
+                }
+            }
+        }
+        _ => (),
+    }
+    refs
+}
+
+fn script_references(instruction: &Instruction, refs: &mut Vec<(RefKind, String)>) {
+    match instruction {
+        Instruction::Talk(text_id, choices) => {
+            refs.push((RefKind::Text, text_id.clone()));
+            refs.extend(choices.iter().map(|(text_id, _)| (RefKind::Text, text_id.clone())));
+        }
+        Instruction::TalkRandom(text_ids) => {
+            refs.extend(text_ids.iter().map(|text_id| (RefKind::Text, text_id.clone())));
+        }
+        Instruction::ReceiveItem(item_id, _) | Instruction::RemoveItem(item_id, _) => {
+            refs.push((RefKind::Item, item_id.clone()));
+        }
+        Instruction::QuestStart(_, _, on_fail, on_complete) => {
+            refs.extend(on_fail.iter().chain(on_complete.iter())
+                        .map(|id| (RefKind::Script, id.clone())));
+        }
+        Instruction::MakeHostile(id) | Instruction::SpawnChara(id) => {
+            refs.push((RefKind::CharaTemplate, id.clone()));
+        }
+        _ => (),
+    }
+}
+
+/// Sections that are the destinations of jumps or choices but not in the script
+fn missing_sections(script: &Script) -> Vec<String> {
+    let mut missing = Vec::new();
+    for (_, section) in script.iter_sections() {
+        for instruction in section {
+            let dests: Vec<&str> = match instruction {
+                Instruction::Jump(dest) | Instruction::JumpIf(dest, _) => vec![dest],
+                Instruction::Talk(_, choices) => choices.iter().map(|(_, dest)| dest.as_str()).collect(),
+                _ => vec![],
+            };
+            for dest in dests {
+                if dest != QUIT_SECTION && dest != CONTINUE_SECTION && !script.has_section(dest)
+                    && !missing.iter().any(|s| s == dest) {
+                    missing.push(dest.to_owned());
+                }
+            }
+        }
+    }
+    missing
+}
+
+/// Read text ids from a text file. Each text starts with "% text-id" line.
+fn read_text_ids(f: &str) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    for line in BufReader::new(File::open(f)?).lines() {
+        let line = line?;
+        if line.starts_with('%') {
+            ids.push(line[1..].trim().to_owned());
+        }
+    }
+    Ok(ids)
+}
+
+/// Find the first line that includes the id in the file
+fn find_line(f: &str, id: &str) -> Option<usize> {
+    let file = File::open(f).ok()?;
+    BufReader::new(file).lines()
+        .position(|line| line.map_or(false, |line| includes_id(&line, id)))
+        .map(|i| i + 1)
+}
+
+/// The line includes the id not as a part of longer ids
+fn includes_id(line: &str, id: &str) -> bool {
+    let is_id_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_' || c == '.';
+    line.match_indices(id).any(|(i, _)| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + id.len()..].chars().next();
+        !before.map_or(false, is_id_char) && !after.map_or(false, is_id_char)
+    })
+}
+
+#[test]
+fn check_test() {
+    use std::io::Write;
+
+    let dir = std::env::temp_dir().join("rusted-ruins-makepak-check-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, s: &str| {
+        let path = dir.join(name);
+        File::create(&path).unwrap().write_all(s.as_bytes()).unwrap();
+        path.to_string_lossy().into_owned()
+    };
+
+    let valid = write("valid.rrscript", "valid\n--- start\ntalk(text-a)\njump(sub)\n--- sub\ntalk(text-b)\n");
+    let invalid = write("invalid.rrscript", "invalid\n--- start\ntalk(text-a)\njump(missing)\n");
+    let text = write("talk.txt", "% text-a\nA\n\n% text-b\nB\n");
+    let files = [valid.as_str(), text.as_str()];
+    assert_eq!(check(&files), 0);
+    let files = [valid.as_str(), invalid.as_str()];
+    assert_eq!(check(&files), 1);
+    assert_eq!(find_line(&invalid, "missing"), Some(4));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn includes_id_test() {
+    assert!(includes_id("jump(sub)", "sub"));
+    assert!(includes_id("id = \"sub\"", "sub"));
+    assert!(!includes_id("jump(sub-2)", "sub"));
+    assert!(!includes_id("--- subsection", "sub"));
+}
@@ -1,4 +1,5 @@
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::fs::File;
 use std::io::{Read, Write};
@@ -6,46 +7,123 @@ use common::obj::Object;
 use common::pakutil::write_object;
 use toml::de::from_str;
 use tar;
+use rayon::prelude::*;
 use crate::error::*;
 use crate::verbose::print_verbose;
 use crate::dir;
 use crate::rrscript::read_rrscript;
+use crate::manifest::{Manifest, source_hash};
 
 use crate::tomlinput::TomlInput;
 use crate::buildobj::build_object;
 
-pub fn compile(files: &[&str], output_file: &String) {
+/// Compile given files into a pak file.
+/// Inputs unchanged since the last compile are copied from the old pak unless `force` is set.
+pub fn compile(files: &[&str], output_file: &String, force: bool) {
+    let manifest_path = Manifest::path_for(output_file);
+    let (old_manifest, old_data) = if force {
+        (Manifest::default(), HashMap::new())
+    } else {
+        (Manifest::load(&manifest_path), read_pak_data(output_file))
+    };
+
     let out = File::create(output_file).unwrap();
     let mut builder = tar::Builder::new(out);
-    
-    for f in files {
-        let f = Path::new(f);
-        if f.is_relative() {
-            dir::set_src_dir(f.parent());
-        }else{
-            dir::set_src_dir(None);
-        }
 
-        let read_result = if Some(true) == f.extension().map(|e| e == "rrscript") {
-            read_rrscript(f)
-        } else {
-            read_toml(f)
-        };
+    let hashes: Vec<Option<u64>> =
+        files.par_iter().map(|f| source_hash(Path::new(f)).ok()).collect();
+    let old: Vec<Option<(String, Vec<u8>)>> = files.iter().zip(hashes.iter()).map(|(f, hash)| {
+        hash.and_then(|hash| old_manifest.unchanged(f, hash))
+            .and_then(|id| old_data.get(id).map(|v| (id.to_owned(), v.clone())))
+    }).collect();
+    let changed: Vec<&str> = files.iter().zip(old.iter())
+        .filter(|(_, old)| old.is_none()).map(|(f, _)| *f).collect();
+    print_verbose(|| format!("{} of {} files are unchanged", files.len() - changed.len(), files.len()));
+    let mut built = build_files(&changed).into_iter();
+    let results: Vec<Result<(String, Vec<u8>), Error>> = old.into_iter().map(|old| {
+        if let Some(old) = old { Ok(old) } else { built.next().unwrap() }
+    }).collect();
+    let mut manifest = Manifest::default();
+    let mut n_failed = 0;
 
-        let obj = match read_result {
-            Ok(o) => o,
+    for ((f, hash), result) in files.iter().zip(hashes.iter()).zip(results.iter()) {
+        match result {
+            Ok((id, v)) => {
+                write_data_to_tar(&mut builder, v, id);
+                if let Some(hash) = hash {
+                    manifest.insert(f, *hash, id);
+                }
+            }
             Err(echain) => {
-                eprintln!("Cannot process \"{}\"", f.to_string_lossy());
+                eprintln!("Cannot process \"{}\"", f);
                 for e in echain.iter_chain() {
                     eprintln!("{}", e);
                 }
-                continue;
+                n_failed += 1;
             }
-        };
-        let v = write_to_vec(&obj).unwrap();
-        write_data_to_tar(&mut builder, &v, &obj.get_id());
+        }
     }
     builder.finish().unwrap();
+
+    if let Err(e) = manifest.save(&manifest_path) {
+        eprintln!("Cannot write manifest \"{}\": {}", manifest_path.display(), e);
+    }
+
+    if n_failed > 0 {
+        eprintln!("{} of {} files failed to compile", n_failed, files.len());
+    }
+}
+
+/// Read serialized objects in the pak file by their ids.
+/// Returns an empty map if the pak file does not exist.
+fn read_pak_data(pak_file: &str) -> HashMap<String, Vec<u8>> {
+    let mut map = HashMap::new();
+    let file = if let Ok(file) = File::open(pak_file) { file } else { return map; };
+    let mut ar = tar::Archive::new(file);
+    let entries = if let Ok(entries) = ar.entries() { entries } else { return map; };
+
+    for entry in entries {
+        let mut entry = if let Ok(entry) = entry { entry } else { return HashMap::new(); };
+        let id = if let Ok(path) = entry.path() {
+            path.to_string_lossy().into_owned()
+        } else {
+            continue;
+        };
+        let mut v = Vec::new();
+        if entry.read_to_end(&mut v).is_err() {
+            return HashMap::new();
+        }
+        map.insert(id, v);
+    }
+    map
+}
+
+/// Build given files on the thread pool.
+/// Results are in the same order as the input files.
+fn build_files(files: &[&str]) -> Vec<Result<(String, Vec<u8>), Error>> {
+    files.par_iter().map(|f| build_file(Path::new(f))).collect()
+}
+
+/// Build an object from the file, and returns its id and serialized data
+fn build_file(f: &Path) -> Result<(String, Vec<u8>), Error> {
+    let obj = read_object(f)?;
+    let v = write_to_vec(&obj)?;
+    Ok((obj.get_id().to_owned(), v))
+}
+
+/// Read an object from a toml or rrscript file
+pub fn read_object(f: &Path) -> Result<Object, Error> {
+    if f.is_relative() {
+        dir::set_src_dir(f.parent());
+    }else{
+        dir::set_src_dir(None);
+    }
+
+    if Some(true) == f.extension().map(|e| e == "rrscript") {
+        read_rrscript(f)
+    } else {
+        read_toml(f)
+    }
 }
 
 fn read_toml<P: AsRef<Path>>(path: P) -> Result<Object, Error> {
@@ -83,3 +161,38 @@ fn write_data_to_tar<W: Write>(builder: &mut tar::Builder<W>, data: &[u8], path:
     builder.append(&header, data).unwrap();
 }
 
+
+#[test]
+fn build_files_test() {
+    let dir = std::env::temp_dir().join("rusted-ruins-makepak-build-files-test");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut paths = Vec::new();
+    for i in 0..16 {
+        let path = dir.join(format!("test{}.rrscript", i));
+        let mut f = File::create(&path).unwrap();
+        write!(f, "test-script{}\n--- start\ntalk(text{})\njump(sub)\n--- sub\ntalk(text-sub)\n", i, i)
+            .unwrap();
+        paths.push(path.to_string_lossy().into_owned());
+    }
+    let invalid = dir.join("invalid.rrscript");
+    File::create(&invalid).unwrap().write_all(b"invalid-script\n--- start\nunknown(a)\n").unwrap();
+    paths.insert(5, invalid.to_string_lossy().into_owned());
+    let files: Vec<&str> = paths.iter().map(|p| p.as_ref()).collect();
+
+    let single_thread: Vec<_> = files.iter().map(|f| build_file(Path::new(f))).collect();
+    let parallel = build_files(&files);
+
+    assert_eq!(parallel.len(), files.len());
+    assert!(parallel[5].is_err());
+    assert!(single_thread[5].is_err());
+    for (a, b) in single_thread.iter().zip(parallel.iter()) {
+        match (a, b) {
+            (Ok(a), Ok(b)) => assert_eq!(a, b),
+            (Err(_), Err(_)) => (),
+            _ => panic!("results differ"),
+        }
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
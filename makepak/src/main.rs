@@ -15,6 +15,9 @@ mod verbose;
 mod tomlinput;
 mod buildobj;
 mod compile;
+mod check;
+mod manifest;
+mod unpack;
 mod rrscript;
 mod dir;
 mod error;
@@ -35,7 +38,23 @@ fn main() {
 
     // Print infomation of pak files
     if matches.is_present("info") {
-        print_info(&files);
+        unpack::print_info(&files);
+        return;
+    }
+
+    // Extract objects in pak files
+    if let Some(dir) = matches.value_of("unpack") {
+        unpack::unpack(&files, dir);
+        return;
+    }
+
+    // Check references between objects without writing a pak file
+    if matches.is_present("check") {
+        let n_problem = check::check(&files);
+        if n_problem > 0 {
+            eprintln!("{} problems found", n_problem);
+            std::process::exit(1);
+        }
         return;
     }
 
@@ -47,11 +66,7 @@ fn main() {
         f
     };
 
-    compile::compile(&files, &output_file);
-}
-
-fn print_info(files: &[&str]) {
-    println!("Infomation of {:?} will be printed", files);
+    compile::compile(&files, &output_file, matches.is_present("force"));
 }
 
 fn create_matches() -> clap::ArgMatches<'static> {
@@ -66,7 +81,21 @@ fn create_matches() -> clap::ArgMatches<'static> {
         .arg(Arg::with_name("info")
              .short("i")
              .long("info")
-             .help("Print given pak file information"))
+             .help("List objects in given pak files with their types and sizes"))
+        .arg(Arg::with_name("unpack")
+             .short("u")
+             .long("unpack")
+             .value_name("DIR")
+             .help("Extract objects in given pak files to toml and png files in the directory")
+             .takes_value(true))
+        .arg(Arg::with_name("check")
+             .short("c")
+             .long("check")
+             .help("Check references between objects. Talk text files (*.txt) can be given with inputs"))
+        .arg(Arg::with_name("force")
+             .short("f")
+             .long("force")
+             .help("Rebuild all objects even if their sources are unchanged"))
         .arg(Arg::with_name("output")
              .short("o")
              .long("output")
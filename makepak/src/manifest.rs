@@ -0,0 +1,146 @@
+
+use fnv::FnvHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use toml::de::from_str;
+use crate::buildobj::script_included_files;
+use crate::dir;
+use crate::error::*;
+use crate::tomlinput::TomlInput;
+
+/// Mixed into source hashes. Increase it when compiled objects change for the same sources,
+/// so that manifests saved by older makepak do not skip recompiling.
+const FORMAT_VERSION: u64 = 8;
+
+/// Manifest records hashes of source files for each input of a pak.
+/// It is saved beside the pak, and unchanged inputs are not recompiled.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// Input file path -> (source hash, object id)
+    entries: HashMap<String, (u64, String)>,
+}
+
+impl Manifest {
+    /// Manifest file path for the pak file
+    pub fn path_for(output_file: &str) -> PathBuf {
+        PathBuf::from(format!("{}.manifest", output_file))
+    }
+
+    /// Load manifest. Returns an empty manifest if the file does not exist or is broken.
+    pub fn load<P: AsRef<Path>>(path: P) -> Manifest {
+        let mut manifest = Manifest::default();
+        let file = if let Ok(file) = File::open(path) { file } else { return manifest; };
+
+        for line in BufReader::new(file).lines() {
+            let line = if let Ok(line) = line { line } else { return Manifest::default(); };
+            let mut cols = line.splitn(3, '\t');
+            let hash = cols.next().and_then(|s| u64::from_str_radix(s, 16).ok());
+            let id = cols.next();
+            let input = cols.next();
+            match (hash, id, input) {
+                (Some(hash), Some(id), Some(input)) => {
+                    manifest.entries.insert(input.to_owned(), (hash, id.to_owned()));
+                }
+                _ => { return Manifest::default(); }
+            }
+        }
+        manifest
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort();
+        let mut file = File::create(path)?;
+        for (input, (hash, id)) in entries {
+            writeln!(file, "{:016x}\t{}\t{}", hash, id, input)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the object id if the input is unchanged since the last compile
+    pub fn unchanged(&self, input: &str, hash: u64) -> Option<&str> {
+        match self.entries.get(input) {
+            Some((h, id)) if *h == hash => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, input: &str, hash: u64, id: &str) {
+        self.entries.insert(input.to_owned(), (hash, id.to_owned()));
+    }
+}
+
+/// Hash the input file and the image and script files used by it
+pub fn source_hash(f: &Path) -> Result<u64, Error> {
+    // FNV is used because the hash must be stable between builds of makepak
+    let mut hasher = FnvHasher::default();
+    hasher.write_u64(FORMAT_VERSION);
+    let mut s = String::new();
+    File::open(f)?.read_to_string(&mut s)?;
+    hasher.write(s.as_bytes());
+
+    // Paths are resolved in the same way as compiling
+    if f.is_relative() {
+        dir::set_src_dir(f.parent());
+    } else {
+        dir::set_src_dir(None);
+    }
+    // An input that cannot be parsed will fail to compile anyway
+    let (image, script) = if Some(true) == f.extension().map(|e| e == "rrscript") {
+        // The first line is the object id
+        (None, s.splitn(2, '\n').nth(1).map(|s| s.to_owned()))
+    } else if let Ok(tomlinput) = from_str::<TomlInput>(&s) {
+        (tomlinput.image.map(|image| image.path), tomlinput.script.map(|script| script.script))
+    } else {
+        (None, None)
+    };
+    let mut dep_files = Vec::new();
+    if let Some(image) = image {
+        let path = Path::new(&image);
+        dep_files.push(if path.is_relative() { dir::path_from_src_dir(path) } else { path.to_owned() });
+    }
+    if let Some(script) = script {
+        dep_files.extend(script_included_files(&script)?);
+    }
+    for path in dep_files {
+        let mut v = Vec::new();
+        File::open(path)?.read_to_end(&mut v)?;
+        hasher.write(&v);
+    }
+    Ok(hasher.finish())
+}
+
+#[test]
+fn manifest_test() {
+    let dir = std::env::temp_dir().join("rusted-ruins-makepak-manifest-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("test.rrscript");
+    File::create(&input).unwrap().write_all(b"test\n--- start\ntalk(text)\n").unwrap();
+    let hash = source_hash(&input).unwrap();
+    let input = input.to_string_lossy().into_owned();
+
+    let mut manifest = Manifest::default();
+    manifest.insert(&input, hash, "test");
+    let path = Manifest::path_for(&dir.join("test.pak").to_string_lossy());
+    manifest.save(&path).unwrap();
+    let manifest = Manifest::load(&path);
+    assert_eq!(manifest.unchanged(&input, hash), Some("test"));
+
+    File::create(&input).unwrap().write_all(b"test\n--- start\ntalk(changed)\n").unwrap();
+    let hash = source_hash(Path::new(&input)).unwrap();
+    assert_eq!(manifest.unchanged(&input, hash), None);
+
+    // Changes of included files are detected
+    let sub = dir.join("sub.rrscript");
+    File::create(&sub).unwrap().write_all(b"--- sub\ntalk(a)\n").unwrap();
+    let script = format!("test\n--- start\njump(sub)\ninclude({})\n", sub.display());
+    File::create(&input).unwrap().write_all(script.as_bytes()).unwrap();
+    let hash = source_hash(Path::new(&input)).unwrap();
+    File::create(&sub).unwrap().write_all(b"--- sub\ntalk(b)\n").unwrap();
+    assert_ne!(source_hash(Path::new(&input)).unwrap(), hash);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
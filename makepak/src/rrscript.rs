@@ -5,7 +5,7 @@ use std::path::Path;
 use common::obj::{Object, ScriptObject};
 use crate::error::*;
 use crate::verbose::print_verbose;
-use crate::buildobj::script_parse;
+use crate::buildobj::script_parse_from_line;
 
 /// Read rrscript file
 pub fn read_rrscript<P: AsRef<Path>>(path: P) -> Result<Object, Error> {
@@ -19,7 +19,8 @@ pub fn read_rrscript<P: AsRef<Path>>(path: P) -> Result<Object, Error> {
     print_verbose(|| format!("Processing \"{:?}\"", path.as_ref()));
 
     let object_id = first_line.trim().to_owned();
-    let script =  script_parse(&script_text)?;
+    // The first line is the object id
+    let script = script_parse_from_line(&script_text, 2)?;
 
     Ok(Object::Script(ScriptObject {
         id: object_id,
@@ -1,6 +1,7 @@
 
 use array2d::Vec2d;
 use common::gamedata::{self, ElementArray};
+use common::obj::{ResourceParams, TrapParams};
 use common::sitegen;
 
 #[derive(Debug, Deserialize)]
@@ -8,8 +9,12 @@ pub struct TomlInput {
     pub object_type: String,
     pub id: String,
     pub image: Option<ImgInput>,
+    pub ability: Option<AbilityDepInput>,
     pub chara_template: Option<CharaTemplateDepInput>,
     pub item: Option<ItemDepInput>,
+    pub recipe: Option<RecipeDepInput>,
+    pub spawn_table: Option<SpawnTableDepInput>,
+    pub deco: Option<DecoDepInput>,
     pub tile: Option<TileDepInput>,
     pub wall: Option<WallDepInput>,
     pub special_tile: Option<SpecialTileDepInput>,
@@ -42,10 +47,23 @@ pub struct ImgInput {
     pub n_pattern: Option<u32>,
     pub n_anim_frame: Option<u32>,
     pub duration: Option<u32>,
+    /// 4 for character images that have frames for each facing direction
+    pub n_direction: Option<u32>,
 }
 
 // Type dependent fields
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AbilityDepInput {
+    pub cost_mp: i32,
+    pub range: u32,
+    pub effect: gamedata::AbilityEffect,
+    pub element: Option<gamedata::Element>,
+    pub dice_n: u16,
+    pub dice_x: u16,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct CharaTemplateDepInput {
@@ -61,6 +79,15 @@ pub struct CharaTemplateDepInput {
     pub wil: u16,
     pub cha: u16,
     pub spd: u16, 
+    pub skills: Option<Vec<SkillLevelInput>>,
+    pub movements: Option<Vec<::common::obj::Movement>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkillLevelInput {
+    pub kind: gamedata::SkillKind,
+    pub lv: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,12 +100,22 @@ pub struct TileDepInput {
 #[serde(deny_unknown_fields)]
 pub struct WallDepInput {
     pub base_draw: Option<bool>,
+    /// Walls without hardness cannot be dug
+    pub hardness: Option<u16>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SpecialTileDepInput {
     pub always_background: Option<bool>,
+    pub resource: Option<ResourceParams>,
+    pub trap: Option<TrapParams>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DecoDepInput {
+    pub light: Option<u8>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -99,6 +136,38 @@ pub struct ItemDepInput {
     /// For armor items
     pub def: Option<ElementArray<u16>>,
     pub nutrition: Option<u16>,
+    /// Light radius for torches and lanterns
+    pub light: Option<u8>,
+    /// Capacity for chests and bags
+    pub container: Option<gamedata::item::ContainerParams>,
+    /// Range of initial charges for wands and tools
+    pub charge: Option<(u32, u32)>,
+    pub has_quality: Option<bool>,
+    pub materials: Option<Vec<gamedata::item::MaterialKind>>,
+    /// Travel parameters for horses and boats
+    pub mount: Option<gamedata::item::MountParams>,
+    /// Digging power for pickaxes and shovels
+    pub dig_power: Option<u16>,
+    /// Anim image id drawn over characters equipping this item
+    pub overlay: Option<String>,
+    /// Skill learned by reading books and scrolls
+    pub learn_skill: Option<gamedata::SkillKind>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnTableDepInput {
+    pub groups: Vec<gamedata::SpawnGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecipeDepInput {
+    pub inputs: Vec<gamedata::RecipeInput>,
+    pub skill: Option<gamedata::SkillKind>,
+    pub skill_level: Option<u32>,
+    pub output: String,
+    pub n_output: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -122,6 +191,7 @@ pub struct SiteGenDepInput {
     pub map_template_id: Vec<String>,
     pub unique_citizens: Option<Vec<sitegen::UniqueCitizenGenData>>,
     pub shops: Option<Vec<sitegen::ShopGenData>>,
+    pub weather: Option<gamedata::weather::Weather>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,196 @@
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use common::obj::*;
+use common::pakutil;
+use tar;
+use crate::error::*;
+
+/// Object type name used in toml inputs
+fn object_type(obj: &Object) -> &'static str {
+    match *obj {
+        Object::Ability(_) => "ability",
+        Object::AnimImg(_) => "anim_img",
+        Object::CharaTemplate(_) => "chara_template",
+        Object::Deco(_) => "deco",
+        Object::Effect(_) => "effect",
+        Object::Item(_) => "item",
+        Object::Recipe(_) => "recipe",
+        Object::SpawnTable(_) => "spawn_table",
+        Object::SpecialTile(_) => "special_tile",
+        Object::Tile(_) => "tile",
+        Object::UIImg(_) => "ui_img",
+        Object::Wall(_) => "wall",
+        Object::MapTemplate(_) => "map_template",
+        Object::RegionGen(_) => "region_gen",
+        Object::Script(_) => "script",
+        Object::SiteGen(_) => "site_gen",
+    }
+}
+
+fn get_img(obj: &Object) -> Option<&Img> {
+    Some(match *obj {
+        Object::AnimImg(ref o) => &o.img,
+        Object::CharaTemplate(ref o) => &o.img,
+        Object::Deco(ref o) => &o.img,
+        Object::Effect(ref o) => &o.img,
+        Object::Item(ref o) => &o.img,
+        Object::SpecialTile(ref o) => &o.img,
+        Object::Tile(ref o) => &o.img,
+        Object::UIImg(ref o) => &o.img,
+        Object::Wall(ref o) => &o.img,
+        _ => { return None; }
+    })
+}
+
+fn object_fields(obj: &Object) -> Result<toml::Value, toml::ser::Error> {
+    match *obj {
+        Object::Ability(ref o) => toml::Value::try_from(o),
+        Object::AnimImg(ref o) => toml::Value::try_from(o),
+        Object::CharaTemplate(ref o) => toml::Value::try_from(o),
+        Object::Deco(ref o) => toml::Value::try_from(o),
+        Object::Effect(ref o) => toml::Value::try_from(o),
+        Object::Item(ref o) => toml::Value::try_from(o),
+        Object::Recipe(ref o) => toml::Value::try_from(o),
+        Object::SpawnTable(ref o) => toml::Value::try_from(o),
+        Object::SpecialTile(ref o) => toml::Value::try_from(o),
+        Object::Tile(ref o) => toml::Value::try_from(o),
+        Object::UIImg(ref o) => toml::Value::try_from(o),
+        Object::Wall(ref o) => toml::Value::try_from(o),
+        Object::MapTemplate(ref o) => toml::Value::try_from(o),
+        Object::RegionGen(ref o) => toml::Value::try_from(o),
+        Object::Script(ref o) => toml::Value::try_from(o),
+        Object::SiteGen(ref o) => toml::Value::try_from(o),
+    }
+}
+
+/// Read objects and their serialized sizes in the pak file
+fn read_pak(pak_file: &str) -> Result<Vec<(Object, u64)>, Error> {
+    let mut ar = tar::Archive::new(File::open(pak_file)?);
+    let mut objs = Vec::new();
+    for entry in ar.entries()? {
+        let entry = entry?;
+        let size = entry.header().size()?;
+        let obj = pakutil::read_object(entry)?;
+        objs.push((obj, size));
+    }
+    Ok(objs)
+}
+
+/// List objects in pak files with their types and sizes
+pub fn print_info(files: &[&str]) {
+    for f in files {
+        let objs = match read_pak(f) {
+            Ok(objs) => objs,
+            Err(e) => {
+                eprintln!("Cannot read \"{}\": {}", f, e);
+                continue;
+            }
+        };
+        println!("{} ({} objects)", f, objs.len());
+        for (obj, size) in &objs {
+            println!("  {:<16}{:<32}{:>10} bytes", object_type(obj), obj.get_id(), size);
+        }
+    }
+}
+
+/// Extract objects in pak files to the directory.
+/// Images are written to png files, and the other fields of objects to toml files.
+/// Type dependent fields are written as the object has them,
+/// so they may need editing before compiling the toml again.
+pub fn unpack(files: &[&str], dir: &str) {
+    let dir = Path::new(dir);
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Cannot create \"{}\": {}", dir.display(), e);
+        return;
+    }
+
+    for f in files {
+        let objs = match read_pak(f) {
+            Ok(objs) => objs,
+            Err(e) => {
+                eprintln!("Cannot read \"{}\": {}", f, e);
+                continue;
+            }
+        };
+        for (obj, _) in &objs {
+            if let Err(e) = unpack_object(obj, dir) {
+                eprintln!("Cannot extract \"{}\": {}", obj.get_id(), e);
+            }
+        }
+    }
+}
+
+fn unpack_object(obj: &Object, dir: &Path) -> Result<(), Error> {
+    let id = obj.get_id();
+    let mut table = toml::value::Table::new();
+    table.insert("object_type".into(), object_type(obj).into());
+    table.insert("id".into(), id.into());
+
+    if let Some(img) = get_img(obj) {
+        let png_file = format!("{}.png", id);
+        File::create(dir.join(&png_file))?.write_all(&img.data)?;
+        let mut image = toml::value::Table::new();
+        image.insert("path".into(), png_file.into());
+        for &(key, value) in &[
+            ("w", img.w), ("h", img.h), ("grid_nx", img.grid_nx), ("grid_ny", img.grid_ny),
+            ("n_frame", img.n_frame), ("n_pattern", img.n_pattern),
+            ("n_anim_frame", img.n_anim_frame), ("duration", img.duration),
+            ("n_direction", img.n_direction)] {
+            image.insert(key.into(), toml::Value::Integer(value.into()));
+        }
+        table.insert("image".into(), toml::Value::Table(image));
+    }
+
+    // Some fields like script instructions cannot be represented in toml
+    let fields = object_fields(obj).and_then(|fields| {
+        let mut fields = if let toml::Value::Table(fields) = fields { fields } else { return Ok(None); };
+        fields.remove("id");
+        fields.remove("img");
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        let fields = toml::Value::Table(fields);
+        toml::to_string(&fields)?;
+        Ok(Some(fields))
+    });
+    match fields {
+        Ok(Some(fields)) => {
+            table.insert(object_type(obj).into(), fields);
+        }
+        Ok(None) => (),
+        Err(e) => eprintln!("Fields of \"{}\" are not extracted: {}", id, e),
+    }
+
+    let s = toml::to_string(&toml::Value::Table(table))?;
+    File::create(dir.join(format!("{}.toml", id)))?.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+#[test]
+fn unpack_test() {
+    use std::io::Read;
+
+    let dir = std::env::temp_dir().join("rusted-ruins-makepak-unpack-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let obj = Object::UIImg(UIImgObject {
+        id: "test-img".to_owned(),
+        img: Img {
+            data: vec![1, 2, 3], w: 1, h: 1, grid_nx: 1, grid_ny: 1,
+            n_frame: 1, n_pattern: 1, n_anim_frame: 1, duration: 0, n_direction: 1,
+        },
+    });
+    unpack_object(&obj, &dir).unwrap();
+
+    let mut data = Vec::new();
+    File::open(dir.join("test-img.png")).unwrap().read_to_end(&mut data).unwrap();
+    assert_eq!(data, vec![1, 2, 3]);
+    let mut s = String::new();
+    File::open(dir.join("test-img.toml")).unwrap().read_to_string(&mut s).unwrap();
+    let value: toml::Value = toml::from_str(&s).unwrap();
+    assert_eq!(value["object_type"].as_str(), Some("ui_img"));
+    assert_eq!(value["image"]["path"].as_str(), Some("test-img.png"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
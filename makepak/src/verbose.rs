@@ -1,19 +1,17 @@
 
-use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-thread_local!(static IS_VERBOSE: Cell<bool> = Cell::new(false));
+/// Shared by all compiling threads
+static IS_VERBOSE: AtomicBool = AtomicBool::new(false);
 
 pub fn set_verbose(is_verbose: bool) {
-    IS_VERBOSE.with(|a| {
-        a.set(is_verbose);
-    });
+    IS_VERBOSE.store(is_verbose, Ordering::Relaxed);
 }
 
 pub fn print_verbose<F: FnOnce() -> String>(f: F) {
-    let is_verbose = IS_VERBOSE.with(|a| { a.get() });
+    let is_verbose = IS_VERBOSE.load(Ordering::Relaxed);
     if !is_verbose { return; }
     
     let s = f();
     println!("{}", s);
 }
-
@@ -5,30 +5,136 @@ use common::objholder::*;
 use common::gobj;
 use common::piece_pattern::*;
 use common::obj::ImgObject;
-use cairo::Context;
+use cairo::{Context, Format, ImageSurface};
 use gdk::prelude::ContextExt;
 use gdk_pixbuf::{Pixbuf, PixbufExt};
 use crate::pixbuf_holder::PixbufHolder;
-use crate::edit_map::EditingMap;
+use crate::edit_map::{EditingMap, LayerVisible};
+
+/// Tiles rendered around the visible area to reuse the rendered image on scrolling
+const CACHE_MARGIN: i32 = 8;
+/// Images of walls, decos and charas can overlap this number of tiles above
+pub const MAX_OVERHANG: i32 = 2;
+
+/// Rendered image of the map around the visible area.
+/// Scrolling within the cached area reuses it, and only changed tiles are rendered again.
+pub struct MapCache {
+    surface: Option<ImageSurface>,
+    /// Map position of the top left of the surface
+    origin: (i32, i32),
+    n_tiles: (i32, i32),
+    zoom: f64,
+    /// Tiles changed since the last rendering
+    dirty: Vec<(i32, i32)>,
+}
+
+impl MapCache {
+    pub fn new() -> MapCache {
+        MapCache { surface: None, origin: (0, 0), n_tiles: (0, 0), zoom: 1.0, dirty: Vec::new() }
+    }
+
+    /// The whole image will be rendered again
+    pub fn invalidate(&mut self) {
+        self.surface = None;
+        self.dirty.clear();
+    }
+
+    pub fn set_dirty(&mut self, pos: (i32, i32)) {
+        self.dirty.push(pos);
+    }
+
+    fn covers(&self, pos: (i32, i32), nx: i32, ny: i32, zoom: f64) -> bool {
+        self.surface.is_some() && self.zoom == zoom &&
+            pos.0 >= self.origin.0 && pos.1 >= self.origin.1 &&
+            pos.0 + nx <= self.origin.0 + self.n_tiles.0 && pos.1 + ny <= self.origin.1 + self.n_tiles.1
+    }
+
+    fn render_all(&mut self, map: &EditingMap, pbh: &PixbufHolder, pos: (i32, i32), nx: i32, ny: i32,
+                  zoom: f64, layer_visible: LayerVisible) {
+        let origin = (pos.0 - CACHE_MARGIN, pos.1 - CACHE_MARGIN);
+        let n_tiles = (nx + CACHE_MARGIN * 2, ny + CACHE_MARGIN * 2);
+        let ts = TILE_SIZE_I as f64 * zoom;
+        self.surface = ImageSurface::create(
+            Format::ARgb32, (n_tiles.0 as f64 * ts).ceil() as i32, (n_tiles.1 as f64 * ts).ceil() as i32).ok();
+        if let Some(surface) = self.surface.as_ref() {
+            let cr = Context::new(surface);
+            cr.scale(zoom, zoom);
+            draw_tiles(&cr, map, pbh, origin, (0, 0), n_tiles, layer_visible);
+        }
+        self.origin = origin;
+        self.n_tiles = n_tiles;
+        self.zoom = zoom;
+    }
+
+    /// Render the rectangle including changed tiles and the images overlapping them
+    fn render_dirty(&mut self, map: &EditingMap, pbh: &PixbufHolder, layer_visible: LayerVisible) {
+        let surface = if let Some(surface) = self.surface.as_ref() { surface } else { return; };
+        let origin = self.origin;
+        let xmin = self.dirty.iter().map(|p| p.0 - origin.0).min().unwrap();
+        let xmax = self.dirty.iter().map(|p| p.0 - origin.0).max().unwrap();
+        let ymin = self.dirty.iter().map(|p| p.1 - origin.1).min().unwrap() - MAX_OVERHANG;
+        let ymax = self.dirty.iter().map(|p| p.1 - origin.1).max().unwrap();
+
+        let cr = Context::new(surface);
+        cr.scale(self.zoom, self.zoom);
+        cr.rectangle((xmin * TILE_SIZE_I) as f64, (ymin * TILE_SIZE_I) as f64,
+                     ((xmax - xmin + 1) * TILE_SIZE_I) as f64, ((ymax - ymin + 1) * TILE_SIZE_I) as f64);
+        cr.clip();
+        // Images on the tiles below can overlap the rectangle
+        draw_tiles(&cr, map, pbh, origin, (xmin, ymin),
+                   (xmax - xmin + 1, ymax - ymin + 1 + MAX_OVERHANG), layer_visible);
+    }
+}
 
 /// Draw tiles and objects on map
-pub fn draw_map(cr: &Context, map: &EditingMap, pbh: &PixbufHolder,
-                width: i32, height: i32, pos: (i32, i32), layer_visible: [bool; N_TILE_IMG_LAYER]) {
-    
-    let tile_nx = width / TILE_SIZE_I + 1;
-    let tile_ny = height / TILE_SIZE_I + 1;
+pub fn draw_map(cr: &Context, map: &EditingMap, pbh: &PixbufHolder, cache: &mut MapCache,
+                width: i32, height: i32, pos: (i32, i32), zoom: f64, layer_visible: LayerVisible,
+                grid_visible: bool, selection: Option<((i32, i32), (i32, i32))>) {
+    let ts = TILE_SIZE_I as f64 * zoom;
+    let tile_nx = (width as f64 / ts) as i32 + 1;
+    let tile_ny = (height as f64 / ts) as i32 + 1;
+
+    if !cache.covers(pos, tile_nx, tile_ny, zoom) {
+        cache.render_all(map, pbh, pos, tile_nx, tile_ny, zoom, layer_visible);
+    } else if !cache.dirty.is_empty() {
+        cache.render_dirty(map, pbh, layer_visible);
+    }
+    cache.dirty.clear();
 
     // Clear drawing area
     cr.set_source_rgb(0.5, 0.5, 0.5);
     cr.paint();
 
-    for iy in 0..tile_ny {
-        for ix in 0..tile_nx {
-            let p = Vec2d(ix + pos.0, iy + pos.1);
-            if p.0 >= map.width as i32 || p.1 >= map.height as i32 { continue; }
+    if let Some(surface) = cache.surface.as_ref() {
+        cr.set_source_surface(
+            surface, (cache.origin.0 - pos.0) as f64 * ts, (cache.origin.1 - pos.1) as f64 * ts);
+        cr.paint();
+    }
+
+    draw_events(cr, map, pos, ts);
+    if grid_visible {
+        draw_grid(cr, map, tile_nx, tile_ny, pos, ts);
+    }
+    if let Some((top_left, bottom_right)) = selection {
+        draw_selection(cr, top_left, bottom_right, pos, ts);
+    }
+}
+
+/// Draw n tiles from start. Positions are relative to origin of the map.
+fn draw_tiles(cr: &Context, map: &EditingMap, pbh: &PixbufHolder, origin: (i32, i32),
+              start: (i32, i32), n: (i32, i32), layer_visible: LayerVisible) {
+    cr.set_source_rgb(0.5, 0.5, 0.5);
+    cr.rectangle((start.0 * TILE_SIZE_I) as f64, (start.1 * TILE_SIZE_I) as f64,
+                 (n.0 * TILE_SIZE_I) as f64, (n.1 * TILE_SIZE_I) as f64);
+    cr.fill();
+
+    for iy in start.1..(start.1 + n.1) {
+        for ix in start.0..(start.0 + n.0) {
+            let p = Vec2d(ix + origin.0, iy + origin.1);
+            if p.0 < 0 || p.1 < 0 || p.0 >= map.width as i32 || p.1 >= map.height as i32 { continue; }
 
             for i_tile in 0..N_TILE_IMG_LAYER {
-                if !layer_visible[i_tile] {
+                if !layer_visible.tile[i_tile] {
                     continue;
                 }
                 if let Some((idx, pp)) = map.tile[p][i_tile].get() {
@@ -38,7 +144,7 @@ pub fn draw_map(cr: &Context, map: &EditingMap, pbh: &PixbufHolder,
             }
 
             // Draw wall
-            if !map.wall[p].is_empty() {
+            if layer_visible.wall && !map.wall[p].is_empty() {
                 draw_wall_pieces(
                     cr, pbh,
                     map.wall[p].idx().unwrap(),
@@ -47,7 +153,7 @@ pub fn draw_map(cr: &Context, map: &EditingMap, pbh: &PixbufHolder,
             }
 
             // Draw deco
-            if let Some(deco_idx) = map.deco[p] {
+            if let Some(deco_idx) = map.deco[p].filter(|_| layer_visible.deco) {
                 let pixbuf = &pbh.get(deco_idx).image;
                 let height = pixbuf.get_height();
                 cr.set_source_pixbuf(pixbuf,
@@ -55,6 +161,115 @@ pub fn draw_map(cr: &Context, map: &EditingMap, pbh: &PixbufHolder,
                                      (iy * TILE_SIZE_I - height + TILE_SIZE_I) as f64);
                 cr.paint();
             }
+
+            // Draw door. Doors are in the wall layer.
+            if layer_visible.wall && map.doors[p].is_some() {
+                if let Some(idx) = gobj::id_to_idx_checked::<SpecialTileIdx>("!door-closed") {
+                    let pixbuf = &pbh.get(idx).icon;
+                    cr.set_source_pixbuf(pixbuf, (ix * TILE_SIZE_I) as f64, (iy * TILE_SIZE_I) as f64);
+                    cr.paint();
+                }
+            }
+
+            // Draw trap
+            if let Some(trap) = map.traps[p].as_ref() {
+                if let Some(idx) = gobj::id_to_idx_checked::<SpecialTileIdx>(trap) {
+                    let pixbuf = &pbh.get(idx).icon;
+                    cr.set_source_pixbuf(pixbuf, (ix * TILE_SIZE_I) as f64, (iy * TILE_SIZE_I) as f64);
+                    cr.paint();
+                }
+            }
+
+            // Draw chara
+            if let Some(chara_gen) = map.charas[p].as_ref() {
+                if let Some(idx) = gobj::id_to_idx_checked::<CharaTemplateIdx>(&chara_gen.id) {
+                    let pixbuf = &pbh.get(idx).icon;
+                    let height = pixbuf.get_height();
+                    cr.set_source_pixbuf(pixbuf,
+                                         (ix * TILE_SIZE_I) as f64,
+                                         (iy * TILE_SIZE_I - height + TILE_SIZE_I) as f64);
+                    cr.paint();
+                }
+            }
+        }
+    }
+}
+
+/// Draw event regions with their script ids
+fn draw_events(cr: &Context, map: &EditingMap, pos: (i32, i32), ts: f64) {
+    cr.set_line_width(1.0);
+    cr.set_font_size(10.0);
+    for e in &map.events {
+        let x = (e.top_left.0 - pos.0) as f64 * ts + 0.5;
+        let y = (e.top_left.1 - pos.1) as f64 * ts + 0.5;
+        let w = (e.bottom_right.0 - e.top_left.0 + 1) as f64 * ts - 1.0;
+        let h = (e.bottom_right.1 - e.top_left.1 + 1) as f64 * ts - 1.0;
+
+        cr.rectangle(x, y, w, h);
+        cr.set_source_rgba(0.2, 0.4, 1.0, 0.2);
+        cr.fill_preserve();
+        cr.set_source_rgb(0.2, 0.4, 1.0);
+        cr.stroke();
+        cr.move_to(x + 2.0, y + h - 3.0);
+        cr.show_text(&e.script_id);
+    }
+}
+
+/// Draw the rectangle selected by a tool. bottom_right is included.
+fn draw_selection(cr: &Context, top_left: (i32, i32), bottom_right: (i32, i32), pos: (i32, i32),
+                  ts: f64) {
+    let x = (top_left.0 - pos.0) as f64 * ts + 0.5;
+    let y = (top_left.1 - pos.1) as f64 * ts + 0.5;
+    let w = (bottom_right.0 - top_left.0 + 1) as f64 * ts - 1.0;
+    let h = (bottom_right.1 - top_left.1 + 1) as f64 * ts - 1.0;
+
+    cr.rectangle(x, y, w, h);
+    cr.set_source_rgba(1.0, 1.0, 0.0, 0.2);
+    cr.fill_preserve();
+    cr.set_source_rgb(1.0, 1.0, 0.0);
+    cr.set_line_width(1.0);
+    cr.stroke();
+}
+
+/// Draw grid lines on tile boundaries, and coordinates on every GRID_LABEL_INTERVAL tiles.
+/// Coordinates are not drawn if tiles are too small.
+fn draw_grid(cr: &Context, map: &EditingMap, tile_nx: i32, tile_ny: i32, pos: (i32, i32), ts: f64) {
+    const GRID_LABEL_INTERVAL: i32 = 5;
+    let nx = std::cmp::min(tile_nx, map.width as i32 - pos.0);
+    let ny = std::cmp::min(tile_ny, map.height as i32 - pos.1);
+    if nx <= 0 || ny <= 0 {
+        return;
+    }
+    let w = nx as f64 * ts;
+    let h = ny as f64 * ts;
+
+    cr.set_source_rgba(1.0, 1.0, 1.0, 0.4);
+    cr.set_line_width(1.0);
+    for ix in 0..=nx {
+        let x = (ix as f64 * ts).floor() + 0.5;
+        cr.move_to(x, 0.0);
+        cr.line_to(x, h);
+    }
+    for iy in 0..=ny {
+        let y = (iy as f64 * ts).floor() + 0.5;
+        cr.move_to(0.0, y);
+        cr.line_to(w, y);
+    }
+    cr.stroke();
+
+    if ts < TILE_SIZE_I as f64 {
+        return;
+    }
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.set_font_size(10.0);
+    for iy in 0..ny {
+        for ix in 0..nx {
+            let p = (ix + pos.0, iy + pos.1);
+            if p.0 % GRID_LABEL_INTERVAL != 0 || p.1 % GRID_LABEL_INTERVAL != 0 {
+                continue;
+            }
+            cr.move_to(ix as f64 * ts + 2.0, iy as f64 * ts + 11.0);
+            cr.show_text(&format!("{},{}", p.0, p.1));
         }
     }
 }
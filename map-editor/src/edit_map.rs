@@ -3,10 +3,11 @@ use array2d::*;
 use common::basic::N_TILE_IMG_LAYER;
 use common::maptemplate::*;
 use common::objholder::*;
-use common::gamedata::{TileLayers, ItemGen};
+use common::gamedata::{TileLayers, ItemGen, MapEvent};
 use common::gobj;
 use common::piece_pattern::*;
 
+#[derive(Clone)]
 pub struct EditingMap {
     pub property: MapProperty,
     pub width: u32,
@@ -15,6 +16,11 @@ pub struct EditingMap {
     pub wall: Array2d<WallIdxPP>,
     pub deco: Array2d<Option<DecoIdx>>,
     pub items: Array2d<Vec<ItemGen>>,
+    pub doors: Array2d<Option<DoorGen>>,
+    /// Special tile ids of traps
+    pub traps: Array2d<Option<String>>,
+    pub charas: Array2d<Option<CharaGen>>,
+    pub events: Vec<MapEvent>,
 }
 
 impl EditingMap {
@@ -24,7 +30,12 @@ impl EditingMap {
         let deco = Array2d::new(width, height, None);
         let property = MapProperty::new(id);
         let items = Array2d::new(width, height, vec![]);
-        EditingMap { property, width, height, tile, wall, deco, items }
+        let doors = Array2d::new(width, height, None);
+        let traps = Array2d::new(width, height, None);
+        let charas = Array2d::new(width, height, None);
+        EditingMap {
+            property, width, height, tile, wall, deco, items, doors, traps, charas, events: vec![],
+        }
     }
 
     pub fn set_tile(&mut self, pos: Vec2d, idx: TileIdx, layer: usize) {
@@ -71,9 +82,58 @@ impl EditingMap {
         self.items[pos].get(0)
     }
 
-    pub fn erase(&mut self, pos: Vec2d) {
+    pub fn set_door(&mut self, pos: Vec2d, door_gen: Option<DoorGen>) {
+        self.doors[pos] = door_gen;
+    }
+
+    pub fn get_door(&self, pos: Vec2d) -> Option<&DoorGen> {
+        self.doors[pos].as_ref()
+    }
+
+    pub fn set_trap(&mut self, pos: Vec2d, trap: Option<String>) {
+        self.traps[pos] = trap;
+    }
+
+    pub fn get_trap(&self, pos: Vec2d) -> Option<&String> {
+        self.traps[pos].as_ref()
+    }
+
+    pub fn set_chara(&mut self, pos: Vec2d, chara_gen: Option<CharaGen>) {
+        self.charas[pos] = chara_gen;
+    }
+
+    pub fn get_chara(&self, pos: Vec2d) -> Option<&CharaGen> {
+        self.charas[pos].as_ref()
+    }
+
+    /// Index of the event region including pos. The last added one is used if overlapped.
+    pub fn event_at(&self, pos: Vec2d) -> Option<usize> {
+        self.events.iter().rposition(|e| e.contains(pos))
+    }
+
+    /// Erase only the wall and the door on it
+    pub fn erase_wall(&mut self, pos: Vec2d) {
         self.wall[pos] = WallIdxPP::default();
-        self.deco[pos] = None;
+        self.doors[pos] = None;
+    }
+
+    /// Returns a copy of this map that hidden layers are erased
+    pub fn visible_layers_only(&self, layer_visible: &LayerVisible) -> EditingMap {
+        let mut map = self.clone();
+        for pos in self.tile.iter_idx() {
+            for i in 0..N_TILE_IMG_LAYER {
+                if !layer_visible.tile[i] {
+                    map.tile[pos][i] = TileIdxPP::default();
+                }
+            }
+            if !layer_visible.wall {
+                map.erase_wall(pos);
+            }
+            if !layer_visible.deco {
+                map.deco[pos] = None;
+            }
+        }
+        map
     }
 
     pub fn erase_layer(&mut self, pos: Vec2d, layer: usize) {
@@ -100,15 +160,116 @@ impl EditingMap {
         self.tile[pos][layer] = TileIdxPP::with_piece_pattern(new_tile_idx, piece_pattern);
     }
 
-    pub fn resize(&mut self, new_w: u32, new_h: u32) {
+    /// Resize the map. The tile at top_left of the current map becomes the top left of the new map,
+    /// so negative coordinates add tiles to the left and top edges.
+    /// Tiles in the added area are filled by fill.
+    pub fn resize(&mut self, top_left: Vec2d, new_w: u32, new_h: u32, fill: TileLayers) {
+        let end = top_left + (new_w as i32, new_h as i32);
         self.width = new_w;
         self.height = new_h;
-        let tile = self.tile.clip_with_default((0, 0), (new_w, new_h), TileLayers::default());
+        let tile = self.tile.clip_with_default(top_left, end, fill);
         self.tile = tile;
-        let wall = self.wall.clip_with_default((0, 0), (new_w, new_h), WallIdxPP::default());
+        let wall = self.wall.clip_with_default(top_left, end, WallIdxPP::default());
         self.wall = wall;
-        let deco = self.deco.clip_with_default((0, 0), (new_w, new_h), None);
+        let deco = self.deco.clip_with_default(top_left, end, None);
         self.deco = deco;
+        let items = self.items.clip_with_default(top_left, end, vec![]);
+        self.items = items;
+        let doors = self.doors.clip_with_default(top_left, end, None);
+        self.doors = doors;
+        let traps = self.traps.clip_with_default(top_left, end, None);
+        self.traps = traps;
+        let charas = self.charas.clip_with_default(top_left, end, None);
+        self.charas = charas;
+        // Events are moved with the tiles. Events out of the map are removed, and the others are clipped.
+        let max = Vec2d(new_w as i32 - 1, new_h as i32 - 1);
+        for e in &mut self.events {
+            e.top_left = e.top_left - top_left;
+            e.bottom_right = e.bottom_right - top_left;
+        }
+        self.events.retain(|e| {
+            e.top_left.0 <= max.0 && e.top_left.1 <= max.1 && e.bottom_right.0 >= 0 && e.bottom_right.1 >= 0
+        });
+        for e in &mut self.events {
+            e.top_left = Vec2d(e.top_left.0.max(0), e.top_left.1.max(0));
+            e.bottom_right = Vec2d(e.bottom_right.0.min(max.0), e.bottom_right.1.min(max.1));
+        }
+    }
+
+    /// Crop the map to the rectangle. bottom_right is included.
+    pub fn crop(&mut self, top_left: Vec2d, bottom_right: Vec2d) {
+        let new_w = (bottom_right.0 - top_left.0 + 1) as u32;
+        let new_h = (bottom_right.1 - top_left.1 + 1) as u32;
+        self.resize(top_left, new_w, new_h, TileLayers::default());
+    }
+
+    /// Tiles connected to start in four directions that have the same target as start
+    pub fn contiguous_area(&self, start: Vec2d, target: FillTarget) -> Vec<Vec2d> {
+        if !self.tile.in_range(start) {
+            return vec![];
+        }
+        let same = |a: Vec2d, b: Vec2d| match target {
+            FillTarget::Tile(layer) => self.tile[a][layer].idx() == self.tile[b][layer].idx(),
+            FillTarget::Wall => self.wall[a].idx() == self.wall[b].idx(),
+            FillTarget::Deco => self.deco[a] == self.deco[b],
+        };
+
+        let mut visited = Array2d::new(self.width, self.height, false);
+        let mut stack = vec![start];
+        let mut area = Vec::new();
+        visited[start] = true;
+        while let Some(pos) = stack.pop() {
+            area.push(pos);
+            for d in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+                let next = pos + *d;
+                if self.tile.in_range(next) && !visited[next] && same(start, next) {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        area
+    }
+
+    /// Copy the rectangle area as a new map. bottom_right is included.
+    pub fn copy_area(&self, top_left: Vec2d, bottom_right: Vec2d) -> EditingMap {
+        let end = bottom_right + (1, 1);
+        let mut map = EditingMap::new(
+            &self.property.id, (end.0 - top_left.0) as u32, (end.1 - top_left.1) as u32);
+        map.tile = self.tile.clip_with_default(top_left, end, TileLayers::default());
+        map.wall = self.wall.clip_with_default(top_left, end, WallIdxPP::default());
+        map.deco = self.deco.clip_with_default(top_left, end, None);
+        map.items = self.items.clip_with_default(top_left, end, vec![]);
+        map.doors = self.doors.clip_with_default(top_left, end, None);
+        map.traps = self.traps.clip_with_default(top_left, end, None);
+        map.charas = self.charas.clip_with_default(top_left, end, None);
+        map
+    }
+
+    /// Paste the copied area with its top left at pos. Parts out of this map are ignored.
+    /// Hidden layers are not changed. Doors are in the wall layer.
+    pub fn paste(&mut self, pos: Vec2d, area: &EditingMap, layer_visible: &LayerVisible) {
+        for p in area.tile.iter_idx() {
+            let dest = pos + p;
+            if !self.tile.in_range(dest) {
+                continue;
+            }
+            for i in 0..N_TILE_IMG_LAYER {
+                if layer_visible.tile[i] {
+                    self.tile[dest][i] = area.tile[p][i];
+                }
+            }
+            if layer_visible.wall {
+                self.wall[dest] = area.wall[p];
+                self.doors[dest] = area.doors[p].clone();
+            }
+            if layer_visible.deco {
+                self.deco[dest] = area.deco[p];
+            }
+            self.items[dest] = area.items[p].clone();
+            self.traps[dest] = area.traps[p].clone();
+            self.charas[dest] = area.charas[p].clone();
+        }
     }
 
     pub fn create_mapobj(&self) -> MapTemplateObject {
@@ -175,6 +336,21 @@ impl EditingMap {
         }
         items.sort();
 
+        // Create doors
+        let doors: Vec<(Vec2d, DoorGen)> = self.doors.iter_with_idx().filter_map(|(pos, door_gen)| {
+            door_gen.as_ref().map(|door_gen| (pos, door_gen.clone()))
+        }).collect();
+
+        // Create traps
+        let traps: Vec<(Vec2d, String)> = self.traps.iter_with_idx().filter_map(|(pos, trap)| {
+            trap.as_ref().map(|trap| (pos, trap.clone()))
+        }).collect();
+
+        // Create charas
+        let charas: Vec<(Vec2d, CharaGen)> = self.charas.iter_with_idx().filter_map(|(pos, chara_gen)| {
+            chara_gen.as_ref().map(|chara_gen| (pos, chara_gen.clone()))
+        }).collect();
+
         MapTemplateObject {
             id: self.property.id.to_owned(),
             w: self.width,
@@ -187,15 +363,93 @@ impl EditingMap {
             deco: deco_map,
             boundary: self.property.boundary,
             items,
+            music: self.property.music.clone(),
+            darkness: self.property.darkness,
+            doors,
+            traps,
+            charas,
+            events: self.events.clone(),
         }
     }
 }
 
-#[derive(Debug)]
+/// Visibility of each layer. Hidden layers are not edited.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LayerVisible {
+    /// Terrain tile image layers
+    pub tile: [bool; N_TILE_IMG_LAYER],
+    pub wall: bool,
+    pub deco: bool,
+}
+
+impl Default for LayerVisible {
+    fn default() -> LayerVisible {
+        LayerVisible { tile: [true; N_TILE_IMG_LAYER], wall: true, deco: true }
+    }
+}
+
+/// The part of tiles compared in flood fill
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillTarget {
+    /// Tile image layer
+    Tile(usize),
+    Wall,
+    Deco,
+}
+
+/// The maximum number of undo entries
+const HISTORY_LIMIT: usize = 100;
+
+/// Edit history for undo and redo.
+/// The whole map is recorded before each edit, so any kind of edit can be reverted.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: Vec<EditingMap>,
+    redo: Vec<EditingMap>,
+}
+
+impl EditHistory {
+    /// Record the map before an edit. Redo entries are discarded by a new edit.
+    pub fn push(&mut self, map: &EditingMap) {
+        self.push_undo(map.clone());
+        self.redo.clear();
+    }
+
+    /// The oldest entry is discarded if the history is full
+    fn push_undo(&mut self, map: EditingMap) {
+        if self.undo.len() >= HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(map);
+    }
+
+    /// Returns the map before the last edit. The current map is recorded for redo.
+    pub fn undo(&mut self, current: &EditingMap) -> Option<EditingMap> {
+        let map = self.undo.pop()?;
+        self.redo.push(current.clone());
+        Some(map)
+    }
+
+    /// Returns the map after the last undone edit. The current map is recorded for undo.
+    pub fn redo(&mut self, current: &EditingMap) -> Option<EditingMap> {
+        let map = self.redo.pop()?;
+        self.push_undo(current.clone());
+        Some(map)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct MapProperty {
     pub id: String,
     pub is_region_map: bool,
     pub boundary: MapTemplateBoundary,
+    pub music: String,
+    pub darkness: u8,
 }
 
 impl MapProperty {
@@ -204,6 +458,8 @@ impl MapProperty {
             id: id.to_owned(),
             is_region_map: false,
             boundary: MapTemplateBoundary::default(),
+            music: String::new(),
+            darkness: 0,
         }
     }
 }
@@ -231,7 +487,22 @@ impl From<MapTemplateObject> for EditingMap {
             map.set_item(*pos, Some(item_gen.clone()));
         }
 
+        for (pos, door_gen) in &obj.doors {
+            map.set_door(*pos, Some(door_gen.clone()));
+        }
+
+        for (pos, trap) in &obj.traps {
+            map.set_trap(*pos, Some(trap.clone()));
+        }
+
+        for (pos, chara_gen) in &obj.charas {
+            map.set_chara(*pos, Some(chara_gen.clone()));
+        }
+        map.events = obj.events.clone();
+
         map.property.boundary = obj.boundary;
+        map.property.music = obj.music.clone();
+        map.property.darkness = obj.darkness;
         
         map
     }
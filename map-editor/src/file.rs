@@ -5,11 +5,12 @@ use common::obj::Object;
 use std::fs::File;
 use std::path::Path;
 use std::error::Error;
-use std::io::Write;
+use std::io::{Read, Write};
 use tar;
 
-pub fn load_from_file(path: &Path) -> Result<MapTemplateObject, Box<Error>> {
-    let mut mapobj: Option<MapTemplateObject> = None;
+/// Load all maps in the pak file. A compiled pak may have other objects and multiple maps.
+pub fn load_from_file(path: &Path) -> Result<Vec<MapTemplateObject>, Box<Error>> {
+    let mut mapobjs = Vec::new();
     let mut errors = Vec::new();
     
     pakutil::read_tar(
@@ -17,27 +18,68 @@ pub fn load_from_file(path: &Path) -> Result<MapTemplateObject, Box<Error>> {
         &mut |object| {
             match object {
                 Object::MapTemplate(o) => {
-                    mapobj = Some(o);
+                    mapobjs.push(o);
                 }
                 _ => (),
             }
         },
         &mut errors);
 
-    Ok(mapobj.ok_or("Object is not found")?)
+    if mapobjs.is_empty() {
+        return Err("Map is not found".into());
+    }
+    Ok(mapobjs)
 }
 
-pub fn save_to_file(path: &Path, map: MapTemplateObject) -> Result<(), Box<Error>> {
-    let file = File::create(path)?;
-    let mut builder = tar::Builder::new(file);
+/// Save the map to the pak file.
+/// If `replaced_id` is given and the file is a pak, the other objects in it are kept and the map
+/// loaded under `replaced_id` is replaced, so compiled paks can be edited directly.
+/// Otherwise the file is overwritten with only this map.
+pub fn save_to_file(path: &Path, map: MapTemplateObject, replaced_id: Option<&str>)
+                    -> Result<(), Box<Error>> {
     let obj = Object::MapTemplate(map);
     let mut data: Vec<u8> = Vec::new();
     pakutil::write_object(&mut data, &obj).unwrap();
-    write_data_to_tar(&mut builder, &data, obj.get_id());
+
+    let mut entries = match replaced_id {
+        Some(_) if path.exists() => read_entries(path).unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let replaced = entries.iter().position(|(_, entry_data)| {
+        match pakutil::read_object(&entry_data[..]) {
+            Ok(Object::MapTemplate(ref o)) => Some(o.id.as_str()) == replaced_id,
+            _ => false,
+        }
+    });
+    if let Some(i) = replaced {
+        entries[i] = (obj.get_id().to_owned(), data);
+    } else {
+        entries.push((obj.get_id().to_owned(), data));
+    }
+
+    let file = File::create(path)?;
+    let mut builder = tar::Builder::new(file);
+    for (entry_path, data) in &entries {
+        write_data_to_tar(&mut builder, data, entry_path);
+    }
     builder.finish()?;
     Ok(())
 }
 
+/// Read paths and data of all entries in the tar file
+fn read_entries(path: &Path) -> Result<Vec<(String, Vec<u8>)>, Box<Error>> {
+    let mut ar = tar::Archive::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for entry in ar.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        entries.push((entry_path, data));
+    }
+    Ok(entries)
+}
+
 fn write_data_to_tar<W: Write>(builder: &mut tar::Builder<W>, data: &[u8], path: &str) {
     let mut header = tar::Header::new_gnu();
     header.set_path(path).unwrap();
@@ -5,7 +5,8 @@ use gtk::prelude::*;
 use crate::edit_map::EditingMap;
 use crate::ui::{Ui, SelectedItem};
 use array2d::Vec2d;
-use common::maptemplate::MapTemplateBoundaryBehavior;
+use common::maptemplate::{MapTemplateBoundaryBehavior, DoorGen, CharaGen};
+use common::gamedata::{MapEvent, MapEventTrigger};
 
 #[derive(Clone)]
 pub struct PropertyControls {
@@ -30,6 +31,22 @@ pub struct PropertyControls {
     pub button_select_tile_mode: gtk::Button,
     pub label_selected_tile: gtk::Label,
     pub entry_item_id:     gtk::Entry,
+    pub check_door:        gtk::CheckButton,
+    pub check_door_locked: gtk::CheckButton,
+    pub entry_door_key:    gtk::Entry,
+    pub entry_door_lock_level: gtk::Entry,
+    pub entry_trap_id:     gtk::Entry,
+    pub entry_chara_id:    gtk::Entry,
+    pub entry_chara_level: gtk::Entry,
+    pub entry_chara_talk:  gtk::Entry,
+    pub check_chara_hostile: gtk::CheckButton,
+    pub label_event:         gtk::Label,
+    pub button_add_event:    gtk::Button,
+    pub entry_event_script:  gtk::Entry,
+    pub check_event_once:    gtk::CheckButton,
+    pub combo_event_trigger: gtk::ComboBoxText,
+    pub entry_event_trigger_arg: gtk::Entry,
+    pub button_remove_event: gtk::Button,
     pub selected_tile: Rc<Cell<Vec2d>>,
 }
 
@@ -57,6 +74,22 @@ impl PropertyControls {
             button_select_tile_mode: get_object!(builder, "button-select-tile-mode"),
             label_selected_tile: get_object!(builder, "label-selected-tile"),
             entry_item_id:     get_object!(builder, "entry-item-id"),
+            check_door:        get_object!(builder, "check-door"),
+            check_door_locked: get_object!(builder, "check-door-locked"),
+            entry_door_key:    get_object!(builder, "entry-door-key"),
+            entry_door_lock_level: get_object!(builder, "entry-door-lock-level"),
+            entry_trap_id:     get_object!(builder, "entry-trap-id"),
+            entry_chara_id:    get_object!(builder, "entry-chara-id"),
+            entry_chara_level: get_object!(builder, "entry-chara-level"),
+            entry_chara_talk:  get_object!(builder, "entry-chara-talk"),
+            check_chara_hostile: get_object!(builder, "check-chara-hostile"),
+            label_event:         get_object!(builder, "label-event"),
+            button_add_event:    get_object!(builder, "button-add-event"),
+            entry_event_script:  get_object!(builder, "entry-event-script"),
+            check_event_once:    get_object!(builder, "check-event-once"),
+            combo_event_trigger: get_object!(builder, "combo-event-trigger"),
+            entry_event_trigger_arg: get_object!(builder, "entry-event-trigger-arg"),
+            button_remove_event: get_object!(builder, "button-remove-event"),
             selected_tile: Rc::new(Cell::new(Vec2d(0, 0))),
         }
     }
@@ -93,6 +126,105 @@ impl PropertyControls {
         } else {
             self.entry_item_id.set_text("");
         }
+        if let Some(door_gen) = map.get_door(self.selected_tile.get()) {
+            self.check_door.set_active(true);
+            self.check_door_locked.set_active(door_gen.locked);
+            self.entry_door_key.set_text(door_gen.key.as_ref().map_or("", |key| key));
+            self.entry_door_lock_level.set_text(&door_gen.lock_level.to_string());
+        } else {
+            self.check_door.set_active(false);
+            self.check_door_locked.set_active(false);
+            self.entry_door_key.set_text("");
+            self.entry_door_lock_level.set_text("");
+        }
+        if let Some(trap) = map.get_trap(self.selected_tile.get()) {
+            self.entry_trap_id.set_text(trap);
+        } else {
+            self.entry_trap_id.set_text("");
+        }
+        if let Some(chara_gen) = map.get_chara(self.selected_tile.get()) {
+            self.entry_chara_id.set_text(&chara_gen.id);
+            self.entry_chara_level.set_text(&chara_gen.level.to_string());
+            self.entry_chara_talk.set_text(chara_gen.talk_script_id.as_ref().map_or("", |id| id));
+            self.check_chara_hostile.set_active(chara_gen.hostile);
+        } else {
+            self.entry_chara_id.set_text("");
+            self.entry_chara_level.set_text("");
+            self.entry_chara_talk.set_text("");
+            self.check_chara_hostile.set_active(false);
+        }
+        if let Some(i) = map.event_at(self.selected_tile.get()) {
+            let e = &map.events[i];
+            self.label_event.set_text(&format!(
+                "Event ({}, {}) - ({}, {})",
+                e.top_left.0, e.top_left.1, e.bottom_right.0, e.bottom_right.1));
+            self.entry_event_script.set_text(&e.script_id);
+            self.check_event_once.set_active(e.once);
+            let (n, arg) = match e.trigger {
+                MapEventTrigger::Enter => (0, ""),
+                MapEventTrigger::Examine => (1, ""),
+                MapEventTrigger::GVar(ref name) => (2, name.as_str()),
+                MapEventTrigger::Defeated(ref id) => (3, id.as_str()),
+            };
+            self.combo_event_trigger.set_active(n);
+            self.entry_event_trigger_arg.set_text(arg);
+        } else {
+            self.label_event.set_text("No Event");
+            self.entry_event_script.set_text("");
+            self.check_event_once.set_active(false);
+            self.combo_event_trigger.set_active(0);
+            self.entry_event_trigger_arg.set_text("");
+        }
+    }
+
+    /// Create the event trigger from event controls
+    fn event_trigger(&self) -> MapEventTrigger {
+        let arg = self.entry_event_trigger_arg.get_text().unwrap_or("".to_owned());
+        match self.combo_event_trigger.get_active() {
+            1 => MapEventTrigger::Examine,
+            2 => MapEventTrigger::GVar(arg),
+            3 => MapEventTrigger::Defeated(arg),
+            _ => MapEventTrigger::Enter,
+        }
+    }
+
+    /// Create chara parameters from chara controls. No chara is placed if the id is empty.
+    fn chara_gen(&self) -> Option<CharaGen> {
+        let id = self.entry_chara_id.get_text().unwrap_or("".to_owned());
+        if id == "" {
+            return None;
+        }
+        let level = self.entry_chara_level.get_text().unwrap_or("".to_owned());
+        let talk_script_id = self.entry_chara_talk.get_text().unwrap_or("".to_owned());
+        Some(CharaGen {
+            id,
+            level: level.parse().unwrap_or(0),
+            talk_script_id: if talk_script_id == "" { None } else { Some(talk_script_id) },
+            hostile: self.check_chara_hostile.get_active(),
+        })
+    }
+
+    /// Select the tile to edit its properties
+    pub fn select_tile(&self, ui: &Ui, pos: Vec2d) {
+        self.selected_tile.set(pos);
+        self.label_selected_tile.set_text(&format!("Selected Tile ({}, {})", pos.0, pos.1));
+        ui.set_signal_mode(false);
+        self.update(&*ui.map.borrow());
+        ui.set_signal_mode(true);
+    }
+
+    /// Create door parameters from door controls
+    fn door_gen(&self) -> Option<DoorGen> {
+        if !self.check_door.get_active() {
+            return None;
+        }
+        let key = self.entry_door_key.get_text().unwrap_or("".to_owned());
+        let lock_level = self.entry_door_lock_level.get_text().unwrap_or("".to_owned());
+        Some(DoorGen {
+            locked: self.check_door_locked.get_active(),
+            key: if key == "" { None } else { Some(key) },
+            lock_level: lock_level.parse().unwrap_or(0),
+        })
     }
 }
 
@@ -236,4 +368,103 @@ fn connect_for_tile_edit_controls(ui: &Ui) {
             uic.map.borrow_mut().set_item(uic.property_controls.selected_tile.get(), item_gen);
         }
     });
+
+    // Door editing
+    let set_door = |uic: &Ui| {
+        if uic.get_signal_mode() {
+            let door_gen = uic.property_controls.door_gen();
+            uic.map.borrow_mut().set_door(uic.property_controls.selected_tile.get(), door_gen);
+            uic.map_redraw();
+        }
+    };
+    let uic = ui.clone();
+    ui.property_controls.check_door.connect_toggled(move |_| set_door(&uic));
+    let uic = ui.clone();
+    ui.property_controls.check_door_locked.connect_toggled(move |_| set_door(&uic));
+    let uic = ui.clone();
+    ui.property_controls.entry_door_key.connect_changed(move |_| set_door(&uic));
+    let uic = ui.clone();
+    ui.property_controls.entry_door_lock_level.connect_changed(move |_| set_door(&uic));
+
+    // Trap editing
+    let uic = ui.clone();
+    ui.property_controls.entry_trap_id.connect_changed(move |widget| {
+        if uic.get_signal_mode() {
+            let text = widget.get_text().unwrap_or("".to_owned());
+            let trap = if text == "" { None } else { Some(text) };
+            uic.map.borrow_mut().set_trap(uic.property_controls.selected_tile.get(), trap);
+            uic.map_redraw();
+        }
+    });
+
+    // Chara editing
+    let set_chara = |uic: &Ui| {
+        if uic.get_signal_mode() {
+            let chara_gen = uic.property_controls.chara_gen();
+            uic.map.borrow_mut().set_chara(uic.property_controls.selected_tile.get(), chara_gen);
+            uic.map_redraw();
+        }
+    };
+    let uic = ui.clone();
+    ui.property_controls.entry_chara_id.connect_changed(move |_| set_chara(&uic));
+    let uic = ui.clone();
+    ui.property_controls.entry_chara_level.connect_changed(move |_| set_chara(&uic));
+    let uic = ui.clone();
+    ui.property_controls.entry_chara_talk.connect_changed(move |_| set_chara(&uic));
+    let uic = ui.clone();
+    ui.property_controls.check_chara_hostile.connect_toggled(move |_| set_chara(&uic));
+
+    connect_for_event_controls(ui);
+}
+
+fn connect_for_event_controls(ui: &Ui) {
+    // Add an event region on the rectangle selected by the select tool
+    let uic = ui.clone();
+    ui.property_controls.button_add_event.connect_clicked(move |_| {
+        let (top_left, bottom_right) = if let Some(rect) = uic.selected_rect() { rect } else { return; };
+        let top_left = Vec2d(top_left.0, top_left.1);
+        uic.push_undo();
+        uic.map.borrow_mut().events.push(MapEvent {
+            top_left,
+            bottom_right: Vec2d(bottom_right.0, bottom_right.1),
+            script_id: String::new(),
+            once: false,
+            trigger: MapEventTrigger::Enter,
+        });
+        uic.property_controls.select_tile(&uic, top_left);
+        uic.map_redraw();
+    });
+    let uic = ui.clone();
+    ui.property_controls.button_remove_event.connect_clicked(move |_| {
+        let i = uic.map.borrow().event_at(uic.property_controls.selected_tile.get());
+        if let Some(i) = i {
+            uic.push_undo();
+            uic.map.borrow_mut().events.remove(i);
+            uic.property_controls.select_tile(&uic, uic.property_controls.selected_tile.get());
+            uic.map_redraw();
+        }
+    });
+
+    let set_event = |uic: &Ui| {
+        if uic.get_signal_mode() {
+            {
+                let mut map = uic.map.borrow_mut();
+                if let Some(i) = map.event_at(uic.property_controls.selected_tile.get()) {
+                    let e = &mut map.events[i];
+                    e.script_id = uic.property_controls.entry_event_script.get_text().unwrap_or("".to_owned());
+                    e.once = uic.property_controls.check_event_once.get_active();
+                    e.trigger = uic.property_controls.event_trigger();
+                }
+            }
+            uic.map_redraw();
+        }
+    };
+    let uic = ui.clone();
+    ui.property_controls.entry_event_script.connect_changed(move |_| set_event(&uic));
+    let uic = ui.clone();
+    ui.property_controls.check_event_once.connect_toggled(move |_| set_event(&uic));
+    let uic = ui.clone();
+    ui.property_controls.combo_event_trigger.connect_changed(move |_| set_event(&uic));
+    let uic = ui.clone();
+    ui.property_controls.entry_event_trigger_arg.connect_changed(move |_| set_event(&uic));
 }
@@ -4,20 +4,40 @@ use std::cell::{Cell, RefCell};
 use std::path::PathBuf;
 use std::error::Error;
 use gtk::prelude::*;
-use array2d::Vec2d;
+use array2d::{Vec2d, RectIter};
 use common::objholder::*;
+use common::obj::MapTemplateObject;
+use common::gamedata::TileLayers;
 use common::basic::TILE_SIZE_I;
 use crate::pixbuf_holder::PixbufHolder;
-use crate::edit_map::EditingMap;
+use crate::draw_map::{MapCache, MAX_OVERHANG};
+use crate::edit_map::{EditingMap, EditHistory, FillTarget, LayerVisible};
 use crate::iconview::IconView;
 use crate::property_controls::PropertyControls;
 
 const WRITE_BUTTON: u32 = 1;
 const CENTERING_BUTTON: u32 = 2;
 const ERASE_BUTTON: u32 = 3;
+/// Selectable zoom levels. Tile sizes are integers in pixels at all levels.
+const ZOOM_LEVELS: [f64; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum DragMode { None, Write, Erase }
+pub enum DragMode { None, Write, Erase, Rect, Select }
+
+/// Tool used by the write button
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tool {
+    /// Draw tiles along the cursor
+    Pen,
+    /// Fill the dragged rectangle
+    Rect,
+    /// Fill the contiguous area of the same tile
+    Fill,
+    /// Select the dragged rectangle to copy
+    Select,
+    /// Paste the copied area
+    Stamp,
+}
 
 #[derive(Clone)]
 pub struct Ui {
@@ -25,11 +45,21 @@ pub struct Ui {
     pub map_drawing_area: gtk::DrawingArea,
     pub new_map_dialog: gtk::Dialog,
     pub resize_dialog: gtk::Dialog,
+    pub goto_dialog: gtk::Dialog,
+    pub goto_x: gtk::Entry,
+    pub goto_y: gtk::Entry,
+    pub select_map_dialog: gtk::Dialog,
+    pub select_map_combo: gtk::ComboBoxText,
     pub new_map_id:     gtk::Entry,
     pub adjustment_map_width: gtk::Adjustment,
     pub adjustment_map_height: gtk::Adjustment,
     pub adjustment_map_pos_x: gtk::Adjustment,
     pub adjustment_map_pos_y: gtk::Adjustment,
+    pub adjustment_resize_top:    gtk::Adjustment,
+    pub adjustment_resize_bottom: gtk::Adjustment,
+    pub adjustment_resize_left:   gtk::Adjustment,
+    pub adjustment_resize_right:  gtk::Adjustment,
+    pub resize_fill_selected: gtk::CheckButton,
     pub label_cursor_pos:    gtk::Label,
     pub label_selected_item: gtk::Label,
     pub radiobutton_layer0: gtk::RadioButton,
@@ -40,20 +70,42 @@ pub struct Ui {
     pub checkbutton_layer1: gtk::CheckButton,
     pub checkbutton_layer2: gtk::CheckButton,
     pub checkbutton_layer3: gtk::CheckButton,
+    pub checkbutton_wall: gtk::CheckButton,
+    pub checkbutton_deco: gtk::CheckButton,
+    pub tool_pen:    gtk::RadioToolButton,
+    pub tool_rect:   gtk::RadioToolButton,
+    pub tool_fill:   gtk::RadioToolButton,
+    pub tool_select: gtk::RadioToolButton,
+    pub tool_stamp:  gtk::RadioToolButton,
     pub iconview: IconView,
     pub property_controls: PropertyControls,
     pub pbh: Rc<PixbufHolder>,
     pub map: Rc<RefCell<EditingMap>>,
     pub selected_item: Rc<Cell<SelectedItem>>,
     pub drag_mode: Rc<Cell<DragMode>>,
+    /// Tile position of the last painting while dragging
+    pub drag_last_pos: Rc<Cell<Option<(i32, i32)>>>,
+    pub tool: Rc<Cell<Tool>>,
+    /// Start and end tile positions of the rectangle dragged by a tool
+    pub selection: Rc<Cell<Option<((i32, i32), (i32, i32))>>>,
+    /// Copied area. It is kept when another map is opened.
+    pub clipboard: Rc<RefCell<Option<EditingMap>>>,
+    /// Maps before each editing stroke
+    pub history: Rc<RefCell<EditHistory>>,
     pub filepath: Rc<RefCell<Option<PathBuf>>>,
+    /// Map id in the file when the map was loaded or saved last
+    pub saved_map_id: Rc<RefCell<Option<String>>>,
     /// If it is false, some signal will not be processed
     pub signal_mode: Rc<Cell<bool>>,
     /// Shift key state
     pub shift: Rc<Cell<bool>>,
     /// Current layer to draw
     pub current_layer: Rc<Cell<usize>>,
-    pub layer_visible: Rc<RefCell<[bool; 4]>>,
+    pub layer_visible: Rc<RefCell<LayerVisible>>,
+    /// Draw grid lines and coordinates over the map
+    pub grid_visible: Rc<Cell<bool>>,
+    pub zoom: Rc<Cell<f64>>,
+    pub map_cache: Rc<RefCell<MapCache>>,
 }
 
 macro_rules! get_object {
@@ -75,11 +127,21 @@ pub fn build_ui(application: &gtk::Application) {
         map_drawing_area: get_object!(builder, "map-drawing-area"),
         new_map_dialog:   get_object!(builder, "new-map-dialog"),
         resize_dialog:    get_object!(builder, "resize-dialog"),
+        goto_dialog:      get_object!(builder, "goto-dialog"),
+        goto_x:           get_object!(builder, "goto-x"),
+        goto_y:           get_object!(builder, "goto-y"),
+        select_map_dialog: get_object!(builder, "select-map-dialog"),
+        select_map_combo:  get_object!(builder, "select-map-combo"),
         new_map_id:       get_object!(builder, "new-map-id"),
         adjustment_map_width:  get_object!(builder, "adjustment-map-width"),
         adjustment_map_height: get_object!(builder, "adjustment-map-height"),
         adjustment_map_pos_x:      get_object!(builder, "adjustment-map-pos-x"),
         adjustment_map_pos_y:      get_object!(builder, "adjustment-map-pos-y"),
+        adjustment_resize_top:    get_object!(builder, "adjustment-resize-top"),
+        adjustment_resize_bottom: get_object!(builder, "adjustment-resize-bottom"),
+        adjustment_resize_left:   get_object!(builder, "adjustment-resize-left"),
+        adjustment_resize_right:  get_object!(builder, "adjustment-resize-right"),
+        resize_fill_selected: get_object!(builder, "resize-fill-selected"),
         label_cursor_pos:    get_object!(builder, "label-cursor-pos"),
         label_selected_item: get_object!(builder, "label-selected-item"),
         radiobutton_layer0: get_object!(builder, "radiobutton-layer0"),
@@ -90,25 +152,48 @@ pub fn build_ui(application: &gtk::Application) {
         checkbutton_layer1: get_object!(builder, "checkbutton-layer1"),
         checkbutton_layer2: get_object!(builder, "checkbutton-layer2"),
         checkbutton_layer3: get_object!(builder, "checkbutton-layer3"),
+        checkbutton_wall:   get_object!(builder, "checkbutton-wall"),
+        checkbutton_deco:   get_object!(builder, "checkbutton-deco"),
+        tool_pen:    get_object!(builder, "tool-pen"),
+        tool_rect:   get_object!(builder, "tool-rect"),
+        tool_fill:   get_object!(builder, "tool-fill"),
+        tool_select: get_object!(builder, "tool-select"),
+        tool_stamp:  get_object!(builder, "tool-stamp"),
         iconview: IconView::build(&builder),
         property_controls: PropertyControls::build(&builder),
         pbh: Rc::new(PixbufHolder::new()),
         map: Rc::new(RefCell::new(EditingMap::new("newmap", 16, 16))),
         selected_item: Rc::new(Cell::new(SelectedItem::Tile(TileIdx::default()))),
         drag_mode: Rc::new(Cell::new(DragMode::None)),
+        drag_last_pos: Rc::new(Cell::new(None)),
+        tool: Rc::new(Cell::new(Tool::Pen)),
+        selection: Rc::new(Cell::new(None)),
+        clipboard: Rc::new(RefCell::new(None)),
+        history: Rc::new(RefCell::new(EditHistory::default())),
         filepath: Rc::new(RefCell::new(None)),
+        saved_map_id: Rc::new(RefCell::new(None)),
         signal_mode: Rc::new(Cell::new(true)),
         shift: Rc::new(Cell::new(false)),
         current_layer: Rc::new(Cell::new(0)),
-        layer_visible: Rc::new(RefCell::new([true; 4])),
+        layer_visible: Rc::new(RefCell::new(LayerVisible::default())),
+        grid_visible: Rc::new(Cell::new(false)),
+        zoom: Rc::new(Cell::new(1.0)),
+        map_cache: Rc::new(RefCell::new(MapCache::new())),
     };
 
     let menu_new:     gtk::MenuItem = get_object!(builder, "menu-new");
     let menu_open:    gtk::MenuItem = get_object!(builder, "menu-open");
     let menu_save:    gtk::MenuItem = get_object!(builder, "menu-save");
     let menu_save_as: gtk::MenuItem = get_object!(builder, "menu-save-as");
+    let menu_export_visible: gtk::MenuItem = get_object!(builder, "menu-export-visible");
     let menu_quit:    gtk::MenuItem = get_object!(builder, "menu-quit");
     let menu_resize:  gtk::MenuItem = get_object!(builder, "menu-resize");
+    let menu_crop:    gtk::MenuItem = get_object!(builder, "menu-crop");
+    let menu_goto:    gtk::MenuItem = get_object!(builder, "menu-goto");
+    let menu_grid:    gtk::CheckMenuItem = get_object!(builder, "menu-grid");
+    let menu_zoom_in:    gtk::MenuItem = get_object!(builder, "menu-zoom-in");
+    let menu_zoom_out:   gtk::MenuItem = get_object!(builder, "menu-zoom-out");
+    let menu_zoom_reset: gtk::MenuItem = get_object!(builder, "menu-zoom-reset");
 
     ui.window.set_application(application);
     // Connect signals
@@ -127,7 +212,9 @@ pub fn build_ui(application: &gtk::Application) {
             let map = uic.map.borrow();
             let pos = uic.get_map_pos();
             crate::draw_map::draw_map(
-                context, &*map, &*uic.pbh, width, height, pos, *uic.layer_visible.borrow());
+                context, &*map, &*uic.pbh, &mut *uic.map_cache.borrow_mut(), width, height, pos,
+                uic.zoom.get(), *uic.layer_visible.borrow(), uic.grid_visible.get(),
+                uic.selected_rect());
             Inhibit(false)
         });
     }
@@ -145,7 +232,12 @@ pub fn build_ui(application: &gtk::Application) {
     { // Map drawing area (button pressed)
         let uic = ui.clone();
         ui.map_drawing_area.connect_button_release_event(move |_, _| {
+            // The stroke ends even if the button is released outside of the map
+            if uic.drag_mode.get() == DragMode::Rect {
+                fill_rect(&uic);
+            }
             uic.drag_mode.set(DragMode::None);
+            uic.drag_last_pos.set(None);
             Inhibit(false)
         });
     }
@@ -169,11 +261,13 @@ pub fn build_ui(application: &gtk::Application) {
                 let new_map_id = uic.new_map_id.get_text().unwrap_or("newmap".into());
                 let new_map = EditingMap::new(&new_map_id, width, height);
                 *uic.map.borrow_mut() = new_map;
+                uic.history.borrow_mut().clear();
                 uic.set_signal_mode(false);
                 uic.property_controls.update(&*uic.map.borrow());
                 uic.set_signal_mode(true);
                 uic.map_redraw();
                 *uic.filepath.borrow_mut() = None;
+                *uic.saved_map_id.borrow_mut() = None;
             }
         });
     }
@@ -182,10 +276,17 @@ pub fn build_ui(application: &gtk::Application) {
         menu_open.connect_activate(move |_| {
             if let Some(path) = file_open(&uic) {
                 match crate::file::load_from_file(&path) {
-                    Ok(mapobj) => {
+                    Ok(mapobjs) => {
+                        let mapobj = if let Some(mapobj) = select_map(&uic, mapobjs) {
+                            mapobj
+                        } else {
+                            return;
+                        };
                         {
+                            *uic.saved_map_id.borrow_mut() = Some(mapobj.id.clone());
                             *uic.map.borrow_mut() = EditingMap::from(mapobj);
                         }
+                        uic.history.borrow_mut().clear();
                         uic.set_signal_mode(false);
                         uic.property_controls.update(&*uic.map.borrow());
                         uic.set_signal_mode(true);
@@ -200,16 +301,16 @@ pub fn build_ui(application: &gtk::Application) {
     { // Menu (save)
         let uic = ui.clone();
         menu_save.connect_activate(move |_| {
-            let path = if let Some(path) = (*uic.filepath).clone().into_inner() {
-                path
+            let (path, replaced_id) = if let Some(path) = (*uic.filepath).clone().into_inner() {
+                (path, uic.saved_map_id.borrow().clone())
             } else {
                 if let Some(path) = file_save_as(&uic) {
-                    path
+                    (path, None)
                 } else {
                     return;
                 }
             };
-            match save_to(&uic, path.clone()) {
+            match save_to(&uic, path.clone(), replaced_id.as_ref().map(|id| id.as_str())) {
                 Ok(_) => { *uic.filepath.borrow_mut() = Some(path); }
                 Err(e) => { show_err_dialog(&uic, &e.to_string()); }
             }
@@ -219,13 +320,25 @@ pub fn build_ui(application: &gtk::Application) {
         let uic = ui.clone();
         menu_save_as.connect_activate(move |_| {
             if let Some(path) = file_save_as(&uic) {
-                match save_to(&uic, path.clone()) {
+                match save_to(&uic, path.clone(), None) {
                     Ok(_) => { *uic.filepath.borrow_mut() = Some(path); }
                     Err(e) => { show_err_dialog(&uic, &e.to_string()); }
                 }
             }
         });
     }
+    { // Menu (export visible layers)
+        let uic = ui.clone();
+        menu_export_visible.connect_activate(move |_| {
+            // Hidden layers are kept in the editing map and the normal save
+            if let Some(path) = file_save_as(&uic) {
+                let map = uic.map.borrow().visible_layers_only(&*uic.layer_visible.borrow());
+                if let Err(e) = crate::file::save_to_file(&path, map.create_mapobj(), None) {
+                    show_err_dialog(&uic, &e.to_string());
+                }
+            }
+        });
+    }
     { // Menu (quit)
         let uic = ui.clone();
         menu_quit.connect_activate(move |_| {
@@ -235,41 +348,126 @@ pub fn build_ui(application: &gtk::Application) {
     { // Menu (resize)
         let uic = ui.clone();
         menu_resize.connect_activate(move |_| {
+            for adjustment in &[&uic.adjustment_resize_top, &uic.adjustment_resize_bottom,
+                                &uic.adjustment_resize_left, &uic.adjustment_resize_right] {
+                adjustment.set_value(0.0);
+            }
             uic.resize_dialog.show();
             let responce_id = uic.resize_dialog.run();
             uic.resize_dialog.hide();
             if responce_id == 1 {
-                let width  = uic.adjustment_map_width.get_value() as u32;
-                let height = uic.adjustment_map_height.get_value() as u32;
-                uic.adjustment_map_pos_x.set_value(0.0);
-                uic.adjustment_map_pos_y.set_value(0.0);
-                uic.adjustment_map_pos_x.set_upper(width as f64);
-                uic.adjustment_map_pos_y.set_upper(height as f64);
-                uic.map.borrow_mut().resize(width, height);
-                uic.map_redraw();
+                let top    = uic.adjustment_resize_top.get_value() as i32;
+                let bottom = uic.adjustment_resize_bottom.get_value() as i32;
+                let left   = uic.adjustment_resize_left.get_value() as i32;
+                let right  = uic.adjustment_resize_right.get_value() as i32;
+                let (width, height) = {
+                    let map = uic.map.borrow();
+                    (map.width as i32 + left + right, map.height as i32 + top + bottom)
+                };
+                if width < 1 || height < 1 {
+                    show_err_dialog(&uic, &format!("Invalid map size {}x{}", width, height));
+                    return;
+                }
+                let fill = match uic.selected_item.get() {
+                    SelectedItem::Tile(idx) if uic.resize_fill_selected.get_active() => TileLayers::from(idx),
+                    _ => TileLayers::default(),
+                };
+                uic.push_undo();
+                uic.map.borrow_mut().resize(Vec2d(-left, -top), width as u32, height as u32, fill);
+                uic.after_resize();
             }
         });
     }
+    { // Menu (crop)
+        let uic = ui.clone();
+        menu_crop.connect_activate(move |_| {
+            if let Some((top_left, bottom_right)) = uic.selected_rect() {
+                uic.push_undo();
+                uic.map.borrow_mut().crop(
+                    Vec2d(top_left.0, top_left.1), Vec2d(bottom_right.0, bottom_right.1));
+                uic.selection.set(None);
+                uic.after_resize();
+            } else {
+                show_err_dialog(&uic, "Select an area with the select tool to crop");
+            }
+        });
+    }
+    { // Menu (go to coordinate)
+        let uic = ui.clone();
+        menu_goto.connect_activate(move |_| {
+            uic.goto_dialog.show();
+            let responce_id = uic.goto_dialog.run();
+            uic.goto_dialog.hide();
+            if responce_id == 1 {
+                let x = uic.goto_x.get_text().unwrap_or_default();
+                let y = uic.goto_y.get_text().unwrap_or_default();
+                match uic.parse_coordinate(&x, &y) {
+                    Ok(pos) => { centering_to(&uic, pos); }
+                    Err(e) => { show_err_dialog(&uic, &e); }
+                }
+            }
+        });
+    }
+    { // Menu (grid)
+        let uic = ui.clone();
+        menu_grid.connect_toggled(move |m| {
+            uic.grid_visible.set(m.get_active());
+            uic.view_redraw();
+        });
+    }
+    { // Menu (zoom)
+        let uic = ui.clone();
+        menu_zoom_in.connect_activate(move |_| {
+            uic.zoom_step(1);
+        });
+        let uic = ui.clone();
+        menu_zoom_out.connect_activate(move |_| {
+            uic.zoom_step(-1);
+        });
+        let uic = ui.clone();
+        menu_zoom_reset.connect_activate(move |_| {
+            uic.set_zoom(1.0);
+        });
+    }
     { // Scroll (x)
         let uic = ui.clone();
         ui.adjustment_map_pos_x.connect_value_changed(move |_| {
-            uic.map_redraw();
+            uic.view_redraw();
         });
     }
     { // Scroll (y)
         let uic = ui.clone();
         ui.adjustment_map_pos_y.connect_value_changed(move |_| {
-            uic.map_redraw();
+            uic.view_redraw();
         });
     }
     { // Key press
-        use gdk::enums::key::{Shift_L, Shift_R};
+        use gdk::enums::key::{Shift_L, Shift_R, c, v, y, z, Z, plus, equal, minus, _0};
         let uic = ui.clone();
         ui.window.connect_key_press_event(move |_, event_key| {
             let keyval = event_key.get_keyval();
             if keyval == Shift_L || keyval == Shift_R {
                 uic.shift.set(true);
             }
+            if event_key.get_state().contains(gdk::ModifierType::CONTROL_MASK) {
+                // Ctrl+Z to undo, and Ctrl+Y or Ctrl+Shift+Z to redo
+                if keyval == z {
+                    uic.undo();
+                } else if keyval == y || keyval == Z {
+                    uic.redo();
+                } else if keyval == c {
+                    uic.copy_selection();
+                } else if keyval == v && uic.clipboard.borrow().is_some() {
+                    // Pasting is done by clicking with the stamp tool
+                    uic.tool_stamp.set_active(true);
+                } else if keyval == plus || keyval == equal {
+                    uic.zoom_step(1);
+                } else if keyval == minus {
+                    uic.zoom_step(-1);
+                } else if keyval == _0 {
+                    uic.set_zoom(1.0);
+                }
+            }
             Inhibit(false)
         });
         let uic = ui.clone();
@@ -299,25 +497,50 @@ pub fn build_ui(application: &gtk::Application) {
             uic.current_layer.set(3);
         });
     }
+    { // Tool buttons
+        let tools = [
+            (&ui.tool_pen, Tool::Pen), (&ui.tool_rect, Tool::Rect), (&ui.tool_fill, Tool::Fill),
+            (&ui.tool_select, Tool::Select), (&ui.tool_stamp, Tool::Stamp)];
+        for &(button, tool) in &tools {
+            let uic = ui.clone();
+            button.connect_toggled(move |b| {
+                if b.get_active() {
+                    uic.tool.set(tool);
+                    uic.selection.set(None);
+                    uic.view_redraw();
+                }
+            });
+        }
+    }
     { // Layer check bottons
         let uic = ui.clone();
         ui.checkbutton_layer0.connect_toggled(move |b| { // Layer 0
-            uic.layer_visible.borrow_mut()[0] = b.get_active();
+            uic.layer_visible.borrow_mut().tile[0] = b.get_active();
             uic.map_redraw();
         });
         let uic = ui.clone();
         ui.checkbutton_layer1.connect_toggled(move |b| { // Layer 1
-            uic.layer_visible.borrow_mut()[1] = b.get_active();
+            uic.layer_visible.borrow_mut().tile[1] = b.get_active();
             uic.map_redraw();
         });
         let uic = ui.clone();
         ui.checkbutton_layer2.connect_toggled(move |b| { // Layer 2
-            uic.layer_visible.borrow_mut()[2] = b.get_active();
+            uic.layer_visible.borrow_mut().tile[2] = b.get_active();
             uic.map_redraw();
         });
         let uic = ui.clone();
         ui.checkbutton_layer3.connect_toggled(move |b| { // Layer 3
-            uic.layer_visible.borrow_mut()[3] = b.get_active();
+            uic.layer_visible.borrow_mut().tile[3] = b.get_active();
+            uic.map_redraw();
+        });
+        let uic = ui.clone();
+        ui.checkbutton_wall.connect_toggled(move |b| { // Wall
+            uic.layer_visible.borrow_mut().wall = b.get_active();
+            uic.map_redraw();
+        });
+        let uic = ui.clone();
+        ui.checkbutton_deco.connect_toggled(move |b| { // Deco
+            uic.layer_visible.borrow_mut().deco = b.get_active();
             uic.map_redraw();
         });
     }
@@ -331,14 +554,47 @@ pub fn build_ui(application: &gtk::Application) {
 
 fn on_map_clicked(ui: &Ui, eb: &gdk::EventButton) {
     let button = eb.get_button();
+    let tile_pos = ui.cursor_to_tile_pos(eb.get_position());
     if button == WRITE_BUTTON {
-        ui.drag_mode.set(DragMode::Write);
-        try_write(ui, eb.get_position());
+        let select_tile = ui.selected_item.get() == SelectedItem::SelectTile;
+        match ui.tool.get() {
+            Tool::Rect if !select_tile => {
+                if ui.is_in_map(tile_pos) {
+                    ui.drag_mode.set(DragMode::Rect);
+                    ui.selection.set(Some((tile_pos, tile_pos)));
+                    ui.view_redraw();
+                }
+            }
+            Tool::Fill if !select_tile => {
+                flood_fill(ui, tile_pos);
+            }
+            Tool::Select => {
+                if ui.is_in_map(tile_pos) {
+                    ui.drag_mode.set(DragMode::Select);
+                    ui.selection.set(Some((tile_pos, tile_pos)));
+                    ui.view_redraw();
+                }
+            }
+            Tool::Stamp => {
+                stamp(ui, tile_pos);
+            }
+            _ => {
+                // One drag stroke is one undo entry
+                if !select_tile {
+                    ui.push_undo();
+                }
+                ui.drag_mode.set(DragMode::Write);
+                ui.drag_last_pos.set(Some(tile_pos));
+                try_write(ui, tile_pos);
+            }
+        }
     } else if button == CENTERING_BUTTON {
-        centering_to(ui, ui.cursor_to_tile_pos(eb.get_position()));
+        centering_to(ui, tile_pos);
     } else if button == ERASE_BUTTON {
+        ui.push_undo();
         ui.drag_mode.set(DragMode::Erase);
-        try_erase(ui, eb.get_position());
+        ui.drag_last_pos.set(Some(tile_pos));
+        try_erase(ui, tile_pos);
     }
 }
 
@@ -347,21 +603,44 @@ fn on_motion(ui: &Ui, em: &gdk::EventMotion, w: i32, h: i32) {
     let h = h as f64;
     let pos = em.get_position();
     if pos.0 < 0.0 || pos.1 < 0.0 || pos.0 > w || pos.1 > h { // Out of drawing widget
+        // Restart the line when the cursor comes back
+        ui.drag_last_pos.set(None);
         return;
     }
-    match ui.drag_mode.get() {
-        DragMode::Write => {
-            try_write(ui, pos);
+    let tile_pos = ui.cursor_to_tile_pos(pos);
+    let drag_mode = ui.drag_mode.get();
+    if drag_mode == DragMode::Rect || drag_mode == DragMode::Select {
+        if let Some((start, _)) = ui.selection.get() {
+            let map = ui.map.borrow();
+            let end = (tile_pos.0.max(0).min(map.width as i32 - 1),
+                       tile_pos.1.max(0).min(map.height as i32 - 1));
+            ui.selection.set(Some((start, end)));
         }
-        DragMode::Erase => {
-            try_erase(ui, pos);
+        ui.view_redraw();
+    } else if drag_mode != DragMode::None && ui.drag_last_pos.get() != Some(tile_pos) {
+        // Paint all tiles between the previous and current positions
+        // because motion events skip tiles when the cursor moves fast
+        let start = ui.drag_last_pos.get().unwrap_or(tile_pos);
+        for p in line_tiles(start, tile_pos) {
+            if Some(p) == ui.drag_last_pos.get() {
+                continue;
+            }
+            match drag_mode {
+                DragMode::Write => try_write(ui, p),
+                DragMode::Erase => try_erase(ui, p),
+                _ => (),
+            }
         }
-        _ => (),
+        ui.drag_last_pos.set(Some(tile_pos));
     }
     // Update cursor position display
-    let (ix, iy) = ui.cursor_to_tile_pos(pos);
-    let text = format!("({},{})", ix, iy);
-    ui.label_cursor_pos.set_text(&text);
+    if ui.is_in_map(tile_pos) {
+        let (ix, iy) = tile_pos;
+        let text = format!("({},{})", ix, iy);
+        ui.label_cursor_pos.set_text(&text);
+    } else {
+        ui.label_cursor_pos.set_text("");
+    }
 }
 
 fn file_open(ui: &Ui) -> Option<PathBuf> {
@@ -382,6 +661,27 @@ fn file_open(ui: &Ui) -> Option<PathBuf> {
     None
 }
 
+/// Select the map to edit if the pak file has multiple maps
+fn select_map(ui: &Ui, mut mapobjs: Vec<MapTemplateObject>) -> Option<MapTemplateObject> {
+    if mapobjs.len() == 1 {
+        return mapobjs.pop();
+    }
+    ui.select_map_combo.remove_all();
+    for mapobj in &mapobjs {
+        let text = format!("{} ({}x{})", mapobj.id, mapobj.w, mapobj.h);
+        ui.select_map_combo.append_text(&text);
+    }
+    ui.select_map_combo.set_active(0);
+    ui.select_map_dialog.show();
+    let responce_id = ui.select_map_dialog.run();
+    ui.select_map_dialog.hide();
+    if responce_id != 1 {
+        return None;
+    }
+    let i = ui.select_map_combo.get_active() as usize;
+    if i < mapobjs.len() { Some(mapobjs.swap_remove(i)) } else { None }
+}
+
 fn file_save_as(ui: &Ui) -> Option<PathBuf> {
     let file_chooser = gtk::FileChooserDialog::new(
         Some("Save map object"), Some(&ui.window), gtk::FileChooserAction::Save);
@@ -399,9 +699,12 @@ fn file_save_as(ui: &Ui) -> Option<PathBuf> {
     None
 }
 
-fn save_to(ui: &Ui, path: PathBuf) -> Result<(), Box<Error>> {
+fn save_to(ui: &Ui, path: PathBuf, replaced_id: Option<&str>) -> Result<(), Box<Error>> {
     let mapobj = ui.map.borrow().create_mapobj();
-    crate::file::save_to_file(&path, mapobj)
+    let id = mapobj.id.clone();
+    crate::file::save_to_file(&path, mapobj, replaced_id)?;
+    *ui.saved_map_id.borrow_mut() = Some(id);
+    Ok(())
 }
 
 fn create_file_filter() -> gtk::FileFilter {
@@ -411,10 +714,40 @@ fn create_file_filter() -> gtk::FileFilter {
     f
 }
 
-fn try_write(ui: &Ui, pos: (f64, f64)) {
-    let (ix, iy) = ui.cursor_to_tile_pos(pos);
-    if ix < ui.map.borrow().width as i32 && iy < ui.map.borrow().height as i32 {
+/// Tiles on the line from p0 to p1 by Bresenham's algorithm
+fn line_tiles(p0: (i32, i32), p1: (i32, i32)) -> Vec<(i32, i32)> {
+    let dx = (p1.0 - p0.0).abs();
+    let dy = -(p1.1 - p0.1).abs();
+    let sx = if p0.0 < p1.0 { 1 } else { -1 };
+    let sy = if p0.1 < p1.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = p0;
+    let mut tiles = Vec::new();
+
+    loop {
+        tiles.push((x, y));
+        if (x, y) == p1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    tiles
+}
+
+fn try_write(ui: &Ui, (ix, iy): (i32, i32)) {
+    if ui.is_in_map((ix, iy)) {
         match ui.selected_item.get() {
+            _ if !ui.is_current_layer_visible() => {
+                return;
+            }
             SelectedItem::Tile(idx) => {
                 if ui.shift.get() {
                     ui.map.borrow_mut().tile_layer_draw(Vec2d(ix, iy), idx, ui.current_layer.get());
@@ -429,42 +762,102 @@ fn try_write(ui: &Ui, pos: (f64, f64)) {
                 ui.map.borrow_mut().set_deco(Vec2d(ix, iy), Some(idx));
             }
             SelectedItem::SelectTile => {
-                ui.property_controls.selected_tile.set(Vec2d(ix, iy));
-                ui.property_controls.label_selected_tile.set_text(&format!(
-                    "Selected Tile ({}, {})", ix, iy));
-                ui.set_signal_mode(false);
-                ui.property_controls.update(&*ui.map.borrow());
-                ui.set_signal_mode(true);
+                ui.property_controls.select_tile(ui, Vec2d(ix, iy));
             }
         }
-        ui.map_redraw();
+        ui.tile_redraw((ix, iy));
+    }
+}
+
+/// Write the selected item to all tiles in the dragged rectangle
+fn fill_rect(ui: &Ui) {
+    let (top_left, bottom_right) = if let Some(rect) = ui.selected_rect() { rect } else { return; };
+    ui.selection.set(None);
+    ui.push_undo();
+    for p in RectIter::new(top_left, bottom_right) {
+        try_write(ui, (p.0, p.1));
+    }
+    // Remove the rectangle drawn over the map
+    ui.view_redraw();
+}
+
+/// Write the selected item to the contiguous area that has the same tile, wall, or deco
+fn flood_fill(ui: &Ui, pos: (i32, i32)) {
+    let target = match ui.selected_item.get() {
+        _ if !ui.is_current_layer_visible() => { return; }
+        SelectedItem::Tile(_) => FillTarget::Tile(ui.current_layer.get()),
+        SelectedItem::Wall(_) => FillTarget::Wall,
+        SelectedItem::Deco(_) => FillTarget::Deco,
+        SelectedItem::SelectTile => { return; }
+    };
+    let area = ui.map.borrow().contiguous_area(Vec2d(pos.0, pos.1), target);
+    if area.is_empty() {
+        return;
+    }
+    ui.push_undo();
+    for p in area {
+        try_write(ui, (p.0, p.1));
+    }
+}
+
+/// Paste the copied area with its top left at the position
+fn stamp(ui: &Ui, pos: (i32, i32)) {
+    if !ui.is_in_map(pos) {
+        return;
     }
+    let clipboard = ui.clipboard.borrow();
+    let area = if let Some(area) = clipboard.as_ref() { area } else { return; };
+    ui.push_undo();
+    ui.map.borrow_mut().paste(Vec2d(pos.0, pos.1), area, &*ui.layer_visible.borrow());
+    ui.map_redraw();
 }
 
-fn try_erase(ui: &Ui, pos: (f64, f64)) {
-    let (ix, iy) = ui.cursor_to_tile_pos(pos);
-    if !(ix < ui.map.borrow().width as i32 && iy < ui.map.borrow().height as i32) {
+fn try_erase(ui: &Ui, (ix, iy): (i32, i32)) {
+    if !ui.is_in_map((ix, iy)) {
         return;
     }
+    // Hidden layers are not edited
+    if !ui.is_current_layer_visible() {
+        return;
+    }
+    let pos = Vec2d(ix, iy);
+    // Only the layer of the selected item is erased
     match ui.selected_item.get() {
         SelectedItem::Tile(_) => {
-            ui.map.borrow_mut().erase_layer(Vec2d(ix, iy), ui.current_layer.get());
+            ui.map.borrow_mut().erase_layer(pos, ui.current_layer.get());
+        }
+        SelectedItem::Wall(_) => {
+            ui.map.borrow_mut().erase_wall(pos);
         }
-        _ => {
-            ui.map.borrow_mut().erase(Vec2d(ix, iy));
+        SelectedItem::Deco(_) => {
+            ui.map.borrow_mut().set_deco(pos, None);
+        }
+        SelectedItem::SelectTile => {
+            // Objects in visible layers, and the trap and the chara on the tile
+            let layer_visible = *ui.layer_visible.borrow();
+            let mut map = ui.map.borrow_mut();
+            if layer_visible.wall {
+                map.erase_wall(pos);
+            }
+            if layer_visible.deco {
+                map.set_deco(pos, None);
+            }
+            map.set_trap(pos, None);
+            map.set_chara(pos, None);
         }
     }
-    ui.map_redraw();
+    ui.tile_redraw((ix, iy));
 }
 
 fn centering_to(ui: &Ui, pos: (i32, i32)) {
     let area_w = ui.map_drawing_area.get_allocated_width();
     let area_h = ui.map_drawing_area.get_allocated_height();
-    let x = pos.0 - area_w / (TILE_SIZE_I * 2);
-    let y = pos.1 - area_h / (TILE_SIZE_I * 2);
+    let ts = ui.tile_size();
+    let x = pos.0 - (area_w as f64 / (ts * 2.0)) as i32;
+    let y = pos.1 - (area_h as f64 / (ts * 2.0)) as i32;
     ui.adjustment_map_pos_x.set_value(x as f64);
     ui.adjustment_map_pos_y.set_value(y as f64);
-    ui.map_redraw();
+    ui.view_redraw();
 }
 
 fn show_err_dialog(ui: &Ui, msg: &str) {
@@ -480,10 +873,51 @@ fn show_err_dialog(ui: &Ui, msg: &str) {
 }
 
 impl Ui {
+    /// Render the whole map again
     pub fn map_redraw(&self) {
+        self.map_cache.borrow_mut().invalidate();
+        self.map_drawing_area.queue_draw();
+    }
+
+    /// Redraw the view for scrolling or overlay changes. The rendered map is reused.
+    pub fn view_redraw(&self) {
         self.map_drawing_area.queue_draw();
     }
 
+    /// Redraw the changed tile and the images overlapping it
+    pub fn tile_redraw(&self, pos: (i32, i32)) {
+        self.map_cache.borrow_mut().set_dirty(pos);
+        let ts = self.tile_size();
+        let map_pos = self.get_map_pos();
+        let x = (pos.0 - map_pos.0) as f64 * ts;
+        let y = (pos.1 - map_pos.1 - MAX_OVERHANG) as f64 * ts;
+        self.map_drawing_area.queue_draw_area(
+            x as i32, y as i32, ts as i32, (ts * (MAX_OVERHANG + 1) as f64) as i32);
+    }
+
+    /// Size of tiles on the screen in pixels
+    pub fn tile_size(&self) -> f64 {
+        TILE_SIZE_I as f64 * self.zoom.get()
+    }
+
+    /// Change zoom keeping the tile at the center of the view
+    pub fn set_zoom(&self, zoom: f64) {
+        let area_w = self.map_drawing_area.get_allocated_width() as f64;
+        let area_h = self.map_drawing_area.get_allocated_height() as f64;
+        let center = self.cursor_to_tile_pos((area_w / 2.0, area_h / 2.0));
+        self.zoom.set(zoom);
+        centering_to(self, center);
+        self.map_redraw();
+    }
+
+    /// Zoom in if step is positive, or zoom out if negative
+    pub fn zoom_step(&self, step: i32) {
+        let current = self.zoom.get();
+        let i = ZOOM_LEVELS.iter().position(|z| *z == current).unwrap_or(3) as i32;
+        let i = (i + step).max(0).min(ZOOM_LEVELS.len() as i32 - 1);
+        self.set_zoom(ZOOM_LEVELS[i as usize]);
+    }
+
     pub fn get_map_pos(&self) -> (i32, i32) {
         let pos_x = self.adjustment_map_pos_x.get_value() as i32;
         let pos_y = self.adjustment_map_pos_y.get_value() as i32;
@@ -505,10 +939,103 @@ impl Ui {
         self.signal_mode.get()
     }
 
+    pub fn is_in_map(&self, pos: (i32, i32)) -> bool {
+        let map = self.map.borrow();
+        pos.0 >= 0 && pos.1 >= 0 && pos.0 < map.width as i32 && pos.1 < map.height as i32
+    }
+
+    /// Parse the tile coordinate input by user. Coordinates out of the map are rejected.
+    pub fn parse_coordinate(&self, x: &str, y: &str) -> Result<(i32, i32), String> {
+        let parse = |s: &str| s.trim().parse::<i32>()
+            .map_err(|_| format!("\"{}\" is not a valid coordinate", s));
+        let pos = (parse(x)?, parse(y)?);
+        if !self.is_in_map(pos) {
+            let map = self.map.borrow();
+            return Err(format!("({}, {}) is out of the map (size {}x{})",
+                               pos.0, pos.1, map.width, map.height));
+        }
+        Ok(pos)
+    }
+
+    /// The layer edited by the selected item is visible
+    pub fn is_current_layer_visible(&self) -> bool {
+        let layer_visible = self.layer_visible.borrow();
+        match self.selected_item.get() {
+            SelectedItem::Tile(_) => layer_visible.tile[self.current_layer.get()],
+            SelectedItem::Wall(_) => layer_visible.wall,
+            SelectedItem::Deco(_) => layer_visible.deco,
+            SelectedItem::SelectTile => true,
+        }
+    }
+
+    /// Save current map to undo stack
+    pub fn push_undo(&self) {
+        self.history.borrow_mut().push(&*self.map.borrow());
+    }
+
+    pub fn undo(&self) {
+        let map = self.history.borrow_mut().undo(&*self.map.borrow());
+        if let Some(map) = map {
+            self.set_map_from_history(map);
+        }
+    }
+
+    pub fn redo(&self) {
+        let map = self.history.borrow_mut().redo(&*self.map.borrow());
+        if let Some(map) = map {
+            self.set_map_from_history(map);
+        }
+    }
+
+    /// Reset the view and the selected tile which may be out of the resized map
+    fn after_resize(&self) {
+        let (width, height) = (self.map.borrow().width, self.map.borrow().height);
+        self.reset_map_size(width, height);
+        self.property_controls.select_tile(self, Vec2d(0, 0));
+        self.map_redraw();
+    }
+
+    fn set_map_from_history(&self, map: EditingMap) {
+        let resized = {
+            let current = self.map.borrow();
+            current.width != map.width || current.height != map.height
+        };
+        *self.map.borrow_mut() = map;
+        if resized {
+            self.after_resize();
+            return;
+        }
+        self.set_signal_mode(false);
+        self.property_controls.update(&*self.map.borrow());
+        self.set_signal_mode(true);
+        self.map_redraw();
+    }
+
+    /// Top left and bottom right of the rectangle dragged by a tool
+    pub fn selected_rect(&self) -> Option<((i32, i32), (i32, i32))> {
+        self.selection.get().map(|(start, end)| {
+            ((start.0.min(end.0), start.1.min(end.1)), (start.0.max(end.0), start.1.max(end.1)))
+        })
+    }
+
+    /// Copy the visible layers of the selected area to the clipboard
+    pub fn copy_selection(&self) {
+        if self.tool.get() != Tool::Select {
+            return;
+        }
+        if let Some((top_left, bottom_right)) = self.selected_rect() {
+            let area = self.map.borrow().copy_area(
+                Vec2d(top_left.0, top_left.1), Vec2d(bottom_right.0, bottom_right.1))
+                .visible_layers_only(&*self.layer_visible.borrow());
+            *self.clipboard.borrow_mut() = Some(area);
+        }
+    }
+
     pub fn cursor_to_tile_pos(&self, pos: (f64, f64)) -> (i32, i32) {
         let map_pos = self.get_map_pos();
-        let ix = (pos.0 / TILE_SIZE_I as f64) as i32;
-        let iy = (pos.1 / TILE_SIZE_I as f64) as i32;
+        let ts = self.tile_size();
+        let ix = (pos.0 / ts).floor() as i32;
+        let iy = (pos.1 / ts).floor() as i32;
         (ix + map_pos.0, iy + map_pos.1)
     }
 }
@@ -0,0 +1,69 @@
+
+use array2d::*;
+use rng::gen_range;
+use super::{GeneratedMap, TileKind};
+use super::fractal::{create_reach_map, pick_passable_tile};
+
+pub fn write_to_map(gm: &mut GeneratedMap, wall_ratio: f32, n_iter: u32) {
+    let (start, reach_map) = loop {
+        for p in gm.tile.iter_idx() {
+            let is_edge = p.0 == 0 || p.1 == 0 || p.0 == gm.size.0 - 1 || p.1 == gm.size.1 - 1;
+            gm.tile[p] = if is_edge || gen_range(0.0, 1.0) < wall_ratio {
+                TileKind::Wall
+            } else {
+                TileKind::Floor
+            };
+        }
+
+        for _ in 0..n_iter {
+            smooth(gm);
+        }
+
+        if !gm.tile.iter().any(|t| t.is_passable()) {
+            continue;
+        }
+        
+        // Determine start and end
+        let start = pick_passable_tile(&gm);
+        let (reach_map, n_reachable_tile) = create_reach_map(&gm, start);
+        // If reachable tiles are too few, create map again
+        if n_reachable_tile < (gm.size.0 * gm.size.1) as u32 / 4 {
+            continue
+        } else {
+            break (start, reach_map);
+        }
+    };
+
+    loop {
+        let end = pick_passable_tile(&gm);
+        if start != end && reach_map[end] {
+            gm.entrance = start;
+            gm.exit = Some(end);
+            break;
+        }
+    }
+
+    // Write walls for unreachable tiles from the start
+    for p in gm.tile.iter_idx() {
+        if !reach_map[p] && gm.tile[p] != TileKind::Wall {
+            gm.tile[p] = TileKind::Wall;
+        }
+    }
+}
+
+/// A tile becomes wall if five or more neighbors are walls.
+/// Walls remain if four neighbors are walls. Tiles outside the map are counted as walls.
+fn smooth(gm: &mut GeneratedMap) {
+    let prev = gm.tile.clone();
+
+    for p in prev.iter_idx() {
+        let n_wall = Direction::EIGHT_DIRS.iter()
+            .filter(|dir| prev.get(p + dir.as_vec()).map_or(true, |t| *t == TileKind::Wall))
+            .count();
+        gm.tile[p] = if n_wall >= 5 || (n_wall == 4 && prev[p] == TileKind::Wall) {
+            TileKind::Wall
+        } else {
+            TileKind::Floor
+        };
+    }
+}
@@ -126,7 +126,7 @@ fn calc_threshold(fractal: &Array2d<f32>, floor_ratio: f32) -> f32 {
 }
 
 /// Calculate tiles are reacheable from given tile
-fn create_reach_map(map: &GeneratedMap, start: Vec2d) -> (Array2d<bool>, u32) {
+pub(crate) fn create_reach_map(map: &GeneratedMap, start: Vec2d) -> (Array2d<bool>, u32) {
     let mut reachable = Array2d::new(map.size.0 as u32, map.size.1 as u32, false);
     let mut reachable_tile_count = 0;
 
@@ -163,7 +163,7 @@ fn create_reach_map(map: &GeneratedMap, start: Vec2d) -> (Array2d<bool>, u32) {
 }
 
 /// Pick one passable tile at random
-fn pick_passable_tile(map: &GeneratedMap) -> Vec2d {    
+pub(crate) fn pick_passable_tile(map: &GeneratedMap) -> Vec2d {    
     loop {
         let p = Vec2d(gen_range(0, map.size.0), gen_range(0, map.size.1));
 
@@ -8,6 +8,7 @@ use array2d::*;
 
 mod lattice;
 mod fractal;
+mod cave;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum TileKind {
@@ -35,6 +36,7 @@ enum MapGenParam {
     Flat,
     Lattice { nx: u32, ny: u32, step_min: u32, step_max: u32, door_weight: f64 },
     Fractal,
+    Cave { wall_ratio: f32, n_iter: u32 },
 }
 
 pub struct MapGenerator {
@@ -80,6 +82,14 @@ impl MapGenerator {
         mg
     }
 
+    /// Create cave map by cellular automata.
+    /// Tiles become walls by wall_ratio at first, and then are smoothed n_iter times.
+    pub fn cave(self, wall_ratio: f32, n_iter: u32) -> MapGenerator {
+        let mut mg = self;
+        mg.genparam = Some(MapGenParam::Cave { wall_ratio, n_iter });
+        mg
+    }
+
     /// Generate one map
     pub fn generate(mut self) -> GeneratedMap {
         match self.genparam.expect("Map generate before giving parameters") {
@@ -95,6 +105,10 @@ impl MapGenerator {
                 fractal::write_to_map(&mut self.map);
                 return self.map;
             },
+            MapGenParam::Cave { wall_ratio, n_iter } => {
+                cave::write_to_map(&mut self.map, wall_ratio, n_iter);
+                return self.map;
+            },
         }
     }
 }
@@ -149,4 +163,13 @@ mod tests {
         let map = MapGenerator::new((30, 30)).fractal().generate();
         println!("{}", map);
     }
+
+    #[test]
+    fn cave_map() {
+        println!("Cave map:");
+        let map = MapGenerator::new((30, 30)).cave(0.45, 4).generate();
+        println!("{}", map);
+        assert!(map.tile[map.entrance].is_passable());
+        assert!(map.tile[map.exit.unwrap()].is_passable());
+    }
 }
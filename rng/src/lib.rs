@@ -1,6 +1,7 @@
 //! Helper crate for using thread local and fast random number generator
 
 use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 use rand::SeedableRng;
 use rand::distributions::uniform::{SampleUniform, SampleBorrow};
 use rand::RngCore;
@@ -8,6 +9,7 @@ use rand_xorshift::XorShiftRng;
 pub use rand::Rng;
 pub use rand::thread_rng;
 pub use rand::seq::SliceRandom;
+use serde_derive::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, Copy)]
 pub struct GameRng;
@@ -45,6 +47,12 @@ impl RngCore for GameRng {
     }
 }
 
+/// State of the random number generator, saved to continue the same random sequence
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RngState(XorShiftRng);
+
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+
 pub fn get_rng() -> GameRng {
     GameRng
 }
@@ -56,6 +64,33 @@ pub fn reseed() {
     })
 }
 
+/// Reseed by given value. The same seed reproduces the same random sequence.
+pub fn reseed_with(seed: u64) {
+    XORSHIFT_RNG.with(|xorshift_rng| {
+        xorshift_rng.replace(XorShiftRng::seed_from_u64(seed));
+    })
+}
+
+/// Start deterministic mode by the seed. Games are reproduced by the same seed and inputs.
+pub fn set_deterministic(seed: u64) {
+    reseed_with(seed);
+    DETERMINISTIC.store(true, Ordering::Relaxed);
+}
+
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
+
+pub fn state() -> RngState {
+    XORSHIFT_RNG.with(|xorshift_rng| RngState(xorshift_rng.borrow().clone()))
+}
+
+pub fn set_state(state: RngState) {
+    XORSHIFT_RNG.with(|xorshift_rng| {
+        xorshift_rng.replace(state.0);
+    })
+}
+
 pub fn next_u32() -> u32 {
     let mut rng = GameRng;
     rng.next_u32()
@@ -92,5 +127,15 @@ mod tests {
         let average = sum / N as f64;
         println!("average is {}", average);
     }    
+
+    #[test]
+    fn state() {
+        reseed_with(1);
+        let saved = super::state();
+        let a: Vec<u32> = (0..10).map(|_| next_u32()).collect();
+        set_state(saved);
+        let b: Vec<u32> = (0..10).map(|_| next_u32()).collect();
+        assert_eq!(a, b);
+    }
 }
 
@@ -20,5 +20,11 @@ pub struct Chara {
     pub sp_weak: i32,
     /// sp border of starving
     pub sp_starving: i32,
+    /// Speed of hasted characters in percent of the normal speed
+    pub haste_spd_percent: u16,
+    /// Max mp is the average of int and wil multiplied by this value
+    pub mp_per_int_wil: i32,
+    /// The probability that characters regenerate one mp per turn
+    pub mp_regen_probability: f64,
 }
 
@@ -12,11 +12,50 @@ pub struct DungeonGenParams {
     pub map_size: Vec2d,
     /// The probability of npc generation for each race
     pub npc_race_probability: HashMap<Race, f32>,
+    /// Id of the spawn table. Npcs are chosen by npc_race_probability if it is not given.
+    #[serde(default)]
+    pub spawn_table: Option<String>,
+    /// The number of npcs generated with a new floor
+    pub npc_num: u32,
+    /// Npcs don't respawn when the floor has this number of npcs
+    pub max_npc: u32,
+    /// The probability that a new npc appears per player turn
+    pub npc_respawn_probability: f64,
     /// Tile and wall ids
     pub terrain: Vec<[String; 2]>,
     /// Items generatation probability on each tile
     pub item_gen_probability: f64,
     /// The range of number of floor of auto generated dungeons
     pub floor_range: [u32; 2],
+    /// Map generation algorithm and its parameters
+    pub map_gen: MapGenKind,
+    /// Music id played on the floors
+    pub music: String,
+    /// Ambient darkness of the floors. 0 is fully lit and 255 is pitch dark.
+    pub darkness: u8,
+    /// Special tile ids of resources and their generation probability on each tile
+    #[serde(default)]
+    pub resource_gen_probability: HashMap<String, f64>,
+    /// Special tile ids of traps and their generation probability on each tile
+    #[serde(default)]
+    pub trap_gen_probability: HashMap<String, f64>,
+}
+
+/// Map generation algorithms
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum MapGenKind {
+    Flat,
+    /// Rooms in a lattice connected by doors and openings
+    Lattice { nx: u32, ny: u32, step_min: u32, step_max: u32, door_weight: f64 },
+    Fractal,
+    /// Caves smoothed by cellular automata
+    Cave { wall_ratio: f32, n_iter: u32 },
+}
+
+impl Default for MapGenKind {
+    fn default() -> MapGenKind {
+        MapGenKind::Fractal
+    }
 }
 
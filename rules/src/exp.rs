@@ -10,9 +10,23 @@ pub struct Exp {
     pub base_factor: f32,
     /// Base exp to weapon skills after attacking
     pub attack: u32,
+    /// Base exp to the used skill after killing a character
+    pub kill: u32,
     /// Base exp to Endurance skill when attacked
     pub endurance: u32,
     /// Base exp to Evasion skill when attacked
     pub evasion: u32,
+    /// Base exp to the skill of the recipe after crafting
+    pub craft: u32,
+    /// Base exp to the skill of the resource after harvesting
+    pub harvest: u32,
+    /// Base exp to Lockpicking skill after unlocking a door
+    pub lockpick: u32,
+    /// Base exp to Perception skill after detecting a trap
+    pub detect_trap: u32,
+    /// Base exp to Lockpicking skill after disarming a trap
+    pub disarm_trap: u32,
+    /// Base exp to Stealth skill when an npc doesn't notice the character in its sight
+    pub stealth: u32,
 }
 
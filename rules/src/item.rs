@@ -0,0 +1,20 @@
+
+use std::collections::HashMap;
+use common::gamedata::*;
+
+/// Rules for item attributes
+#[derive(Serialize, Deserialize)]
+pub struct Item {
+    /// Weight to choose each quality when generated
+    pub quality_gen_weight: HashMap<ItemQuality, f32>,
+    pub quality: HashMap<ItemQuality, ItemModifier>,
+    pub material: HashMap<MaterialKind, ItemModifier>,
+}
+
+/// Modifies the price and effectiveness of items
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ItemModifier {
+    pub price_percent: u32,
+    /// Applied to weapon dice, armor defence and medical effect
+    pub eff_percent: u32,
+}
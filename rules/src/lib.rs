@@ -10,10 +10,13 @@ pub mod chara;
 pub mod charagen;
 pub mod dungeon_gen;
 pub mod exp;
+pub mod item;
 pub mod newgame;
+pub mod npc;
 pub mod params;
 pub mod quest;
 pub mod town;
+pub mod weather;
 
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
@@ -27,10 +30,13 @@ pub struct Rules {
     pub chara_gen: charagen::CharaGen,
     pub dungeon_gen: dungeon_gen::DungeonGen,
     pub exp: exp::Exp,
+    pub item: item::Item,
     pub newgame: newgame::NewGame,
+    pub npc: npc::Npc,
     pub params: params::Params,
     pub quest: quest::Quest,
     pub town: town::Town,
+    pub weather: weather::Weather,
 }
 
 impl Rules {
@@ -40,10 +46,13 @@ impl Rules {
             chara_gen:   read_from_json(&rules_dir.join("charagen.json")),
             dungeon_gen: read_from_json(&rules_dir.join("dungeon_gen.json")),
             exp:         read_from_json(&rules_dir.join("exp.json")),
+            item:        read_from_json(&rules_dir.join("item.json")),
             newgame:     read_from_json(&rules_dir.join("newgame.json")),
+            npc:         read_from_json(&rules_dir.join("npc.json")),
             params:      read_from_json(&rules_dir.join("params.json")),
             quest:       read_from_json(&rules_dir.join("quest.json")),
             town:        read_from_json(&rules_dir.join("town.json")),
+            weather:     read_from_json(&rules_dir.join("weather.json")),
         }
     }
 }
@@ -8,10 +8,24 @@ use common::gamedata::*;
 pub struct NewGame {
     /// The choices of character class
     pub class_choices: Vec<CharaClass>,
-    pub start_region: String,
-    pub start_pos: Vec2d,
-    pub start_money: u32,
+    /// The choices of starting scenario
+    pub scenarios: Vec<StartScenario>,
+    /// Attribute points that the player allocates at character creation
+    pub stat_points: u32,
+    /// Maximum points allocated to one attribute
+    pub max_stat_points_per_attr: u32,
     pub chara_template_table: HashMap<CharaClass, String>,
     pub common_initial_skills: Vec<SkillKind>,
+    /// Ability ids that characters of the class know at the start
+    pub class_abilities: HashMap<CharaClass, Vec<String>>,
 }
 
+/// Where and how the player starts the game
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StartScenario {
+    /// Used as the text id to display
+    pub id: String,
+    pub start_region: String,
+    pub start_pos: Vec2d,
+    pub start_money: u32,
+}
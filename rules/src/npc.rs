@@ -0,0 +1,31 @@
+
+/// Rules for NPC behavior
+#[derive(Serialize, Deserialize)]
+pub struct Npc {
+    /// Hunger of town npcs is increased by this value per turn
+    pub hunger_per_turn: u16,
+    /// Fatigue of town npcs is increased by this value per turn
+    pub fatigue_per_turn: u16,
+    /// Social need of town npcs is increased by this value per turn
+    pub social_per_turn: u16,
+    /// The maximum value of each need
+    pub max_need: u16,
+    /// Utility of the default goal. Needs are prioritized when their utility exceeds this.
+    pub default_goal_utility: f32,
+    /// Fatigue is decreased by this value per turn while resting
+    pub rest_recovery: u16,
+    /// Defensive companions fight enemies within this distance from the player
+    pub defensive_range: i32,
+    /// Allies whose hp ratio is lower than this are healed by support companions
+    pub wounded_hp_ratio: f32,
+    /// Hp healed by support companions per turn
+    pub support_heal: i32,
+    /// Shopkeepers don't wander farther than this distance from their home
+    pub shopkeeper_range: i32,
+    /// Npcs don't wander farther than this distance from the place of their schedule
+    pub schedule_range: i32,
+    /// Detection ranges change by one tile per this many levels of perception and stealth skills
+    pub perception_divisor: u32,
+    /// Hostile npcs within this distance hear attacks
+    pub noise_range: i32,
+}
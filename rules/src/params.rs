@@ -14,5 +14,17 @@ pub struct Params {
     pub initial_date_day: u32,
     /// Initial game date (hour)
     pub initial_date_hour: u32,
+    /// Lockpicking succeeds if the skill level plus a roll of 1 to this value exceeds the lock level
+    pub lockpick_dice: u32,
+    /// Detecting and disarming traps succeed if the skill level plus a roll of 1 to this value exceeds the difficulty
+    pub trap_dice: u32,
+    /// The player tries to detect traps within this distance every turn
+    pub trap_detect_range: i32,
+    /// Items can be thrown within the strength divided by this value plus one tiles
+    pub throw_range_divisor: u16,
+    /// Hardness of floors when digging down to the floor below
+    pub dig_down_hardness: u16,
+    /// Digging takes at most this many turns
+    pub max_dig_turns: u32,
 }
 
@@ -6,4 +6,6 @@ pub struct Town {
     pub min_shop_items: u32,
     /// The maximum number of shop items
     pub max_shop_items: u32,
+    /// Items on shops are restocked when the player visits the town after this days
+    pub shop_restock_days: u64,
 }
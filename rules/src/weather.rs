@@ -0,0 +1,17 @@
+
+use common::gamedata;
+
+/// Used for weather changes
+#[derive(Serialize, Deserialize)]
+pub struct Weather {
+    /// Weather lasts for a random number of hours between these values
+    pub min_hours: u64,
+    /// Weather lasts for a random number of hours between these values
+    pub max_hours: u64,
+    /// Relative frequencies of the weathers chosen when the weather changes
+    pub weights: Vec<(gamedata::Weather, f32)>,
+    /// Snow falls instead of rain in these months
+    pub snow_months: Vec<u16>,
+    /// Sight radius of characters in fog
+    pub fog_view_range: i32,
+}
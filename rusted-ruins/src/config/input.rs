@@ -1,11 +1,145 @@
 
 use crate::game::Command;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct InputConfig {
+    #[serde(default)]
     pub normal: HashMap<String, Command>,
+    #[serde(default)]
     pub dialog: HashMap<String, Command>,
+    #[serde(default)]
     pub targeting: HashMap<String, Command>,
+    /// Commands that had default bindings when the user saved bindings
+    #[serde(default)]
+    pub known: KnownCommands,
 }
 
+/// Commands that have default bindings in each mode.
+/// Default bindings of unknown commands, e.g. commands added after the user saved bindings,
+/// are added to the user's bindings. Known commands that the user unbound are kept unbound.
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct KnownCommands {
+    #[serde(default)]
+    pub normal: Vec<Command>,
+    #[serde(default)]
+    pub dialog: Vec<Command>,
+    #[serde(default)]
+    pub targeting: Vec<Command>,
+}
+
+impl InputConfig {
+    /// Commands bound in this config
+    pub fn commands(&self) -> KnownCommands {
+        let commands = |bindings: &HashMap<String, Command>| {
+            let mut v: Vec<Command> = Vec::new();
+            for command in bindings.values() {
+                if !v.contains(command) {
+                    v.push(command.clone());
+                }
+            }
+            v
+        };
+        KnownCommands {
+            normal: commands(&self.normal),
+            dialog: commands(&self.dialog),
+            targeting: commands(&self.targeting),
+        }
+    }
+}
+
+/// Key bindings changed in game are saved to this file.
+/// It is loaded over the default config file if it exists.
+pub fn user_input_config_path() -> PathBuf {
+    let mut path = super::USER_DIR.clone();
+    path.push(common::basic::CFG_FILES_DIR);
+    path.push("input.toml");
+    path
+}
+
+/// Load key bindings saved by the user over the default bindings.
+pub fn load_user_input_config(default: InputConfig) -> InputConfig {
+    let path = user_input_config_path();
+    if !path.exists() {
+        return default;
+    }
+    info!("Loading config file : \"{}\"", path.to_string_lossy());
+    let result = crate::util::read_file_as_string(&path).map_err(|e| e.to_string())
+        .and_then(|s| toml::de::from_str(&s).map_err(|e| e.to_string()));
+    match result {
+        Ok(config) => merge_input_config(default, config),
+        Err(e) => {
+            warn!("Cannot load config file \"{}\"\n{}", path.to_string_lossy(), e);
+            default
+        }
+    }
+}
+
+fn merge_input_config(default: InputConfig, user: InputConfig) -> InputConfig {
+    let known = user.known;
+    InputConfig {
+        normal: merge_bindings(default.normal, user.normal, &known.normal),
+        dialog: merge_bindings(default.dialog, user.dialog, &known.dialog),
+        targeting: merge_bindings(default.targeting, user.targeting, &known.targeting),
+        known: default.known,
+    }
+}
+
+/// Add default bindings of the commands that the user does not know, e.g. commands added
+/// after the user saved bindings. Keys bound by the user are not changed, and default keys of
+/// commands that the user rebound or unbound are not added.
+fn merge_bindings(default: HashMap<String, Command>, mut user: HashMap<String, Command>,
+                  known: &[Command]) -> HashMap<String, Command> {
+    let user_commands: Vec<Command> = user.values().cloned().collect();
+    for (key, command) in default {
+        if !user_commands.contains(&command) && !known.contains(&command) {
+            user.entry(key).or_insert(command);
+        }
+    }
+    user
+}
+
+pub fn save_user_input_config(config: &InputConfig) -> Result<(), String> {
+    let path = user_input_config_path();
+    let s = toml::ser::to_string(config).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, s).map_err(|e| e.to_string())
+}
+
+#[test]
+fn merge_bindings_test() {
+    let bindings = |b: &[(&str, Command)]| -> HashMap<String, Command> {
+        b.iter().map(|(key, command)| (key.to_string(), command.clone())).collect()
+    };
+    let default = bindings(&[("f", Command::Shot), ("i", Command::OpenItemMenu), ("f12", Command::Screenshot)]);
+    // Shot is rebound from f to g, and f12 is used for another command
+    let user = bindings(&[("g", Command::Shot), ("i", Command::OpenItemMenu), ("f12", Command::OpenConsole)]);
+    assert_eq!(merge_bindings(default, user, &[]),
+               bindings(&[("g", Command::Shot), ("i", Command::OpenItemMenu), ("f12", Command::OpenConsole)]));
+
+    let default = bindings(&[("f", Command::Shot), ("f12", Command::Screenshot)]);
+    let user = bindings(&[("g", Command::Shot)]);
+    assert_eq!(merge_bindings(default, user, &[]), bindings(&[("g", Command::Shot), ("f12", Command::Screenshot)]));
+
+    // Screenshot is unbound by the user, and EatItem is added after the user saved
+    let default = bindings(&[("f", Command::Shot), ("e", Command::EatItem), ("f12", Command::Screenshot)]);
+    let user = bindings(&[("g", Command::Shot)]);
+    let known = [Command::Shot, Command::Screenshot];
+    assert_eq!(merge_bindings(default, user, &known), bindings(&[("g", Command::Shot), ("e", Command::EatItem)]));
+}
+
+#[test]
+fn known_commands_toml_test() {
+    let mut normal = HashMap::new();
+    normal.insert("f".to_owned(), Command::Shot);
+    let mut config = InputConfig {
+        normal, dialog: HashMap::new(), targeting: HashMap::new(), known: KnownCommands::default(),
+    };
+    config.known = config.commands();
+    let s = toml::ser::to_string(&config).unwrap();
+    let config: InputConfig = toml::de::from_str(&s).unwrap();
+    assert_eq!(config.known.normal, vec![Command::Shot]);
+}
@@ -48,17 +48,18 @@ lazy_static! {
     pub static ref APP_DIR: PathBuf = get_app_dir().expect("Cannot get data directory path");
     pub static ref USER_DIR: PathBuf = get_user_dir();
     pub static ref ADDON_DIR: Option<PathBuf> = get_addon_dir();
-    pub static ref CONFIG: Config = load_config_file!("config.toml");
-    pub static ref SCREEN_CFG: visual::ScreenConfig = load_config_file!("screen/800x600.toml");
+    pub static ref CONFIG: Config = load_user_config()
+        .unwrap_or_else(|| load_config_file!("config.toml"));
+    pub static ref SCREEN_CFG: visual::ScreenConfig = load_config_file!(&CONFIG.screen_config);
     pub static ref UI_CFG: visual::UIConfig = load_config_file!("ui.toml");
-    pub static ref INPUT_CFG: input::InputConfig = load_config_file!("input.toml");
+    pub static ref INPUT_CFG: input::InputConfig = load_input_config();
     pub static ref FONT_CFG: font::FontConfig = load_config_file!("font.toml");
     
-    pub static ref PAK_DIRS: Vec<PathBuf> = {
-        let mut v = Vec::new();
-        v.push(abs_path("paks"));
-        v
-    };
+    /// Pak directories of the application, addons and enabled mods
+    pub static ref PAK_DIRS: Vec<PathBuf> = get_data_dirs()
+        .into_iter()
+        .map(|dir| dir.join("paks"))
+        .collect();
 }
 
 /// Get application directory
@@ -94,7 +95,7 @@ fn get_addon_dir() -> Option<PathBuf> {
     None
 }
 
-/// Get application and each addon's directories
+/// Get application and each addon's directories, and then enabled mods' directories.
 /// They will be the root path for searching pak or text, and other data files.
 pub fn get_data_dirs() -> Vec<PathBuf> {
     let mut v = Vec::new();
@@ -104,6 +105,10 @@ pub fn get_data_dirs() -> Vec<PathBuf> {
         v.push(ADDON_DIR.clone().unwrap());
     }
 
+    for m in CONFIG.mods.iter().filter(|m| !m.disabled) {
+        v.push(abs_path(&m.dir));
+    }
+
     v
 }
 
@@ -130,6 +135,18 @@ pub struct CfgRect {
     pub w: u32,
     #[serde(default)]
     pub h: u32,
+    /// Keep the distance from the right edge when the screen is resized
+    #[serde(default)]
+    pub anchor_right: bool,
+    /// Keep the distance from the bottom edge when the screen is resized
+    #[serde(default)]
+    pub anchor_bottom: bool,
+    /// Grow with the screen width
+    #[serde(default)]
+    pub stretch_w: bool,
+    /// Grow with the screen height
+    #[serde(default)]
+    pub stretch_h: bool,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
@@ -143,11 +160,102 @@ pub struct CfgColor {
     pub a: Option<u8>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub lang: String,
     pub second_lang: String,
     pub screen_config: String,
     pub hardware_acceleration: bool,
+    /// Music volume in percent
+    pub music_volume: u32,
+    /// Sound effect volume in percent
+    pub sound_volume: u32,
+    /// Integer scale factor of the whole screen for high-DPI displays
+    pub ui_scale: u32,
+    /// Developer mode to reload changed pak and text files without restarting
+    #[serde(default)]
+    pub hot_reload: bool,
+    /// Enable the console to execute debug commands, and exporting the whole map
+    #[serde(default)]
+    pub debug_console: bool,
+    /// Auto-save every this number of player turns. 0 disables it.
+    #[serde(default)]
+    pub autosave_turns: u32,
+    /// Auto-save when the player moves to another map or floor
+    #[serde(default)]
+    pub autosave_on_map_change: bool,
+    /// The number of auto-save slots written in rotation for each game
+    #[serde(default = "default_autosave_slots")]
+    pub autosave_slots: u32,
+    /// Mod directories loaded in this order.
+    /// Objects and texts in later mods override the earlier ones.
+    #[serde(default)]
+    pub mods: Vec<ModConfig>,
+}
+
+fn default_autosave_slots() -> u32 {
+    3
+}
+
+/// A mod directory has paks and texts in the same layout as the application directory
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ModConfig {
+    /// Relative paths are from the application directory
+    pub dir: String,
+    #[serde(default)]
+    pub disabled: bool,
 }
 
+/// Settings changed in game are saved to this file.
+/// It is loaded instead of the default config file if it exists.
+pub fn user_config_path() -> PathBuf {
+    let mut path = USER_DIR.clone();
+    path.push(basic::CFG_FILES_DIR);
+    path.push("config.toml");
+    path
+}
+
+/// Load settings saved by the user. Returns None if there are no saved settings.
+fn load_user_config() -> Option<Config> {
+    let path = user_config_path();
+    if !path.exists() {
+        return None;
+    }
+    info!("Loading config file : \"{}\"", path.to_string_lossy());
+    let result = read_file_as_string(&path).map_err(|e| e.to_string())
+        .and_then(|s| toml::de::from_str(&s).map_err(|e| e.to_string()));
+    match result {
+        Ok(config) => Some(config),
+        Err(e) => {
+            warn!("Cannot load config file \"{}\"\n{}", path.to_string_lossy(), e);
+            None
+        }
+    }
+}
+
+/// Key bindings including changes saved in this session
+pub fn load_input_config() -> input::InputConfig {
+    input::load_user_input_config(default_input_config())
+}
+
+/// Default key bindings. Commands bound in them are known to the user after saving bindings.
+pub fn default_input_config() -> input::InputConfig {
+    let mut config: input::InputConfig = load_config_file!("input.toml");
+    config.known = config.commands();
+    config
+}
+
+/// Settings including changes saved in this session.
+/// CONFIG keeps the values at startup because some of them are applied only at startup.
+pub fn current_config() -> Config {
+    load_user_config().unwrap_or_else(|| CONFIG.clone())
+}
+
+pub fn save_user_config(config: &Config) -> Result<(), String> {
+    let path = user_config_path();
+    let s = toml::ser::to_string(config).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, s).map_err(|e| e.to_string())
+}
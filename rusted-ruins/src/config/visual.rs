@@ -9,11 +9,7 @@ pub struct ScreenConfig {
     pub main_window: CfgRect,
     pub log_window: CfgRect,
     pub minimap_window: CfgRect,
-    pub hp_indicator: CfgRect,
     pub floor_info: CfgRect,
-    pub date_info: CfgRect,
-    pub time_info: CfgRect,
-    pub status_info: CfgRect,
     pub hborders: Vec<BorderConfig>,
     pub vborders: Vec<BorderConfig>,
 }
@@ -23,6 +19,13 @@ pub struct BorderConfig {
     pub x: i32,
     pub y: i32,
     pub len: u32,
+    #[serde(default)]
+    pub anchor_right: bool,
+    #[serde(default)]
+    pub anchor_bottom: bool,
+    /// Grow with the screen size along the border
+    #[serde(default)]
+    pub stretch: bool,
 }
 
 /// UI colors, fonts, and other widget settings
@@ -42,6 +45,8 @@ pub struct UIConfig {
     pub newgame_dialog: NewGameDialogConfig,
     pub choose_save_file_dialog: ChooseSaveFileDialogConfig,
     pub choose_class_dialog: ChooseClassDialogConfig,
+    pub allocate_stats_dialog: AllocateStatsDialogConfig,
+    pub choose_scenario_dialog: ChooseScenarioDialogConfig,
     pub item_window: ItemWindowConfig,
     pub equip_window: EquipWindowConfig,
     pub scrolling_text_window: ScrollingTextWindowConfig,
@@ -50,6 +55,16 @@ pub struct UIConfig {
     pub game_info_window: GameInfoWindowConfig,
     pub skill_window: SkillWindowConfig,
     pub quest_window: QuestWindowConfig,
+    pub ability_window: AbilityWindowConfig,
+    pub craft_window: CraftWindowConfig,
+    pub key_binding_window: KeyBindingWindowConfig,
+    pub settings_window: SettingsWindowConfig,
+    pub mod_window: ModWindowConfig,
+    pub party_window: PartyWindowConfig,
+    pub log_history_window: LogHistoryWindowConfig,
+    pub console_window: ConsoleWindowConfig,
+    pub map_window: MapWindowConfig,
+    pub hud: HudConfig,
     pub label_widget: LabelWidgetConfig,
     pub list_widget: ListWidgetConfig,
 }
@@ -70,7 +85,11 @@ pub struct UIColorConfig {
     pub gauge_border_dark: CfgColor,
     pub gauge_bg: CfgColor,
     pub gauge_hp: CfgColor,
+    pub gauge_mp: CfgColor,
     pub gauge_exp: CfgColor,
+    pub flash_hit: CfgColor,
+    pub flash_heal: CfgColor,
+    pub flash_buff: CfgColor,
 }
 
 #[derive(Debug, Deserialize)]
@@ -108,6 +127,8 @@ pub struct TalkWindowConfig {
     pub image_window_pos_x: i32,
     /// Relative position to parent talk window
     pub image_window_pos_y: i32,
+    /// Indicator displayed when the text continues to the next page
+    pub more_indicator_rect: CfgRect,
 }
 
 #[derive(Debug, Deserialize)]
@@ -147,6 +168,17 @@ pub struct ChooseClassDialogConfig {
     pub rect: CfgRect,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AllocateStatsDialogConfig {
+    pub rect: CfgRect,
+    pub column_pos: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChooseScenarioDialogConfig {
+    pub rect: CfgRect,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ItemWindowConfig {
     pub rect: CfgRect,
@@ -171,6 +203,7 @@ pub struct StatusWindowConfig {
     pub image_rect: CfgRect,
     pub name_label_rect: CfgRect,
     pub hp_label_rect: CfgRect,
+    pub mp_label_rect: CfgRect,
     pub str_label_rect: CfgRect,
     pub vit_label_rect: CfgRect,
     pub dex_label_rect: CfgRect,
@@ -200,6 +233,83 @@ pub struct QuestWindowConfig {
     pub n_row: u32,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AbilityWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CraftWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KeyBindingWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettingsWindowConfig {
+    pub rect: CfgRect,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ModWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PartyWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+    pub column_pos: Vec<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogHistoryWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConsoleWindowConfig {
+    pub rect: CfgRect,
+    pub n_row: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MapWindowConfig {
+    pub rect: CfgRect,
+    pub max_tile_size: i32,
+}
+
+/// Widgets drawn over the main window
+#[derive(Debug, Deserialize)]
+pub struct HudConfig {
+    pub hp_bar: HudWidgetConfig,
+    pub mp_bar: HudWidgetConfig,
+    pub date: HudWidgetConfig,
+    pub time: HudWidgetConfig,
+    pub hunger: HudWidgetConfig,
+    /// Icons are stacked upward from this rect
+    pub status_icons: HudWidgetConfig,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HudWidgetConfig {
+    pub show: bool,
+    pub rect: CfgRect,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LabelWidgetConfig {
     pub h: i32,
@@ -1,5 +1,6 @@
 
 use std::cell::Cell;
+use array2d::{Direction, HDirection, VDirection};
 use common::obj::Img;
 
 thread_local!(static FRAME_COUNT: Cell<u32> = Cell::new(0));
@@ -22,3 +23,51 @@ pub fn calc_frame(img: &Img) -> u32 {
     debug_assert!(n < img.n_anim_frame);
     n
 }
+
+/// The number of drawn frames
+pub fn frame_count() -> u32 {
+    FRAME_COUNT.with(|frame_count| frame_count.get())
+}
+
+/// Calculate the frame of character images for the facing direction.
+/// Frames of each direction include all patterns, and the first pattern is used.
+/// Images that have only one direction use the same frames for all directions.
+pub fn calc_chara_frame(img: &Img, dir: Direction) -> u32 {
+    direction_index(dir, img.n_direction) * img.n_pattern * img.n_anim_frame + calc_frame(img)
+}
+
+/// Index of image rows for directions. Diagonal directions use the west or east images.
+fn direction_index(dir: Direction, n_direction: u32) -> u32 {
+    if n_direction < 4 {
+        return 0;
+    }
+    match (dir.hdir, dir.vdir) {
+        (HDirection::Left, _) => 1,
+        (HDirection::Right, _) => 2,
+        (HDirection::None, VDirection::Up) => 3,
+        _ => 0,
+    }
+}
+
+#[test]
+fn direction_index_test() {
+    assert_eq!(direction_index(Direction::N, 1), 0);
+    assert_eq!(direction_index(Direction::NONE, 4), 0);
+    assert_eq!(direction_index(Direction::S, 4), 0);
+    assert_eq!(direction_index(Direction::W, 4), 1);
+    assert_eq!(direction_index(Direction::SE, 4), 2);
+    assert_eq!(direction_index(Direction::N, 4), 3);
+}
+
+#[test]
+fn calc_chara_frame_test() {
+    let img = Img {
+        data: Vec::new(), w: 1, h: 1, grid_nx: 8, grid_ny: 1,
+        n_frame: 8, n_pattern: 2, n_anim_frame: 1, duration: 0, n_direction: 4,
+    };
+    assert_eq!(calc_chara_frame(&img, Direction::S), 0);
+    assert_eq!(calc_chara_frame(&img, Direction::W), 2);
+    assert_eq!(calc_chara_frame(&img, Direction::N), 6);
+    let img = Img { n_frame: 2, grid_nx: 2, n_direction: 1, ..img };
+    assert_eq!(calc_chara_frame(&img, Direction::N), 0);
+}
@@ -0,0 +1,74 @@
+//! Lighting layer drawn over map tiles.
+//! Dark maps are shaded by tiles, and light sources brighten tiles around them.
+
+use array2d::*;
+use common::gamedata::*;
+use common::gobj;
+
+/// Lights larger than this are clipped
+const MAX_LIGHT_RADIUS: u8 = 8;
+
+/// Collect light sources that can reach the given range.
+/// Decorations, items on tiles and items equipped by characters give lights.
+pub fn light_sources(gd: &GameData, range: RectIter) -> Vec<(Vec2d, u8)> {
+    let map = gd.get_current_map();
+    let r = MAX_LIGHT_RADIUS as i32;
+    let (top_left, bottom_right) = range_corners(range);
+    let mut sources = Vec::new();
+
+    for p in RectIter::new(top_left - (r, r), bottom_right + (r, r)) {
+        if !map.is_inside(p) {
+            continue;
+        }
+        let tile = &map.tile[p];
+        let mut light = tile.deco.map_or(0, |idx| gobj::get_obj(idx).light);
+        if let Some(item_list) = tile.item_list.as_ref() {
+            for (item, _) in item_list.iter() {
+                light = std::cmp::max(light, gobj::get_obj(item.idx).light);
+            }
+        }
+        if let Some(cid) = tile.chara {
+            for (_, _, item) in gd.chara.get(cid).equip.item_iter() {
+                light = std::cmp::max(light, gobj::get_obj(item.idx).light);
+            }
+        }
+        if light > 0 {
+            sources.push((p, std::cmp::min(light, MAX_LIGHT_RADIUS)));
+        }
+    }
+    sources
+}
+
+/// Darkness of the tile after lighting by given sources
+pub fn tile_darkness(darkness: u8, p: Vec2d, sources: &[(Vec2d, u8)]) -> u8 {
+    let brightness = sources.iter().map(|&(pos, radius)| {
+        let d = pos - p;
+        light_factor(((d.0 * d.0 + d.1 * d.1) as f32).sqrt(), radius)
+    }).fold(0.0, f32::max);
+    (darkness as f32 * (1.0 - brightness)) as u8
+}
+
+/// Brightness by a light. It is 1.0 at the source and decreases linearly until the radius.
+fn light_factor(distance: f32, radius: u8) -> f32 {
+    let r = radius as f32 + 1.0;
+    if radius == 0 || distance >= r {
+        0.0
+    } else {
+        1.0 - distance / r
+    }
+}
+
+fn range_corners(range: RectIter) -> (Vec2d, Vec2d) {
+    let xs = range.iter0();
+    let ys = range.iter1();
+    (Vec2d(xs.start, ys.start), Vec2d(xs.end - 1, ys.end - 1))
+}
+
+#[test]
+fn tile_darkness_test() {
+    let sources = [(Vec2d(0, 0), 3)];
+    assert_eq!(tile_darkness(200, Vec2d(0, 0), &sources), 0);
+    assert_eq!(tile_darkness(200, Vec2d(2, 0), &sources), 100);
+    assert_eq!(tile_darkness(200, Vec2d(4, 0), &sources), 200);
+    assert_eq!(tile_darkness(200, Vec2d(4, 0), &[]), 200);
+}
@@ -2,19 +2,23 @@
 use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
 use sdl2::pixels::Color;
-use sdl2::render::Texture;
+use sdl2::render::{Texture, BlendMode};
 use array2d::*;
 use common::basic::{TILE_SIZE, TILE_SIZE_I, PIECE_SIZE_I};
-use common::objholder::{Holder, UIImgIdx};
+use common::objholder::{Holder, UIImgIdx, AnimImgIdx};
 use common::obj::*;
 use common::gobj;
 use common::gamedata::*;
-use crate::game::{Game, Animation, InfoGetter};
+use crate::game::{Game, Animation, FlashKind, InfoGetter};
+use crate::config::UI_CFG;
 use crate::game::view::ViewMap;
+use crate::game::mount;
 use crate::context::*;
 use super::tile_getter::*;
-use super::frame::calc_frame;
+use super::frame::{calc_frame, calc_chara_frame};
 use super::overlay;
+use super::lighting;
+use super::weather;
 
 const CHARA_DRAW_OFFSET: i32 = 16;
 
@@ -62,10 +66,11 @@ impl MainWinDrawer {
         let map = game.gd.get_current_map();
         self.update_draw_params((map.w as i32, map.h as i32),
                                 ct, player_move_adjust);
-        self.draw_except_anim(context, game, player_move_adjust, player_move_dir);
+        self.draw_except_anim(context, game, player_move_adjust, player_move_dir, true);
         let canvas = &mut context.canvas;
         let sv = &mut context.sv;
         self.draw_overlay_all(canvas, game, sv);
+        self.draw_weather(canvas, game);
 
         if let Some(anim) = anim {
             self.draw_anim(canvas, game, sv, anim.0, anim.1);
@@ -76,9 +81,29 @@ impl MainWinDrawer {
         }
     }
 
+    /// Draw the part of the map whose top left pixel is (left, top), without fog and animations.
+    /// Used to export the whole map to an image.
+    pub fn draw_map_page(&mut self, context: &mut Context, game: &Game, left: i32, top: i32) {
+        self.dx = -left;
+        self.dy = -top;
+        self.topleft = Vec2d(left / TILE_SIZE_I, top / TILE_SIZE_I);
+        self.draw_except_anim(context, game, (0, 0), None, false);
+    }
+
+    /// Convert a position on the screen to the tile position.
+    /// Returns None if the position is outside of the main window.
+    pub fn pos_to_tile(&self, x: i32, y: i32) -> Option<Vec2d> {
+        if !self.rect.contains_point((x, y)) {
+            return None;
+        }
+        Some(Vec2d(
+            (x - self.rect.x - self.dx).div_euclid(TILE_SIZE_I),
+            (y - self.rect.y - self.dy).div_euclid(TILE_SIZE_I)))
+    }
+
     fn draw_except_anim(
         &mut self, context: &mut Context, game: &Game,
-        player_move_adjust: (i32, i32), player_move_dir: Option<Direction>) {
+        player_move_adjust: (i32, i32), player_move_dir: Option<Direction>, with_fog: bool) {
 
         context.set_viewport(self.rect);
         context.canvas.set_draw_color(Color::RGB(120, 120, 120));
@@ -143,18 +168,28 @@ impl MainWinDrawer {
             }
             // Draw player during moving animation
             if is_player_moving && ny == player_drawing_row {
-                let chara = gd.chara.get(CharaId::Player);
-                let ct = gobj::get_obj(chara.template);
-                let src = Rect::from(ct.img_rect());
-                let dest = self.centering_at_tile(
-                    src, player_pos,
-                    -player_move_adjust.0, -player_move_adjust.1 - CHARA_DRAW_OFFSET);
-                check_draw!(context.canvas.copy(context.sv.tex().get(chara.template), src, dest));
+                self.draw_chara(
+                    context, gd.chara.get(CharaId::Player), player_pos,
+                    -player_move_adjust.0, -player_move_adjust.1);
+            }
+        }
+        // Draw lighting layer
+        if map.darkness > 0 {
+            let sources = lighting::light_sources(gd, tile_range);
+            context.canvas.set_blend_mode(BlendMode::Blend);
+            for p in tile_range {
+                let a = lighting::tile_darkness(map.darkness, p, &sources);
+                if a == 0 { continue; }
+                context.canvas.set_draw_color(Color::RGBA(0, 0, 0, a));
+                check_draw!(context.canvas.fill_rect(self.tile_rect(p, 0, 0)));
             }
+            context.canvas.set_blend_mode(BlendMode::None);
         }
         // Draw background parts
-        for p in tile_range {
-            self.draw_overlay(context.canvas, game, context.sv, p);
+        if with_fog {
+            for p in tile_range {
+                self.draw_overlay(context.canvas, game, context.sv, p);
+            }
         }
     }
 
@@ -201,22 +236,44 @@ impl MainWinDrawer {
 
         // Draw character on the tile
         if let Some(chara_id) = di.chara {
-            let chara = gd.chara.get(chara_id);
-            let ct = gobj::get_obj(chara.template);
-            let src = Rect::from(ct.img_rect());
-            
             if !(chara_id == CharaId::Player && is_player_moving) {
-                let dest = if chara_id == CharaId::Player {
-                    self.centering_at_tile(
-                        src, p, -player_move_adjust.0, -player_move_adjust.1 - CHARA_DRAW_OFFSET)
+                let (dx, dy) = if chara_id == CharaId::Player {
+                    (-player_move_adjust.0, -player_move_adjust.1)
                 }else{
-                    self.centering_at_tile(src, p, 0, -CHARA_DRAW_OFFSET)
+                    (0, 0)
                 };
-                check_draw!(context.canvas.copy(context.sv.tex().get(chara.template), src, dest));
+                self.draw_chara(context, gd.chara.get(chara_id), p, dx, dy);
             }
         }
     }
 
+    /// Draw the character by the frame for its facing direction, and overlays of its equipments
+    fn draw_chara(&self, context: &mut Context, chara: &Chara, p: Vec2d, dx: i32, dy: i32) {
+        // Riders are drawn with other images
+        let template = mount::drawn_template(chara);
+        let ct = gobj::get_obj(template);
+        let src = Rect::from(ct.img_rect_nth(calc_chara_frame(&ct.img, chara.facing)));
+        let dest = self.centering_at_tile(src, p, dx, dy - CHARA_DRAW_OFFSET);
+        check_draw!(context.canvas.copy(context.sv.tex().get(template), src, dest));
+
+        for (_, _, item) in chara.equip.item_iter() {
+            let overlay = if let Some(overlay) = gobj::get_obj(item.idx).overlay.as_ref() {
+                overlay
+            } else {
+                continue;
+            };
+            let idx: AnimImgIdx = if let Some(idx) = gobj::id_to_idx_checked(overlay) {
+                idx
+            } else {
+                continue;
+            };
+            let obj = gobj::get_obj(idx);
+            let src = Rect::from(obj.img_rect_nth(calc_chara_frame(&obj.img, chara.facing)));
+            let dest = self.centering_at_tile(src, p, dx, dy - CHARA_DRAW_OFFSET);
+            check_draw!(context.canvas.copy(context.sv.tex().get(idx), src, dest));
+        }
+    }
+
     /// Draw overlay for a tile
     fn draw_overlay(&self, canvas: &mut WindowCanvas, game: &Game, sv: &SdlValues, p: Vec2d) {
 
@@ -256,7 +313,28 @@ impl MainWinDrawer {
         }
     }
 
-    fn draw_anim(&mut self, canvas: &mut WindowCanvas, _game: &Game, sv: &SdlValues,
+    /// Draw rain, snow and tints by the weather
+    fn draw_weather(&self, canvas: &mut WindowCanvas, game: &Game) {
+        let w = if let Some(w) = crate::game::weather::visible_weather(&game.gd) { w } else { return; };
+        canvas.set_blend_mode(BlendMode::Blend);
+        if let Some((r, g, b, a)) = weather::tint(w) {
+            canvas.set_draw_color(Color::RGBA(r, g, b, a));
+            check_draw!(canvas.fill_rect(Rect::new(0, 0, self.w, self.h)));
+        }
+        let frame = super::frame::frame_count();
+        for (x, y) in weather::particles(w, self.w, self.h, frame) {
+            if w == Weather::Snow {
+                canvas.set_draw_color(Color::RGBA(255, 255, 255, 220));
+                check_draw!(canvas.fill_rect(Rect::new(x, y, 2, 2)));
+            } else {
+                canvas.set_draw_color(Color::RGBA(180, 180, 255, 160));
+                check_draw!(canvas.draw_line((x, y), (x - 2, y + 8)));
+            }
+        }
+        canvas.set_blend_mode(BlendMode::None);
+    }
+
+    fn draw_anim(&mut self, canvas: &mut WindowCanvas, game: &Game, sv: &SdlValues,
                  anim: &Animation, i_frame: u32) {
         match anim {
             &Animation::Img { idx, range, .. } => {
@@ -278,6 +356,22 @@ impl MainWinDrawer {
                 };
                 check_draw!(canvas.copy(sv.tex().get(idx), src, dest));
             }
+            &Animation::Flash { n_frame, kind, range } => {
+                let color: Color = match kind {
+                    FlashKind::Hit => UI_CFG.color.flash_hit,
+                    FlashKind::Heal => UI_CFG.color.flash_heal,
+                    FlashKind::Buff => UI_CFG.color.flash_buff,
+                }.into();
+                // Fade out by frames
+                let a = color.a as u32 * (n_frame - i_frame) / n_frame;
+                canvas.set_blend_mode(BlendMode::Blend);
+                canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, a as u8));
+                // Unseen tiles must not reveal effects on hidden characters
+                for p in range.filter(|p| game.view_map.get_tile_visible(*p)) {
+                    check_draw!(canvas.fill_rect(self.tile_rect(p, 0, 0)));
+                }
+                canvas.set_blend_mode(BlendMode::None);
+            }
             _ => (),
         }
     }
@@ -397,3 +491,14 @@ impl MainWinDrawer {
     }
 }
 
+
+#[test]
+fn pos_to_tile_test() {
+    let mut drawer = MainWinDrawer::new(Rect::new(10, 20, 480, 480));
+    drawer.dx = -24;
+    drawer.dy = 0;
+    assert_eq!(drawer.pos_to_tile(5, 30), None);
+    assert_eq!(drawer.pos_to_tile(10, 20), Some(Vec2d(0, 0)));
+    assert_eq!(drawer.pos_to_tile(10 + 24, 20 + 47), Some(Vec2d(1, 0)));
+    assert_eq!(drawer.pos_to_tile(10 + 100, 20 + 100), Some(Vec2d(2, 2)));
+}
@@ -4,4 +4,6 @@ pub mod border;
 pub mod frame;
 mod tile_getter;
 mod overlay;
+mod lighting;
+mod weather;
 
@@ -2,6 +2,7 @@
 use array2d::*;
 use common::piece_pattern::*;
 use common::objholder::EffectIdx;
+use common::gamedata::time::{NIGHT_START_HOUR, NIGHT_END_HOUR};
 use crate::game::{Game, InfoGetter};
 use crate::game::frequent_tex::Overlay;
 
@@ -40,8 +41,10 @@ pub fn all(game: &Game) -> Option<EffectIdx> {
     let date = game.gd.time.current_date();
     let hour = date.hour;
     let minute = date.minute;
-    let dawn_hour = 5;
-    let dusk_hour = 18;
+    // Twilight is the last hour of night and the hour before night starts,
+    // so the overlay agrees with Date::is_night used by scripts.
+    let dawn_hour = NIGHT_END_HOUR - 1;
+    let dusk_hour = NIGHT_START_HOUR - 1;
     assert!(dawn_hour < dusk_hour);
 
     if dawn_hour < hour && hour < dusk_hour { // Daytime
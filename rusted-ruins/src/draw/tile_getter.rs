@@ -3,7 +3,6 @@ use array2d::*;
 use common::objholder::*;
 use common::gamedata::*;
 use common::gobj;
-use common::obj::SpecialTileObject;
 use crate::game::view::ViewMap;
 
 /// Needed infomation to draw background parts of an tile
@@ -39,10 +38,8 @@ impl<'a> BackgroundDrawInfo<'a> {
         di.tile = tile;
 
         if map.is_inside(pos) {
-            if let Some(special_tile_id) = map.observed_tile[pos].special.obj_id() {
-                let special_tile_obj: &'static SpecialTileObject = gobj::get_by_id(special_tile_id);
-                if special_tile_obj.always_background {
-                    let special_tile_idx: SpecialTileIdx = gobj::id_to_idx(special_tile_id);
+            if let Some(special_tile_idx) = map.observed_tile[pos].special.obj_idx() {
+                if gobj::get_obj(special_tile_idx).always_background {
                     di.special = Some(special_tile_idx);
                 }
             }
@@ -69,10 +66,8 @@ impl<'a> ForegroundDrawInfo<'a> {
         let mut di = ForegroundDrawInfo::default();
 
         if map.is_inside(pos) {
-            if let Some(special_tile_id) = map.observed_tile[pos].special.obj_id() {
-                let special_tile_obj: &'static SpecialTileObject = gobj::get_by_id(special_tile_id);
-                if !special_tile_obj.always_background {
-                    let special_tile_idx: SpecialTileIdx = gobj::id_to_idx(special_tile_id);
+            if let Some(special_tile_idx) = map.observed_tile[pos].special.obj_idx() {
+                if !gobj::get_obj(special_tile_idx).always_background {
                     di.special = Some(special_tile_idx);
                 }
             }
@@ -0,0 +1,58 @@
+//! Weather layer drawn over the main window.
+//! Rain and snow are particles falling by frames, and cloudy and foggy skies tint the map.
+
+use common::gamedata::Weather;
+
+/// Particles per 10000 pixels of the window
+const PARTICLE_DENSITY: u32 = 4;
+
+/// Translucent color covering the whole window
+pub fn tint(weather: Weather) -> Option<(u8, u8, u8, u8)> {
+    match weather {
+        Weather::Cloudy => Some((0, 0, 20, 40)),
+        Weather::Rain => Some((0, 0, 30, 60)),
+        Weather::Snow => Some((200, 200, 220, 30)),
+        Weather::Fog => Some((200, 200, 200, 120)),
+        Weather::Fine => None,
+    }
+}
+
+/// Falling speed of particles in pixels per frame, and their horizontal drift
+fn fall_speed(weather: Weather) -> Option<(u32, i32)> {
+    match weather {
+        Weather::Rain => Some((12, -3)),
+        Weather::Snow => Some((2, 1)),
+        _ => None,
+    }
+}
+
+/// Positions of falling particles in the window of given size at the frame
+pub fn particles(weather: Weather, w: u32, h: u32, frame: u32) -> Vec<(i32, i32)> {
+    let (speed, drift) = if let Some(speed) = fall_speed(weather) { speed } else { return Vec::new(); };
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    let n = (w as u64 * h as u64 * PARTICLE_DENSITY as u64 / 10000) as u32;
+    (0..n).map(|i| {
+        // Scatter the particles by a fixed hash of the index
+        let seed = i.wrapping_mul(2654435761);
+        let x0 = seed % w;
+        let y0 = (seed >> 16).wrapping_mul(31) % h;
+        let t = frame.wrapping_add(i);
+        let y = (y0 + t.wrapping_mul(speed) % h) % h;
+        let x = (x0 as i64 + drift as i64 * (y as i64 / speed as i64)).rem_euclid(w as i64);
+        (x as i32, y as i32)
+    }).collect()
+}
+
+#[test]
+fn particles_test() {
+    assert!(particles(Weather::Fine, 800, 600, 0).is_empty());
+    assert!(particles(Weather::Fog, 800, 600, 0).is_empty());
+
+    let rain = particles(Weather::Rain, 800, 600, 10);
+    assert_eq!(rain.len(), 192);
+    assert!(rain.iter().all(|&(x, y)| 0 <= x && x < 800 && 0 <= y && y < 600));
+    // Particles move as frames pass
+    assert_ne!(rain, particles(Weather::Rain, 800, 600, 11));
+}
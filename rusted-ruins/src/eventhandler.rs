@@ -4,10 +4,12 @@ use sdl2;
 use sdl2::event::Event;
 use sdl2::joystick::Joystick;
 use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
 use std::time::Instant;
 use array2d::*;
 use crate::game::Command;
 use crate::config::{INPUT_CFG, UI_CFG};
+use crate::replay::Replay;
 
 /// Convert from SDL Event to Command
 pub struct EventHandler {
@@ -21,11 +23,15 @@ pub struct EventHandler {
     is_instant: bool,
     prev_input_mode: InputMode,
     waiting_dir_release: WaitingDirRelease,
+    mouse_pos: Option<(i32, i32)>,
+    replay: Option<Replay>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum InputMode {
     Normal, Dialog, Targeting, TextInput,
+    /// Key presses are converted to KeyInput commands to change key bindings
+    KeyInput,
 }
 
 /// Used to prevent unintentional cursor moving after dialog opening
@@ -38,6 +44,9 @@ enum WaitingDirRelease {
 pub enum RawCommand {
     KeyPress(Keycode),
     TextInput(String),
+    TextEditing(String, i32),
+    MouseButtonUp(i32, i32),
+    MouseWheel(i32),
 }
 
 impl EventHandler {
@@ -66,8 +75,25 @@ impl EventHandler {
             is_instant: false,
             prev_input_mode: InputMode::Dialog,
             waiting_dir_release: WaitingDirRelease::No,
+            mouse_pos: None,
+            replay: None,
         }
     }
+
+    /// Record input commands, or play back recorded commands instead of inputs
+    pub fn set_replay(&mut self, replay: Replay) {
+        self.replay = Some(replay);
+    }
+    
+    /// The last mouse position on the screen
+    pub fn mouse_pos(&self) -> Option<(i32, i32)> {
+        self.mouse_pos
+    }
+
+    /// Replace the key bindings of normal mode
+    pub fn set_normal_bindings(&mut self, normal: &HashMap<String, Command>) {
+        self.conv_table.normal = CommandConvTable::conv_bindings(normal);
+    }
     
     pub fn process_event(&mut self, event: Event) -> bool {
         match event {
@@ -114,6 +140,20 @@ impl EventHandler {
             Event::TextInput { text, .. } => {
                 self.command_queue.push_back(RawCommand::TextInput(text));
             },
+            Event::TextEditing { text, start, .. } => {
+                self.command_queue.push_back(RawCommand::TextEditing(text, start));
+            },
+            // Mouse events
+            Event::MouseMotion { x, y, .. } => {
+                self.mouse_pos = Some((x, y));
+            },
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                self.mouse_pos = Some((x, y));
+                self.command_queue.push_back(RawCommand::MouseButtonUp(x, y));
+            },
+            Event::MouseWheel { y, .. } => {
+                self.command_queue.push_back(RawCommand::MouseWheel(y));
+            },
             
             _ => {}
         }
@@ -121,6 +161,16 @@ impl EventHandler {
     }
 
     pub fn get_command(&mut self, mode: InputMode) -> Option<Command> {
+        if let Some(mut replay) = self.replay.take() {
+            let command = replay.command(|| self.get_input_command(mode));
+            self.replay = Some(replay);
+            command
+        } else {
+            self.get_input_command(mode)
+        }
+    }
+
+    fn get_input_command(&mut self, mode: InputMode) -> Option<Command> {
         // If input mode switched normal, cursor shouldn't move until direction key released once
         if mode == InputMode::Dialog && self.prev_input_mode == InputMode::Normal {
             self.waiting_dir_release = WaitingDirRelease::Waiting;
@@ -233,36 +283,31 @@ pub struct CommandConvTable {
 
 impl CommandConvTable {
     fn new() -> CommandConvTable {
-        let mut normal = HashMap::new();
-        let mut dialog = HashMap::new();
-        let mut targeting = HashMap::new();
-
-        for (k, v) in INPUT_CFG.normal.iter() {
-            let k = conv_str_to_keycode(k);
-
-            normal.insert(RawCommand::KeyPress(k), v.clone());
-        }
-
-        for (k, v) in INPUT_CFG.dialog.iter() {
-            let k = conv_str_to_keycode(k);
-
-            dialog.insert(RawCommand::KeyPress(k), v.clone());
-        }
-
-        for (k, v) in INPUT_CFG.targeting.iter() {
-            let k = conv_str_to_keycode(k);
-
-            targeting.insert(RawCommand::KeyPress(k), v.clone());
-        }
-
         CommandConvTable {
-            normal,
-            dialog,
-            targeting,
+            normal: CommandConvTable::conv_bindings(&INPUT_CFG.normal),
+            dialog: CommandConvTable::conv_bindings(&INPUT_CFG.dialog),
+            targeting: CommandConvTable::conv_bindings(&INPUT_CFG.targeting),
         }
     }
 
+    fn conv_bindings(bindings: &HashMap<String, Command>) -> HashMap<RawCommand, Command> {
+        bindings.iter()
+            .map(|(k, v)| (RawCommand::KeyPress(conv_str_to_keycode(k)), v.clone()))
+            .collect()
+    }
+
     fn conv(&self, raw: RawCommand, mode: InputMode) -> Option<Command> {
+        // Mouse inputs don't depend on key bindings
+        match raw {
+            RawCommand::MouseButtonUp(x, y) if accepts_mouse(mode) => {
+                return Some(Command::MouseButtonUp { x, y });
+            }
+            RawCommand::MouseWheel(y) if accepts_mouse(mode) => {
+                return Some(Command::MouseWheel { y });
+            }
+            _ => (),
+        }
+
         let table = match mode {
             InputMode::Normal => &self.normal,
             InputMode::Dialog => &self.dialog,
@@ -270,29 +315,54 @@ impl CommandConvTable {
             InputMode::TextInput => {
                 return text_input_conv(raw);
             },
+            InputMode::KeyInput => {
+                return key_input_conv(raw);
+            },
         };
 
         table.get(&raw).cloned()
     }
 }
 
+/// Mouse clicks and wheel are ignored while the player is typing
+fn accepts_mouse(mode: InputMode) -> bool {
+    mode != InputMode::TextInput && mode != InputMode::KeyInput
+}
+
 /// In text input mode, all event is ignored except for text input or finish key press
 fn text_input_conv(raw: RawCommand) -> Option<Command> {
     match raw {
         RawCommand::TextInput(text) => {
             Some(Command::TextInput { text })
         },
+        RawCommand::TextEditing(text, start) => {
+            Some(Command::TextEditing { text, start })
+        },
         RawCommand::KeyPress(keycode) if keycode == Keycode::Return => {
             Some(Command::Enter)
         },
         RawCommand::KeyPress(keycode) if keycode == Keycode::Escape => {
             Some(Command::Cancel)
         },
-        RawCommand::KeyPress(keycode)
-            if keycode == Keycode::Backspace || keycode == Keycode::Delete => {
-                
+        RawCommand::KeyPress(keycode) if keycode == Keycode::Backspace => {
             Some(Command::TextDelete)
         },
+        RawCommand::KeyPress(keycode) if keycode == Keycode::Delete => {
+            Some(Command::TextDeleteForward)
+        },
+        _ => None,
+    }
+}
+
+/// In key input mode, pressed keys are passed as their names in input configfile
+fn key_input_conv(raw: RawCommand) -> Option<Command> {
+    match raw {
+        RawCommand::KeyPress(keycode) if keycode == Keycode::Escape => {
+            Some(Command::Cancel)
+        },
+        RawCommand::KeyPress(keycode) => {
+            conv_keycode_to_str(keycode).map(|key| Command::KeyInput { key })
+        },
         _ => None,
     }
 }
@@ -308,11 +378,22 @@ macro_rules! impl_conv_str_to_keycode {
             )*
                 panic!("Invalid keycode field : \"{}\"", s);
         }
+
+        /// Convert keycodes to lowercase strings used in input configfile
+        fn conv_keycode_to_str(keycode: Keycode) -> Option<String> {
+            $(
+                if keycode == Keycode::$m {
+                    return Some(stringify!($m).to_lowercase());
+                }
+            )*
+                None
+        }
     }
 }
 
 impl_conv_str_to_keycode!(
     A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
-    Space, Return, Tab, Escape
+    Space, Return, Tab, Escape,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12
 );
 
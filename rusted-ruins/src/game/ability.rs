@@ -0,0 +1,102 @@
+//! Functions for abilities that characters use by consuming mp
+
+use common::gamedata::*;
+use common::gobj;
+use common::objholder::AbilityIdx;
+use super::{Game, FlashKind};
+use super::combat;
+use super::extrait::*;
+
+/// Use the ability to the target.
+/// Returns false if the ability cannot be used, and mp is not consumed then.
+pub fn use_ability(game: &mut Game, cid: CharaId, idx: AbilityIdx, target: CharaId) -> bool {
+    let ability: &AbilityObject = gobj::get_obj(idx);
+
+    if game.gd.chara.get(cid).mp < ability.cost_mp {
+        if cid == CharaId::Player {
+            game_log_i!("not-enough-mp");
+        }
+        return false;
+    }
+
+    let map = game.gd.get_current_map();
+    let (pos, target_pos) = match (map.chara_pos(cid), map.chara_pos(target)) {
+        (Some(pos), Some(target_pos)) => (pos, target_pos),
+        _ => return false,
+    };
+    // Walls between the user and the target block abilities
+    let distance = if let Some(distance) = super::view::calc_visual_distance(map, pos, target_pos) {
+        distance
+    } else {
+        if cid == CharaId::Player {
+            game_log_i!("target-not-in-sight");
+        }
+        return false;
+    };
+    if distance > ability.range as i32 {
+        if cid == CharaId::Player {
+            game_log_i!("ability-out-of-range");
+        }
+        return false;
+    }
+
+    {
+        let chara = game.gd.chara.get_mut(cid);
+        chara.mp -= ability.cost_mp;
+        game_log!("use-ability"; chara=chara, ability=idx);
+    }
+
+    let power = rng::dice(ability.dice_n.into(), ability.dice_x.into());
+    match ability.effect {
+        AbilityEffect::Damage => {
+            combat::ability_damage(game, cid, target, power, ability.element);
+            game.anim_queue.push_shot(pos, target_pos);
+            game.anim_queue.push_burst(FlashKind::Hit, target_pos, 1);
+        }
+        AbilityEffect::Heal => {
+            let chara = game.gd.chara.get_mut(target);
+            chara.hp = std::cmp::min(chara.attr.max_hp, chara.hp + power);
+            game_log!("heal-hp"; chara=chara, value=power);
+            game.anim_queue.push_burst(FlashKind::Heal, target_pos, 0);
+        }
+        AbilityEffect::Haste => {
+            let chara = game.gd.chara.get_mut(target);
+            chara.add_status(CharaStatus::Hasted { turn_left: power as u16 });
+            chara.update();
+            game_log!("hasted"; chara=chara);
+            game.anim_queue.push_burst(FlashKind::Buff, target_pos, 0);
+        }
+    }
+    true
+}
+
+#[test]
+fn use_ability_test() {
+    use array2d::Vec2d;
+    super::script::init_test_objs();
+
+    let mut game = Game::empty();
+    let rid = game.gd.region.add_region(Region::new("test-region", Map::new(5, 1), 0));
+    let mid = MapId::RegionMap { rid };
+    game.gd.set_initial_mapid(mid);
+    let player = Chara { name: Some("player".to_owned()), mp: 5, ..Chara::default() };
+    let player = game.gd.add_chara(player, CharaKind::Player);
+    let mut target = Chara { name: Some("target".to_owned()), hp: 5, ..Chara::default() };
+    target.attr.max_hp = 20;
+    let target = game.gd.add_chara_to_map(target, mid);
+    game.gd.get_current_map_mut().locate_chara(player, Vec2d(0, 0));
+    game.gd.get_current_map_mut().locate_chara(target, Vec2d(2, 0));
+    let idx: AbilityIdx = gobj::id_to_idx("test-heal");
+
+    assert!(use_ability(&mut game, player, idx, target));
+    assert_eq!(game.gd.chara.get(player).mp, 2);
+    assert_eq!(game.gd.chara.get(target).hp, 6);
+    // Not enough mp
+    assert!(!use_ability(&mut game, player, idx, target));
+    assert_eq!(game.gd.chara.get(target).hp, 6);
+    // Out of range
+    game.gd.chara.get_mut(player).mp = 5;
+    game.gd.get_current_map_mut().locate_chara(target, Vec2d(4, 0));
+    assert!(!use_ability(&mut game, player, idx, target));
+    assert_eq!(game.gd.chara.get(player).mp, 5);
+}
@@ -7,10 +7,20 @@ use common::gobj;
 use super::Game;
 use super::combat;
 use super::extrait::*;
+use super::InfoGetter;
+use rules::RULES;
 
 pub fn try_move(game: &mut Game, chara_id: CharaId, dir: Direction) -> bool {
     if dir.as_vec() == (0, 0) { return true; } // Move to current tile always success
-    let dest_tile = game.gd.get_current_map().chara_pos(chara_id).unwrap() + dir.as_vec();
+    game.gd.chara.get_mut(chara_id).facing = dir;
+    let orig_tile = game.gd.get_current_map().chara_pos(chara_id).unwrap();
+    let dest_tile = orig_tile + dir.as_vec();
+
+    // Moving to a closed door opens it
+    if game.gd.get_current_map().is_inside(dest_tile) &&
+        game.gd.get_current_map().tile[dest_tile].special.is_closed_door() {
+        return super::door::open_door(&mut game.gd, chara_id, dest_tile);
+    }
 
     if !game.gd.get_current_map().is_passable(game.gd.chara.get(chara_id), dest_tile) {
         return false;
@@ -22,13 +32,9 @@ pub fn try_move(game: &mut Game, chara_id: CharaId, dir: Direction) -> bool {
         if chara_id == CharaId::Player {
             game.anim_queue.push_player_move(dir);
         }
+        step_on_tile(game, chara_id, orig_tile, dest_tile);
     }else{
-        let rel = {
-            let chara = game.gd.chara.get(chara_id);
-            let other_chara = game.gd.chara.get(other_chara.unwrap());
-            chara.rel.relative(other_chara.rel)
-        };
-        match rel {
+        match game.relation_between(chara_id, other_chara.unwrap()) {
             Relationship::ALLY | Relationship::FRIENDLY |Relationship::NEUTRAL => {
                 {
                     let current_map = game.gd.get_current_map_mut();
@@ -37,6 +43,7 @@ pub fn try_move(game: &mut Game, chara_id: CharaId, dir: Direction) -> bool {
                 if chara_id == CharaId::Player {
                     game.anim_queue.push_player_move(dir);
                 }
+                step_on_tile(game, chara_id, orig_tile, dest_tile);
             },
             Relationship::HOSTILE => {
                 combat::attack_neighbor(game, chara_id, other_chara.unwrap());
@@ -46,6 +53,114 @@ pub fn try_move(game: &mut Game, chara_id: CharaId, dir: Direction) -> bool {
     true
 }
 
+/// Process the special tile that the character has stepped on,
+/// and the event region that the player has entered
+fn step_on_tile(game: &mut Game, cid: CharaId, from: Vec2d, pos: Vec2d) {
+    if let SpecialTileKind::Trap { .. } = game.gd.get_current_map().tile[pos].special {
+        super::trap::trigger(game, cid, pos);
+    }
+    if cid == CharaId::Player && game.script.is_none() {
+        if let Some(script_id) = game.gd.get_current_map_mut().enter_event(from, pos) {
+            game.start_event_script(&script_id);
+        }
+    }
+}
+
+/// Push the adjacent character in the direction.
+/// If the character cannot be pushed, they swap positions instead.
+pub fn push_chara(game: &mut Game, cid: CharaId, dir: Direction) -> bool {
+    let pos = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return false; };
+    let target_pos = pos + dir.as_vec();
+    let target = if let Some(target) = game.gd.get_current_map().get_chara(target_pos) {
+        target
+    } else {
+        if cid == CharaId::Player {
+            game_log_i!("push-no-chara");
+        }
+        return false;
+    };
+    if game.relation_between(cid, target) == Relationship::HOSTILE {
+        if cid == CharaId::Player {
+            game_log_i!("push-hostile"; chara=game.gd.chara.get(target));
+        }
+        return false;
+    }
+
+    let dest = target_pos + dir.as_vec();
+    let map = game.gd.get_current_map();
+    let can_enter = |c: CharaId, p: Vec2d| {
+        map.is_passable(game.gd.chara.get(c), p) && !map.tile[p].special.is_closed_door()
+    };
+    if can_enter(target, dest) && map.get_chara(dest).is_none() {
+        game.gd.get_current_map_mut().move_chara(target, dir);
+        game_log_i!("push-chara"; chara=game.gd.chara.get(cid), target=game.gd.chara.get(target));
+        step_on_tile(game, target, target_pos, dest);
+    } else if can_enter(target, pos) && can_enter(cid, target_pos) {
+        game.gd.get_current_map_mut().move_chara(cid, dir);
+        if cid == CharaId::Player {
+            game.anim_queue.push_player_move(dir);
+        }
+        game_log_i!("swap-chara"; chara=game.gd.chara.get(cid), target=game.gd.chara.get(target));
+        step_on_tile(game, cid, pos, target_pos);
+        step_on_tile(game, target, target_pos, pos);
+    } else {
+        if cid == CharaId::Player {
+            game_log_i!("push-failure"; chara=game.gd.chara.get(target));
+        }
+        return false;
+    }
+    true
+}
+
+/// Throw one item at the target within the range decided by strength.
+/// Items that have medical effects take effect on the target, and the others damage it
+/// and drop on its tile.
+pub fn throw_item(game: &mut Game, cid: CharaId, il: ItemLocation, target: CharaId) -> bool {
+    let start = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return false; };
+    let target_pos = if let Some(pos) = game.gd.chara_pos(target) { pos } else { return false; };
+    let divisor = std::cmp::max(RULES.params.throw_range_divisor, 1);
+    let range = (game.gd.chara.get(cid).attr.str / divisor) as i32 + 1;
+    match super::view::calc_visual_distance(game.gd.get_current_map(), start, target_pos) {
+        Some(distance) if distance <= range => (),
+        Some(_) => {
+            if cid == CharaId::Player {
+                game_log_i!("throw-out-of-range");
+            }
+            return false;
+        }
+        None => {
+            if cid == CharaId::Player {
+                game_log_i!("target-not-in-sight");
+            }
+            return false;
+        }
+    }
+
+    let item = game.gd.get_item(il).0.clone();
+    let item_obj = gobj::get_obj(item.idx);
+    game_log!("throw-item"; chara=game.gd.chara.get(cid), item=item, target=game.gd.chara.get(target));
+    game.anim_queue.push_throw(start, target_pos);
+
+    if item_obj.medical_effect != MedicalEffect::None {
+        game.gd.remove_item_and_get(il, 1);
+        let eff = super::item::apply_percent(item_obj.eff, item.eff_percent());
+        apply_medical_effect(game.gd.chara.get_mut(target), item_obj.medical_effect, eff);
+    } else {
+        let dice = if item_obj.dice_n > 0 {
+            super::item::apply_percent(
+                rng::dice(item_obj.dice_n as i32, item_obj.dice_x as i32), item.eff_percent())
+        } else {
+            1 + (item_obj.w / 1000) as i32 // Heavier items are more damaging
+        };
+        let mid = game.gd.get_current_mapid();
+        game.gd.move_item(il, ItemListLocation::OnMap { mid, pos: target_pos }, 1);
+        if !combat::throw_damage(game, cid, target, dice) {
+            game_log!("throw-miss");
+        }
+    }
+    true
+}
+
 /// Shot target
 pub fn shot_target(game: &mut Game, cid: CharaId, target: CharaId) -> bool {
     combat::shot_target(game, cid, target)
@@ -59,7 +174,7 @@ pub fn drink_item(gd: &mut GameData, il: ItemLocation, cid: CharaId) {
     let chara = gd.chara.get_mut(cid);
     game_log!("drink-item"; chara=chara, item=item);
 
-    let eff: i32 = item_obj.eff.into();
+    let eff = super::item::apply_percent(item_obj.eff, item.eff_percent());
     apply_medical_effect(chara, item_obj.medical_effect, eff);
 }
 
@@ -72,10 +187,30 @@ pub fn eat_item(gd: &mut GameData, il: ItemLocation, cid: CharaId) {
     game_log!("eat-item"; chara=chara, item=item);
     chara.add_sp(item_obj.nutrition.into(), cid);
 
-    let eff: i32 = item_obj.eff.into();
+    let eff = super::item::apply_percent(item_obj.eff, item.eff_percent());
     apply_medical_effect(chara, item_obj.medical_effect, eff);
 }
 
+/// Read one item and learn its skill. Returns false if the skill is already learned.
+pub fn read_item(gd: &mut GameData, il: ItemLocation, cid: CharaId) -> bool {
+    let item_obj = gobj::get_obj(gd.get_item(il).0.idx);
+    let kind = if let Some(kind) = item_obj.learn_skill { kind } else {
+        warn!("tried to read unreadable item \"{}\"", item_obj.id);
+        return false;
+    };
+    if gd.chara.get(cid).skills.skills.contains_key(&kind) {
+        game_log_i!("already-learned-skill"; chara=gd.chara.get(cid), skill=kind);
+        return false;
+    }
+
+    let item = gd.remove_item_and_get(il, 1);
+    let chara = gd.chara.get_mut(cid);
+    game_log!("read-item"; chara=chara, item=item);
+    chara.skills.learn_new_skill(kind);
+    game_log!("learn-skill"; chara=chara, skill=kind);
+    true
+}
+
 fn apply_medical_effect(chara: &mut Chara, me: MedicalEffect, eff: i32) {
     match me {
         MedicalEffect::None => (),
@@ -85,13 +220,94 @@ fn apply_medical_effect(chara: &mut Chara, me: MedicalEffect, eff: i32) {
             game_log!("heal-hp"; chara=chara, value=eff);
         }
         MedicalEffect::Sleep => {
-            chara.add_status(CharaStatus::Asleep { turn_left: eff as u16 });
+            chara.add_status(CharaStatus::Asleep { turn_left: super::item::clamp_u16(eff) });
             game_log!("fall-asleep"; chara=chara);
         }
         MedicalEffect::Poison => {
             chara.add_status(CharaStatus::Poisoned);
             game_log!("poisoned"; chara=chara);
         }
+        MedicalEffect::Haste => {
+            chara.add_status(CharaStatus::Hasted { turn_left: super::item::clamp_u16(eff) });
+            chara.update();
+            game_log!("hasted"; chara=chara);
+        }
     }
 }
 
+
+#[test]
+fn enter_event_talk_test() {
+    let mut game = super::script::test_game(3, 1);
+    game.gd.get_current_map_mut().events.push(MapEvent {
+        top_left: Vec2d(1, 0),
+        bottom_right: Vec2d(2, 0),
+        script_id: "test-event-talk".to_owned(),
+        once: true,
+        trigger: MapEventTrigger::Enter,
+    });
+
+    assert!(try_move(&mut game, CharaId::Player, Direction::E));
+    // Event scripts talk as the player
+    assert_eq!(super::script::requested_talk(&mut game),
+               Some((CharaId::Player, "test-event-text".to_owned())));
+    assert!(game.script.is_some());
+    assert!(game.gd.get_current_map().events.is_empty());
+}
+
+#[test]
+fn push_chara_test() {
+    use super::script::{add_test_npc, test_game};
+    let mut game = test_game(3, 1);
+    let npc = add_test_npc(&mut game, Vec2d(1, 0));
+
+    // Pushed to the empty tile
+    assert!(push_chara(&mut game, CharaId::Player, Direction::E));
+    assert_eq!(game.gd.chara_pos(npc), Some(Vec2d(2, 0)));
+    assert_eq!(game.gd.chara_pos(CharaId::Player), Some(Vec2d(0, 0)));
+
+    // Swapped at the edge of the map
+    game.gd.get_current_map_mut().move_chara(CharaId::Player, Direction::E);
+    assert!(push_chara(&mut game, CharaId::Player, Direction::E));
+    assert_eq!(game.gd.chara_pos(npc), Some(Vec2d(1, 0)));
+    assert_eq!(game.gd.chara_pos(CharaId::Player), Some(Vec2d(2, 0)));
+}
+
+#[test]
+fn throw_item_test() {
+    use super::script::{add_test_npc, test_game};
+    let mut game = test_game(10, 1);
+    game.gd.chara.get_mut(CharaId::Player).attr.str = 3;
+    let npc = add_test_npc(&mut game, Vec2d(2, 0));
+    game.gd.chara.get_mut(npc).hp = 50;
+    let ill = ItemListLocation::Chara { cid: CharaId::Player };
+    let potion = super::item::gen::gen_item_from_idx(gobj::id_to_idx("test-potion"));
+    game.gd.get_item_list_mut(ill).append(potion, 2);
+
+    // Medical items take effect on the target
+    assert!(throw_item(&mut game, CharaId::Player, (ill, 0), npc));
+    assert_eq!(game.gd.chara.get(npc).hp, 60);
+    assert_eq!(game.gd.get_item_list(ill).get_number(0), 1);
+
+    // The range is str / throw_range_divisor + 1
+    game.gd.get_current_map_mut().move_chara(npc, Direction::E);
+    assert!(!throw_item(&mut game, CharaId::Player, (ill, 0), npc));
+    assert_eq!(game.gd.get_item_list(ill).get_number(0), 1);
+}
+
+#[test]
+fn read_item_test() {
+    let mut game = super::script::test_game(1, 1);
+    let gd = &mut game.gd;
+    let ill = ItemListLocation::Chara { cid: CharaId::Player };
+    let book = super::item::gen::gen_item_from_idx(gobj::id_to_idx("test-book"));
+    gd.get_item_list_mut(ill).append(book, 2);
+
+    assert!(read_item(gd, (ill, 0), CharaId::Player));
+    assert!(gd.chara.get(CharaId::Player).skills.skills.contains_key(&SkillKind::Crafting));
+    assert_eq!(gd.get_item_list(ill).get_number(0), 1);
+
+    // The book is not consumed if the skill is already learned
+    assert!(!read_item(gd, (ill, 0), CharaId::Player));
+    assert_eq!(gd.get_item_list(ill).get_number(0), 1);
+}
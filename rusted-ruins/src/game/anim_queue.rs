@@ -31,9 +31,27 @@ impl AnimQueue {
         self.push(Animation::img_onetile(idx, tile));
     }
 
+    /// Flash on the tile where an attack hits
+    pub fn push_hit_flash(&mut self, tile: Vec2d) {
+        self.push(Animation::flash(FlashKind::Hit, RectIter::one(tile)));
+    }
+
+    /// Flash around the center tile by given radius
+    pub fn push_burst(&mut self, kind: FlashKind, center: Vec2d, radius: i32) {
+        let range = RectIter::new(center - (radius, radius), center + (radius, radius));
+        self.push(Animation::flash(kind, range));
+    }
+
     pub fn push_shot(&mut self, start: Vec2d, target: Vec2d) {
         let idx: AnimImgIdx = gobj::id_to_idx("!arrow");
         self.push(Animation::shot(idx, start, target));
     }
+
+    /// Thrown items fly like arrows if the throwing animation is not found
+    pub fn push_throw(&mut self, start: Vec2d, target: Vec2d) {
+        let idx: AnimImgIdx = gobj::id_to_idx_checked("!throw")
+            .unwrap_or_else(|| gobj::id_to_idx("!arrow"));
+        self.push(Animation::shot(idx, start, target));
+    }
 }
 
@@ -22,6 +22,18 @@ pub enum Animation {
         start: Vec2d,
         target: Vec2d,
     },
+    /// Colored flash fading out on tiles
+    Flash {
+        n_frame: u32,
+        kind: FlashKind,
+        range: RectIter,
+    },
+}
+
+/// Decides the color of flash animations
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlashKind {
+    Hit, Heal, Buff,
 }
 
 impl Animation {
@@ -30,6 +42,7 @@ impl Animation {
             &Animation::PlayerMove { n_frame, .. } => n_frame,
             &Animation::Img { n_frame, .. } => n_frame,
             &Animation::Shot { n_frame, .. } => n_frame,
+            &Animation::Flash { n_frame, .. } => n_frame,
         }
     }
     
@@ -48,6 +61,14 @@ impl Animation {
         }
     }
 
+    pub fn flash(kind: FlashKind, range: RectIter) -> Animation {
+        Animation::Flash {
+            n_frame: 6,
+            kind,
+            range,
+        }
+    }
+
     pub fn shot(idx: AnimImgIdx, start: Vec2d, target: Vec2d) -> Animation {
         let dx = (target.0 - start.0) as f32;
         let dy = (target.1 - start.1) as f32;
@@ -1,7 +1,8 @@
 
 use std::collections::HashMap;
+use array2d::Direction;
 use common::basic::WAIT_TIME_NUMERATOR;
-use common::obj::CharaTemplateObject;
+use common::obj::{CharaTemplateObject, SpawnTableObject};
 use common::objholder::CharaTemplateIdx;
 use common::gamedata::*;
 use common::gobj;
@@ -28,11 +29,18 @@ pub fn create_chara(chara_template_idx: CharaTemplateIdx, lv: u32) -> Chara {
         sp: RULES.chara.default_sp,
         skills: gen_skill_list(ct, lv),
         rel: Relationship::NEUTRAL,
+        faction: Faction::default(),
         trigger_talk: None,
+        mp: 0,
+        abilities: Vec::new(),
+        bonus_attr: CharaAttrRevision::default(),
+        mount: None,
+        facing: Direction::none(),
     };
     
     chara.update();
     chara.hp = chara.attr.max_hp;
+    chara.mp = chara.attr.max_mp;
     chara.reset_wait_time();
     chara
 }
@@ -42,13 +50,74 @@ pub fn create_npc_chara(dungeon: DungeonKind, floor_level: u32) -> Chara {
     let idx = choose_npc_chara_template(
         &RULES.dungeon_gen.get(&dungeon).expect("No rule for npc generation").npc_race_probability,
         floor_level);
-    let ct = gobj::get_obj(idx);    
+    create_monster_chara(idx)
+}
+
+/// Create a hostile monster of the template at its generation level
+pub fn create_monster_chara(idx: CharaTemplateIdx) -> Chara {
+    let ct = gobj::get_obj(idx);
     let mut chara = create_chara(idx, ct.gen_level);
     set_skill(&mut chara);
     chara.rel = Relationship::HOSTILE;
+    chara.faction = Faction::Monster;
     chara
 }
 
+/// Choose one group of the spawn table by weight from the groups appearing on the floor
+pub fn choose_spawn_group(table: &SpawnTableObject, floor_level: u32) -> Option<&SpawnGroup> {
+    let groups: Vec<&SpawnGroup> = table.groups.iter()
+        .filter(|group| group.floor_range[0] <= floor_level && floor_level <= group.floor_range[1])
+        .collect();
+    let sum: f64 = groups.iter().map(|group| group.weight as f64).sum();
+    if !(sum > 0.0) {
+        return groups.first().cloned();
+    }
+
+    let r = gen_range(0.0, sum);
+    let mut sum = 0.0;
+    for group in &groups {
+        sum += group.weight as f64;
+        if r < sum {
+            return Some(group);
+        }
+    }
+    groups.last().cloned()
+}
+
+/// Create hostile members of the spawn group.
+/// Templates are chosen by gen_level and gen_weight, so deeper floors have stronger members.
+pub fn create_spawn_group(group: &SpawnGroup, floor_level: u32) -> Vec<Chara> {
+    let weight_dist = CalcLevelWeightDist::new(floor_level);
+    let templates: Vec<(CharaTemplateIdx, f64)> = group.chara.iter().filter_map(|id| {
+        if let Some(idx) = gobj::id_to_idx_checked::<CharaTemplateIdx>(id) {
+            let ct = gobj::get_obj(idx);
+            Some((idx, weight_dist.calc(ct.gen_level) * ct.gen_weight as f64))
+        } else {
+            warn!("unknown chara template \"{}\" in spawn group", id);
+            None
+        }
+    }).collect();
+    if templates.is_empty() {
+        return Vec::new();
+    }
+    let sum: f64 = templates.iter().map(|&(_, w)| w).sum();
+
+    let n = gen_range(group.n[0], group.n[1] + 1);
+    (0..n).map(|_| {
+        let idx = if sum > 0.0 {
+            let r = gen_range(0.0, sum);
+            let mut sum = 0.0;
+            templates.iter().find(|&&(_, w)| {
+                sum += w;
+                r < sum
+            }).unwrap_or(&templates[templates.len() - 1]).0
+        } else {
+            templates[0].0
+        };
+        create_monster_chara(idx)
+    }).collect()
+}
+
 /// Choose one chara_template by race, gen_level and gen_weight
 pub fn choose_npc_chara_template(nrp: &HashMap<Race, f32>, floor_level: u32) -> CharaTemplateIdx {
     let chara_templates = &gobj::get_objholder().chara_template;
@@ -114,6 +183,7 @@ impl CalcLevelWeightDist {
 pub fn create_ai(ai_kind: NpcAIKind) -> CharaAI {
     CharaAI {
         kind: ai_kind,
+        ..CharaAI::default()
     }
 }
 
@@ -122,7 +192,8 @@ fn set_skill(chara: &mut Chara) {
     let ct = gobj::get_obj(chara.template);
     
     match ct.race {
-        Race::Animal | Race::Bug | Race::Slime => {
+        Race::Animal | Race::Bug | Race::Slime
+            if !ct.skill_levels.iter().any(|&(kind, _)| kind == SkillKind::MartialArts) => {
             chara.skills.set_skill_level(SkillKind::MartialArts, ct.gen_level / 2 + 5)
         }
         _ => (),
@@ -130,13 +201,32 @@ fn set_skill(chara: &mut Chara) {
 }
 
 /// Generate skill list based on floor level and CharaTemplateObject
-fn gen_skill_list(_ct: &CharaTemplateObject, lv: u32) -> SkillList {
+fn gen_skill_list(ct: &CharaTemplateObject, lv: u32) -> SkillList {
     let mut skill_list = SkillList::default();
     let common_skills = &RULES.chara_gen.common_skills;
 
     for skill_kind in common_skills {
         skill_list.set_skill_level(*skill_kind, lv)
     }
+
+    // Skill levels given by the template override common skills
+    for &(skill_kind, skill_lv) in &ct.skill_levels {
+        skill_list.set_skill_level(skill_kind, skill_lv)
+    }
     
     skill_list
 }
+
+#[test]
+fn spawn_group_test() {
+    crate::game::script::init_test_objs();
+    let table: &SpawnTableObject = gobj::get_by_id("test-spawn");
+    assert_eq!(choose_spawn_group(table, 1), Some(&table.groups[0]));
+    assert_eq!(choose_spawn_group(table, 4), Some(&table.groups[1]));
+    assert_eq!(choose_spawn_group(table, 9), None);
+
+    let charas = create_spawn_group(&table.groups[0], 1);
+    assert_eq!(charas.len(), 2);
+    let idx: CharaTemplateIdx = gobj::id_to_idx("test-chara");
+    assert!(charas.iter().all(|chara| chara.template == idx && chara.rel == Relationship::HOSTILE));
+}
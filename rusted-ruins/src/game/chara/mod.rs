@@ -18,6 +18,8 @@ pub trait CharaEx {
     fn add_skill_exp(&mut self, kind: SkillKind, add_exp: u32, base_level: u32);
     /// Add exp when this character attacks.
     fn add_attack_exp(&mut self, kind: SkillKind, target_level: u32);
+    /// Add exp when this character kills the target.
+    fn add_kill_exp(&mut self, kind: SkillKind, target_level: u32);
     /// Add exp when damaged.
     fn add_damage_exp(&mut self, damage: i32, attacker_level: u32);
     /// Add exp when attacked.
@@ -45,6 +47,10 @@ impl CharaEx for Chara {
         self.add_skill_exp(kind, RULES.exp.attack, target_level);
     }
 
+    fn add_kill_exp(&mut self, kind: SkillKind, target_level: u32) {
+        self.add_skill_exp(kind, RULES.exp.kill, target_level);
+    }
+
     fn add_damage_exp(&mut self, damage: i32, attacker_level: u32) {
         let rel_damage = damage as f32 / self.attr.max_hp as f32;
         let exp = rel_damage * RULES.exp.endurance as f32;
@@ -118,8 +124,87 @@ pub fn damage(game: &mut Game, cid: CharaId, damage: i32, damage_kind: DamageKin
             DamageKind::Poison => {
                 game_log!("killed-by-poison-damage"; chara=chara);
             }
+            DamageKind::Starve => {
+                game_log!("killed-by-starvation"; chara=chara);
+            }
+            DamageKind::Ability => {
+                game_log!("killed-by-ability"; chara=chara);
+            }
+            DamageKind::Trap => {
+                game_log!("killed-by-trap"; chara=chara);
+            }
         }
     }
     chara.hp
 }
 
+
+/// Relationship between two characters.
+/// Decided by their factions. If one of them doesn't belong to any faction,
+/// falls back to their relationships to the player.
+pub fn relation_between(gd: &GameData, a: CharaId, b: CharaId) -> Relationship {
+    let ca = gd.chara.get(a);
+    let cb = gd.chara.get(b);
+    if ca.faction == Faction::Unknown || cb.faction == Faction::Unknown {
+        return ca.rel.relative(cb.rel);
+    }
+    gd.faction.relation(ca.faction, cb.faction)
+}
+
+/// The faction of the target becomes hostile to the player if the player attacks it
+pub fn provoke(gd: &mut GameData, attacker: CharaId, target: CharaId) {
+    if attacker != CharaId::Player {
+        return;
+    }
+    let faction = gd.chara.get(target).faction;
+    if faction == Faction::Unknown || faction == Faction::Player {
+        return;
+    }
+    if gd.faction.player_relation(faction) != Relationship::HOSTILE {
+        gd.faction.set_player_relation(faction, Relationship::HOSTILE);
+        game_log!("faction-become-hostile"; faction=faction);
+    }
+}
+
+#[cfg(test)]
+fn test_chara(faction: Faction, rel: Relationship) -> Chara {
+    Chara { faction, rel, ..Chara::default() }
+}
+
+#[test]
+fn relation_between_test() {
+    let mut gd = GameData::empty();
+    let sid = SiteId::default();
+    let player = gd.add_chara(test_chara(Faction::Player, Relationship::ALLY), CharaKind::Player);
+    let citizen = gd.add_chara_to_site(test_chara(Faction::Citizen, Relationship::FRIENDLY), sid, 0);
+    let monster = gd.add_chara_to_site(test_chara(Faction::Monster, Relationship::HOSTILE), sid, 1);
+    let wanderer = gd.add_chara_to_site(test_chara(Faction::Unknown, Relationship::NEUTRAL), sid, 2);
+
+    assert_eq!(relation_between(&gd, player, citizen), Relationship::FRIENDLY);
+    assert_eq!(relation_between(&gd, player, monster), Relationship::HOSTILE);
+    assert_eq!(relation_between(&gd, player, wanderer), Relationship::NEUTRAL);
+    assert_eq!(relation_between(&gd, citizen, monster), Relationship::HOSTILE);
+
+    // Symmetric
+    for &a in &[player, citizen, monster, wanderer] {
+        for &b in &[player, citizen, monster, wanderer] {
+            assert_eq!(relation_between(&gd, a, b), relation_between(&gd, b, a));
+        }
+    }
+}
+
+#[test]
+fn provoke_test() {
+    let mut gd = GameData::empty();
+    let sid = SiteId::default();
+    let player = gd.add_chara(test_chara(Faction::Player, Relationship::ALLY), CharaKind::Player);
+    let citizen = gd.add_chara_to_site(test_chara(Faction::Citizen, Relationship::FRIENDLY), sid, 0);
+    let other_citizen = gd.add_chara_to_site(test_chara(Faction::Citizen, Relationship::FRIENDLY), sid, 1);
+
+    provoke(&mut gd, citizen, player);
+    assert_eq!(relation_between(&gd, player, citizen), Relationship::FRIENDLY);
+
+    provoke(&mut gd, player, citizen);
+    assert_eq!(relation_between(&gd, player, other_citizen), Relationship::HOSTILE);
+    assert_eq!(relation_between(&gd, citizen, other_citizen), Relationship::FRIENDLY);
+}
@@ -1,6 +1,7 @@
 
 use common::gamedata::*;
 use rules::RULES;
+use rng::Rng;
 use super::Game;
 use crate::game::combat::DamageKind;
 use crate::game::extrait::*;
@@ -9,6 +10,7 @@ use crate::game::extrait::*;
 /// 
 pub fn preturn(game: &mut Game, cid: CharaId) -> bool {
     let mut is_poisoned = false;
+    let mut is_starving = false;
     
     {
         let chara = game.gd.chara.get_mut(cid);
@@ -18,13 +20,20 @@ pub fn preturn(game: &mut Game, cid: CharaId) -> bool {
             s.advance_turn(1);
         }
         
+        let n_status = chara.status.len();
         chara.status.retain(|s| !s.is_expired()); // Remove expired status
+        if chara.status.len() != n_status { // Remove modifiers of expired status
+            chara.update();
+        }
         
         for s in chara.status.iter() {
             match *s {
                 CharaStatus::Poisoned => {
                     is_poisoned = true;
                 }
+                CharaStatus::Starving => {
+                    is_starving = true;
+                }
                 _ => (),
             }
         }
@@ -37,8 +46,18 @@ pub fn preturn(game: &mut Game, cid: CharaId) -> bool {
         super::damage(game, cid, damage, DamageKind::Poison);
     }
 
+    if is_starving {
+        let chara = game.gd.chara.get_mut(cid);
+        let damage = std::cmp::max(chara.attr.max_hp / 50, 1);
+        game_log!("starve-damage"; chara=chara, damage=damage);
+        super::damage(game, cid, damage, DamageKind::Starve);
+    }
+
     let chara = game.gd.chara.get_mut(cid);
     chara.add_sp(-RULES.chara.sp_consumption, cid);
+    if chara.mp < chara.attr.max_mp && rng::get_rng().gen_bool(RULES.chara.mp_regen_probability) {
+        chara.mp += 1;
+    }
     can_act(chara)
 }
 
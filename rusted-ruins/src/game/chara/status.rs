@@ -28,6 +28,19 @@ impl CharaStatusOperation for Chara {
                     }
                 }
             }
+            CharaStatus::Hasted { turn_left: turn_left_new } => {
+                for s in self.status.iter_mut() {
+                    match *s {
+                        CharaStatus::Hasted { ref mut turn_left } => {
+                            if turn_left_new > *turn_left {
+                                *turn_left = turn_left_new;
+                            }
+                            return;
+                        }
+                        _ => (),
+                    }
+                }
+            }
             CharaStatus::Poisoned => {
                 for s in self.status.iter_mut() {
                     match *s {
@@ -64,7 +77,8 @@ impl CharaStatusEx for CharaStatus {
 
     fn advance_turn(&mut self, n: u16) {
         match *self {
-            CharaStatus::Asleep { ref mut turn_left } => {
+            CharaStatus::Asleep { ref mut turn_left } |
+            CharaStatus::Hasted { ref mut turn_left } => {
                 if *turn_left > n {
                     *turn_left -= n;
                 } else {
@@ -80,6 +94,7 @@ impl CharaStatusEx for CharaStatus {
     fn is_expired(&self) -> bool {
         match *self {
             CharaStatus::Asleep { turn_left } if turn_left == 0 => true,
+            CharaStatus::Hasted { turn_left } if turn_left == 0 => true,
             _ => false,
         }
     }
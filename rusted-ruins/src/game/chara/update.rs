@@ -13,6 +13,7 @@ pub fn update_attributes(chara: &mut Chara) {
     } else {
         ct.base_attr
     };
+    let base_attr = base_attr.revise(chara.bonus_attr);
 
     chara.attr.max_hp = calc_max_hp(chara, ct);
     chara.attr.str = base_attr.str as u16;
@@ -22,10 +23,17 @@ pub fn update_attributes(chara: &mut Chara) {
     chara.attr.wil = base_attr.wil as u16;
     chara.attr.cha = base_attr.cha as u16;
     chara.attr.spd = base_attr.spd as u16;
+    if chara.status.iter().any(|s| if let CharaStatus::Hasted { .. } = s { true } else { false }) {
+        chara.attr.spd = chara.attr.spd * RULES.chara.haste_spd_percent / 100;
+    }
     chara.attr.view_range = RULES.chara.default_view_range;
+    chara.attr.max_mp = calc_max_mp(chara);
 }
 
 fn calc_max_hp(chara: &mut Chara, ct: &CharaTemplateObject) -> i32 {
     (chara.skills.get(SkillKind::Endurance) as i32 + 8) * ct.base_attr.base_hp / 8
 }
 
+fn calc_max_mp(chara: &Chara) -> i32 {
+    (chara.attr.int as i32 + chara.attr.wil as i32) * RULES.chara.mp_per_int_wil / 2
+}
@@ -1,7 +1,8 @@
 
 use crate::rng;
 use super::Game;
-use super::chara::CharaEx;
+use super::chara::{self, CharaEx};
+use super::item::{ItemEx, apply_percent, clamp_u16};
 use common::gobj;
 use common::gamedata::*;
 use rules::RULES;
@@ -11,10 +12,15 @@ pub enum DamageKind {
     MeleeAttack,
     RangedAttack,
     Poison,
+    Starve,
+    Ability,
+    Trap,
 }
 
 pub struct AttackParams {
     attacker_id: Option<CharaId>,
+    /// The skill that gains exp when the target is killed
+    skill_kind: Option<SkillKind>,
     kind: DamageKind,
     element: Element,
     attack_power: f64,
@@ -28,6 +34,8 @@ pub fn attack_neighbor(game: &mut Game, attacker_id: CharaId, target_id: CharaId
         let target = game.gd.chara.get(target_id);
         game_log!("attack"; attacker=attacker, target=target);
     }
+    chara::provoke(&mut game.gd, attacker_id, target_id);
+    make_attack_noise(game, attacker_id);
     // Animation pushing
     game.anim_queue.push_attack(game.gd.get_current_map().chara_pos(target_id).unwrap());
     // Judges hit or miss
@@ -56,7 +64,8 @@ pub fn attack_neighbor(game: &mut Game, attacker_id: CharaId, target_id: CharaId
             let weapon_kind = get_weapon_kind(weapon_obj);
             skill_kind = SkillKind::Weapon(weapon_kind);
             
-            let dice_result = rng::dice(weapon_obj.dice_n as i32, weapon_obj.dice_x as i32);
+            let dice_result = apply_percent(
+                rng::dice(weapon_obj.dice_n as i32, weapon_obj.dice_x as i32), weapon.eff_percent());
             let weapon_skill_level = attacker.skills.get(skill_kind);
             calc_attack_power(dice_result, attacker.attr.str, weapon_skill_level)
         } else { // Attack by martial arts
@@ -68,6 +77,7 @@ pub fn attack_neighbor(game: &mut Game, attacker_id: CharaId, target_id: CharaId
     };
     let attack_params = AttackParams {
         attacker_id: Some(attacker_id),
+        skill_kind: Some(skill_kind),
         kind: DamageKind::MeleeAttack,
         element: Element::Physical,
         attack_power,
@@ -98,6 +108,14 @@ pub fn shot_target(game: &mut Game, attacker_id: CharaId, target_id: CharaId) ->
     let attacker_pos = game.gd.get_current_map().chara_pos(attacker_id).unwrap();
     let target_pos = game.gd.get_current_map().chara_pos(target_id).unwrap();
 
+    // Walls between the attacker and the target block shots
+    if super::view::calc_visual_distance(game.gd.get_current_map(), attacker_pos, target_pos).is_none() {
+        if attacker_id == CharaId::Player {
+            game_log_i!("target-not-in-sight");
+        }
+        return false;
+    }
+
     // Judges hit or miss
     {
         let attacker = game.gd.chara.get(attacker_id);
@@ -110,6 +128,8 @@ pub fn shot_target(game: &mut Game, attacker_id: CharaId, target_id: CharaId) ->
             // Exp to target chara
             game.gd.chara.get_mut(target_id).add_evasion_exp(attacker_level);
             game.anim_queue.push_shot(attacker_pos, target_pos);
+            chara::provoke(&mut game.gd, attacker_id, target_id);
+            make_attack_noise(game, attacker_id);
             return true;
         }
     }
@@ -118,13 +138,15 @@ pub fn shot_target(game: &mut Game, attacker_id: CharaId, target_id: CharaId) ->
     let (attack_params, weapon_kind, attacker_pos) = {
         let weapon_obj = gobj::get_obj(weapon.idx);
         let weapon_kind = get_weapon_kind(weapon_obj);
-        let dice_result = rng::dice(weapon_obj.dice_n as i32, weapon_obj.dice_x as i32);
+        let dice_result = apply_percent(
+            rng::dice(weapon_obj.dice_n as i32, weapon_obj.dice_x as i32), weapon.eff_percent());
         
         let weapon_skill_level = attacker.skills.get(SkillKind::Weapon(weapon_kind));
         let attack_power = calc_attack_power(dice_result, attacker.attr.dex, weapon_skill_level);
 
         let attack_params = AttackParams {
             attacker_id: Some(attacker_id),
+            skill_kind: Some(SkillKind::Weapon(weapon_kind)),
             kind: DamageKind::RangedAttack,
             element: Element::Physical,
             attack_power,
@@ -137,6 +159,8 @@ pub fn shot_target(game: &mut Game, attacker_id: CharaId, target_id: CharaId) ->
         let target = game.gd.chara.get(target_id);
         game_log!("shot-target"; attacker=attacker, target=target);
     }
+    chara::provoke(&mut game.gd, attacker_id, target_id);
+    make_attack_noise(game, attacker_id);
     // Damage target
     let _damage = attack_target(game, attack_params, target_id);
     // Exp processing
@@ -147,16 +171,70 @@ pub fn shot_target(game: &mut Game, attacker_id: CharaId, target_id: CharaId) ->
     }
     // Animation pushing
     game.anim_queue.push_shot(attacker_pos, target_pos);
+    game.anim_queue.push_hit_flash(target_pos);
     // Sound effect
     crate::audio::play_sound("arrow");
     true
 }
 
+/// Hostile npcs around the attacker hear the attack
+fn make_attack_noise(game: &mut Game, attacker_id: CharaId) {
+    if let Some(pos) = game.gd.get_current_map().chara_pos(attacker_id) {
+        super::npc::perception::make_noise(&mut game.gd, attacker_id, pos);
+    }
+}
+
+/// Damage the target by a thrown item. The item hits or misses like shots.
+/// Returns false if missed.
+pub fn throw_damage(game: &mut Game, attacker_id: CharaId, target_id: CharaId, dice: i32) -> bool {
+    let attacker = game.gd.chara.get(attacker_id);
+    let attacker_level = attacker.level;
+    let accuracy_power = calc_accuracy_power(1, 1, attacker.attr.dex);
+    chara::provoke(&mut game.gd, attacker_id, target_id);
+    make_attack_noise(game, attacker_id);
+    if !hit_judge(&game.gd, accuracy_power, target_id, DamageKind::RangedAttack) {
+        game.gd.chara.get_mut(target_id).add_evasion_exp(attacker_level);
+        return false;
+    }
+
+    let attacker = game.gd.chara.get(attacker_id);
+    let attack_params = AttackParams {
+        attacker_id: Some(attacker_id),
+        skill_kind: None,
+        kind: DamageKind::RangedAttack,
+        element: Element::Physical,
+        attack_power: calc_attack_power(std::cmp::max(dice, 1), attacker.attr.str, 0),
+    };
+    attack_target(game, attack_params, target_id);
+    let target_pos = game.gd.get_current_map().chara_pos(target_id);
+    if let Some(target_pos) = target_pos {
+        game.anim_queue.push_hit_flash(target_pos);
+    }
+    true
+}
+
+/// Damage the target by an ability. Ability damage always hits.
+pub fn ability_damage(
+    game: &mut Game, attacker_id: CharaId, target_id: CharaId, power: i32, element: Element) {
+
+    let attacker = game.gd.chara.get(attacker_id);
+    let attack_params = AttackParams {
+        attacker_id: Some(attacker_id),
+        skill_kind: None,
+        kind: DamageKind::Ability,
+        element,
+        attack_power: calc_attack_power(std::cmp::max(power, 1), attacker.attr.int, 0),
+    };
+    chara::provoke(&mut game.gd, attacker_id, target_id);
+    attack_target(game, attack_params, target_id);
+}
+
 /// Routines for targetted character
 fn attack_target(game: &mut Game, attack_params: AttackParams, target_id: CharaId) -> i32 {
     let equip_def = calc_equip_defence(&game.gd, target_id);
     let target = game.gd.chara.get(target_id);
     let idx = target.template;
+    let target_level = target.level;
     let defence_skill_level = target.skills.get(SkillKind::Defence);
     let defence_power = calc_defence_power(
         equip_def[attack_params.element], target.attr.vit, defence_skill_level);
@@ -176,7 +254,12 @@ fn attack_target(game: &mut Game, attack_params: AttackParams, target_id: CharaI
             target.add_damage_exp(damage, attacker_level);
         }
     } else {
-        super::quest::count_slayed_monster(&mut game.gd, idx);
+        // Exp for the attacker who killed the target
+        if let (Some(attacker_id), Some(skill_kind)) = (attack_params.attacker_id, attack_params.skill_kind) {
+            let attacker = game.gd.chara.get_mut(attacker_id);
+            attacker.add_kill_exp(skill_kind, target_level);
+        }
+        super::quest::process_quest_event(&mut game.gd, super::quest::QuestEvent::Kill(idx));
     }
 
     damage
@@ -195,8 +278,9 @@ fn calc_equip_defence(gd: &GameData, cid: CharaId) -> ElementArray<u16> {
     
     for (_, _, item) in gd.get_equip_list(cid).item_iter() {
         let item_obj: &ItemObject = gobj::get_obj(item.idx);
+        let eff_percent = item.eff_percent();
         for e in &ELEMENTS {
-            def[*e] = def[*e].saturating_add(item_obj.def[*e]);
+            def[*e] = def[*e].saturating_add(clamp_u16(apply_percent(item_obj.def[*e], eff_percent)));
         }
     }
 
@@ -268,3 +352,25 @@ fn hit_judge(gd: &GameData, accuracy_power: f64, target_id: CharaId, kind: Damag
     is_hit
 }
 
+
+#[test]
+fn throw_damage_test() {
+    use array2d::Vec2d;
+    use super::script::{add_test_npc, test_game};
+    let mut game = test_game(3, 1);
+    let npc = add_test_npc(&mut game, Vec2d(2, 0));
+    let player = game.gd.chara.get_mut(CharaId::Player);
+    player.attr.str = 10;
+    player.attr.dex = 2000;
+
+    // Hits almost always with high dexterity
+    assert!(throw_damage(&mut game, CharaId::Player, npc, 1));
+    assert!(game.gd.chara.get(npc).hp < 100);
+
+    // Misses almost always against high dexterity
+    let hp = game.gd.chara.get(npc).hp;
+    game.gd.chara.get_mut(CharaId::Player).attr.dex = 0;
+    game.gd.chara.get_mut(npc).attr.dex = 2000;
+    assert!(!throw_damage(&mut game, CharaId::Player, npc, 1));
+    assert_eq!(game.gd.chara.get(npc).hp, hp);
+}
@@ -11,9 +11,33 @@ pub enum Command {
     Cancel,
     RotateWindowRight, RotateWindowLeft,
     Shot,
-    OpenExitWin, OpenItemMenu, OpenEquipWin, OpenStatusWin, OpenGameInfoWin,
-    PickUpItem, DropItem, DrinkItem, EatItem,
+    OpenExitWin, OpenItemMenu, OpenEquipWin, OpenStatusWin, OpenGameInfoWin, OpenJournalWin, OpenAbilityWin,
+    OpenCraftWin, OpenLogWin, OpenMapWin, OpenPartyWin, ToggleMinimap,
+    PickUpItem, DropItem, DrinkItem, EatItem, ReadItem, ThrowItem, CloseDoor, DisarmTrap,
+    /// Push the adjacent character in the direction input next
+    PushChara,
+    /// Get off the horse or boat
+    Dismount,
+    /// Dig in the direction input next, or dig down by enter
+    Dig,
     TargetingMode,
     TextInput { text: String },
+    /// Text being composed by IME
+    TextEditing { text: String, start: i32 },
+    /// Delete the char before the cursor
     TextDelete,
+    /// Delete the char after the cursor
+    TextDeleteForward,
+    /// A key pressed in key input mode. Used to change key bindings.
+    KeyInput { key: String },
+    /// Left mouse button is released at the position on the screen
+    MouseButtonUp { x: i32, y: i32 },
+    /// Mouse wheel is scrolled. Positive y means scrolling up.
+    MouseWheel { y: i32 },
+    /// Save the current screen to an image file
+    Screenshot,
+    /// Save the whole current map to an image file for debugging
+    ExportMap,
+    /// Open the console to execute debug commands
+    OpenConsole,
 }
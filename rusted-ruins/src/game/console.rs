@@ -0,0 +1,93 @@
+
+//! Developer console commands. Each command line is converted to script instructions,
+//! and executed by the script engine in the same way as scripts in paks.
+
+use std::rc::Rc;
+use common::gamedata::{SiteKind, Weather};
+use common::hashmap::HashMap;
+use common::script::*;
+use super::Game;
+use super::script::ScriptEngine;
+
+/// Usages shown by "help"
+pub const HELP: &[&str] = &[
+    "item <id> [n]",
+    "chara <id> [n]",
+    "money <n>",
+    "gset <name> <value>",
+    "teleport region | teleport <site kind> <n> [floor]",
+    "weather <weather> [hours]",
+    "reveal",
+];
+
+/// Execute one command line
+pub fn exec(game: &mut Game, line: &str) -> Result<(), String> {
+    let instructions = parse(line)?;
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), instructions);
+    let script = Rc::new(Script::from_map(map));
+    game.start_script_engine(ScriptEngine::with_script(script, None));
+    Ok(())
+}
+
+fn parse(line: &str) -> Result<Vec<Instruction>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (command, args) = if let Some((command, args)) = words.split_first() {
+        (*command, args)
+    } else {
+        return Err("empty command".to_owned());
+    };
+    let arg = |i: usize| args.get(i).cloned().ok_or_else(|| format!("too few arguments for {}", command));
+    let n = |i: usize, default: i32| args.get(i).map_or(Ok(default), |s| parse_int(s));
+
+    let instructions = match command {
+        "item" => vec![Instruction::ReceiveItem(arg(0)?.to_owned(), Expr::Value(Value::Int(n(1, 1)?)))],
+        "chara" => vec![Instruction::SpawnChara(arg(0)?.to_owned()); n(1, 1)?.max(0) as usize],
+        "money" => vec![Instruction::ReceiveMoney(Expr::Value(Value::Int(parse_int(arg(0)?)?)))],
+        "gset" => vec![Instruction::GSet(arg(0)?.to_owned(), Expr::Value(parse_value(arg(1)?)?))],
+        "teleport" => {
+            let kind = arg(0)?;
+            let dest = if kind == "region" {
+                None
+            } else {
+                let kind: SiteKind = kind.parse().map_err(|_| format!("unknown site kind {}", kind))?;
+                Some((kind, parse_int(arg(1)?)?.max(0) as u32, n(2, 0)?.max(0) as u32))
+            };
+            vec![Instruction::Teleport(dest)]
+        }
+        "weather" => {
+            let weather = arg(0)?;
+            let weather: Weather = weather.parse().map_err(|_| format!("unknown weather {}", weather))?;
+            vec![Instruction::SetWeather(weather, Expr::Value(Value::Int(n(1, 24)?)))]
+        }
+        "reveal" => vec![Instruction::Special(SpecialInstruction::RevealMap)],
+        _ => return Err(format!("unknown command {}", command)),
+    };
+    Ok(instructions)
+}
+
+fn parse_int(s: &str) -> Result<i32, String> {
+    s.parse().map_err(|_| format!("{} is not a number", s))
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    match s {
+        "true" => Ok(Value::Bool(true)),
+        "false" => Ok(Value::Bool(false)),
+        _ => Ok(Value::Int(parse_int(s)?)),
+    }
+}
+
+#[test]
+fn console_parse_test() {
+    let int = |i| Expr::Value(Value::Int(i));
+    assert_eq!(parse("item potion 3"), Ok(vec![Instruction::ReceiveItem("potion".to_owned(), int(3))]));
+    assert_eq!(parse(" chara  slime 2 "), Ok(vec![Instruction::SpawnChara("slime".to_owned()); 2]));
+    assert_eq!(parse("gset visited true"),
+               Ok(vec![Instruction::GSet("visited".to_owned(), Expr::Value(Value::Bool(true)))]));
+    assert_eq!(parse("teleport region"), Ok(vec![Instruction::Teleport(None)]));
+    assert_eq!(parse("teleport town 1"), Ok(vec![Instruction::Teleport(Some((SiteKind::Town, 1, 0)))]));
+    assert!(parse("money many").is_err());
+    assert!(parse("item").is_err());
+    assert!(parse("fly").is_err());
+}
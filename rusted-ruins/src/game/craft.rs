@@ -0,0 +1,113 @@
+//! Crafting items from other items by recipes
+
+use common::gamedata::*;
+use common::gobj;
+use common::objholder::{ItemIdx, RecipeIdx};
+use rules::RULES;
+use super::chara::CharaEx;
+use super::item::ItemListEx;
+use super::item::gen::gen_item_from_idx;
+
+/// Item indices and numbers of the inputs.
+/// Returns None if the recipe has unknown item ids.
+pub fn recipe_inputs(recipe: &RecipeObject) -> Option<Vec<(ItemIdx, u32)>> {
+    recipe.inputs.iter().map(|input| {
+        gobj::id_to_idx_checked::<ItemIdx>(&input.id).map(|idx| (idx, input.n))
+    }).collect()
+}
+
+/// The item list has all inputs
+pub fn has_inputs(item_list: &ItemList, inputs: &[(ItemIdx, u32)]) -> bool {
+    inputs.iter().all(|&(idx, n)| item_list.count(idx) >= n)
+}
+
+/// Recipes that the player has all inputs for
+pub fn available_recipes(gd: &GameData) -> Vec<RecipeIdx> {
+    let item_list = gd.get_item_list(ItemListLocation::Chara { cid: CharaId::Player });
+    gobj::get_objholder().recipe.iter().enumerate().filter_map(|(i, recipe)| {
+        let inputs = recipe_inputs(recipe)?;
+        if has_inputs(item_list, &inputs) {
+            Some(RecipeIdx::from_usize(i))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+/// Consume inputs and give the output to the character.
+/// Returns false if the character lacks inputs or the skill level.
+pub fn craft(gd: &mut GameData, cid: CharaId, idx: RecipeIdx) -> bool {
+    let recipe: &RecipeObject = gobj::get_obj(idx);
+    let (inputs, output) = match (recipe_inputs(recipe), gobj::id_to_idx_checked::<ItemIdx>(&recipe.output)) {
+        (Some(inputs), Some(output)) => (inputs, output),
+        _ => {
+            warn!("unknown item id in recipe \"{}\"", recipe.id);
+            return false;
+        }
+    };
+
+    let chara = gd.chara.get_mut(cid);
+    if chara.skills.get(recipe.skill) < recipe.skill_level {
+        game_log_i!("craft-lack-skill"; chara=chara, skill=recipe.skill);
+        return false;
+    }
+    if !has_inputs(&chara.item_list, &inputs) {
+        return false;
+    }
+
+    for &(idx, n) in &inputs {
+        chara.item_list.remove_by_idx(idx, n);
+    }
+    let item = gen_item_from_idx(output);
+    game_log_i!("craft"; chara=chara, item=&item);
+    chara.item_list.append(item, recipe.n_output);
+    chara.add_skill_exp(recipe.skill, RULES.exp.craft, recipe.skill_level);
+    true
+}
+
+#[test]
+fn has_inputs_test() {
+    let item = |i| Item {
+        idx: ItemIdx::from_usize(i),
+        kind: ItemKind::Material,
+        flags: ItemFlags::empty(),
+        rank: ItemRank::default(),
+        attributes: vec![],
+    };
+    let mut item_list = ItemList::new();
+    item_list.append(item(0), 2);
+    item_list.append(item(1), 1);
+
+    let a = ItemIdx::from_usize(0);
+    let b = ItemIdx::from_usize(1);
+    let c = ItemIdx::from_usize(2);
+    assert!(has_inputs(&item_list, &[(a, 2), (b, 1)]));
+    assert!(!has_inputs(&item_list, &[(a, 3)]));
+    assert!(!has_inputs(&item_list, &[(a, 1), (c, 1)]));
+    assert!(has_inputs(&item_list, &[]));
+}
+
+#[test]
+fn craft_test() {
+    use super::skill::SkillListEx;
+    let mut gd = super::script::test_game(1, 1).gd;
+    let idx: RecipeIdx = gobj::id_to_idx("test-recipe");
+    let potion: ItemIdx = gobj::id_to_idx("test-potion");
+    let ill = ItemListLocation::Chara { cid: CharaId::Player };
+    gd.get_item_list_mut(ill).append(gen_item_from_idx(gobj::id_to_idx("test-item")), 3);
+
+    // The skill level is lacking
+    assert!(!craft(&mut gd, CharaId::Player, idx));
+    assert!(available_recipes(&gd).contains(&idx));
+
+    gd.chara.get_mut(CharaId::Player).skills.learn_new_skill(SkillKind::Crafting);
+    assert!(craft(&mut gd, CharaId::Player, idx));
+    let item_list = gd.get_item_list(ill);
+    assert_eq!(item_list.count(gobj::id_to_idx("test-item")), 1);
+    assert_eq!(item_list.count(potion), 1);
+    assert!(gd.chara.get(CharaId::Player).skills.get_level_exp(SkillKind::Crafting).1 > 0);
+
+    // Inputs are lacking
+    assert!(!available_recipes(&gd).contains(&idx));
+    assert!(!craft(&mut gd, CharaId::Player, idx));
+}
@@ -0,0 +1,90 @@
+//! Digging walls and floors with pickaxes and shovels
+
+use array2d::*;
+use common::gamedata::*;
+use common::gobj;
+use rules::RULES;
+use super::InfoGetter;
+use super::map::MapEx;
+
+/// The best digging power of items in the inventory and equipment
+fn dig_power(chara: &Chara) -> u16 {
+    let items = chara.item_list.iter().map(|(item, _)| item);
+    let equips = chara.equip.item_iter().map(|(_, _, item)| item);
+    items.chain(equips).map(|item| gobj::get_obj(item.idx).dig_power).max().unwrap_or(0)
+}
+
+/// Turns needed to dig through the hardness with the power
+pub fn dig_turns(hardness: u16, power: u16, max_turns: u32) -> u32 {
+    let power = std::cmp::max(power, 1) as u32;
+    let turns = (hardness as u32 + power - 1) / power;
+    turns.max(1).min(max_turns)
+}
+
+/// Dig the wall, or mine the resource in the direction.
+/// Returns the number of turns spent, or None if nothing is dug.
+pub fn dig(gd: &mut GameData, cid: CharaId, dir: Direction) -> Option<u32> {
+    let pos = gd.chara_pos(cid)? + dir.as_vec();
+    let map = gd.get_current_map();
+    if !map.is_inside(pos) {
+        game_log_i!("dig-nothing");
+        return None;
+    }
+    if let SpecialTileKind::Resource { .. } = map.tile[pos].special {
+        return if super::harvest::harvest(gd, cid, pos) { Some(1) } else { None };
+    }
+    let wall = if let Some(wall) = map.tile[pos].wall.idx() { wall } else {
+        game_log_i!("dig-nothing");
+        return None;
+    };
+    let hardness = if let Some(hardness) = gobj::get_obj(wall).hardness { hardness } else {
+        game_log_i!("dig-too-hard");
+        return None;
+    };
+
+    let chara = gd.chara.get(cid);
+    let power = dig_power(chara);
+    if power == 0 {
+        game_log_i!("dig-lack-tool"; chara=chara);
+        return None;
+    }
+    game_log_i!("dig-wall"; chara=chara);
+    gd.get_current_map_mut().remove_wall(pos);
+    Some(dig_turns(hardness, power, RULES.params.max_dig_turns))
+}
+
+/// Dig down at the character's tile in underground dungeons, and make stairs to the floor below.
+/// Returns the number of turns spent, or None if the character cannot dig there.
+pub fn dig_down(gd: &mut GameData, cid: CharaId) -> Option<u32> {
+    let pos = gd.chara_pos(cid)?;
+    let mid = gd.get_current_mapid();
+    let can_dig = !mid.is_region_map() && {
+        let site = gd.region.get_site(mid.sid());
+        site.is_underground() && mid.floor() + 1 < site.max_floor()
+    } && gd.get_current_map().tile[pos].special.is_none();
+    if !can_dig {
+        game_log_i!("dig-down-cannot");
+        return None;
+    }
+
+    let chara = gd.chara.get(cid);
+    let power = dig_power(chara);
+    if power == 0 {
+        game_log_i!("dig-lack-tool"; chara=chara);
+        return None;
+    }
+    game_log_i!("dig-down"; chara=chara);
+    gd.get_current_map_mut().tile[pos].special = SpecialTileKind::Stairs {
+        dest_floor: mid.floor() + 1,
+        kind: StairsKind::DownStairs,
+    };
+    Some(dig_turns(RULES.params.dig_down_hardness, power, RULES.params.max_dig_turns))
+}
+
+#[test]
+fn dig_turns_test() {
+    assert_eq!(dig_turns(30, 10, 50), 3);
+    assert_eq!(dig_turns(31, 10, 50), 4);
+    assert_eq!(dig_turns(0, 10, 50), 1);
+    assert_eq!(dig_turns(1000, 1, 50), 50);
+}
@@ -0,0 +1,84 @@
+//! Opening, closing and unlocking doors
+
+use array2d::*;
+use common::gamedata::*;
+use rng;
+use rules::RULES;
+use super::chara::CharaEx;
+use super::InfoGetter;
+use super::item::ItemListEx;
+use super::skill::skill_check;
+
+/// Try to open the door at the position. Locked doors are unlocked by the key or lockpicking.
+/// Returns true if the character spends its turn.
+pub fn open_door(gd: &mut GameData, cid: CharaId, pos: Vec2d) -> bool {
+    let (state, key, lock_level) = match gd.get_current_map().tile[pos].special {
+        SpecialTileKind::Door { state, key, lock_level } => (state, key, lock_level),
+        _ => { return false; }
+    };
+    let is_player = cid == CharaId::Player;
+
+    match state {
+        DoorState::Open => { return false; }
+        DoorState::Closed => (),
+        DoorState::Locked => {
+            // Npcs don't try to unlock doors
+            if !is_player {
+                return false;
+            }
+            let chara = gd.chara.get_mut(cid);
+            if key.map_or(false, |key| chara.item_list.find(key).is_some()) {
+                game_log_i!("door-unlock"; chara=chara);
+            } else {
+                let skill_level = chara.skills.get(SkillKind::Lockpicking);
+                let roll = rng::gen_range(1, RULES.params.lockpick_dice + 1);
+                if !skill_check(skill_level, lock_level, roll) {
+                    game_log_i!("lockpick-failure"; chara=chara);
+                    return true;
+                }
+                game_log_i!("lockpick-success"; chara=chara);
+                chara.add_skill_exp(SkillKind::Lockpicking, RULES.exp.lockpick, lock_level);
+            }
+        }
+    }
+
+    if is_player {
+        game_log_i!("door-open"; chara=gd.chara.get(cid));
+    }
+    set_door_state(gd, pos, DoorState::Open);
+    true
+}
+
+/// Close an open door around the character.
+/// Doors that have characters or items on them cannot be closed.
+pub fn close_door(gd: &mut GameData, cid: CharaId) -> bool {
+    let center = if let Some(pos) = gd.chara_pos(cid) { pos } else { return false; };
+    let map = gd.get_current_map();
+    let pos = Direction::EIGHT_DIRS.iter().map(|dir| center + dir.as_vec()).find(|&p| {
+        if !map.is_inside(p) {
+            return false;
+        }
+        let tile = &map.tile[p];
+        match tile.special {
+            SpecialTileKind::Door { state: DoorState::Open, .. } => {
+                tile.chara.is_none() && tile.item_list.as_ref().map_or(true, |il| il.is_empty())
+            }
+            _ => false,
+        }
+    });
+
+    if let Some(pos) = pos {
+        game_log_i!("door-close"; chara=gd.chara.get(cid));
+        set_door_state(gd, pos, DoorState::Closed);
+        true
+    } else {
+        game_log_i!("door-not-found");
+        false
+    }
+}
+
+fn set_door_state(gd: &mut GameData, pos: Vec2d, new_state: DoorState) {
+    if let SpecialTileKind::Door { ref mut state, .. } = gd.get_current_map_mut().tile[pos].special {
+        *state = new_state;
+    }
+}
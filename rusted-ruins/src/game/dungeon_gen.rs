@@ -26,12 +26,16 @@ pub fn extend_site_floor(gd: &mut GameData, sid: SiteId) {
             let map_size = RULES.dungeon_gen[&dungeon_kind].map_size;
             let tile_idx = gobj::id_to_idx(&RULES.dungeon_gen[&dungeon_kind].terrain[0][0]);
             let wall_idx = gobj::id_to_idx(&RULES.dungeon_gen[&dungeon_kind].terrain[0][1]);
-            MapBuilder::new(map_size.0 as u32, map_size.1 as u32)
+            let mut map = MapBuilder::new(map_size.0 as u32, map_size.1 as u32)
                 .floor(floor)
                 .tile(tile_idx)
                 .wall(wall_idx)
+                .map_gen(RULES.dungeon_gen[&dungeon_kind].map_gen)
                 .deepest_floor(is_deepest_floor)
-                .build()
+                .build();
+            map.music = RULES.dungeon_gen[&dungeon_kind].music.clone();
+            map.darkness = RULES.dungeon_gen[&dungeon_kind].darkness;
+            map
         }
         _ => {
             MapBuilder::new(40, 40).floor(floor).build()
@@ -40,8 +44,13 @@ pub fn extend_site_floor(gd: &mut GameData, sid: SiteId) {
 
     let map_random_id = gen_box_id(gd);
     let mid = gd.add_map(map, sid, map_random_id);
-    super::map::gen_npcs(gd, mid, 10, mid.floor());
+    let npc_num = match gd.region.get_site(sid).content {
+        SiteContent::AutoGenDungeon { dungeon_kind } => RULES.dungeon_gen[&dungeon_kind].npc_num,
+        _ => 10,
+    };
+    super::map::gen_npcs(gd, mid, npc_num, mid.floor());
     super::map::gen_items(gd, mid);
+    super::map::gen_special_tiles(gd, mid);
     
     if is_deepest_floor {
         add_for_deepest_floor(gd, mid);
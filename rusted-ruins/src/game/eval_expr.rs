@@ -1,8 +1,19 @@
 
 use common::gamedata::*;
 use common::gobj;
+use common::objholder::ItemIdx;
 use common::script::{Expr, Value, Operator, ExprErrorKind};
 
+/// Unwrap Ok or return the error value
+macro_rules! ur {
+    ($a:expr) => {{
+        match $a {
+            Ok(a) => a,
+            Err(v) => { return v; }
+        }
+    }}
+}
+
 pub trait EvalExpr {
     fn eval(&self, gd: &GameData) -> Value;
 }
@@ -31,6 +42,13 @@ impl EvalExpr for Expr {
             Expr::IsGVarEmpty(var_name) => {
                 Value::Bool(gd.vars.global_var(var_name).is_some())
             }
+            Expr::LVar(var_name) => {
+                if let Some(v) = gd.vars.local_var(var_name) {
+                    v.clone()
+                } else {
+                    Value::RefUnknownVar
+                }
+            }
             Expr::CurrentTime => {
                 Value::Time(gd.time.current_time())
             }
@@ -41,23 +59,62 @@ impl EvalExpr for Expr {
                 }
             }
             Expr::HasItem(item_id) => {
-                if let Some(idx) = gobj::id_to_idx_checked(item_id) {
-                    let item_list = gd.get_item_list(ItemListLocation::Chara { cid: CharaId::Player });
-                    for (item, _) in item_list.iter() {
-                        if item.idx == idx {
-                            return Value::Bool(true);
-                        }
-                    }
-                    Value::Bool(false)
-                } else {
-                    warn!("script error: unknown id {}", item_id);
-                    Value::Error(ExprErrorKind::UnknownIdRef)
+                let idx = ur!(item_idx(item_id));
+                Value::Bool(count_player_item(gd, idx, None) > 0)
+            }
+            Expr::ItemCount(item_id) => {
+                let idx = ur!(item_idx(item_id));
+                Value::Int(count_player_item(gd, idx, None) as i32)
+            }
+            Expr::HasItemQuality(item_id, tier) => {
+                let idx = ur!(item_idx(item_id));
+                match tier.eval(gd) {
+                    Value::Int(tier) => Value::Bool(count_player_item(gd, idx, Some(tier)) > 0),
+                    _ => Value::Error(ExprErrorKind::InvalidType),
                 }
             }
+            Expr::HaveMoney => {
+                // Value::Int is 32bit, so larger money is saturated
+                Value::Int(std::cmp::min(gd.player.money(), i32::max_value() as i64) as i32)
+            }
+            Expr::CurrentHour => {
+                Value::Int(gd.time.current_date().hour.into())
+            }
+            Expr::IsNight => {
+                Value::Bool(gd.time.current_date().is_night())
+            }
+            Expr::IsWeather(weather) => {
+                Value::Bool(super::weather::local_weather(gd) == *weather)
+            }
+            Expr::QuestState(quest_id) => {
+                Value::QuestState(gd.quest.script_quest_state(quest_id))
+            }
+            Expr::QuestStage(quest_id) => {
+                Value::Int(gd.quest.script_quest_stage(quest_id) as i32)
+            }
         }
     }
 }
 
+fn item_idx(item_id: &str) -> Result<ItemIdx, Value> {
+    if let Some(idx) = gobj::id_to_idx_checked(item_id) {
+        Ok(idx)
+    } else {
+        warn!("script error: unknown id {}", item_id);
+        Err(Value::Error(ExprErrorKind::UnknownIdRef))
+    }
+}
+
+/// Count the items in player's inventory.
+/// If min_rank is given, items of lower rank are not counted.
+fn count_player_item(gd: &GameData, idx: ItemIdx, min_rank: Option<i32>) -> u32 {
+    let item_list = gd.get_item_list(ItemListLocation::Chara { cid: CharaId::Player });
+    item_list.iter()
+        .filter(|(item, _)| item.idx == idx && min_rank.map_or(true, |r| item.rank.as_int() >= r))
+        .map(|(_, n)| n)
+        .sum()
+}
+
 fn binary_operation(o: Operator, a: Value, b: Value) -> Value {
     use self::Value::*;
 
@@ -65,6 +122,7 @@ fn binary_operation(o: Operator, a: Value, b: Value) -> Value {
     let (a, b) = match (a, b) {
         (Bool(a), Bool(b)) => (Bool(a), Bool(b)),
         (Int(a), Int(b)) => (Int(a), Int(b)),
+        (QuestState(a), QuestState(b)) => (QuestState(a), QuestState(b)),
         (Bool(a), RefUnknownVar) => (Bool(a), Bool(false)),
         (RefUnknownVar, Bool(b)) => (Bool(false), Bool(b)),
         (Int(a), RefUnknownVar) => (Int(a), Int(0)),
@@ -138,3 +196,86 @@ fn binary_operation_test() {
     assert_eq!(binary_operation(Operator::Div, Int(150), Int(25)), Int(6));
 }
 
+
+#[test]
+fn quest_state_test() {
+    let mut gd = GameData::empty();
+    // Same as "quest_state(main) == complete"
+    let e = Expr::Term(vec![
+        (Operator::None, Expr::QuestState("main".to_owned())),
+        (Operator::Eq, Expr::Value(Value::QuestState(ScriptQuestState::Complete)))]);
+
+    assert_eq!(Expr::QuestState("main".to_owned()).eval(&gd),
+               Value::QuestState(ScriptQuestState::Inactive));
+    // Completing inactive quest is ignored
+    assert!(!gd.quest.complete_script_quest("main"));
+    assert_eq!(e.eval(&gd), Value::Bool(false));
+    assert!(gd.quest.start_script_quest("main", None, None, None));
+    assert_eq!(e.eval(&gd), Value::Bool(false));
+    assert!(gd.quest.complete_script_quest("main"));
+    assert_eq!(e.eval(&gd), Value::Bool(true));
+    assert!(!gd.quest.start_script_quest("main", None, None, None));
+}
+
+#[test]
+fn have_money_test() {
+    let mut gd = GameData::empty();
+    // Same as "have_money() >= 500"
+    let e = Expr::Term(vec![
+        (Operator::None, Expr::HaveMoney),
+        (Operator::GreaterEq, Expr::Value(Value::Int(500)))]);
+
+    gd.player.set_money(499);
+    assert_eq!(Expr::HaveMoney.eval(&gd), Value::Int(499));
+    assert_eq!(e.eval(&gd), Value::Bool(false));
+    gd.player.set_money(500);
+    assert_eq!(e.eval(&gd), Value::Bool(true));
+    gd.player.set_money(1 << 40);
+    assert_eq!(Expr::HaveMoney.eval(&gd), Value::Int(i32::max_value()));
+}
+
+#[test]
+fn item_count_test() {
+    use std::rc::Rc;
+    use common::hashmap::HashMap;
+    use common::script::{Instruction, Script};
+    use super::script::{test_game, ExecResult, ScriptEngine};
+
+    let mut gd = test_game(1, 1).gd;
+    let potion: ItemIdx = gobj::id_to_idx("test-item");
+    let item = Item {
+        idx: potion,
+        kind: ItemKind::Potion,
+        flags: ItemFlags::empty(),
+        rank: ItemRank::default(),
+        attributes: Vec::new(),
+    };
+
+    // jump_if(enough, item_count(test-item) >= 3)
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::JumpIf("enough".to_owned(), Expr::Term(vec![
+            (Operator::None, Expr::ItemCount("test-item".to_owned())),
+            (Operator::GreaterEq, Expr::Value(Value::Int(3)))])),
+        Instruction::Talk("not-enough".to_owned(), vec![]),
+    ]);
+    map.insert("enough".to_owned(), vec![Instruction::Talk("enough".to_owned(), vec![])]);
+    let script = Rc::new(Script::from_map(map));
+    let talk = |gd: &mut GameData| {
+        match ScriptEngine::with_script(script.clone(), Some(CharaId::Player)).exec(gd) {
+            ExecResult::Talk(_, talk_text, _) => talk_text.text_id,
+            result => panic!("unexpected result {:?}", result),
+        }
+    };
+
+    let il = ItemListLocation::Chara { cid: CharaId::Player };
+    gd.get_item_list_mut(il).append(item.clone(), 2);
+    assert_eq!(talk(&mut gd), "not-enough");
+    gd.get_item_list_mut(il).append(item.clone(), 1);
+    assert_eq!(talk(&mut gd), "enough");
+
+    assert_eq!(count_player_item(&gd, potion, Some(1)), 0);
+    let good_item = Item { rank: ItemRank { base: 2, ..ItemRank::default() }, ..item };
+    gd.get_item_list_mut(il).append(good_item, 1);
+    assert_eq!(count_player_item(&gd, potion, Some(1)), 1);
+}
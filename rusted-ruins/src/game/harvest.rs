@@ -0,0 +1,113 @@
+//! Harvesting items from resources on maps
+
+use array2d::*;
+use common::gamedata::*;
+use common::gobj;
+use common::obj::{ResourceParams, ResourceYield};
+use common::objholder::ItemIdx;
+use rng;
+use rules::RULES;
+use super::chara::CharaEx;
+use super::item::ItemListEx;
+use super::item::gen::gen_item_from_idx;
+
+/// The remaining number of harvesting at the time.
+/// An exhausted resource is refilled after its regrowth time.
+pub fn remaining_uses(remaining: u32, regrow_at: Time, now: Time, max_uses: u32) -> u32 {
+    if remaining == 0 && now >= regrow_at {
+        max_uses
+    } else {
+        remaining
+    }
+}
+
+/// Choose one yield by weights
+fn choose_yield(yields: &[ResourceYield]) -> Option<&ResourceYield> {
+    let sum: f32 = yields.iter().map(|y| y.weight).sum();
+    if sum <= 0.0 {
+        return None;
+    }
+    let r = rng::gen_range(0.0, sum);
+    let mut sum = 0.0;
+    for y in yields {
+        sum += y.weight;
+        if r < sum {
+            return Some(y);
+        }
+    }
+    yields.last()
+}
+
+/// The character has the tool in its inventory or equipment
+fn has_tool(chara: &Chara, tool: ItemIdx) -> bool {
+    chara.item_list.find(tool).is_some() ||
+        chara.equip.item_iter().any(|(_, _, item)| item.idx == tool)
+}
+
+/// Harvest the resource at the position.
+/// Returns false if there is no resource or the character cannot harvest it.
+pub fn harvest(gd: &mut GameData, cid: CharaId, pos: Vec2d) -> bool {
+    let now = gd.time.current_time();
+    let (idx, remaining, regrow_at) = match gd.get_current_map().tile[pos].special {
+        SpecialTileKind::Resource { idx, remaining, regrow_at } => (idx, remaining, regrow_at),
+        _ => { return false; }
+    };
+    let params: &ResourceParams = if let Some(params) = gobj::get_obj(idx).resource.as_ref() {
+        params
+    } else {
+        warn!("special tile \"{}\" is not a resource", gobj::idx_to_id(idx));
+        return false;
+    };
+
+    let remaining = remaining_uses(remaining, regrow_at, now, params.max_uses);
+    if remaining == 0 {
+        game_log_i!("harvest-exhausted");
+        return false;
+    }
+
+    let chara = gd.chara.get_mut(cid);
+    if let Some(tool) = params.tool.as_ref() {
+        let tool: ItemIdx = if let Some(tool) = gobj::id_to_idx_checked(tool) {
+            tool
+        } else {
+            warn!("unknown item id \"{}\" for a tool", tool);
+            return false;
+        };
+        if !has_tool(chara, tool) {
+            game_log_i!("harvest-lack-tool"; chara=chara, tool=tool);
+            return false;
+        }
+    }
+    if chara.skills.get(params.skill) < params.skill_level {
+        game_log_i!("harvest-lack-skill"; chara=chara, skill=params.skill);
+        return false;
+    }
+
+    if let Some(y) = choose_yield(&params.yields) {
+        if let Some(item_idx) = gobj::id_to_idx_checked::<ItemIdx>(&y.id) {
+            let item = gen_item_from_idx(item_idx);
+            game_log_i!("harvest"; chara=chara, item=&item, n=y.n);
+            chara.item_list.append(item, y.n);
+        } else {
+            warn!("unknown item id \"{}\" in resource \"{}\"", y.id, gobj::idx_to_id(idx));
+        }
+    }
+    chara.add_skill_exp(params.skill, RULES.exp.harvest, params.skill_level);
+
+    // Regrowth time counts from the last harvesting
+    let remaining = remaining - 1;
+    let mut regrow_at = now;
+    regrow_at.advance(params.regrowth_hours as u64 * SECS_PER_HOUR);
+    gd.get_current_map_mut().tile[pos].special = SpecialTileKind::Resource {
+        idx, remaining, regrow_at,
+    };
+    true
+}
+
+#[test]
+fn remaining_uses_test() {
+    let t = |secs| Time::from_seconds(secs);
+    assert_eq!(remaining_uses(2, t(100), t(0), 5), 2);
+    assert_eq!(remaining_uses(0, t(100), t(50), 5), 0);
+    assert_eq!(remaining_uses(0, t(100), t(100), 5), 5);
+}
@@ -1,9 +1,6 @@
 
 use array2d::*;
 use common::gamedata::*;
-use common::gobj;
-use common::objholder::*;
-use crate::game::extrait::*;
 
 /// Helper functions to get information for event processing and drawing
 pub trait InfoGetter {
@@ -13,14 +10,14 @@ pub trait InfoGetter {
     fn player_pos(&self) -> Vec2d;
     /// Get player's (maxhp, hp)
     fn player_hp(&self) -> (i32, i32);
-    /// Get item location that player has
-    fn player_item_location(&self, id: &str) -> Option<ItemLocation>;
     /// Get current map size
     fn map_size(&self) -> (u32, u32);
     /// Character position on the current map
     fn chara_pos(&self, cid: CharaId) -> Option<Vec2d>;
     /// Player's current tile is entrance/exit or not
     fn on_map_entrance(&self) -> bool;
+    /// Player is on a harvestable resource or not
+    fn on_resource(&self) -> bool;
     /// Return item list in the tile that player stands on
     fn item_on_player_tile(&self) -> Option<&common::gamedata::item::ItemList>;
     /// Return any item exist or not on player tile
@@ -43,15 +40,6 @@ impl InfoGetter for GameData {
         (player.attr.max_hp, player.hp)
     }
 
-    fn player_item_location(&self, id: &str) -> Option<ItemLocation> {
-        let idx: ItemIdx = gobj::id_to_idx_checked(id)?;
-        let ill = ItemListLocation::Chara { cid: CharaId::Player };
-        let il = self.get_item_list(ill);
-
-        let i = il.find(idx)?;
-        Some((ill, i))
-    }
-
     fn map_size(&self) -> (u32, u32) {
         let map = self.get_current_map();
         (map.w, map.h)
@@ -75,6 +63,16 @@ impl InfoGetter for GameData {
         }
     }
 
+    fn on_resource(&self) -> bool {
+        use common::gamedata::map::SpecialTileKind;
+
+        let map = self.get_current_map();
+        match map.tile[self.player_pos()].special {
+            SpecialTileKind::Resource { .. } => true,
+            _ => false,
+        }
+    }
+
     fn item_on_player_tile(&self) -> Option<&common::gamedata::item::ItemList> {
         let player_pos = self.player_pos();
         self.get_current_map().tile[player_pos].item_list.as_ref()
@@ -0,0 +1,103 @@
+//! Containers are items that hold other items, like chests and bags.
+
+use common::gamedata::*;
+use common::gobj;
+use super::ItemEx;
+
+/// Reasons why items cannot be put into a container
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PutInError {
+    /// Containers cannot be put into containers
+    Nested,
+    /// The total weight exceeds the capacity
+    TooHeavy,
+    /// The number of contents exceeds the capacity
+    Full,
+}
+
+/// Total weight of the contents (gram)
+pub fn contents_w(contents: &ItemList) -> u32 {
+    contents.iter().map(|(item, n)| item.w() * n).sum()
+}
+
+/// Check n items can be put into the container
+pub fn check_put_in(container: &Item, item: &Item, n: u32) -> Result<(), PutInError> {
+    let params = gobj::get_obj(container.idx).container
+        .expect("Tried to put items into an item that is not a container");
+    let contents = container.contents().expect("Container item without contents");
+    let contents_n = contents.iter().map(|(_, n)| n).sum();
+    check_capacity(params, contents_w(contents), contents_n, item.w(), item.is_container(), n)
+}
+
+fn check_capacity(params: ContainerParams, contents_w: u32, contents_n: u32,
+                  item_w: u32, is_container: bool, n: u32) -> Result<(), PutInError> {
+    if is_container {
+        Err(PutInError::Nested)
+    } else if contents_w + item_w * n > params.max_w {
+        Err(PutInError::TooHeavy)
+    } else if contents_n + n > params.max_n {
+        Err(PutInError::Full)
+    } else {
+        Ok(())
+    }
+}
+
+/// Put items into the container at the given location.
+/// Returns false if the container doesn't have enough capacity.
+pub fn put_in(gd: &mut GameData, il: ItemLocation, container: (ContainerLocation, u32), n: u32)
+              -> bool {
+    let (parent, i) = container;
+    let container_item = gd.get_item((parent.into(), i)).0;
+    let item = gd.get_item(il).0;
+    if let Err(e) = check_put_in(container_item, item, n) {
+        let text_id = match e {
+            PutInError::Nested => "container-nested",
+            PutInError::TooHeavy => "container-too-heavy",
+            PutInError::Full => "container-full",
+        };
+        game_log_i!(text_id; item=item, container=container_item);
+        return false;
+    }
+    game_log_i!("item-put-in"; item=item, container=container_item);
+    gd.move_item(il, ItemListLocation::Container { parent, i }, n);
+    true
+}
+
+#[test]
+fn check_capacity_test() {
+    let params = ContainerParams { max_w: 1000, max_n: 5 };
+    assert_eq!(check_capacity(params, 0, 0, 200, false, 5), Ok(()));
+    assert_eq!(check_capacity(params, 900, 1, 200, false, 1), Err(PutInError::TooHeavy));
+    assert_eq!(check_capacity(params, 0, 4, 10, false, 2), Err(PutInError::Full));
+    assert_eq!(check_capacity(params, 0, 0, 10, true, 1), Err(PutInError::Nested));
+}
+
+#[test]
+fn put_in_test() {
+    use array2d::Vec2d;
+    let mut gd = super::super::script::test_game(1, 1).gd;
+    let cid = CharaId::Player;
+    let ill = ItemListLocation::Chara { cid };
+    let chest = super::gen::gen_item_from_idx(gobj::id_to_idx("test-chest"));
+    let item = super::gen::gen_item_from_idx(gobj::id_to_idx("test-item"));
+    gd.get_item_list_mut(ill).append(chest, 1);
+    gd.get_item_list_mut(ill).append(item, 5);
+    let chest = (ContainerLocation::Chara { cid }, 0);
+
+    // Containers are at the front of the list
+    assert!(put_in(&mut gd, (ill, 1), chest, 2));
+    assert!(!put_in(&mut gd, (ill, 1), chest, 2));
+    assert!(put_in(&mut gd, (ill, 1), chest, 1));
+    assert_eq!(gd.get_item_list(ill).get_number(1), 2);
+    let contents = gd.get_item_list(ItemListLocation::Container { parent: chest.0, i: 0 });
+    assert_eq!(contents.get_number(0), 3);
+
+    // Contents are saved with the container
+    let mid = gd.get_current_mapid();
+    gd.move_item((ill, 0), ItemListLocation::OnMap { mid, pos: Vec2d(0, 0) }, 1);
+    let map = &gd.get_current_map().tile[Vec2d(0, 0)];
+    let data = serde_cbor::to_vec(&map.item_list).unwrap();
+    let item_list: Option<ItemList> = serde_cbor::from_slice(&data).unwrap();
+    let (chest, _) = item_list.as_ref().unwrap().iter().next().unwrap();
+    assert_eq!(chest.contents().unwrap().get_number(0), 3);
+}
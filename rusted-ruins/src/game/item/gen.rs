@@ -1,7 +1,9 @@
 
+use std::collections::HashMap;
 use common::gamedata::*;
 use common::gobj;
 use common::objholder::ItemIdx;
+use rules::RULES;
 
 /// Generate new item on dungeon floor
 pub fn gen_dungeon_item(floor_level: u32) -> Item {
@@ -12,20 +14,60 @@ pub fn gen_dungeon_item(floor_level: u32) -> Item {
 /// f is weight adjustment function.
 pub fn gen_item_by_level<F: FnMut(&ItemObject) -> f64>(level: u32, f: F, is_shop: bool) -> Item {
     let idx = choose_item_by_floor_level(level, f, is_shop);
+    gen_item_from_idx(idx)
+}
 
+/// Generate an item of the given object
+pub fn gen_item_from_idx(idx: ItemIdx) -> Item {
     let item_obj = gobj::get_obj(idx);
+    let mut attributes = vec![];
+    if item_obj.container.is_some() {
+        attributes.push(ItemAttribute::Container(ItemList::new()));
+    }
+    if let Some((min, max)) = item_obj.charge {
+        attributes.push(ItemAttribute::Charge { n: rng::gen_range(min, max + 1) });
+    }
+    if item_obj.has_quality {
+        let quality = choose_quality(&RULES.item.quality_gen_weight);
+        if quality != ItemQuality::Normal {
+            attributes.push(ItemAttribute::Quality(quality));
+        }
+    }
+    if !item_obj.materials.is_empty() {
+        let i = rng::gen_range(0, item_obj.materials.len());
+        attributes.push(ItemAttribute::Material(item_obj.materials[i]));
+    }
     Item {
         idx,
         flags: item_obj.default_flags,
         kind: item_obj.kind,
         rank: ItemRank::default(),
-        attributes: vec![],
+        attributes,
+    }
+}
+
+/// Choose quality by generation weights
+fn choose_quality(weights: &HashMap<ItemQuality, f32>) -> ItemQuality {
+    const QUALITIES: [ItemQuality; 4] = [
+        ItemQuality::Poor, ItemQuality::Normal, ItemQuality::Good, ItemQuality::Excellent];
+    let sum: f32 = QUALITIES.iter().map(|q| weights.get(q).cloned().unwrap_or(0.0)).sum();
+    if sum <= 0.0 {
+        return ItemQuality::Normal;
+    }
+    let r = rng::gen_range(0.0, sum);
+    let mut sum = 0.0;
+    for q in &QUALITIES {
+        sum += weights.get(q).cloned().unwrap_or(0.0);
+        if r < sum {
+            return *q;
+        }
     }
+    ItemQuality::Normal
 }
 
 /// Choose item by floor level.
 /// f is weight adjustment function.
-fn choose_item_by_floor_level<F: FnMut(&ItemObject) -> f64>(
+pub fn choose_item_by_floor_level<F: FnMut(&ItemObject) -> f64>(
     floor_level: u32, mut f: F, is_shop: bool) -> ItemIdx {
     
     let items = &gobj::get_objholder().item;
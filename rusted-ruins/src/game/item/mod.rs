@@ -1,10 +1,13 @@
 
+pub mod container;
 pub mod filter;
 pub mod gen;
 
 use common::gamedata::*;
 use common::gobj;
 use common::objholder::ItemIdx;
+use rules::RULES;
+use rules::item::ItemModifier;
 
 /// Additional Item methods
 pub trait ItemEx {
@@ -13,13 +16,16 @@ pub trait ItemEx {
     /// Calculate item selling price
     fn selling_price(&self) -> i64;
     fn w(&self) -> u32;
+    /// Effectiveness modified by quality and material in percent
+    fn eff_percent(&self) -> u32;
 }
 
 impl ItemEx for Item {
     fn price(&self) -> i64 {
         let item_obj = gobj::get_obj(self.idx);
+        let price_percent = modifier_percent(self, |m| m.price_percent);
 
-        item_obj.basic_price as i64
+        item_obj.basic_price as i64 * price_percent as i64 / 100
     }
 
     fn selling_price(&self) -> i64 {
@@ -29,13 +35,41 @@ impl ItemEx for Item {
     fn w(&self) -> u32 {
         let item_obj = gobj::get_obj(self.idx);
 
-        item_obj.w
+        item_obj.w + self.contents().map_or(0, container::contents_w)
     }
+
+    fn eff_percent(&self) -> u32 {
+        modifier_percent(self, |m| m.eff_percent)
+    }
+}
+
+/// Multiply percentages of the quality and material modifiers
+fn modifier_percent<F: Fn(&ItemModifier) -> u32>(item: &Item, f: F) -> u32 {
+    let r = &RULES.item;
+    let quality = r.quality.get(&item.quality()).map_or(100, &f);
+    let material = item.material().and_then(|m| r.material.get(&m)).map_or(100, &f);
+    quality * material / 100
+}
+
+/// Apply percentage to an effect value
+pub fn apply_percent<T: Into<i64>>(value: T, percent: u32) -> i32 {
+    (value.into() * percent as i64 / 100) as i32
+}
+
+/// Convert an effect value to u16. Values out of the range are clamped instead of truncated.
+pub fn clamp_u16(value: i32) -> u16 {
+    use std::convert::TryFrom;
+    u16::try_from(value).unwrap_or(if value < 0 { 0 } else { u16::max_value() })
 }
 
 pub trait ItemListEx {
     /// Return the first item found
     fn find(&self, idx: ItemIdx) -> Option<u32>;
+    /// Count items of given idx
+    fn count(&self, idx: ItemIdx) -> u32;
+    /// Remove n items of given idx.
+    /// If this list doesn't have enough items, nothing is removed and returns false.
+    fn remove_by_idx(&mut self, idx: ItemIdx, n: u32) -> bool;
 }
 
 impl ItemListEx for ItemList {
@@ -47,6 +81,24 @@ impl ItemListEx for ItemList {
         }
         None
     }
+
+    fn count(&self, idx: ItemIdx) -> u32 {
+        self.iter().filter(|(item, _)| item.idx == idx).map(|(_, n)| n).sum()
+    }
+
+    fn remove_by_idx(&mut self, idx: ItemIdx, n: u32) -> bool {
+        if self.count(idx) < n {
+            return false;
+        }
+        let mut n = n;
+        while n > 0 {
+            let i = self.find(idx).unwrap();
+            let removed = std::cmp::min(self.get_number(i), n);
+            self.remove(i, removed);
+            n -= removed;
+        }
+        true
+    }
 }
 
 
@@ -62,3 +114,50 @@ pub fn change_equipment(
     }
 }
 
+/// Remove specified character's equipment, and put it into the character's inventory
+pub fn remove_equipment(gd: &mut GameData, cid: CharaId, slot: (EquipSlotKind, u8)) {
+    if let Some(item) = gd.get_equip_list_mut(cid).remove(slot.0, slot.1 as usize) {
+        game_log_i!("item-unequip"; chara=gd.chara.get(cid), item=item);
+        gd.get_item_list_mut(ItemListLocation::Chara { cid }).append(item, 1);
+    }
+}
+
+
+#[test]
+fn apply_percent_test() {
+    assert_eq!(apply_percent(10, 100), 10);
+    assert_eq!(apply_percent(10, 150), 15);
+    assert_eq!(apply_percent(7u32, 50), 3);
+}
+
+#[test]
+fn clamp_u16_test() {
+    assert_eq!(clamp_u16(300), 300);
+    assert_eq!(clamp_u16(-1), 0);
+    assert_eq!(clamp_u16(70000), u16::max_value());
+}
+
+#[test]
+fn item_attributes_test() {
+    super::script::init_test_objs();
+    let wand = gen::gen_item_from_idx(gobj::id_to_idx("test-wand"));
+    assert_eq!(wand.charge(), Some(3));
+    assert_eq!(wand.material(), Some(MaterialKind::Steel));
+    assert_eq!(wand.quality(), ItemQuality::Normal);
+    // Modified by rules/item.json
+    assert_eq!(wand.price(), 150);
+    assert_eq!(wand.eff_percent(), 115);
+
+    let mut good_wand = wand.clone();
+    good_wand.attributes.push(ItemAttribute::Quality(ItemQuality::Good));
+    assert_eq!(good_wand.price(), 100 * (150 * 150 / 100) / 100);
+    assert_eq!(good_wand.eff_percent(), 115 * 115 / 100);
+
+    // Items of different attributes are not stacked
+    let mut item_list = ItemList::new();
+    item_list.append(wand.clone(), 1);
+    item_list.append(wand, 1);
+    item_list.append(good_wand, 1);
+    assert_eq!(item_list.items.len(), 2);
+    assert_eq!(item_list.get_number(0) + item_list.get_number(1), 3);
+}
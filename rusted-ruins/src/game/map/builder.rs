@@ -3,6 +3,7 @@ use array2d::*;
 use common::objholder::*;
 use common::gamedata::map::*;
 use common::gobj;
+use rules::dungeon_gen::MapGenKind;
 use crate::map_generator::{MapGenerator, GeneratedMap, TileKind};
 
 #[derive(Default)]
@@ -12,6 +13,7 @@ pub struct MapBuilder {
     is_deepest_floor: bool,
     tile: TileIdx,
     wall: WallIdx,
+    map_gen: MapGenKind,
 }
 
 impl MapBuilder {
@@ -23,7 +25,16 @@ impl MapBuilder {
     }
     
     pub fn build(self) -> Map {
-        let generated_map = MapGenerator::new((self.w, self.h)).fractal().generate();
+        let mg = MapGenerator::new((self.w, self.h));
+        let mg = match self.map_gen {
+            MapGenKind::Flat => mg.flat(),
+            MapGenKind::Lattice { nx, ny, step_min, step_max, door_weight } => {
+                mg.lattice(nx, ny, step_min, step_max, door_weight)
+            }
+            MapGenKind::Fractal => mg.fractal(),
+            MapGenKind::Cave { wall_ratio, n_iter } => mg.cave(wall_ratio, n_iter),
+        };
+        let generated_map = mg.generate();
         generated_map_to_map(generated_map, self.tile, self.wall, self.floor, self.is_deepest_floor)
     }
 
@@ -42,6 +53,11 @@ impl MapBuilder {
         self
     }
 
+    pub fn map_gen(mut self, map_gen: MapGenKind) -> MapBuilder {
+        self.map_gen = map_gen;
+        self
+    }
+
     pub fn deepest_floor(mut self, is_deepest_floor: bool) -> MapBuilder {
         self.is_deepest_floor = is_deepest_floor;
         self
@@ -77,6 +93,11 @@ pub fn generated_map_to_map(gm: GeneratedMap, tile: TileIdx, wall: WallIdx,
                 };
                 map.tile[p].wall = WallIdxPP::with_piece_pattern(wall, piece_pattern);
             },
+            TileKind::Door => {
+                map.tile[p].special = SpecialTileKind::Door {
+                    state: DoorState::Closed, key: None, lock_level: 0,
+                };
+            }
             _ => (),
         }
     }
@@ -93,7 +114,7 @@ pub fn generated_map_to_map(gm: GeneratedMap, tile: TileIdx, wall: WallIdx,
         let dest_floor = floor + 1;
         map.tile[gm.exit.unwrap()].special = SpecialTileKind::Stairs { dest_floor, kind: exit_stairs };;
     }
-    
+    map.tile.compact();
     map
 }
 
@@ -3,18 +3,21 @@ use common::maptemplate::*;
 use common::gamedata::*;
 use common::gobj;
 use crate::game::item::gen::from_item_gen;
+use crate::game::chara::gen::create_chara;
 
 pub fn from_template(t: &MapTemplateObject) -> Map {
     let mut map = create_terrain(t);
     set_boundary(&mut map, t, 0);
     gen_items(&mut map, t);
+    set_doors(&mut map, t);
+    set_traps(&mut map, t);
+    map.music = t.music.clone();
+    map.darkness = t.darkness;
+    map.events = t.events.clone();
+    map.tile.compact();
     map
 }
 
-pub fn from_template_id(id: &str) -> Option<Map> {
-    Some(from_template(gobj::get_by_id_checked(id)?))
-}
-
 /// Create map its terrains (tile, wall) are loaded from template
 fn create_terrain(t: &MapTemplateObject) -> Map {
     let mut map = Map::new(t.w, t.h);
@@ -75,3 +78,60 @@ fn gen_items(map: &mut Map, t: &MapTemplateObject) {
     }
 }
 
+/// Setting doors
+fn set_doors(map: &mut Map, t: &MapTemplateObject) {
+    // Doors still work without their images, but they are not drawn
+    let closed_door = SpecialTileKind::Door { state: DoorState::Closed, key: None, lock_level: 0 };
+    if !t.doors.is_empty() && closed_door.obj_idx().is_none() {
+        warn!("door objects are not found in paks, so doors are not drawn");
+    }
+    for (pos, door_gen) in &t.doors {
+        let key = door_gen.key.as_ref().and_then(|key| {
+            let idx = gobj::id_to_idx_checked(key);
+            if idx.is_none() {
+                warn!("unknown item id \"{}\" for a door key", key);
+            }
+            idx
+        });
+        let state = if door_gen.locked { DoorState::Locked } else { DoorState::Closed };
+        map.tile[*pos].special = SpecialTileKind::Door { state, key, lock_level: door_gen.lock_level };
+    }
+}
+
+/// Generate characters. This needs to be called after the map is added to the game data.
+/// Maps are created from templates only for towns and regions, and both call this.
+/// Other sites like auto generated dungeons don't use templates.
+pub fn gen_charas(gd: &mut GameData, mid: MapId, t: &MapTemplateObject) {
+    use common::objholder::CharaTemplateIdx;
+
+    for (pos, chara_gen) in &t.charas {
+        let idx: CharaTemplateIdx = if let Some(idx) = gobj::id_to_idx_checked(&chara_gen.id) {
+            idx
+        } else {
+            warn!("unknown chara template id \"{}\"", chara_gen.id);
+            continue;
+        };
+        let level = if chara_gen.level == 0 { gobj::get_obj(idx).gen_level } else { chara_gen.level };
+        let mut chara = create_chara(idx, level);
+        chara.rel = if chara_gen.hostile { Relationship::HOSTILE } else { Relationship::FRIENDLY };
+        chara.trigger_talk = chara_gen.talk_script_id.clone();
+
+        let cid = gd.add_chara_to_map(chara, mid);
+        gd.region.get_map_mut(mid).locate_chara(cid, *pos);
+    }
+}
+
+/// Setting traps
+fn set_traps(map: &mut Map, t: &MapTemplateObject) {
+    use common::objholder::SpecialTileIdx;
+
+    for (pos, id) in &t.traps {
+        let idx: Option<SpecialTileIdx> = gobj::id_to_idx_checked(id);
+        match idx {
+            Some(idx) if gobj::get_obj::<SpecialTileIdx>(idx).trap.is_some() => {
+                map.tile[*pos].special = SpecialTileKind::Trap { idx, detected: false };
+            }
+            _ => warn!("special tile \"{}\" is not a trap", id),
+        }
+    }
+}
@@ -7,31 +7,49 @@ use array2d::*;
 use common::basic::MAX_ITEM_FOR_DRAW;
 use common::gamedata::*;
 use common::gobj;
-use common::obj::TileKind;
+use common::obj::{TileKind, Movement};
+use common::objholder::CharaTemplateIdx;
+use common::script::Value;
 use crate::text::ToText;
 use super::Game;
-use super::chara::gen::create_npc_chara;
+use super::chara::gen::{create_npc_chara, choose_spawn_group, create_spawn_group};
 use super::item::gen::gen_dungeon_item;
 use rules::RULES;
 
 pub trait MapEx {
     /// The tile is passable for given character or not.
     fn is_passable(&self, chara: &Chara, pos: Vec2d) -> bool;
+    /// The tile is walkable or not. Characters on the tile are not considered.
+    fn is_walkable(&self, pos: Vec2d) -> bool;
+    /// The tile can be entered by the movements or not. Characters on the tile are not considered.
+    fn is_passable_by(&self, movements: &[Movement], pos: Vec2d) -> bool;
     fn move_chara(&mut self, cid: CharaId, dir: Direction) -> bool;
+    /// Remove the wall at the position, and fix piece patterns of the walls around it.
+    fn remove_wall(&mut self, pos: Vec2d);
 }
 
 impl MapEx for Map {
-    fn is_passable(&self, _chara: &Chara, pos: Vec2d) -> bool {
+    fn is_passable(&self, chara: &Chara, pos: Vec2d) -> bool {
+        self.is_passable_by(super::mount::movements(chara), pos)
+    }
+
+    fn is_walkable(&self, pos: Vec2d) -> bool {
+        self.is_passable_by(&[Movement::Walk], pos)
+    }
+
+    fn is_passable_by(&self, movements: &[Movement], pos: Vec2d) -> bool {
         if !self.is_inside(pos) {
             return false;
         }
         
+        // Closed doors are walkable because characters open them by moving
+        if let SpecialTileKind::Door { state: DoorState::Locked, .. } = self.tile[pos].special {
+            return false;
+        }
+        
         if self.tile[pos].wall.is_empty() {
             let tile = gobj::get_obj(self.tile[pos].main_tile());
-            match tile.kind {
-                TileKind::Ground => true,
-                TileKind::Water => false,
-            }
+            tile.kind.passable_by(movements)
         } else {
             false
         }
@@ -45,6 +63,25 @@ impl MapEx for Map {
             false
         }
     }
+
+    fn remove_wall(&mut self, pos: Vec2d) {
+        self.tile[pos].wall = WallIdxPP::default();
+        for dir in &Direction::EIGHT_DIRS {
+            let p = pos + dir.as_vec();
+            if !self.is_inside(p) {
+                continue;
+            }
+            let idx = if let Some(idx) = self.tile[p].wall.idx() { idx } else { continue; };
+            // Outside of the map is treated as walls like map generation
+            let mut piece_pattern_flags = PiecePatternFlags::new();
+            for d in &Direction::EIGHT_DIRS {
+                let q = p + d.as_vec();
+                piece_pattern_flags.set(*d, !self.is_inside(q) || !self.tile[q].wall.is_empty());
+            }
+            let n_pattern = gobj::get_obj(idx).img.n_pattern;
+            self.tile[p].wall.set_piece_pattern(piece_pattern_flags.to_piece_pattern(n_pattern));
+        }
+    }
 }
 
 /// Switch current map to the specified map
@@ -61,6 +98,10 @@ pub fn switch_map(game: &mut Game, mid: MapId) {
         }
         let prev_mid = gd.get_current_mapid();
         gd.region.preload_map(mid, save_dir.join("maps"));
+        let followers = super::party::leave_map(gd);
+        if !mid.is_region_map() {
+            super::mount::put_away(gd, CharaId::Player);
+        }
         gd.set_current_mapid(mid);
 
         let new_player_pos = if mid.is_region_map() && !prev_mid.is_region_map()
@@ -73,17 +114,57 @@ pub fn switch_map(game: &mut Game, mid: MapId) {
             };
     
         gd.get_current_map_mut().locate_chara(CharaId::Player, new_player_pos);
+        super::party::arrive_map(gd, &followers);
+
+        if !mid.is_region_map() && (prev_mid.is_region_map() || prev_mid.sid() != mid.sid()) {
+            super::quest::process_quest_event(gd, super::quest::QuestEvent::EnterSite(mid.sid()));
+            super::town::restock_shops(gd, mid.sid());
+        }
+    }
+    if crate::config::CONFIG.autosave_on_map_change {
+        game.autosave_requested = true;
     }
     crate::audio::play_sound("floor-change");
+    play_map_music(&game.gd);
     super::view::update_view_map(game);
 }
 
+/// Play the music of the current map if it is specified
+pub fn play_map_music(gd: &GameData) {
+    let music = &gd.get_current_map().music;
+    if music.is_empty() {
+        crate::audio::stop_music();
+    } else {
+        crate::audio::play_music(music);
+    }
+}
+
 pub fn gen_npcs(gd: &mut GameData, mid: MapId, n: u32, floor_level: u32) {
     let dungeon_kind = match gd.region.get_site(mid.sid()).content {
         SiteContent::AutoGenDungeon { dungeon_kind } => dungeon_kind,
         _ => DungeonKind::Ruin,
     };
 
+    if let Some(table) = spawn_table(dungeon_kind) {
+        let mut n_generated = 0;
+        while n_generated < n {
+            let p = if let Some(p) = choose_empty_tile(gd.region.get_map(mid)) { p } else {
+                warn!("Failed npc generating because empty tile not found");
+                return;
+            };
+            let charas = choose_spawn_group(table, floor_level)
+                .map(|group| create_spawn_group(group, floor_level))
+                .unwrap_or_default();
+            if charas.is_empty() {
+                warn!("No npc group of spawn table \"{}\" for floor {}", table.id, floor_level);
+                return;
+            }
+            n_generated += charas.len() as u32;
+            locate_npc_group(gd, mid, charas, p, |_| true);
+        }
+        return;
+    }
+
     for _ in 0..n {
         if let Some(p) = choose_empty_tile(gd.region.get_map(mid)) {
             let chara = create_npc_chara(dungeon_kind, floor_level);
@@ -98,6 +179,93 @@ pub fn gen_npcs(gd: &mut GameData, mid: MapId, n: u32, floor_level: u32) {
     }
 }
 
+/// Generate a npc on the current dungeon floor by the respawn probability.
+/// Npcs don't appear in the player's sight.
+pub fn respawn_npcs(game: &mut Game) {
+    use rng::Rng;
+    let mid = game.gd.get_current_mapid();
+    if mid.is_region_map() {
+        return;
+    }
+    let dungeon_kind = match game.gd.region.get_site(mid.sid()).content {
+        SiteContent::AutoGenDungeon { dungeon_kind } => dungeon_kind,
+        _ => return,
+    };
+    let rule = &RULES.dungeon_gen[&dungeon_kind];
+
+    let map = game.gd.get_current_map();
+    let n_npc = map.iter_charaid().filter(|&&cid| cid != CharaId::Player).count() as u32;
+    if n_npc >= rule.max_npc || !rng::get_rng().gen_bool(rule.npc_respawn_probability) {
+        return;
+    }
+    let p = if let Some(p) = choose_empty_tile(map) { p } else { return; };
+    if game.view_map.get_tile_visible(p) {
+        return;
+    }
+
+    let charas = if let Some(table) = spawn_table(dungeon_kind) {
+        choose_spawn_group(table, mid.floor())
+            .map(|group| create_spawn_group(group, mid.floor()))
+            .unwrap_or_default()
+    } else {
+        vec![create_npc_chara(dungeon_kind, mid.floor())]
+    };
+    let view_map = &game.view_map;
+    locate_npc_group(&mut game.gd, mid, charas, p, |p| !view_map.get_tile_visible(p));
+}
+
+/// The spawn table given for the dungeon kind by the rules
+fn spawn_table(dungeon_kind: DungeonKind) -> Option<&'static SpawnTableObject> {
+    let id = RULES.dungeon_gen.get(&dungeon_kind)?.spawn_table.as_ref()?;
+    let table = gobj::get_by_id_checked(id);
+    if table.is_none() {
+        warn!("unknown spawn table \"{}\"", id);
+    }
+    table
+}
+
+/// Locate npcs on the nearest empty tiles around the center that satisfy `f`.
+/// Npcs are discarded if there is no such tile for them.
+fn locate_npc_group<F: Fn(Vec2d) -> bool>(
+    gd: &mut GameData, mid: MapId, charas: Vec<Chara>, center: Vec2d, f: F) {
+    const MAX_DISTANCE: i32 = 2;
+
+    for chara in charas {
+        let map = gd.region.get_map(mid);
+        let p = MDistRangeIter::new(center, MAX_DISTANCE)
+            .filter(|&(_, p)| map.is_inside(p) && map.is_walkable(p) && map.get_chara(p).is_none() && f(p))
+            .min_by_key(|&(d, _)| d);
+        let p = if let Some((_, p)) = p { p } else { return; };
+        trace!("Generate new npc {}", chara.to_text());
+        let cid = gd.add_chara_to_map(chara, mid);
+        gd.region.get_map_mut(mid).locate_chara(cid, p);
+    }
+}
+
+/// Start the script of the event on the current map whose condition has become true
+pub fn check_condition_events(game: &mut Game) {
+    if game.script.is_some() {
+        return;
+    }
+    let gd = &game.gd;
+    let map = gd.get_current_map();
+    let is_met = |trigger: &MapEventTrigger| match trigger {
+        MapEventTrigger::GVar(name) => gd.vars.global_var(name) == Some(&Value::Bool(true)),
+        MapEventTrigger::Defeated(id) => {
+            if let Some(idx) = gobj::id_to_idx_checked::<CharaTemplateIdx>(id) {
+                !map.iter_charaid().any(|&cid| gd.chara.get(cid).template == idx)
+            } else {
+                false
+            }
+        }
+        MapEventTrigger::Enter | MapEventTrigger::Examine => false,
+    };
+    let i = if let Some(i) = map.events.iter().position(|e| is_met(&e.trigger)) { i } else { return; };
+
+    let event = game.gd.get_current_map_mut().events.remove(i);
+    game.start_event_script(&event.script_id);
+}
+
 /// Choose one empty tile in random
 pub fn choose_empty_tile(map: &Map) -> Option<Vec2d> {
     use rng::gen_range;
@@ -141,6 +309,21 @@ pub fn choose_empty_tile(map: &Map) -> Option<Vec2d> {
     }
 }
 
+/// The nearest walkable tile without characters around the center
+pub fn empty_tile_near(map: &Map, center: Vec2d) -> Option<Vec2d> {
+    const MAX_DISTANCE: i32 = 5;
+
+    for r in 1..=MAX_DISTANCE {
+        let found = RectIter::new(center - Vec2d(r, r), center + Vec2d(r, r))
+            .filter(|&p| (p.0 - center.0).abs() == r || (p.1 - center.1).abs() == r)
+            .find(|&p| map.is_walkable(p) && map.get_chara(p).is_none());
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
 /// Locate some items for a new map
 pub fn gen_items(gd: &mut GameData, mid: MapId) {
     use rng::*;
@@ -172,27 +355,160 @@ pub fn gen_items(gd: &mut GameData, mid: MapId) {
     
 }
 
+/// Locate harvestable resources and traps for a new map
+pub fn gen_special_tiles(gd: &mut GameData, mid: MapId) {
+    use rng::*;
+    use common::obj::SpecialTileObject;
+    use common::objholder::SpecialTileIdx;
+
+    let rule = match gd.region.get_site(mid.sid()).content {
+        SiteContent::AutoGenDungeon { dungeon_kind } => &RULES.dungeon_gen[&dungeon_kind],
+        _ => { return; } // No special tile generation
+    };
+    let obj = |id: &str| -> Option<(SpecialTileIdx, &'static SpecialTileObject)> {
+        let idx: SpecialTileIdx = gobj::id_to_idx_checked(id)?;
+        Some((idx, gobj::get_obj(idx)))
+    };
+
+    let mut kinds: Vec<(SpecialTileKind, f64)> = Vec::new();
+    for (id, &p) in &rule.resource_gen_probability {
+        match obj(id) {
+            Some((idx, o)) if o.resource.is_some() => {
+                let remaining = o.resource.as_ref().unwrap().max_uses;
+                let kind = SpecialTileKind::Resource { idx, remaining, regrow_at: Time::default() };
+                kinds.push((kind, p));
+            }
+            _ => warn!("special tile \"{}\" is not a resource", id),
+        }
+    }
+    for (id, &p) in &rule.trap_gen_probability {
+        match obj(id) {
+            Some((idx, o)) if o.trap.is_some() => {
+                kinds.push((SpecialTileKind::Trap { idx, detected: false }, p));
+            }
+            _ => warn!("special tile \"{}\" is not a trap", id),
+        }
+    }
+    if kinds.is_empty() {
+        return;
+    }
+    let map = gd.region.get_map_mut(mid);
+
+    for p in map.tile.iter_idx() {
+        let tile = &mut map.tile[p];
+        if !tile.wall.is_empty() || !tile.special.is_none() { continue; }
+
+        for &(kind, probability) in &kinds {
+            if get_rng().gen_bool(probability.max(0.0).min(1.0)) {
+                tile.special = kind;
+                break;
+            }
+        }
+    }
+}
+
 pub fn update_observed_map(game: &mut Game) {
     let view_map = &game.view_map;
     let map = game.gd.get_current_map_mut();
 
     for p in map.tile.iter_idx() {
-        if !view_map.get_tile_visible(p) { continue; }
+        if view_map.get_tile_visible(p) {
+            observe_tile(map, p);
+        }
+    }
+}
 
-        let tile = &map.tile[p];
-        let observed_tile = &mut map.observed_tile[p];
+/// Mark all tiles observed. Used to export the whole map for debugging.
+pub fn observe_all_tiles(map: &mut Map) {
+    for p in map.tile.iter_idx() {
+        observe_tile(map, p);
+    }
+}
 
-        observed_tile.tile = true;
-        observed_tile.wall = tile.wall;
-        observed_tile.deco = tile.deco;
-        observed_tile.special = tile.special;
-        observed_tile.items.clear();
+/// Only changed tiles are written not to mark unchanged chunks dirty
+fn observe_tile(map: &mut Map, p: Vec2d) {
+    let tile = &map.tile[p];
+    let mut observed_tile = ObservedTileInfo {
+        tile: true,
+        wall: tile.wall,
+        deco: tile.deco,
+        special: if tile.special.is_hidden_trap() {
+            SpecialTileKind::None
+        } else {
+            tile.special
+        },
+        ..ObservedTileInfo::default()
+    };
 
-        if let Some(ref item_list) = tile.item_list {
-            for &(ref item, _) in item_list.iter().take(MAX_ITEM_FOR_DRAW) {
-                observed_tile.items.push(item.idx);
-            }
+    if let Some(ref item_list) = tile.item_list {
+        for &(ref item, _) in item_list.iter().take(MAX_ITEM_FOR_DRAW) {
+            observed_tile.items.push(item.idx);
         }
     }
+
+    if map.observed_tile[p] != observed_tile {
+        map.observed_tile[p] = observed_tile;
+    }
 }
 
+#[test]
+fn condition_events_test() {
+    use super::script::{requested_talk, test_game};
+    let mut game = test_game(3, 1);
+    let event = |trigger| MapEvent {
+        top_left: Vec2d(0, 0),
+        bottom_right: Vec2d(0, 0),
+        script_id: "test-event-talk".to_owned(),
+        once: false,
+        trigger,
+    };
+    let talk = Some((CharaId::Player, "test-event-text".to_owned()));
+    let mid = game.gd.get_current_mapid();
+    let boss = Chara {
+        name: Some("boss".to_owned()),
+        template: gobj::id_to_idx("test-swimmer"),
+        ..Chara::default()
+    };
+    let boss = game.gd.add_chara_to_map(boss, mid);
+    game.gd.get_current_map_mut().locate_chara(boss, Vec2d(2, 0));
+    game.gd.get_current_map_mut().events.push(event(MapEventTrigger::GVar("opened".to_owned())));
+    game.gd.get_current_map_mut().events.push(event(MapEventTrigger::Defeated("test-swimmer".to_owned())));
+
+    check_condition_events(&mut game);
+    assert!(game.script.is_none());
+
+    game.gd.vars.set_global_var("opened", Value::Bool(true));
+    check_condition_events(&mut game);
+    assert_eq!(requested_talk(&mut game), talk);
+    // Condition events fire only once even if the condition is still true
+    game.script = None;
+    check_condition_events(&mut game);
+    assert!(game.script.is_none());
+    assert_eq!(game.gd.get_current_map().events.len(), 1);
+
+    game.gd.remove_chara(boss);
+    check_condition_events(&mut game);
+    assert_eq!(requested_talk(&mut game), talk);
+    assert!(game.gd.get_current_map().events.is_empty());
+}
+
+#[test]
+fn locate_npc_group_test() {
+    use super::chara::gen::create_monster_chara;
+    let mut game = super::script::test_game(3, 3);
+    let mid = game.gd.get_current_mapid();
+    let idx: CharaTemplateIdx = gobj::id_to_idx("test-chara");
+    let group = || (0..3).map(|_| create_monster_chara(idx)).collect();
+
+    // Npcs are located around the center, but not on the rejected tiles
+    locate_npc_group(&mut game.gd, mid, group(), Vec2d(1, 1), |p| p.1 != 0);
+    let map = game.gd.get_current_map();
+    assert_eq!(map.iter_charaid().count(), 4);
+    assert!(map.get_chara(Vec2d(1, 1)).is_some());
+    assert!(map.get_chara(Vec2d(1, 0)).is_none());
+    assert!(map.get_chara(Vec2d(2, 0)).is_none());
+
+    // Npcs are discarded if there is no tile for them
+    locate_npc_group(&mut game.gd, mid, group(), Vec2d(1, 1), |p| p.1 == 0);
+    assert_eq!(game.gd.get_current_map().iter_charaid().count(), 6);
+}
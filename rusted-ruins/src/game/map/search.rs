@@ -2,6 +2,7 @@
 
 use common::gamedata::*;
 use crate::game::view::calc_visual_distance;
+use crate::game::chara::relation_between;
 
 /// Search the nearest chara's position that has given Relationship on the current map.
 pub fn search_nearest_target(gd: &GameData, center_cid: CharaId, rel: Relationship) -> Option<CharaId> {
@@ -14,6 +15,7 @@ pub fn search_nearest_target(gd: &GameData, center_cid: CharaId, rel: Relationsh
         return None;
     };
     
+    let view_range = crate::game::weather::view_range(gd, chara.attr.view_range);
     let mut target_cid = None;
     let mut min_distance = i32::max_value();
     
@@ -21,7 +23,7 @@ pub fn search_nearest_target(gd: &GameData, center_cid: CharaId, rel: Relationsh
         if center_cid == *cid {
             continue;
         }
-        if gd.chara.get(*cid).rel.relative(chara.rel) != rel {
+        if relation_between(gd, center_cid, *cid) != rel {
             continue;
         }
         
@@ -33,7 +35,7 @@ pub fn search_nearest_target(gd: &GameData, center_cid: CharaId, rel: Relationsh
             continue;
         };
 
-        if visual_distance <= chara.attr.view_range && visual_distance < min_distance {
+        if visual_distance <= view_range && visual_distance < min_distance {
             target_cid = Some(*cid);
             min_distance = visual_distance;
         }
@@ -1,14 +1,24 @@
 
 pub mod playeract;
+pub mod ability;
 pub mod item;
 pub mod frequent_tex;
 mod npc;
+pub mod party;
+mod pathfinding;
 mod action;
 mod command;
 mod region;
 pub mod site;
-mod map;
+pub mod map;
+pub mod mount;
+pub mod weather;
 pub mod chara;
+pub mod craft;
+mod dig;
+mod door;
+mod harvest;
+mod trap;
 mod skill;
 mod infogetter;
 mod animation;
@@ -20,10 +30,12 @@ mod town;
 mod turnloop;
 pub mod view;
 mod script;
+pub mod script_check;
 mod eval_expr;
 pub mod shop;
 mod dungeon_gen;
 pub mod saveload;
+pub mod console;
 
 use std::borrow::Cow;
 use std::path::PathBuf;
@@ -31,9 +43,9 @@ use array2d::Vec2d;
 use common::gamedata::*;
 pub use self::command::Command;
 pub use self::infogetter::InfoGetter;
-pub use self::animation::Animation;
+pub use self::animation::{Animation, FlashKind};
 pub use self::playeract::DoPlayerAction;
-pub use self::script::TalkText;
+pub use self::script::{TalkText, replace_talk_vars};
 use self::script::*;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -55,6 +67,12 @@ pub struct Game {
     /// Player's current target of shot and similer actions
     target_chara: Option<CharaId>,
     save_dir: Option<PathBuf>,
+    /// Player turns since the last auto-save
+    turns_since_autosave: u32,
+    /// Auto-save at the next player turn
+    autosave_requested: bool,
+    /// Hostile npcs have noticed the player. Updated every player turn.
+    pub player_noticed: bool,
     pub view_map: view::ViewMap,
     pub frequent_tex: self::frequent_tex::FrequentTextures,
 }
@@ -71,6 +89,9 @@ impl Game {
             script: None,
             target_chara: None,
             save_dir: Some(save_dir),
+            turns_since_autosave: 0,
+            autosave_requested: false,
+            player_noticed: false,
             view_map: view::ViewMap::new(),
             frequent_tex: self::frequent_tex::FrequentTextures::new(),
         }
@@ -87,6 +108,9 @@ impl Game {
             script: None,
             target_chara: None,
             save_dir: None,
+            turns_since_autosave: 0,
+            autosave_requested: false,
+            player_noticed: false,
             view_map: view::ViewMap::new(),
             frequent_tex: self::frequent_tex::FrequentTextures::new(),
         }
@@ -96,6 +120,11 @@ impl Game {
         self.state
     }
 
+    /// Relationship between two characters
+    pub fn relation_between(&self, a: CharaId, b: CharaId) -> Relationship {
+        chara::relation_between(&self.gd, a, b)
+    }
+
     pub fn advance_turn(&mut self) {
         crate::log::new_line(); // Insert break to log lines
         turnloop::turn_loop(self);
@@ -109,6 +138,16 @@ impl Game {
     /// Update some parameters before starting player's turn
     pub fn update_before_player_turn(&mut self) {
         view::update_view_map(self);
+        trap::detect_traps(&mut self.gd);
+        self.player_noticed = npc::perception::is_player_noticed(&self.gd);
+        quest::check_deadlines(self);
+        map::check_condition_events(self);
+        self.check_autosave();
+    }
+
+    /// Play the music of the current map. Called when the game starts.
+    pub fn play_map_music(&self) {
+        map::play_map_music(&self.gd);
     }
 
     pub fn finish_player_turn(&mut self) {
@@ -133,7 +172,20 @@ impl Game {
     }
 
     pub fn start_script(&mut self, id: &str, cid: Option<CharaId>) {
-        self.script = Some(ScriptEngine::new(id, cid));
+        if let Some(engine) = ScriptEngine::new(id, cid) {
+            self.start_script_engine(engine);
+        }
+    }
+
+    /// Start a script triggered by maps or quests. It has no speaker,
+    /// so its talks are shown as the player's.
+    pub fn start_event_script(&mut self, id: &str) {
+        self.start_script(id, Some(CharaId::Player));
+    }
+
+    fn start_script_engine(&mut self, engine: ScriptEngine) {
+        self.gd.vars.clear_local_vars();
+        self.script = Some(engine);
         self.advance_script(None);
     }
 
@@ -155,7 +207,7 @@ impl Game {
             ExecResult::Talk(cid, talk_text, need_open_talk_dialog) => {
                 if need_open_talk_dialog {
                     self.request_dialog_open(
-                        DialogOpenRequest::Talk { cid, talk_text }
+                        DialogOpenRequest::Talk { cid, talk_text: talk_text.clone() }
                     );
                 }
                 AdvanceScriptResult::UpdateTalkText(talk_text)
@@ -172,6 +224,23 @@ impl Game {
                 self.request_dialog_open(DialogOpenRequest::Quest);
                 AdvanceScriptResult::Continue
             }
+            ExecResult::MovePlayer(mid, pos) => {
+                if mid != self.gd.get_current_mapid() {
+                    map::switch_map(self, mid);
+                }
+                let map = self.gd.get_current_map_mut();
+                if !map.is_inside(pos) || !map.locate_chara(CharaId::Player, pos) {
+                    warn!("script error: cannot move the player to {:?}", pos);
+                }
+                view::update_view_map(self);
+                self.advance_script(None)
+            }
+            ExecResult::Teleport(mid) => {
+                if mid != self.gd.get_current_mapid() {
+                    map::switch_map(self, mid);
+                }
+                self.advance_script(None)
+            }
         }
     }
 
@@ -0,0 +1,131 @@
+
+//! Riding horses and boats on region maps
+
+use common::gamedata::*;
+use common::gobj;
+use common::obj::Movement;
+use common::objholder::CharaTemplateIdx;
+use crate::game::InfoGetter;
+use super::map::MapEx;
+
+fn mount_params(chara: &Chara) -> Option<&'static MountParams> {
+    chara.mount.as_ref().and_then(|item| gobj::get_obj(item.idx).mount.as_ref())
+}
+
+/// Movements of the character. Mounts replace the character's own movements.
+pub fn movements(chara: &Chara) -> &'static [Movement] {
+    if let Some(mount) = mount_params(chara) {
+        &mount.movements
+    } else {
+        &gobj::get_obj(chara.template).movements
+    }
+}
+
+/// Percentage of the time passing per turn on region maps
+pub fn travel_time(chara: &Chara) -> u16 {
+    mount_params(chara).map_or(100, |mount| mount.travel_time)
+}
+
+/// The character template drawn as the character
+pub fn drawn_template(chara: &Chara) -> CharaTemplateIdx {
+    mount_params(chara)
+        .and_then(|mount| gobj::id_to_idx_checked(&mount.rider_img))
+        .unwrap_or(chara.template)
+}
+
+pub fn is_mount(item: &Item) -> bool {
+    gobj::get_obj(item.idx).mount.is_some()
+}
+
+/// Ride the item. The current mount is put into the inventory.
+pub fn ride(gd: &mut GameData, cid: CharaId, il: ItemLocation) -> bool {
+    if !gd.get_current_mapid().is_region_map() {
+        game_log_i!("mount-region-only");
+        return false;
+    }
+    if !is_mount(gd.get_item(il).0) {
+        return false;
+    }
+    let item = gd.remove_item_and_get(il, 1);
+    let chara = gd.chara.get_mut(cid);
+    game_log_i!("mount-ride"; chara=chara, item=item);
+    if let Some(prev) = std::mem::replace(&mut chara.mount, Some(item)) {
+        chara.item_list.append(prev, 1);
+    }
+    true
+}
+
+/// Get off the mount, and put it into the inventory.
+/// Fails on tiles the character cannot stand on without the mount, like water for boats.
+pub fn dismount(gd: &mut GameData, cid: CharaId) -> bool {
+    let pos = if let Some(pos) = gd.chara_pos(cid) { pos } else { return false; };
+    let chara = gd.chara.get(cid);
+    if chara.mount.is_none() {
+        game_log_i!("mount-not-riding");
+        return false;
+    }
+    let own_movements = &gobj::get_obj(chara.template).movements;
+    if !gd.get_current_map().is_passable_by(own_movements, pos) {
+        game_log_i!("mount-cannot-dismount"; chara=chara);
+        return false;
+    }
+
+    let chara = gd.chara.get_mut(cid);
+    let item = chara.mount.take().unwrap();
+    game_log_i!("mount-dismount"; chara=chara, item=item);
+    chara.item_list.append(item, 1);
+    true
+}
+
+/// Mounts are put into the inventory when the character enters sites
+pub fn put_away(gd: &mut GameData, cid: CharaId) {
+    let chara = gd.chara.get_mut(cid);
+    if let Some(item) = chara.mount.take() {
+        game_log_i!("mount-dismount"; chara=chara, item=item);
+        chara.item_list.append(item, 1);
+    }
+}
+
+#[test]
+fn ride_and_dismount_test() {
+    use array2d::Vec2d;
+    use common::objholder::{ItemIdx, TileIdx};
+    use super::item::ItemListEx;
+    super::script::init_test_objs();
+
+    let mut gd = GameData::empty();
+    let rid = gd.region.add_region(Region::new("test-region", Map::new(2, 1), 0));
+    gd.set_initial_mapid(MapId::RegionMap { rid });
+    let player = Chara { name: Some("player".to_owned()), ..Chara::default() };
+    let player = gd.add_chara(player, CharaKind::Player);
+    gd.get_current_map_mut().locate_chara(player, Vec2d(0, 0));
+    let water: TileIdx = gobj::id_to_idx("test-water");
+    gd.get_current_map_mut().tile[Vec2d(1, 0)].tile = water.into();
+    let ill = ItemListLocation::Chara { cid: player };
+    let boat: ItemIdx = gobj::id_to_idx("test-boat");
+    let item: ItemIdx = gobj::id_to_idx("test-item");
+    gd.get_item_list_mut(ill).append(super::item::gen::gen_item_from_idx(item), 1);
+    gd.get_item_list_mut(ill).append(super::item::gen::gen_item_from_idx(boat), 1);
+    let boat_il = (ill, gd.get_item_list(ill).iter().position(|(i, _)| i.idx == boat).unwrap() as u32);
+    let item_il = (ill, gd.get_item_list(ill).iter().position(|(i, _)| i.idx == item).unwrap() as u32);
+
+    assert!(!gd.get_current_map().is_passable(gd.chara.get(player), Vec2d(1, 0)));
+    assert!(!ride(&mut gd, player, item_il));
+    assert!(ride(&mut gd, player, boat_il));
+    let chara = gd.chara.get(player);
+    assert_eq!(movements(chara), &[Movement::Swim]);
+    assert_eq!(travel_time(chara), 50);
+    assert_eq!(drawn_template(chara), gobj::id_to_idx("test-swimmer"));
+    assert!(gd.get_current_map().is_passable(chara, Vec2d(1, 0)));
+    assert_eq!(gd.get_item_list(ill).count(boat), 0);
+
+    // Cannot get off the boat on water
+    gd.get_current_map_mut().locate_chara(player, Vec2d(1, 0));
+    assert!(!dismount(&mut gd, player));
+    gd.get_current_map_mut().locate_chara(player, Vec2d(0, 0));
+    assert!(dismount(&mut gd, player));
+    let chara = gd.chara.get(player);
+    assert!(chara.mount.is_none());
+    assert_eq!(travel_time(chara), 100);
+    assert_eq!(gd.get_item_list(ill).count(boat), 1);
+}
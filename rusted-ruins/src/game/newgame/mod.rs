@@ -3,6 +3,7 @@ use common::gamedata;
 use common::gamedata::*;
 use common::gobj;
 use rules::RULES;
+use rules::newgame::StartScenario;
 use super::extrait::*;
 use super::skill::SkillListEx;
 
@@ -10,6 +11,8 @@ pub struct NewGameBuilder {
     gd: GameData,
     player_name: Option<String>,
     chara_class: Option<CharaClass>,
+    scenario: Option<StartScenario>,
+    bonus_attr: CharaAttrRevision,
 }
 
 impl NewGameBuilder {
@@ -18,6 +21,8 @@ impl NewGameBuilder {
             gd: GameData::empty(),
             player_name: None,
             chara_class: None,
+            scenario: None,
+            bonus_attr: CharaAttrRevision::default(),
         }
     }
 
@@ -29,28 +34,42 @@ impl NewGameBuilder {
         self.chara_class = Some(chara_class);
     }
 
+    pub fn set_scenario(&mut self, scenario: StartScenario) {
+        self.scenario = Some(scenario);
+    }
+
+    /// Set attribute points allocated by the player
+    pub fn set_bonus_attr(&mut self, bonus_attr: CharaAttrRevision) {
+        self.bonus_attr = bonus_attr;
+    }
+
     pub fn build(mut self) -> GameData {
         {
             let mut gd = &mut self.gd;
+            let scenario = self.scenario.take().unwrap_or_else(|| RULES.newgame.scenarios[0].clone());
 
             gd.meta.set_save_name(self.player_name.as_ref().unwrap());
 
-            super::region::add_region(&mut gd, &RULES.newgame.start_region);
+            super::region::add_region(&mut gd, &scenario.start_region);
 
             let mid = MapId::RegionMap { rid: RegionId::default() };
             gd.set_initial_mapid(mid);
-            let start_pos = RULES.newgame.start_pos;
+            let start_pos = scenario.start_pos;
 
             super::region::gen_dungeon(&mut gd, mid.rid());
 
             let chara_template_id = &RULES.newgame.chara_template_table[&self.chara_class.unwrap()];
             let mut chara = super::chara::gen::create_chara(gobj::id_to_idx(chara_template_id), 1);
             chara.rel = gamedata::chara::Relationship::ALLY;
+            chara.faction = Faction::Player;
             chara.name = Some(self.player_name.as_ref().unwrap().clone());
             set_initial_skills(&mut chara);
+            set_initial_abilities(&mut chara, self.chara_class.unwrap());
+            chara.bonus_attr = self.bonus_attr;
             chara.update();
+            chara.mp = chara.attr.max_mp;
 
-            gd.player.set_money(RULES.newgame.start_money as i64);
+            gd.player.set_money(scenario.start_money as i64);
             
             /* Test code for equipment */
             use common::gamedata::chara::Race;
@@ -78,3 +97,71 @@ fn set_initial_skills(chara: &mut Chara) {
     }
 }
 
+/// Set initial abilities of the class from rule
+fn set_initial_abilities(chara: &mut Chara, chara_class: CharaClass) {
+    let ability_ids = if let Some(ids) = RULES.newgame.class_abilities.get(&chara_class) {
+        ids
+    } else {
+        return;
+    };
+    for id in ability_ids {
+        if let Some(idx) = gobj::id_to_idx_checked(id) {
+            chara.abilities.push(idx);
+        } else {
+            warn!("unknown ability id \"{}\" in newgame rules", id);
+        }
+    }
+}
+
+/// Attribute points allocated by the player at character creation
+#[derive(Clone, Copy, Default, Debug)]
+pub struct StatAllocation {
+    /// Points of str, vit, dex, int, wil and cha
+    pub points: [u32; 6],
+}
+
+impl StatAllocation {
+    pub fn remaining(&self, total: u32) -> u32 {
+        total.saturating_sub(self.points.iter().sum())
+    }
+
+    /// Move one point to or from the attribute. Returns false if not changed.
+    pub fn change(&mut self, i: usize, increase: bool, total: u32, max_per_attr: u32) -> bool {
+        if increase {
+            if self.points[i] >= max_per_attr || self.remaining(total) == 0 {
+                return false;
+            }
+            self.points[i] += 1;
+        } else {
+            if self.points[i] == 0 {
+                return false;
+            }
+            self.points[i] -= 1;
+        }
+        true
+    }
+
+    pub fn to_revision(&self) -> CharaAttrRevision {
+        let p = |i: usize| self.points[i] as i16;
+        CharaAttrRevision {
+            str: p(0), vit: p(1), dex: p(2), int: p(3), wil: p(4), cha: p(5),
+            .. CharaAttrRevision::default()
+        }
+    }
+}
+
+#[test]
+fn stat_allocation_test() {
+    let mut a = StatAllocation::default();
+    assert!(a.change(0, true, 3, 2));
+    assert!(a.change(0, true, 3, 2));
+    assert!(!a.change(0, true, 3, 2));
+    assert!(a.change(2, true, 3, 2));
+    assert!(!a.change(3, true, 3, 2));
+    assert_eq!(a.remaining(3), 0);
+    assert!(a.change(0, false, 3, 2));
+    assert!(!a.change(4, false, 3, 2));
+    assert_eq!(a.remaining(3), 1);
+    let r = a.to_revision();
+    assert_eq!((r.str, r.dex, r.hp), (1, 1, 0));
+}
@@ -2,7 +2,7 @@
 
 use array2d::*;
 use common::gamedata::*;
-use crate::game::InfoGetter;
+use crate::game::chara::relation_between;
 
 /// Search nearest other character from cid.
 /// If f() returns false, skip the character.
@@ -35,21 +35,13 @@ where F: FnMut(&GameData, CharaId, CharaId) -> bool {
 pub fn search_nearest_enemy(gd: &GameData, cid: CharaId) -> Option<CharaId> {
     search_nearest_chara(
         gd, cid,
-        |gd, c0, c1| {
-            let c0 = gd.chara.get(c0);
-            let c1 = gd.chara.get(c1);
-            c0.rel.relative(c1.rel) == Relationship::HOSTILE
-        }
+        |gd, c0, c1| relation_between(gd, c0, c1) == Relationship::HOSTILE
     )
 }
 
-/// Returns direction to target chara
-pub fn dir_to_chara(gd: &GameData, cid: CharaId, pos: Vec2d) -> Direction {
-    if let Some(target_pos) = gd.chara_pos(cid) {
-        dir_2pos(pos, target_pos)
-    } else {
-        Direction::NONE
-    }
+/// Returns true if p2 is on the same or a neighbor tile of p1
+pub fn is_adjacent(p1: Vec2d, p2: Vec2d) -> bool {
+    (p2.0 - p1.0).abs() <= 1 && (p2.1 - p1.1).abs() <= 1
 }
 
 /// Direction from p1 to p2
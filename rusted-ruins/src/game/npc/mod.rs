@@ -1,16 +1,18 @@
 //! Functions for NPC's AI and actions
 
 pub mod map_search;
+pub mod needs;
+pub mod perception;
+pub mod schedule;
+pub mod state;
+pub mod tactics;
 
-use array2d::*;
 use common::gamedata::*;
-use super::{Game, InfoGetter};
-use super::action;
-use rng::*;
+use super::Game;
 
 pub fn process_npc_turn(game: &mut Game, cid: CharaId) {
 
-    {
+    let tactics = {
         let chara = game.gd.chara.get(cid);
         let ai = &chara.ai;
 
@@ -23,30 +25,24 @@ pub fn process_npc_turn(game: &mut Game, cid: CharaId) {
             }
             _ => (),
         }
-    }
+        ai.tactics
+    };
 
-    if gen_range(0, 3) == 0 {
-        move_to_nearest_enemy(game, cid);
-    } else {
-        random_walk(game, cid);
+    // Companions act by their tactics
+    if let Some(tactics) = tactics {
+        tactics::process_tactics(game, cid, tactics);
+        return;
     }
-}
 
-/// Move npc at random
-fn random_walk(game: &mut Game, cid: CharaId) {
-    let dir = Direction::new(
-        *[HDirection::Left, HDirection::None, HDirection::Right].choose(&mut get_rng()).unwrap(),
-        *[VDirection::Up, VDirection::None, VDirection::Down].choose(&mut get_rng()).unwrap());
-    action::try_move(game, cid, dir);
-}
+    // Pressing needs have priority over the default behavior
+    if needs::process_needs(game, cid) {
+        return;
+    }
 
-/// Move npc to nearest enemy
-fn move_to_nearest_enemy(game: &mut Game, cid: CharaId) {
-    if let Some(target) = map_search::search_nearest_enemy(&game.gd, cid) {
-        if let Some(pos) = game.gd.chara_pos(cid) {
-            let dir = map_search::dir_to_chara(&game.gd, target, pos);
-            action::try_move(game, cid, dir);
-        }
+    // Scheduled places have priority over the default behavior
+    if schedule::process_schedule(game, cid) {
+        return;
     }
-}
 
+    state::process_state(game, cid);
+}
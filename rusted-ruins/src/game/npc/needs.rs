@@ -0,0 +1,263 @@
+//! Needs of town NPCs and utility based goal selection.
+
+use array2d::*;
+use common::gamedata::*;
+use rules::RULES;
+use rules::npc::Npc as NpcRules;
+use crate::game::{Game, InfoGetter};
+use crate::game::action;
+use crate::game::pathfinding;
+use crate::game::chara::relation_between;
+use super::map_search;
+
+/// Goal of npcs chosen from their needs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NpcGoal {
+    /// Go to a food source and eat
+    Eat,
+    /// Stay and rest
+    Rest,
+    /// Go to other characters to talk
+    Socialize,
+    /// No need is pressing. The npc acts by its AI kind.
+    Default,
+}
+
+/// Positions that npcs move toward for each goal
+#[derive(Clone, Copy, Default, Debug)]
+pub struct GoalTargets {
+    pub food: Option<Vec2d>,
+    pub social: Option<Vec2d>,
+}
+
+/// Process npc's turn by its needs.
+/// If the npc doesn't have needs or no need is pressing, returns false.
+pub fn process_needs(game: &mut Game, cid: CharaId) -> bool {
+    let r = &RULES.npc;
+    let goal = {
+        let chara = game.gd.chara.get_mut(cid);
+        let needs = if let Some(needs) = chara.ai.needs.as_mut() { needs } else { return false; };
+        advance_needs(needs, r);
+        choose_goal(needs, r)
+    };
+    if goal == NpcGoal::Default {
+        return false;
+    }
+    let pos = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return false; };
+
+    let targets = GoalTargets {
+        food: search_food(&game.gd, cid, pos),
+        social: map_search::search_nearest_chara(&game.gd, cid, |gd, c0, c1| {
+            relation_between(gd, c0, c1) != Relationship::HOSTILE
+        }).and_then(|target| game.gd.chara_pos(target)),
+    };
+    let dir = if let Some(dir) = goal_dir(goal, pos, &targets) { dir } else { return false; };
+
+    // Satisfy the need if the npc has reached its target
+    {
+        let needs = game.gd.chara.get_mut(cid).ai.needs.as_mut().unwrap();
+        match goal {
+            NpcGoal::Eat if is_reached(pos, targets.food) => {
+                needs.hunger = 0;
+                eat_food_item(&mut game.gd, targets.food.unwrap());
+                return true;
+            }
+            NpcGoal::Socialize if is_reached(pos, targets.social) => {
+                needs.social = 0;
+                return true;
+            }
+            NpcGoal::Rest => {
+                needs.fatigue = needs.fatigue.saturating_sub(r.rest_recovery);
+                return true;
+            }
+            _ => (),
+        }
+    }
+
+    // Follow the route to the target instead of moving straight
+    let dir = goal_target(goal, &targets)
+        .map_or(dir, |target| pathfinding::first_step(
+            game.gd.get_current_map(), game.gd.chara.get(cid), pos, target));
+    action::try_move(game, cid, dir);
+    true
+}
+
+/// Increase needs by one turn
+pub fn advance_needs(needs: &mut CharaNeeds, r: &NpcRules) {
+    use std::cmp::min;
+    needs.hunger = min(needs.hunger.saturating_add(r.hunger_per_turn), r.max_need);
+    needs.fatigue = min(needs.fatigue.saturating_add(r.fatigue_per_turn), r.max_need);
+    needs.social = min(needs.social.saturating_add(r.social_per_turn), r.max_need);
+}
+
+/// Choose the goal that has the highest utility.
+/// The utility of a need is its value relative to the maximum.
+pub fn choose_goal(needs: &CharaNeeds, r: &NpcRules) -> NpcGoal {
+    let mut goal = NpcGoal::Default;
+    let mut max_utility = r.default_goal_utility;
+
+    for &(g, v) in &[
+        (NpcGoal::Eat, needs.hunger),
+        (NpcGoal::Rest, needs.fatigue),
+        (NpcGoal::Socialize, needs.social)] {
+
+        let utility = v as f32 / r.max_need as f32;
+        if utility > max_utility {
+            goal = g;
+            max_utility = utility;
+        }
+    }
+    goal
+}
+
+/// Returns the direction to move for given goal.
+/// If the target of the goal is not found, returns None.
+pub fn goal_dir(goal: NpcGoal, pos: Vec2d, targets: &GoalTargets) -> Option<Direction> {
+    if goal == NpcGoal::Rest {
+        return Some(Direction::NONE);
+    }
+    let target = goal_target(goal, targets)?;
+    Some(map_search::dir_2pos(pos, target))
+}
+
+/// The position to move toward for given goal
+fn goal_target(goal: NpcGoal, targets: &GoalTargets) -> Option<Vec2d> {
+    match goal {
+        NpcGoal::Eat => targets.food,
+        NpcGoal::Socialize => targets.social,
+        // Npcs without pressing needs move by their AI kind
+        NpcGoal::Rest | NpcGoal::Default => None,
+    }
+}
+
+/// Target is on the same or a neighbor tile
+fn is_reached(pos: Vec2d, target: Option<Vec2d>) -> bool {
+    target.map_or(false, |target| map_search::is_adjacent(pos, target))
+}
+
+/// Search the nearest food source within the view range of the npc.
+/// Food shopkeepers and tiles that have food items are food sources.
+fn search_food(gd: &GameData, cid: CharaId, pos: Vec2d) -> Option<Vec2d> {
+    let map = gd.get_current_map();
+    let view_range = std::cmp::max(gd.chara.get(cid).attr.view_range, 0);
+    let mut result = None;
+    let mut distance = i32::max_value();
+
+    for (d, p) in MDistRangeIter::new(pos, view_range) {
+        if !map.is_inside(p) {
+            continue;
+        }
+        let tile = &map.tile[p];
+        let is_food_source = match tile.chara {
+            Some(tile_cid) if tile_cid != cid => is_food_shopkeeper(gd, tile_cid),
+            _ => false,
+        } || tile.item_list.as_ref().map_or(false, |item_list| {
+            item_list.iter().any(|(item, _)| item.kind == ItemKind::Food)
+        });
+
+        if is_food_source {
+            if d < distance {
+                distance = d;
+                result = Some(p);
+            }
+        }
+    }
+    result
+}
+
+/// Eat one food item on the tile. Food shopkeepers don't lose their items.
+fn eat_food_item(gd: &mut GameData, pos: Vec2d) {
+    let food = gd.get_current_map().tile[pos].item_list.as_ref().and_then(|item_list| {
+        item_list.iter().position(|(item, _)| item.kind == ItemKind::Food)
+    });
+    if let Some(i) = food {
+        let mid = gd.get_current_mapid();
+        gd.remove_item((ItemListLocation::OnMap { mid, pos }, i as u32), 1);
+    }
+}
+
+fn is_food_shopkeeper(gd: &GameData, cid: CharaId) -> bool {
+    if let CharaId::OnSite { sid, n } = cid {
+        if let SiteContent::Town { ref town } = gd.region.get_site(sid).content {
+            if let Some(shop) = town.get_shop(n) {
+                return shop.kind == ShopKind::Food;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+pub fn test_rules() -> NpcRules {
+    NpcRules {
+        hunger_per_turn: 2,
+        fatigue_per_turn: 1,
+        social_per_turn: 1,
+        max_need: 10000,
+        default_goal_utility: 0.6,
+        rest_recovery: 100,
+        defensive_range: 3,
+        wounded_hp_ratio: 0.5,
+        support_heal: 5,
+        shopkeeper_range: 2,
+        schedule_range: 3,
+        perception_divisor: 5,
+        noise_range: 6,
+    }
+}
+
+#[test]
+fn hungry_npc_goes_to_food_test() {
+    let r = test_rules();
+    let targets = GoalTargets {
+        food: Some(Vec2d(5, 1)),
+        social: None,
+    };
+    let pos = Vec2d(1, 1);
+
+    let needs = CharaNeeds::default();
+    let goal = choose_goal(&needs, &r);
+    assert_eq!(goal, NpcGoal::Default);
+    assert_eq!(goal_dir(goal, pos, &targets), None);
+
+    let needs = CharaNeeds { hunger: 9000, fatigue: 7000, social: 0 };
+    let goal = choose_goal(&needs, &r);
+    assert_eq!(goal, NpcGoal::Eat);
+    assert_eq!(goal_dir(goal, pos, &targets), Some(Direction::E));
+}
+
+#[test]
+fn advance_needs_test() {
+    let r = test_rules();
+    let mut needs = CharaNeeds { hunger: 9999, fatigue: 0, social: 0 };
+    advance_needs(&mut needs, &r);
+    assert_eq!(needs, CharaNeeds { hunger: 10000, fatigue: 1, social: 1 });
+}
+
+#[test]
+fn hungry_npc_eats_food_test() {
+    use crate::game::script::{add_test_npc, test_game};
+    let mut game = test_game(8, 1);
+    let npc = add_test_npc(&mut game, Vec2d(1, 0));
+    game.gd.chara.get_mut(npc).ai.needs = Some(CharaNeeds {
+        hunger: RULES.npc.max_need, fatigue: 0, social: 0,
+    });
+    let food = common::gobj::id_to_idx("test-food");
+    let mid = game.gd.get_current_mapid();
+    let ill = ItemListLocation::OnMap { mid, pos: Vec2d(4, 0) };
+    let food = crate::game::item::gen::gen_item_from_idx(food);
+    game.gd.get_current_map_mut().locate_item(food, Vec2d(4, 0), 2);
+
+    assert_eq!(search_food(&game.gd, npc, Vec2d(1, 0)), Some(Vec2d(4, 0)));
+    // Food out of the view range is not found
+    game.gd.chara.get_mut(npc).attr.view_range = 2;
+    assert_eq!(search_food(&game.gd, npc, Vec2d(1, 0)), None);
+    game.gd.chara.get_mut(npc).attr.view_range = 5;
+
+    for _ in 0..3 {
+        assert!(process_needs(&mut game, npc));
+    }
+    assert_eq!(game.gd.chara_pos(npc), Some(Vec2d(3, 0)));
+    assert_eq!(game.gd.chara.get(npc).ai.needs.unwrap().hunger, 0);
+    assert_eq!(game.gd.get_item_list(ill).get_number(0), 1);
+}
@@ -0,0 +1,61 @@
+
+//! Noticing enemies by sight and sound. Npcs don't know where enemies are until they notice them.
+
+use std::cmp::max;
+use array2d::*;
+use common::gamedata::*;
+use rules::RULES;
+use crate::game::chara::relation_between;
+
+/// Distance within which the observer notices the target in sight.
+/// Perception skill of the observer widens it, and stealth skill of the target narrows it.
+pub fn detection_range(observer: &Chara, target: &Chara) -> i32 {
+    calc_detection_range(
+        observer.attr.view_range,
+        observer.skills.get(SkillKind::Perception),
+        target.skills.get(SkillKind::Stealth),
+        RULES.npc.perception_divisor)
+}
+
+fn calc_detection_range(view_range: i32, perception: u32, stealth: u32, divisor: u32) -> i32 {
+    let divisor = max(divisor, 1);
+    let range = view_range + (perception / divisor) as i32 - (stealth / divisor) as i32;
+    max(range, 1)
+}
+
+/// Npcs hostile to the character hear the noise it made at the position,
+/// and notice the position even if it is not in sight
+pub fn make_noise(gd: &mut GameData, cid: CharaId, pos: Vec2d) {
+    let range = RULES.npc.noise_range;
+    let listeners: Vec<CharaId> = {
+        let map = gd.get_current_map();
+        map.tile.iter_idx()
+            .filter(|&p| p.mdistance(pos) <= range)
+            .filter_map(|p| map.tile[p].chara)
+            .filter(|&listener| {
+                listener != CharaId::Player && relation_between(gd, listener, cid) == Relationship::HOSTILE
+            })
+            .collect()
+    };
+    for listener in listeners {
+        gd.chara.get_mut(listener).ai.last_seen = Some(pos);
+    }
+}
+
+/// Some hostile npcs on the current map have noticed the player or the allies
+pub fn is_player_noticed(gd: &GameData) -> bool {
+    let map = gd.get_current_map();
+    map.tile.iter_idx().filter_map(|p| map.tile[p].chara).any(|cid| {
+        cid != CharaId::Player &&
+            relation_between(gd, cid, CharaId::Player) == Relationship::HOSTILE &&
+            gd.chara.get(cid).ai.last_seen.is_some()
+    })
+}
+
+#[test]
+fn detection_range_test() {
+    assert_eq!(calc_detection_range(8, 0, 0, 5), 8);
+    assert_eq!(calc_detection_range(8, 10, 4, 5), 10);
+    assert_eq!(calc_detection_range(8, 0, 20, 5), 4);
+    assert_eq!(calc_detection_range(8, 0, 100, 5), 1);
+}
@@ -0,0 +1,54 @@
+//! Daily schedules of town NPCs
+
+use common::gamedata::*;
+use rules::RULES;
+use crate::game::{Game, InfoGetter};
+use crate::game::action;
+use crate::game::pathfinding;
+use super::state;
+
+/// Move the npc to the place of the current schedule entry.
+/// If the npc has no schedule or the current entry has no place, returns false.
+pub fn process_schedule(game: &mut Game, cid: CharaId) -> bool {
+    let hour = game.gd.time.current_date().hour;
+    let target = {
+        let schedule = &game.gd.chara.get(cid).ai.schedule;
+        if let Some(target) = current_entry(schedule, hour).and_then(|entry| entry.pos) {
+            target
+        } else {
+            return false;
+        }
+    };
+    let pos = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return false; };
+
+    // Npcs walk around near the place after arriving at it
+    let range = RULES.npc.schedule_range;
+    let dir = if pos.mdistance(target) <= range {
+        state::keep_near_home(pos, state::random_dir(), target, range)
+    } else {
+        pathfinding::first_step(game.gd.get_current_map(), game.gd.chara.get(cid), pos, target)
+    };
+    action::try_move(game, cid, dir);
+    true
+}
+
+/// The entry that started last before the given hour.
+/// Entries of the previous day continue until the first entry of the day starts.
+pub fn current_entry(schedule: &[ScheduleEntry], hour: u16) -> Option<&ScheduleEntry> {
+    schedule.iter().rev().find(|entry| entry.hour <= hour).or_else(|| schedule.last())
+}
+
+#[test]
+fn current_entry_test() {
+    use array2d::Vec2d;
+    let shop = ScheduleEntry { hour: 8, pos: Some(Vec2d(3, 4)) };
+    let wander = ScheduleEntry { hour: 18, pos: None };
+    let home = ScheduleEntry { hour: 21, pos: Some(Vec2d(10, 2)) };
+    let schedule = [shop, wander, home];
+
+    assert_eq!(current_entry(&[], 12), None);
+    assert_eq!(current_entry(&schedule, 8), Some(&shop));
+    assert_eq!(current_entry(&schedule, 20), Some(&wander));
+    assert_eq!(current_entry(&schedule, 23), Some(&home));
+    assert_eq!(current_entry(&schedule, 3), Some(&home));
+}
@@ -0,0 +1,139 @@
+//! Behavior states of npcs chosen by their AI kind
+
+use array2d::*;
+use common::gamedata::*;
+use rng::*;
+use rules::RULES;
+use crate::game::{Game, InfoGetter};
+use crate::game::action;
+use crate::game::pathfinding;
+use crate::game::chara::CharaEx;
+use crate::game::view::calc_visual_distance;
+use super::map_search;
+use super::perception::detection_range;
+use super::tactics::is_wounded;
+
+/// Surroundings of an npc referred to choose its state
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Surroundings {
+    /// The position of the nearest enemy noticed, or the position where an enemy was noticed last
+    pub enemy: Option<Vec2d>,
+    /// The npc's hp is low or not
+    pub wounded: bool,
+}
+
+/// Choose the state of the npc and act by it
+pub fn process_state(game: &mut Game, cid: CharaId) {
+    let r = &RULES.npc;
+    let pos = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return; };
+
+    let enemy = notice_enemy(game, cid, pos);
+
+    let (kind, state, home, last_seen) = {
+        let chara = game.gd.chara.get_mut(cid);
+        // Go to the position where an enemy was noticed last, and forget it after arriving
+        if enemy.is_some() {
+            chara.ai.last_seen = enemy;
+        } else if chara.ai.last_seen == Some(pos) {
+            chara.ai.last_seen = None;
+        }
+        let surroundings = Surroundings { enemy: chara.ai.last_seen, wounded: is_wounded(chara, r) };
+        let ai = &mut chara.ai;
+        ai.state = choose_state(ai.kind, &surroundings);
+        if ai.kind == NpcAIKind::Shopkeeper && ai.home.is_none() {
+            ai.home = Some(pos);
+        }
+        (ai.kind, ai.state, ai.home, ai.last_seen)
+    };
+
+    let dir = match state {
+        AiState::Idle => { return; }
+        AiState::Wander => {
+            let dir = random_dir();
+            match home {
+                Some(home) if kind == NpcAIKind::Shopkeeper => keep_near_home(pos, dir, home, r.shopkeeper_range),
+                _ => dir,
+            }
+        }
+        AiState::Chase => pathfinding::first_step(
+            game.gd.get_current_map(), game.gd.chara.get(cid), pos, last_seen.unwrap()),
+        AiState::Flee => map_search::dir_2pos(last_seen.unwrap(), pos),
+    };
+    action::try_move(game, cid, dir);
+}
+
+/// The position of the nearest enemy if the npc notices it in sight.
+/// Enemies unnoticed by their stealth gain exp.
+fn notice_enemy(game: &mut Game, cid: CharaId, pos: Vec2d) -> Option<Vec2d> {
+    let target = map_search::search_nearest_enemy(&game.gd, cid)?;
+    let enemy_pos = game.gd.chara_pos(target)?;
+    let distance = calc_visual_distance(game.gd.get_current_map(), pos, enemy_pos)?;
+    let observer = game.gd.chara.get(cid);
+    let view_range = crate::game::weather::view_range(&game.gd, observer.attr.view_range);
+    if distance > view_range {
+        return None;
+    }
+    let range = std::cmp::min(detection_range(observer, game.gd.chara.get(target)), view_range);
+    if distance <= range {
+        return Some(enemy_pos);
+    }
+    let observer_level = observer.level;
+    game.gd.chara.get_mut(target).add_skill_exp(SkillKind::Stealth, RULES.exp.stealth, observer_level);
+    None
+}
+
+/// Choose npc's state from its AI kind and surroundings
+pub fn choose_state(kind: NpcAIKind, surroundings: &Surroundings) -> AiState {
+    match kind {
+        NpcAIKind::None | NpcAIKind::NoMove => AiState::Idle,
+        NpcAIKind::Villager | NpcAIKind::Shopkeeper => AiState::Wander,
+        NpcAIKind::Melee => {
+            if surroundings.enemy.is_some() { AiState::Chase } else { AiState::Wander }
+        }
+        NpcAIKind::Coward => {
+            match surroundings.enemy {
+                Some(_) if surroundings.wounded => AiState::Flee,
+                Some(_) => AiState::Chase,
+                None => AiState::Wander,
+            }
+        }
+    }
+}
+
+/// If moving to the direction takes the npc too far from home, go back to home instead
+pub fn keep_near_home(pos: Vec2d, dir: Direction, home: Vec2d, range: i32) -> Direction {
+    if (pos + dir.as_vec()).mdistance(home) > range {
+        map_search::dir_2pos(pos, home)
+    } else {
+        dir
+    }
+}
+
+pub fn random_dir() -> Direction {
+    Direction::new(
+        *[HDirection::Left, HDirection::None, HDirection::Right].choose(&mut get_rng()).unwrap(),
+        *[VDirection::Up, VDirection::None, VDirection::Down].choose(&mut get_rng()).unwrap())
+}
+
+#[test]
+fn choose_state_test() {
+    let no_enemy = Surroundings { enemy: None, wounded: false };
+    let enemy = Surroundings { enemy: Some(Vec2d(3, 3)), wounded: false };
+    let wounded = Surroundings { enemy: Some(Vec2d(3, 3)), wounded: true };
+
+    assert_eq!(choose_state(NpcAIKind::Villager, &enemy), AiState::Wander);
+    assert_eq!(choose_state(NpcAIKind::NoMove, &enemy), AiState::Idle);
+    assert_eq!(choose_state(NpcAIKind::Melee, &no_enemy), AiState::Wander);
+    assert_eq!(choose_state(NpcAIKind::Melee, &wounded), AiState::Chase);
+    assert_eq!(choose_state(NpcAIKind::Coward, &enemy), AiState::Chase);
+    assert_eq!(choose_state(NpcAIKind::Coward, &wounded), AiState::Flee);
+}
+
+#[test]
+fn keep_near_home_test() {
+    let r = super::needs::test_rules();
+    let home = Vec2d(5, 5);
+
+    assert_eq!(keep_near_home(Vec2d(5, 6), Direction::S, home, r.shopkeeper_range), Direction::S);
+    assert_eq!(keep_near_home(Vec2d(5, 7), Direction::S, home, r.shopkeeper_range), Direction::N);
+}
@@ -0,0 +1,152 @@
+//! Combat tactics of companions
+
+use array2d::*;
+use common::gamedata::*;
+use rules::RULES;
+use rules::npc::Npc as NpcRules;
+use crate::game::{Game, InfoGetter};
+use crate::game::action;
+use crate::game::pathfinding;
+use crate::game::chara::relation_between;
+use super::map_search::{self, is_adjacent};
+
+/// Positions referred to determine companion's action
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TacticsTargets {
+    /// The character that companions follow
+    pub leader: Option<Vec2d>,
+    /// The nearest enemy
+    pub enemy: Option<Vec2d>,
+    /// The nearest wounded ally
+    pub wounded: Option<Vec2d>,
+}
+
+/// Where the companion goes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TacticsTarget {
+    /// Move toward the position. Moving to an enemy means attacking.
+    Toward(Vec2d),
+    /// Heal the wounded ally
+    Heal,
+    Stay,
+}
+
+/// Process companion's turn by its tactics and the order from the player
+pub fn process_tactics(game: &mut Game, cid: CharaId, tactics: Tactics) {
+    let r = &RULES.npc;
+    let pos = if let Some(pos) = game.gd.chara_pos(cid) { pos } else { return; };
+
+    // Orders have priority over the tactics
+    let order = game.gd.chara.get(cid).ai.order;
+    let order_target = match order {
+        PartyOrder::Attack(target) => {
+            let target_pos = game.gd.chara_pos(target);
+            if target_pos.is_none() {
+                // The target is defeated or has left
+                game.gd.chara.get_mut(cid).ai.order = PartyOrder::Follow;
+            }
+            target_pos
+        }
+        _ => None,
+    };
+    let tactics = match order {
+        PartyOrder::Wait => Tactics::Hold,
+        _ if order_target.is_some() => Tactics::Aggressive,
+        _ => tactics,
+    };
+
+    let wounded = if tactics == Tactics::Support {
+        map_search::search_nearest_chara(&game.gd, cid, |gd, c0, c1| {
+            relation_between(gd, c0, c1) == Relationship::ALLY && is_wounded(gd.chara.get(c1), r)
+        })
+    } else {
+        None
+    };
+    let targets = TacticsTargets {
+        leader: game.gd.chara_pos(CharaId::Player),
+        enemy: order_target.or_else(|| map_search::search_nearest_enemy(&game.gd, cid)
+            .and_then(|target| game.gd.chara_pos(target))),
+        wounded: wounded.and_then(|target| game.gd.chara_pos(target)),
+    };
+
+    match choose_target(tactics, pos, &targets, r) {
+        TacticsTarget::Toward(target) => {
+            let dir = pathfinding::first_step(
+                game.gd.get_current_map(), game.gd.chara.get(cid), pos, target);
+            action::try_move(game, cid, dir);
+        }
+        TacticsTarget::Heal => {
+            use std::cmp::min;
+            let chara = game.gd.chara.get_mut(wounded.unwrap());
+            chara.hp = min(chara.attr.max_hp, chara.hp + r.support_heal);
+            game_log!("heal-hp"; chara=chara, value=r.support_heal);
+        }
+        TacticsTarget::Stay => (),
+    }
+}
+
+/// Choose the destination of companion from its tactics and the positions of targets
+pub fn choose_target(
+    tactics: Tactics, pos: Vec2d, targets: &TacticsTargets, r: &NpcRules) -> TacticsTarget {
+
+    let adjacent_enemy = targets.enemy.filter(|&e| is_adjacent(pos, e));
+
+    let target = match tactics {
+        Tactics::Aggressive => targets.enemy,
+        Tactics::Defensive => {
+            targets.enemy.filter(|&e| {
+                is_adjacent(pos, e) ||
+                    targets.leader.map_or(false, |l| l.mdistance(e) <= r.defensive_range)
+            })
+        }
+        Tactics::Support => {
+            match targets.wounded {
+                Some(w) if is_adjacent(pos, w) => { return TacticsTarget::Heal; }
+                Some(w) => {
+                    return TacticsTarget::Toward(w);
+                }
+                None => adjacent_enemy,
+            }
+        }
+        Tactics::Hold => {
+            return adjacent_enemy.map_or(TacticsTarget::Stay, TacticsTarget::Toward);
+        }
+    };
+
+    if let Some(target) = target {
+        return TacticsTarget::Toward(target);
+    }
+
+    // Follow the leader if there is no target
+    match targets.leader {
+        Some(l) if !is_adjacent(pos, l) => TacticsTarget::Toward(l),
+        _ => TacticsTarget::Stay,
+    }
+}
+
+pub fn is_wounded(chara: &Chara, r: &NpcRules) -> bool {
+    (chara.hp as f32) < chara.attr.max_hp as f32 * r.wounded_hp_ratio
+}
+
+#[test]
+fn defensive_and_aggressive_test() {
+    let r = super::needs::test_rules();
+    let pos = Vec2d(2, 2);
+    let targets = TacticsTargets {
+        leader: Some(Vec2d(2, 3)),
+        enemy: Some(Vec2d(10, 2)),
+        wounded: None,
+    };
+
+    // Defensive companion stays near the player while the enemy is distant
+    assert_eq!(choose_target(Tactics::Defensive, pos, &targets, &r), TacticsTarget::Stay);
+    let far_targets = TacticsTargets { leader: Some(Vec2d(2, 6)), ..targets };
+    assert_eq!(choose_target(Tactics::Defensive, pos, &far_targets, &r),
+               TacticsTarget::Toward(Vec2d(2, 6)));
+    // Aggressive companion pursues the distant enemy
+    assert_eq!(choose_target(Tactics::Aggressive, pos, &targets, &r),
+               TacticsTarget::Toward(Vec2d(10, 2)));
+    // Hold and support companions don't chase
+    assert_eq!(choose_target(Tactics::Hold, pos, &targets, &r), TacticsTarget::Stay);
+    assert_eq!(choose_target(Tactics::Support, pos, &targets, &r), TacticsTarget::Stay);
+}
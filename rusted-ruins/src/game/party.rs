@@ -0,0 +1,161 @@
+
+//! Companions in the player's party
+
+use common::gamedata::*;
+use crate::game::InfoGetter;
+use super::map::MapEx;
+
+/// The character joins the player's party. Returns the new id of the character.
+pub fn join_party(gd: &mut GameData, cid: CharaId) -> Option<CharaId> {
+    let new_cid = gd.join_party(cid)?;
+    let chara = gd.chara.get_mut(new_cid);
+    chara.faction = Faction::Player;
+    chara.rel = Relationship::ALLY;
+    // Talk scripts are for the original role, e.g. shopkeepers need their OnSite ids
+    chara.trigger_talk = None;
+
+    // Companions act by their tactics instead of needs and schedules
+    let ai = &mut chara.ai;
+    ai.kind = NpcAIKind::Melee;
+    ai.tactics = Some(Tactics::Defensive);
+    ai.needs = None;
+    ai.home = None;
+    ai.schedule.clear();
+    ai.last_seen = None;
+    ai.order = PartyOrder::Follow;
+    game_log_i!("join-party"; chara=chara);
+    Some(new_cid)
+}
+
+/// Party members on the current map, in the order they joined
+pub fn members_on_map(gd: &GameData) -> Vec<CharaId> {
+    gd.chara.party().into_iter().filter(|&cid| gd.chara_pos(cid).is_some()).collect()
+}
+
+/// Remove party members following the player from the current map.
+/// Called before switching maps. Members ordered to wait stay on the map.
+pub fn leave_map(gd: &mut GameData) -> Vec<CharaId> {
+    let followers: Vec<CharaId> = members_on_map(gd).into_iter()
+        .filter(|&cid| gd.chara.get(cid).ai.order != PartyOrder::Wait)
+        .collect();
+    for &cid in &followers {
+        gd.remove_party_member_from_map(cid);
+        // Attack targets are left on the previous map
+        gd.chara.get_mut(cid).ai.order = PartyOrder::Follow;
+    }
+    followers
+}
+
+/// Locate the followers on the nearest empty tiles around the player
+pub fn arrive_map(gd: &mut GameData, followers: &[CharaId]) {
+    let center = if let Some(pos) = gd.chara_pos(CharaId::Player) { pos } else { return; };
+    for &cid in followers {
+        let pos = {
+            let map = gd.get_current_map();
+            let chara = gd.chara.get(cid);
+            map.tile.iter_idx()
+                .filter(|&p| map.tile[p].chara.is_none() && map.is_passable(chara, p))
+                .min_by_key(|&p| p.mdistance(center))
+        };
+        if let Some(pos) = pos {
+            gd.get_current_map_mut().locate_chara(cid, pos);
+        } else {
+            warn!("No space to locate the party member {:?}", cid);
+        }
+    }
+}
+
+/// Give the order to the party member
+pub fn set_order(gd: &mut GameData, cid: CharaId, order: PartyOrder) {
+    let chara = gd.chara.get_mut(cid);
+    chara.ai.order = order;
+    match order {
+        PartyOrder::Follow => game_log_i!("party-follow"; chara=chara),
+        PartyOrder::Wait => game_log_i!("party-wait"; chara=chara),
+        PartyOrder::Attack(_) => game_log_i!("party-attack"; chara=chara),
+    }
+}
+
+/// Move all items at the location to another character's inventory
+pub fn hand_item(gd: &mut GameData, il: ItemLocation, from: CharaId, to: CharaId) {
+    let n = gd.get_item_list(il.0).get_number(il.1);
+    let item = gd.remove_item_and_get(il, n);
+    game_log_i!("hand-item"; chara=gd.chara.get(from), target=gd.chara.get(to), item=item, n=n);
+    gd.get_item_list_mut(ItemListLocation::Chara { cid: to }).append(item, n);
+}
+
+#[cfg(test)]
+fn test_party_game_data() -> (GameData, CharaId) {
+    use array2d::Vec2d;
+    super::script::init_test_objs();
+
+    let mut gd = GameData::empty();
+    let rid = gd.region.add_region(Region::new("test-region", Map::new(5, 5), 0));
+    let mid = MapId::RegionMap { rid };
+    gd.set_initial_mapid(mid);
+    let name = |name: &str| Some(name.to_owned());
+    let player = gd.add_chara(Chara { name: name("player"), ..Chara::default() }, CharaKind::Player);
+    gd.get_current_map_mut().locate_chara(player, Vec2d(0, 0));
+    let npc = Chara {
+        name: name("npc"), faction: Faction::Citizen, trigger_talk: Some("shop".to_owned()),
+        ..Chara::default()
+    };
+    let npc = gd.add_chara_to_map(npc, mid);
+    gd.get_current_map_mut().locate_chara(npc, Vec2d(1, 0));
+    (gd, npc)
+}
+
+#[test]
+fn join_party_test() {
+    use array2d::Vec2d;
+    let (mut gd, npc) = test_party_game_data();
+
+    let cid = join_party(&mut gd, npc).unwrap();
+    assert_eq!(cid, CharaId::Party { n: 0 });
+    assert_eq!(gd.chara_pos(cid), Some(Vec2d(1, 0)));
+    let chara = gd.chara.get(cid);
+    assert_eq!(chara.faction, Faction::Player);
+    assert_eq!(chara.trigger_talk, None);
+    assert_eq!(chara.ai.order, PartyOrder::Follow);
+    assert_eq!(members_on_map(&gd), vec![cid]);
+    // The player and party members cannot join again
+    assert_eq!(join_party(&mut gd, CharaId::Player), None);
+    assert_eq!(join_party(&mut gd, cid), None);
+}
+
+#[test]
+fn leave_and_arrive_map_test() {
+    use array2d::Vec2d;
+    let (mut gd, npc) = test_party_game_data();
+    let follower = join_party(&mut gd, npc).unwrap();
+    let mid = gd.get_current_mapid();
+    let waiting = Chara { name: Some("waiting".to_owned()), ..Chara::default() };
+    let waiting = gd.add_chara_to_map(waiting, mid);
+    gd.get_current_map_mut().locate_chara(waiting, Vec2d(4, 4));
+    let waiting = join_party(&mut gd, waiting).unwrap();
+    set_order(&mut gd, waiting, PartyOrder::Wait);
+
+    let followers = leave_map(&mut gd);
+    assert_eq!(followers, vec![follower]);
+    assert_eq!(gd.chara_pos(follower), None);
+    assert_eq!(gd.chara_pos(waiting), Some(Vec2d(4, 4)));
+
+    // Followers are located next to the player
+    gd.get_current_map_mut().locate_chara(CharaId::Player, Vec2d(2, 2));
+    arrive_map(&mut gd, &followers);
+    assert_eq!(gd.chara_pos(follower).unwrap().mdistance(Vec2d(2, 2)), 1);
+}
+
+#[test]
+fn hand_item_test() {
+    let (mut gd, npc) = test_party_game_data();
+    let member = join_party(&mut gd, npc).unwrap();
+    let idx: common::objholder::ItemIdx = common::gobj::id_to_idx("test-item");
+    let player_ill = ItemListLocation::Chara { cid: CharaId::Player };
+    gd.get_item_list_mut(player_ill).append(super::item::gen::gen_item_from_idx(idx), 3);
+
+    hand_item(&mut gd, (player_ill, 0), CharaId::Player, member);
+    assert!(gd.get_item_list(player_ill).is_empty());
+    let member_list = gd.get_item_list(ItemListLocation::Chara { cid: member });
+    assert_eq!(member_list.get_number(0), 3);
+}
@@ -0,0 +1,101 @@
+//! Route search on maps used for NPC movement
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use array2d::*;
+use common::gamedata::*;
+use super::map::MapEx;
+use super::npc::map_search::dir_2pos;
+
+/// Search the shortest route from `from` to `to` on the map by A*.
+/// Returns the directions to move along the route.
+/// The destination is always treated as reachable because it is usually occupied by the target.
+/// Tiles are passable or not by the movements of the character.
+pub fn route(map: &Map, chara: &Chara, from: Vec2d, to: Vec2d) -> Option<Vec<Direction>> {
+    route_by(map.size(), from, to, |pos| map.is_passable(chara, pos))
+}
+
+/// The first direction of the route to the destination.
+/// If there is no route, returns the direction toward the destination.
+pub fn first_step(map: &Map, chara: &Chara, from: Vec2d, to: Vec2d) -> Direction {
+    route(map, chara, from, to)
+        .and_then(|route| route.first().cloned())
+        .unwrap_or_else(|| dir_2pos(from, to))
+}
+
+/// Search the shortest route on the area of given size.
+/// `is_passable` decides the tile can be stepped on or not.
+pub fn route_by<F>(size: (u32, u32), from: Vec2d, to: Vec2d, is_passable: F) -> Option<Vec<Direction>>
+where F: Fn(Vec2d) -> bool {
+
+    let mut cost: Array2d<u32> = Array2d::new(size.0, size.1, u32::max_value());
+    let mut prev: Array2d<Option<Direction>> = Array2d::new(size.0, size.1, None);
+
+    if !cost.in_range(from) || !cost.in_range(to) {
+        return None;
+    }
+
+    let mut open = BinaryHeap::new();
+    cost[from] = 0;
+    open.push(Reverse((heuristic(from, to), 0, from.0, from.1)));
+
+    while let Some(Reverse((_, c, x, y))) = open.pop() {
+        let pos = Vec2d(x, y);
+        if pos == to {
+            return Some(trace_back(&prev, from, to));
+        }
+        if c > cost[pos] {
+            continue;
+        }
+
+        for &dir in &Direction::EIGHT_DIRS {
+            let next = pos + dir.as_vec();
+            if !cost.in_range(next) || (next != to && !is_passable(next)) {
+                continue;
+            }
+            let next_cost = c + 1;
+            if next_cost < cost[next] {
+                cost[next] = next_cost;
+                prev[next] = Some(dir);
+                open.push(Reverse((next_cost + heuristic(next, to), next_cost, next.0, next.1)));
+            }
+        }
+    }
+    None
+}
+
+/// Steps needed to move between two tiles when diagonal moves are allowed
+fn heuristic(a: Vec2d, b: Vec2d) -> u32 {
+    std::cmp::max((a.0 - b.0).abs(), (a.1 - b.1).abs()) as u32
+}
+
+fn trace_back(prev: &Array2d<Option<Direction>>, from: Vec2d, to: Vec2d) -> Vec<Direction> {
+    let mut route = Vec::new();
+    let mut pos = to;
+    while pos != from {
+        let dir = prev[pos].unwrap();
+        route.push(dir);
+        pos = pos - dir.as_vec();
+    }
+    route.reverse();
+    route
+}
+
+#[test]
+fn route_test() {
+    // Wall at x = 2 except y = 4
+    let is_passable = |pos: Vec2d| pos.0 != 2 || pos.1 == 4;
+
+    let route = route_by((5, 5), Vec2d(0, 0), Vec2d(4, 0), is_passable).unwrap();
+    let mut pos = Vec2d(0, 0);
+    for dir in &route {
+        pos = pos + dir.as_vec();
+        assert!(is_passable(pos));
+    }
+    assert_eq!(pos, Vec2d(4, 0));
+    assert_eq!(route.len(), 8);
+
+    assert_eq!(route_by((5, 5), Vec2d(1, 1), Vec2d(1, 1), is_passable), Some(vec![]));
+    // Blocked completely
+    assert_eq!(route_by((5, 5), Vec2d(0, 0), Vec2d(4, 0), |pos| pos.0 != 2), None);
+}
@@ -4,7 +4,10 @@ use super::Game;
 use super::action;
 use crate::text::ToText;
 use common::gamedata::*;
+use common::gobj;
+use common::objholder::{AbilityIdx, RecipeIdx};
 use crate::game::{InfoGetter, DialogOpenRequest, AdvanceScriptResult};
+use crate::game::chara::relation_between;
 
 /// Player actions are processed through this.
 /// Mutable access to Game or GameData is limited by this wrapper.
@@ -33,10 +36,9 @@ impl<'a> DoPlayerAction<'a> {
         let will_talk = {
             if dir.as_vec() != (0, 0) {
                 let gd = self.gd();
-                let player_chara = gd.chara.get(CharaId::Player);
-                if let Some(other_chara) = gd.get_current_map().get_chara(dest_tile) {
-                    let other_chara = gd.chara.get(other_chara);
-                    match player_chara.rel.relative(other_chara.rel) {
+                if let Some(other_cid) = gd.get_current_map().get_chara(dest_tile) {
+                    let other_chara = gd.chara.get(other_cid);
+                    match relation_between(gd, CharaId::Player, other_cid) {
                         Relationship::ALLY | Relationship::FRIENDLY => {
                             if !other_chara.trigger_talk.is_none() {
                                 true
@@ -68,6 +70,24 @@ impl<'a> DoPlayerAction<'a> {
         }
     }
 
+    /// Take one step toward the destination. Used to travel by mouse clicks.
+    /// Returns false if the travel should be stopped.
+    pub fn travel_step(&mut self, dest: Vec2d) -> bool {
+        let pos = self.gd().player_pos();
+        if pos == dest {
+            return false;
+        }
+        let dir = super::pathfinding::route(
+            self.gd().get_current_map(), self.gd().chara.get(CharaId::Player), pos, dest)
+            .and_then(|route| route.first().cloned());
+        let dir = if let Some(dir) = dir { dir } else { return false; };
+        self.try_move(dir);
+
+        // Stop if the player couldn't move or enemies come into sight
+        self.gd().player_pos() != pos && crate::game::map::search::search_nearest_target(
+            self.gd(), CharaId::Player, Relationship::HOSTILE).is_none()
+    }
+
     /// Try to go to next floor
     /// This function will be called when players use stairs or try to exit from map boundaries.
     /// In the latter case, dir is not None and represents player's move direction.
@@ -197,12 +217,39 @@ impl<'a> DoPlayerAction<'a> {
         }
     }
 
+    /// Use the ability. Abilities of range zero target the player.
+    /// Other abilities target the current target, or the nearest enemy for damaging ones.
+    pub fn use_ability(&mut self, idx: AbilityIdx) {
+        let ability: &AbilityObject = gobj::get_obj(idx);
+        if ability.range > 0 && self.0.target_chara.is_none() && ability.effect == AbilityEffect::Damage {
+            self.0.target_chara = crate::game::map::search::search_nearest_target(
+                self.gd(), CharaId::Player, Relationship::HOSTILE);
+        }
+        let target = if ability.range == 0 {
+            CharaId::Player
+        } else if let Some(target) = self.0.target_chara {
+            target
+        } else if ability.effect == AbilityEffect::Damage {
+            game_log_i!("ability-no-target");
+            return;
+        } else {
+            CharaId::Player
+        };
+
+        if super::ability::use_ability(&mut self.0, CharaId::Player, idx, target) {
+            self.0.finish_player_turn();
+        }
+    }
+
     /// Pick up an item on tile
     pub fn pick_up_item(&mut self, il: ItemLocation, n: u32) -> bool {
+        use super::quest::{process_quest_event, QuestEvent};
         let gd = self.gd_mut();
         let player_item_list_location = ItemListLocation::Chara { cid: CharaId::Player };
         game_log_i!("item-pickup"; chara=gd.chara.get(CharaId::Player), item=gd.get_item(il).0);
+        let idx = gd.get_item(il).0.idx;
         gd.move_item(il, player_item_list_location, n);
+        process_quest_event(gd, QuestEvent::PickUp(idx, n));
         true
     }
 
@@ -218,18 +265,157 @@ impl<'a> DoPlayerAction<'a> {
         true
     }
 
+    /// Put items into a container
+    pub fn put_in_container(&mut self, il: ItemLocation, container: (ContainerLocation, u32), n: u32)
+                            -> bool {
+        super::item::container::put_in(self.gd_mut(), il, container, n)
+    }
+
+    /// Take items out of a container into the player's inventory
+    pub fn take_out_from_container(&mut self, il: ItemLocation, n: u32) -> bool {
+        let gd = self.gd_mut();
+        let player_item_list_location = ItemListLocation::Chara { cid: CharaId::Player };
+        game_log_i!("item-take-out"; chara=gd.chara.get(CharaId::Player), item=gd.get_item(il).0);
+        gd.move_item(il, player_item_list_location, n);
+        true
+    }
+
+    /// Craft an item by the recipe
+    pub fn craft(&mut self, idx: RecipeIdx) {
+        if super::craft::craft(self.gd_mut(), CharaId::Player, idx) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Close an open door around the player
+    pub fn close_door(&mut self) {
+        if super::door::close_door(self.gd_mut(), CharaId::Player) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Disarm a detected trap around the player
+    pub fn disarm_trap(&mut self) {
+        if super::trap::disarm(self.0) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Harvest the resource on the player's tile
+    pub fn harvest(&mut self) {
+        let pos = self.gd().player_pos();
+        if super::harvest::harvest(self.gd_mut(), CharaId::Player, pos) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Examine the player's tile, and start the script of the event there
+    pub fn examine(&mut self) {
+        if self.0.script.is_some() {
+            return;
+        }
+        let pos = self.gd().player_pos();
+        if let Some(script_id) = self.gd_mut().get_current_map_mut().examine_event(pos) {
+            self.0.start_event_script(&script_id);
+        }
+    }
+
+    /// Save the game with the description shown in the save file list
+    pub fn save_game(&mut self, description: &str) {
+        self.gd_mut().meta.set_description(description);
+        self.0.save_file();
+    }
+
+    /// Give a new name to the character. Empty names are ignored.
+    pub fn rename_chara(&mut self, cid: CharaId, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        self.gd_mut().chara.get_mut(cid).name = Some(name.to_owned());
+    }
+
+    /// Execute a command line input in the debug console
+    pub fn exec_console_command(&mut self, line: &str) -> Result<(), String> {
+        super::console::exec(self.0, line)
+    }
+
     /// Drink one item
     pub fn drink_item(&mut self, il: ItemLocation) {
         super::action::drink_item(self.gd_mut(), il, CharaId::Player);
         self.0.finish_player_turn();
     }
 
+    /// Throw one item at the current target, or the nearest enemy if there is no target
+    pub fn throw_item(&mut self, il: ItemLocation) {
+        if self.0.target_chara.is_none() {
+            self.0.target_chara = crate::game::map::search::search_nearest_target(
+                self.gd(), CharaId::Player, Relationship::HOSTILE);
+        }
+
+        if let Some(target) = self.0.target_chara {
+            if super::action::throw_item(&mut self.0, CharaId::Player, il, target) {
+                self.0.finish_player_turn();
+            }
+        } else {
+            game_log_i!("throw-no-target");
+        }
+    }
+
+    /// Push the adjacent character, or swap positions with them
+    pub fn push_chara(&mut self, dir: Direction) {
+        if super::action::push_chara(&mut self.0, CharaId::Player, dir) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Dig the wall or resource in the direction
+    pub fn dig(&mut self, dir: Direction) {
+        if let Some(turns) = super::dig::dig(self.gd_mut(), CharaId::Player, dir) {
+            self.spend_turns(turns);
+        }
+    }
+
+    /// Dig down to the floor below
+    pub fn dig_down(&mut self) {
+        if let Some(turns) = super::dig::dig_down(self.gd_mut(), CharaId::Player) {
+            self.spend_turns(turns);
+        }
+    }
+
+    /// Finish the player turn that takes longer than usual
+    fn spend_turns(&mut self, turns: u32) {
+        self.gd_mut().chara.get_mut(CharaId::Player).wait_time *= turns;
+        self.0.finish_player_turn();
+    }
+
+    /// Ride the horse or boat
+    pub fn ride(&mut self, il: ItemLocation) {
+        if super::mount::ride(self.gd_mut(), CharaId::Player, il) {
+            self.0.finish_player_turn();
+        }
+    }
+
+    /// Get off the horse or boat
+    pub fn dismount(&mut self) {
+        if super::mount::dismount(self.gd_mut(), CharaId::Player) {
+            self.0.finish_player_turn();
+        }
+    }
+
     /// Eat one item
     pub fn eat_item(&mut self, il: ItemLocation) {
         super::action::eat_item(self.gd_mut(), il, CharaId::Player);
         self.0.finish_player_turn();
     }
 
+    /// Read one item
+    pub fn read_item(&mut self, il: ItemLocation) {
+        if super::action::read_item(self.gd_mut(), il, CharaId::Player) {
+            self.0.finish_player_turn();
+        }
+    }
+
     /// Buy item
     pub fn buy_item(&mut self, il: ItemLocation) {
         super::shop::buy_item(self.gd_mut(), il);
@@ -245,6 +431,37 @@ impl<'a> DoPlayerAction<'a> {
         super::item::change_equipment(self.gd_mut(), cid, slot, il)
     }
 
+    /// Remove specified character's equipment
+    pub fn remove_equipment(&mut self, cid: CharaId, slot: (EquipSlotKind, u8)) {
+        super::item::remove_equipment(self.gd_mut(), cid, slot)
+    }
+
+    /// Give the order to the party member
+    pub fn order_party_member(&mut self, cid: CharaId, order: PartyOrder) {
+        super::party::set_order(self.gd_mut(), cid, order);
+    }
+
+    /// Order the party member to attack the current target
+    pub fn order_attack_target(&mut self, cid: CharaId) {
+        let target = self.0.target_chara
+            .filter(|&target| relation_between(self.gd(), CharaId::Player, target) == Relationship::HOSTILE);
+        if let Some(target) = target {
+            super::party::set_order(self.gd_mut(), cid, PartyOrder::Attack(target));
+        } else {
+            game_log_i!("party-no-target");
+        }
+    }
+
+    /// Give the player's item to the party member
+    pub fn give_item(&mut self, il: ItemLocation, cid: CharaId) {
+        super::party::hand_item(self.gd_mut(), il, CharaId::Player, cid);
+    }
+
+    /// Take the item from the party member
+    pub fn take_item(&mut self, il: ItemLocation, cid: CharaId) {
+        super::party::hand_item(self.gd_mut(), il, cid, CharaId::Player);
+    }
+
     /// Try talk to next chara
     /// If success, returns id of the talk script
     pub fn try_talk(&mut self, dir: Direction) {
@@ -254,12 +471,11 @@ impl<'a> DoPlayerAction<'a> {
         let mut cid = None;
         {
             let gd = self.gd();
-            let player_chara = gd.chara.get(CharaId::Player);
             let dest_tile = gd.get_current_map().chara_pos(CharaId::Player).unwrap() + dir.as_vec();
-            if let Some(other_chara) = gd.get_current_map().get_chara(dest_tile) {
-                cid = Some(other_chara);
-                let other_chara = gd.chara.get(other_chara);
-                match player_chara.rel.relative(other_chara.rel) {
+            if let Some(other_cid) = gd.get_current_map().get_chara(dest_tile) {
+                cid = Some(other_cid);
+                let other_chara = gd.chara.get(other_cid);
+                match relation_between(gd, CharaId::Player, other_cid) {
                     Relationship::ALLY | Relationship::FRIENDLY => {
                         if let Some(ref t) = other_chara.trigger_talk {
                             trigger_talk = Some(t.clone())
@@ -299,3 +515,55 @@ pub fn msg_switch_map(next_mid: MapId) -> std::borrow::Cow<'static, str> {
     }
 }
 
+
+#[test]
+fn examine_test() {
+    use super::script::{requested_talk, test_game};
+    let mut game = test_game(2, 1);
+    let event = |top_left, once| MapEvent {
+        top_left,
+        bottom_right: top_left,
+        script_id: "test-event-talk".to_owned(),
+        once,
+        trigger: MapEventTrigger::Examine,
+    };
+    let talk = Some((CharaId::Player, "test-event-text".to_owned()));
+    game.gd.get_current_map_mut().events.push(event(Vec2d(1, 0), true));
+    game.gd.get_current_map_mut().events.push(event(Vec2d(0, 0), false));
+
+    // Repeatable events stay on the map
+    for _ in 0..2 {
+        DoPlayerAction::new(&mut game).examine();
+        assert_eq!(requested_talk(&mut game), talk);
+        game.script = None;
+    }
+    assert_eq!(game.gd.get_current_map().events.len(), 2);
+
+    game.gd.get_current_map_mut().locate_chara(CharaId::Player, Vec2d(1, 0));
+    DoPlayerAction::new(&mut game).examine();
+    assert_eq!(requested_talk(&mut game), talk);
+    game.script = None;
+    DoPlayerAction::new(&mut game).examine();
+    assert_eq!(requested_talk(&mut game), None);
+    assert_eq!(game.gd.get_current_map().events.len(), 1);
+}
+
+#[test]
+fn rename_and_save_description_test() {
+    let mut game = super::script::test_game(1, 1);
+    {
+        let mut pa = DoPlayerAction::new(&mut game);
+        pa.rename_chara(CharaId::Player, "  hero ");
+        // Empty names are ignored
+        pa.rename_chara(CharaId::Player, "   ");
+    }
+    assert_eq!(game.gd.chara.get(CharaId::Player).name.as_ref().map(|s| s.as_str()), Some("hero"));
+
+    // The description is saved in the metadata read by the save file list
+    let dir = std::env::temp_dir().join("rusted-ruins-save-description-test");
+    game.gd.meta.set_description(" before the boss\n");
+    game.gd.save(&dir).unwrap();
+    let meta = GameData::load_metadata(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(meta.description(), "before the boss");
+}
@@ -1,14 +1,16 @@
 //! Quest handlings
 
 use common::gamedata::*;
-use common::objholder::CharaTemplateIdx;
+use common::objholder::{CharaTemplateIdx, ItemIdx};
 use rules::RULES;
 use super::Game;
 use super::chara::gen::choose_npc_chara_template;
+use super::item::gen::choose_item_by_floor_level;
 
 /// Update quest list of current town
 pub fn update_town_quest(gd: &mut GameData) {
     let mid = gd.get_current_mapid();
+    let quest = gen_quest(gd);
     let town = match gd.region.get_site_mut(mid.sid()).content {
         SiteContent::Town { ref mut town } => town,
         _ => unreachable!(),
@@ -16,7 +18,7 @@ pub fn update_town_quest(gd: &mut GameData) {
 
     town.quests.clear();
 
-    town.quests.push(gen_quest());
+    town.quests.push(quest);
 }
 
 /// Returns available quest in the current town
@@ -42,13 +44,49 @@ pub fn undertake_quest(game: &mut Game, i: u32) {
     game.gd.quest.start_new_quest(quest);
 }
 
+/// Fail script quests whose deadline has passed, and execute their on-fail scripts
+pub fn check_deadlines(game: &mut Game) {
+    let now = game.gd.time.current_time();
+    // The remaining overdue quests are failed after the started script finishes
+    while game.script.is_none() {
+        let (quest_id, on_fail) = if let Some(failed) = game.gd.quest.fail_overdue_quest(now) {
+            failed
+        } else {
+            return;
+        };
+        game_log_i!("quest-failed"; quest=quest_id);
+        if let Some(script_id) = on_fail {
+            game.start_event_script(&script_id);
+        }
+    }
+}
+
 /// Generate an quest
-fn gen_quest() -> Quest {
+fn gen_quest(gd: &GameData) -> Quest {
     let reward = Reward {
         money: 1000,
         item: Vec::new(),
     };
 
+    match rng::gen_range(0, 3) {
+        1 => {
+            return Quest::CollectItems {
+                reward,
+                idx: choose_item_by_floor_level(1, |_| 1.0, false),
+                goal: rng::gen_range(1, 4),
+                collected: 0,
+            };
+        }
+        2 => {
+            let destinations = quest_destinations(gd, gd.get_current_mapid().sid().rid);
+            if !destinations.is_empty() {
+                let sid = destinations[rng::gen_range(0, destinations.len())];
+                return Quest::ReachSite { reward, sid, reached: false };
+            }
+        }
+        _ => (),
+    }
+
     Quest::SlayMonsters {
         reward, 
         idx: choose_npc_chara_template(
@@ -59,24 +97,70 @@ fn gen_quest() -> Quest {
     }
 }
 
-pub fn count_slayed_monster(gd: &mut GameData, t: CharaTemplateIdx) {
+/// Dungeons in the region that reach site quests can target
+fn quest_destinations(gd: &GameData, rid: RegionId) -> Vec<SiteId> {
+    let region = gd.region.get(rid);
+    let kind = SiteKind::AutoGenDungeon;
+    (0..region.get_site_n(kind))
+        .map(|n| SiteId { rid, kind, n })
+        .filter(|&sid| gd.region.get_site_checked(sid).is_some())
+        .collect()
+}
+
+/// Game events that advance quest objectives
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuestEvent {
+    /// A monster is killed
+    Kill(CharaTemplateIdx),
+    /// Player picked up items
+    PickUp(ItemIdx, u32),
+    /// Player entered a site
+    EnterSite(SiteId),
+}
 
+/// Update objectives of active quests by the event.
+/// Quests whose objective is met become completed.
+pub fn process_quest_event(gd: &mut GameData, event: QuestEvent) {
     for (state, quest) in gd.quest.iter_mut() {
-        match quest {
-            Quest::SlayMonsters { idx, goal, killed, .. } => {
-                if *state == QuestState::Active && *idx == t {
-                    *killed += 1;
-                    if *killed == *goal {
-                        *state = QuestState::Completed;
-                        // Log
-                        game_log_i!("quest-complete-slay_monsters"; monster=idx, n=goal);
-                    }
+        if *state != QuestState::Active {
+            continue;
+        }
+        if advance_objective(quest, event) {
+            *state = QuestState::Completed;
+            match quest {
+                Quest::SlayMonsters { idx, goal, .. } => {
+                    game_log_i!("quest-complete-slay_monsters"; monster=idx, n=goal);
+                }
+                _ => {
+                    game_log_i!("quest-complete"; quest=quest);
                 }
             }
         }
     }
 }
 
+/// Advance the objective progress by the event.
+/// Returns true if the objective has been met by this event.
+pub fn advance_objective(quest: &mut Quest, event: QuestEvent) -> bool {
+    if quest.is_objective_met() {
+        return false;
+    }
+
+    match (&mut *quest, event) {
+        (Quest::SlayMonsters { idx, killed, .. }, QuestEvent::Kill(t)) if *idx == t => {
+            *killed += 1;
+        }
+        (Quest::CollectItems { idx, collected, .. }, QuestEvent::PickUp(t, n)) if *idx == t => {
+            *collected += n;
+        }
+        (Quest::ReachSite { sid, reached, .. }, QuestEvent::EnterSite(t)) if *sid == t => {
+            *reached = true;
+        }
+        _ => { return false; }
+    }
+    quest.is_objective_met()
+}
+
 pub fn receive_rewards(gd: &mut GameData) -> bool {
     let mut money = 0;
     let mut exist_completed_quest = false;
@@ -98,3 +182,79 @@ pub fn receive_rewards(gd: &mut GameData) -> bool {
     exist_completed_quest
 }
 
+
+#[test]
+fn slay_monsters_objective_test() {
+    let target = CharaTemplateIdx::from_usize(1);
+    let other = CharaTemplateIdx::from_usize(2);
+    let mut quest = Quest::SlayMonsters {
+        reward: Reward { money: 100, item: Vec::new() },
+        idx: target,
+        goal: 3,
+        killed: 0,
+    };
+
+    assert!(!advance_objective(&mut quest, QuestEvent::Kill(target)));
+    assert!(!advance_objective(&mut quest, QuestEvent::Kill(other)));
+    assert!(!advance_objective(&mut quest, QuestEvent::PickUp(ItemIdx::from_usize(1), 1)));
+    assert!(!advance_objective(&mut quest, QuestEvent::Kill(target)));
+    assert_eq!(quest.progress(), (2, 3));
+    assert!(advance_objective(&mut quest, QuestEvent::Kill(target)));
+    assert_eq!(quest.progress(), (3, 3));
+    // Completed objective is not advanced any more
+    assert!(!advance_objective(&mut quest, QuestEvent::Kill(target)));
+    assert_eq!(quest.progress(), (3, 3));
+}
+
+#[test]
+fn quest_deadline_test() {
+    let mut quests = QuestHolder::new();
+    let on_fail = Some("rescue-failed".to_owned());
+    assert!(quests.start_script_quest("rescue", Some(Time::from_seconds(100)), on_fail.clone(), None));
+    assert!(quests.start_script_quest("main", None, None, None));
+
+    assert!(quests.start_script_quest("escort", Some(Time::from_seconds(50)), None, None));
+
+    assert_eq!(quests.fail_overdue_quest(Time::from_seconds(50)), None);
+    // Overdue quests are failed one by one
+    assert_eq!(quests.fail_overdue_quest(Time::from_seconds(101)), Some(("escort".to_owned(), None)));
+    assert_eq!(quests.script_quest_state("rescue"), ScriptQuestState::Active);
+    assert_eq!(quests.fail_overdue_quest(Time::from_seconds(101)), Some(("rescue".to_owned(), on_fail)));
+    assert_eq!(quests.script_quest_state("rescue"), ScriptQuestState::Failed);
+    assert_eq!(quests.script_quest_state("main"), ScriptQuestState::Active);
+    // Failed quests are not failed again, and cannot be completed
+    assert_eq!(quests.fail_overdue_quest(Time::from_seconds(200)), None);
+    assert!(!quests.complete_script_quest("rescue"));
+}
+
+#[test]
+fn check_deadlines_test() {
+    let mut game = super::script::test_game(1, 1);
+    let deadline = Some(Time::from_seconds(100));
+    let on_fail = Some("test-event-talk".to_owned());
+    game.gd.quest.start_script_quest("rescue", deadline, on_fail, None);
+    game.gd.quest.start_script_quest("transport", deadline, None, None);
+    game.gd.time = GameTime::new(1, 1, 1, 1);
+    assert!(game.gd.time.current_time() > Time::from_seconds(100));
+
+    // The on-fail script talks, and the other quest is failed after the script
+    check_deadlines(&mut game);
+    assert_eq!(super::script::requested_talk(&mut game),
+               Some((CharaId::Player, "test-event-text".to_owned())));
+    assert_eq!(game.gd.quest.script_quest_state("rescue"), ScriptQuestState::Failed);
+    assert_eq!(game.gd.quest.script_quest_state("transport"), ScriptQuestState::Active);
+    game.script = None;
+    check_deadlines(&mut game);
+    assert_eq!(game.gd.quest.script_quest_state("transport"), ScriptQuestState::Failed);
+}
+
+#[test]
+fn quest_destinations_test() {
+    use array2d::Vec2d;
+    let mut gd = GameData::empty();
+    let rid = gd.region.add_region(Region::new("test-region", Map::new(1, 1), 0));
+    let region = gd.region.get_mut(rid);
+    let dungeon = region.add_site(Site::new(1), SiteKind::AutoGenDungeon, Vec2d(0, 0)).unwrap();
+    region.add_site(Site::new(1), SiteKind::Town, Vec2d(1, 0)).unwrap();
+    assert_eq!(quest_destinations(&gd, rid), vec![dungeon]);
+}
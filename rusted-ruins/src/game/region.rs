@@ -10,15 +10,17 @@ use rng::*;
 pub fn add_region(gd: &mut GameData, id: &str) {
     let rg: &RegionGenObject = gobj::get_by_id(id);
     
-    let map = if let Some(map) = super::map::from_template::from_template_id(&rg.id) {
-        map
+    let t = if let Some(t) = gobj::get_by_id_checked(&rg.id) {
+        t
     } else {
         error!("Map generation failed from \"{}\"", rg.id);
         panic!();
     };
+    let map = super::map::from_template::from_template(t);
     
     let region = Region::new(id, map, gen_box_id(gd));
     let rid = gd.region.add_region(region);
+    super::map::from_template::gen_charas(gd, MapId::from(rid), t);
     add_sites_from_genobj(gd, rg, rid);
 }
 
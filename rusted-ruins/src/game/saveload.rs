@@ -1,13 +1,17 @@
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use common::basic::{SAVE_EXTENSION, SAVE_DIR_NAME};
 use common::gamedata::GameData;
-use crate::config::USER_DIR;
+use crate::config::{CONFIG, USER_DIR};
 use crate::game::{Game, InfoGetter};
 
+/// Auto-saves are written in this directory under the save directory
+const AUTOSAVE_DIR_NAME: &str = "autosave";
+
 impl Game {
-    pub fn save_file(&self) {
+    pub fn save_file(&mut self) {
         let save_dir = get_save_dir();
 
         if !save_dir.exists() {
@@ -21,12 +25,58 @@ impl Game {
         }
 
         let path = self.gd.save_dir(save_dir);
+        self.gd.rng_state = Some(rng::state());
         
         match self.gd.save(&path) {
             Ok(_) => info!("Saved to {:?}", path.to_string_lossy()),
             Err(e) => warn!("Faild to saving to {:?}: {}", path.to_string_lossy(), e),
         }
     }
+
+    /// Called every player turn. Auto-save if the interval in config has passed,
+    /// or the player has moved to another map.
+    pub fn check_autosave(&mut self) {
+        self.turns_since_autosave += 1;
+        let interval = CONFIG.autosave_turns;
+        if (interval > 0 && self.turns_since_autosave >= interval) || self.autosave_requested {
+            self.autosave();
+        }
+    }
+
+    /// Write to the oldest auto-save slot. The manual save and its maps are not changed.
+    fn autosave(&mut self) {
+        self.turns_since_autosave = 0;
+        self.autosave_requested = false;
+        if CONFIG.autosave_slots == 0 {
+            return;
+        }
+
+        let slots: Vec<PathBuf> = (1..=CONFIG.autosave_slots)
+            .map(|slot| get_autosave_dir().join(
+                format!("{}-{}.{}", self.gd.meta.save_name(), slot, SAVE_EXTENSION)))
+            .collect();
+        let times: Vec<Option<SystemTime>> = slots.iter().map(|path| modified_time(path)).collect();
+        let path = &slots[oldest_slot(&times)];
+        self.gd.rng_state = Some(rng::state());
+
+        match self.gd.save_copy(path, get_map_dir(&self.gd)) {
+            Ok(_) => info!("Auto-saved to {:?}", path.to_string_lossy()),
+            Err(e) => warn!("Faild to auto-saving to {:?}: {}", path.to_string_lossy(), e),
+        }
+    }
+}
+
+/// The slot that has not been written, or was written least recently
+fn oldest_slot(times: &[Option<SystemTime>]) -> usize {
+    if let Some(i) = times.iter().position(|t| t.is_none()) {
+        return i;
+    }
+    (0..times.len()).min_by_key(|&i| times[i]).unwrap_or(0)
+}
+
+/// Modified time of the save. Directories are not updated when files in them are overwritten.
+fn modified_time(save_dir: &Path) -> Option<SystemTime> {
+    fs::metadata(save_dir.join("gamedata")).and_then(|m| m.modified()).ok()
 }
 
 pub fn save_file_list() -> Result<Vec<PathBuf>, std::io::Error> {
@@ -51,12 +101,75 @@ pub fn save_file_list() -> Result<Vec<PathBuf>, std::io::Error> {
     Ok(list)
 }
 
+/// Auto-saves of all games, the newest first
+pub fn autosave_file_list() -> Vec<PathBuf> {
+    let entries = if let Ok(entries) = fs::read_dir(get_autosave_dir()) { entries } else {
+        return Vec::new();
+    };
+    let mut list: Vec<(PathBuf, Option<SystemTime>)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && path.extension().map_or(false, |e| e == SAVE_EXTENSION))
+        .map(|path| {
+            let time = modified_time(&path);
+            (path, time)
+        })
+        .collect();
+    list.sort_by(|a, b| b.1.cmp(&a.1));
+    list.into_iter().map(|(path, _)| path).collect()
+}
+
+/// Copy the auto-save to a new save of the game, to continue from it.
+/// The manual save is kept, and the restored game is renamed if the manual save exists.
+/// Maps not loaded yet are read from the save directory of the game during playing.
+pub fn restore_autosave(autosave_dir: &Path, gd: &mut GameData) -> Result<(), std::io::Error> {
+    let save_name = restored_save_name(gd.meta.save_name(), |name| {
+        get_save_dir().join(format!("{}.{}", name, SAVE_EXTENSION)).exists()
+    });
+    gd.meta.set_save_name(&save_name);
+    copy_dir(autosave_dir, &get_each_save_dir(gd))
+}
+
+/// The save name, or a name with a suffix if it is used
+fn restored_save_name<F: Fn(&str) -> bool>(name: &str, exists: F) -> String {
+    if !exists(name) {
+        return name.to_owned();
+    }
+    (1..).map(|i| format!("{}-restored{}", name, i)).find(|name| !exists(name)).unwrap()
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> Result<(), std::io::Error> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dest = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Continue the random sequence of the loaded game in deterministic mode.
+/// Otherwise loaded games use the random sequence seeded at startup.
+pub fn restore_rng_state(gd: &mut GameData) {
+    if !rng::is_deterministic() {
+        return;
+    }
+    match gd.rng_state.take() {
+        Some(state) => rng::set_state(state),
+        None => warn!("The random number generator state is not saved"),
+    }
+}
+
 /// Generate random id for FileBox
 pub fn gen_box_id(gd: &GameData) -> u64 {
     use rng::*;
     
     loop {
-        let s = thread_rng().gen::<u64>();
+        let s = get_rng().gen::<u64>();
         
         // Check generated name is not used
         let mut path = get_each_save_dir(gd);
@@ -72,6 +185,10 @@ fn get_save_dir() -> PathBuf {
     USER_DIR.clone().join(SAVE_DIR_NAME)
 }
 
+fn get_autosave_dir() -> PathBuf {
+    get_save_dir().join(AUTOSAVE_DIR_NAME)
+}
+
 /// Get each save directory path "save_dir/save_name"
 pub fn get_each_save_dir(gd: &GameData) -> PathBuf {
     get_save_dir().join(format!("{}.{}", gd.meta.save_name(), SAVE_EXTENSION))
@@ -82,3 +199,33 @@ pub fn get_map_dir(gd: &GameData) -> PathBuf {
     get_each_save_dir(gd).join("maps")
 }
 
+
+#[test]
+fn rng_state_test() {
+    rng::set_deterministic(1);
+    let mut gd = GameData::empty();
+    gd.rng_state = Some(rng::state());
+    let a = rng::next_u32();
+
+    // Saved with the game data
+    let data = serde_cbor::to_vec(&gd).unwrap();
+    let mut gd: GameData = serde_cbor::from_slice(&data).unwrap();
+    restore_rng_state(&mut gd);
+    assert_eq!(rng::next_u32(), a);
+}
+
+#[test]
+fn restored_save_name_test() {
+    let used = ["hero", "hero-restored1"];
+    assert_eq!(restored_save_name("rogue", |name| used.contains(&name)), "rogue");
+    assert_eq!(restored_save_name("hero", |name| used.contains(&name)), "hero-restored2");
+}
+
+#[test]
+fn oldest_slot_test() {
+    use std::time::Duration;
+    let t = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+    assert_eq!(oldest_slot(&[t(10), None, None]), 1);
+    assert_eq!(oldest_slot(&[t(30), t(10), t(20)]), 1);
+    assert_eq!(oldest_slot(&[t(10), t(20), t(30)]), 0);
+}
@@ -1,17 +1,50 @@
 //! Script engine implementation
 
+use std::rc::Rc;
+use array2d::Vec2d;
 use common::gobj;
 use common::gamedata::*;
+use common::objholder::{CharaTemplateIdx, ItemIdx};
 use common::script::*;
 
 use crate::game::eval_expr::EvalExpr;
-use crate::game::InfoGetter;
+use crate::game::chara::CharaEx;
+use crate::game::item::ItemListEx;
+use crate::text::ToText;
+use rng::{self, SliceRandom};
+
+/// Scripts are stopped if they execute this number of instructions without waiting for the player
+const MAX_STEPS: u32 = 10000;
 
 pub struct ScriptEngine {
-    script: &'static Script,
+    script: ScriptRef,
     pos: ScriptPos,
     cid: Option<CharaId>,
     talking: bool,
+    /// Scripts and positions to return to after the called script quits
+    call_stack: Vec<(ScriptRef, ScriptPos)>,
+    /// Instructions that change the world, like generating dungeons, are skipped
+    headless: bool,
+    /// The last error that stopped the script
+    error: Option<&'static str>,
+}
+
+/// Scripts in paks are borrowed from the object holder.
+/// Only scripts made at runtime, like console commands, are owned.
+#[derive(Clone)]
+enum ScriptRef {
+    Pak(&'static Script),
+    Owned(Rc<Script>),
+}
+
+impl std::ops::Deref for ScriptRef {
+    type Target = Script;
+    fn deref(&self) -> &Script {
+        match self {
+            ScriptRef::Pak(script) => script,
+            ScriptRef::Owned(script) => script,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -20,13 +53,17 @@ pub enum ExecResult {
     ShopBuy(CharaId),
     ShopSell,
     Quest,
+    /// Move the player to the position of given map
+    MovePlayer(MapId, Vec2d),
+    /// Move the player to given map. The position is decided as moving by stairs.
+    Teleport(MapId),
     Quit,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TalkText {
-    pub text_id: &'static str,
-    pub choices: Option<&'static [(String, String)]>,
+    pub text_id: String,
+    pub choices: Option<Vec<(String, String)>>,
 }
 
 /// Unwrap Value as bool
@@ -72,33 +109,113 @@ macro_rules! jump {
 
 /// Unwrap or return with warning message.
 macro_rules! ur {
-    ($a:expr, $e:expr) => {{
+    ($s:expr, $a:expr, $e:expr) => {{
         if let Some(a) = $a {
             a
         } else {
             warn!(concat!("script error: ", $e));
+            $s.error = Some($e);
             return ExecResult::Quit;
         }
     }}
 }
 
 impl ScriptEngine {
-    pub fn new(id: &str, cid: Option<CharaId>) -> ScriptEngine {
-        let script_obj: &ScriptObject = gobj::get_by_id(id);
+    /// Returns None if the script is not found
+    pub fn new(id: &str, cid: Option<CharaId>) -> Option<ScriptEngine> {
+        Some(ScriptEngine::with_script_ref(get_script(id)?, cid))
+    }
+
+    pub fn with_script(script: Rc<Script>, cid: Option<CharaId>) -> ScriptEngine {
+        ScriptEngine::with_script_ref(ScriptRef::Owned(script), cid)
+    }
+
+    fn with_script_ref(script: ScriptRef, cid: Option<CharaId>) -> ScriptEngine {
         ScriptEngine {
-            script: &script_obj.script,
+            script,
             pos: ScriptPos {
                 section: "start".to_owned(),
                 i: 0
             },
             cid,
             talking: false,
+            call_stack: Vec::new(),
+            headless: false,
+            error: None,
         }
     }
 
+    /// Run without a game world to verify scripts
+    pub fn headless(mut self) -> ScriptEngine {
+        self.headless = true;
+        self
+    }
+
+    pub fn error(&self) -> Option<&'static str> {
+        self.error
+    }
+
+    /// Execute the script. When a called script quits, return to the caller.
+    /// Local variables are shared with called scripts, and discarded when the script ends.
     pub fn exec(&mut self, gd: &mut GameData) -> ExecResult {
+        loop {
+            let result = self.exec_current(gd);
+            if result == ExecResult::Quit {
+                if self.return_to_caller() {
+                    continue;
+                }
+                gd.vars.clear_local_vars();
+            }
+            return result;
+        }
+    }
+
+    /// Execute another script. The current script is resumed from the next instruction
+    /// after the called script quits.
+    fn call(&mut self, script: ScriptRef) {
+        let mut return_pos = self.pos.clone();
+        return_pos.advance();
+        let caller = std::mem::replace(&mut self.script, script);
+        self.call_stack.push((caller, return_pos));
+        self.pos = ScriptPos {
+            section: "start".to_owned(),
+            i: 0
+        };
+    }
+
+    fn talk(&mut self, cid: CharaId, talk_text: TalkText) -> ExecResult {
+        let need_open_talk_dialog = if self.talking {
+            false
+        } else {
+            self.talking = true;
+            true
+        };
+        ExecResult::Talk(cid, talk_text, need_open_talk_dialog)
+    }
+
+    /// Returns false if there is no caller script
+    fn return_to_caller(&mut self) -> bool {
+        if let Some((script, pos)) = self.call_stack.pop() {
+            self.script = script;
+            self.pos = pos;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn exec_current(&mut self, gd: &mut GameData) -> ExecResult {
+        let mut steps = 0;
         let result = loop {
-            let instruction = if let Some(instruction) = self.script.get(&self.pos) {
+            steps += 1;
+            if steps > MAX_STEPS {
+                warn!("script error: too many instructions without talking");
+                self.error = Some("too many instructions without talking");
+                break ExecResult::Quit;
+            }
+            // Instructions borrow this clone because the engine changes its own script by calls
+            let script = self.script.clone();
+            let instruction = if let Some(instruction) = script.get(&self.pos) {
                 instruction
             } else {
                 break ExecResult::Quit;
@@ -114,48 +231,185 @@ impl ScriptEngine {
                     }
                 }
                 Instruction::Talk(text_id, choices) => {
-                    let cid = ur!(self.cid, "cid is needed");
-                    let need_open_talk_dialog = if self.talking {
-                        false
-                    } else {
-                        self.talking = true;
-                        true
-                    };
-                    
-                    let choices = if choices.is_empty() { None } else { Some(choices.as_ref()) };
-                    return ExecResult::Talk(
-                        cid, TalkText { text_id, choices }, need_open_talk_dialog );
+                    let cid = ur!(self, self.cid, "cid is needed");
+                    let choices = if choices.is_empty() { None } else { Some(choices.clone()) };
+                    return self.talk(cid, TalkText { text_id: text_id.clone(), choices });
+                }
+                Instruction::TalkRandom(text_ids) => {
+                    let cid = ur!(self, self.cid, "cid is needed");
+                    let text_id = ur!(self, text_ids.choose(&mut rng::get_rng()), "empty text list");
+                    return self.talk(cid, TalkText { text_id: text_id.clone(), choices: None });
                 }
                 Instruction::GSet(name, v) => {
                     let v = v.eval(gd);
                     gd.vars.set_global_var(name, v);
                 }
+                Instruction::LSet(name, v) => {
+                    let v = v.eval(gd);
+                    gd.vars.set_local_var(name, v);
+                }
                 Instruction::ReceiveMoney(v) => {
                     let v = v.eval(gd);
                     gd.player.add_money(as_int!(v) as i64);
                 }
-                Instruction::RemoveItem(item_id) => {
-                    let il = ur!(gd.player_item_location(item_id), "cannot find item");
-                    gd.remove_item(il, 1);
+                Instruction::ReceiveItem(item_id, n) => {
+                    let idx: ItemIdx = ur!(self, gobj::id_to_idx_checked(item_id), "unknown item id");
+                    let n = as_int!(n.eval(gd));
+                    if n > 0 {
+                        let item = crate::game::item::gen::gen_item_from_idx(idx);
+                        game_log_i!("player-receive-item"; item=item, n=n);
+                        gd.get_item_list_mut(ItemListLocation::Chara { cid: CharaId::Player })
+                            .append(item, n as u32);
+                    }
+                }
+                Instruction::RemoveItem(item_id, n) => {
+                    let idx: ItemIdx = ur!(self, gobj::id_to_idx_checked(item_id), "unknown item id");
+                    let n = as_int!(n.eval(gd)).max(0) as u32;
+                    let result = gd.get_item_list_mut(ItemListLocation::Chara { cid: CharaId::Player })
+                        .remove_by_idx(idx, n);
+                    gd.vars.set_last_result(Value::Bool(result));
+                }
+                Instruction::ModifyStat(kind, v) => {
+                    let v = as_int!(v.eval(gd));
+                    let chara = gd.chara.get_mut(CharaId::Player);
+                    match kind {
+                        StatKind::Hp => {
+                            // Hp is clamped to max, and scripts cannot kill the player
+                            let hp = std::cmp::min(chara.hp.saturating_add(v), chara.attr.max_hp);
+                            chara.hp = std::cmp::max(hp, 1);
+                        }
+                        StatKind::Sp => {
+                            chara.add_sp(v, CharaId::Player);
+                        }
+                    }
+                }
+                Instruction::QuestStart(quest_id, deadline, on_fail, on_complete) => {
+                    let deadline = if let Some(deadline) = deadline {
+                        let hours = as_int!(deadline.eval(gd));
+                        let mut time = gd.time.current_time();
+                        time.advance(hours.max(0) as u64 * SECS_PER_HOUR);
+                        Some(time)
+                    } else {
+                        None
+                    };
+                    gd.quest.start_script_quest(
+                        quest_id, deadline, on_fail.clone(), on_complete.clone());
+                }
+                Instruction::QuestComplete(quest_id) => {
+                    if gd.quest.complete_script_quest(quest_id) {
+                        let on_complete = gd.quest.script_quest(quest_id)
+                            .and_then(|quest| quest.on_complete.as_ref());
+                        if let Some(script) = on_complete.and_then(|script_id| get_script(script_id)) {
+                            self.call(script);
+                            continue;
+                        }
+                    }
+                }
+                Instruction::QuestSet(quest_id, stage) => {
+                    let stage = as_int!(stage.eval(gd));
+                    gd.quest.set_script_quest_stage(quest_id, stage.max(0) as u32);
+                }
+                Instruction::MovePlayer(floor, x, y) => {
+                    let floor = as_int!(floor.eval(gd));
+                    let pos = Vec2d(as_int!(x.eval(gd)), as_int!(y.eval(gd)));
+                    let mid = gd.get_current_mapid();
+                    if self.headless {
+                        // There is no site to move in
+                    } else if mid.is_region_map() || floor < 0 {
+                        warn!("script error: move_player needs a site floor");
+                    } else {
+                        let mid = MapId::SiteMap { sid: mid.sid(), floor: floor as u32 };
+                        // Floors are not created by scripts
+                        if !gd.region.map_exist(mid) {
+                            warn!("script error: unknown map {:?}", mid);
+                            self.error = Some("unknown map");
+                            return ExecResult::Quit;
+                        }
+                        break ExecResult::MovePlayer(mid, pos);
+                    }
+                }
+                Instruction::MakeHostile(chara_id) => {
+                    let idx: CharaTemplateIdx = ur!(self, gobj::id_to_idx_checked(chara_id), "unknown chara id");
+                    let ids: Vec<CharaId> = gd.get_current_map().iter_charaid().cloned().collect();
+                    for cid in ids {
+                        let chara = gd.chara.get_mut(cid);
+                        if chara.template == idx {
+                            chara.faction = Faction::Unknown;
+                            chara.rel = Relationship::HOSTILE;
+                        }
+                    }
+                }
+                Instruction::SetFactionRelation(faction, rel) => {
+                    gd.faction.set_player_relation(*faction, *rel);
+                }
+                Instruction::PlaySound(sound_id) => {
+                    // Unknown sound ids are warned by the audio player
+                    crate::audio::play_sound(sound_id);
+                }
+                Instruction::JoinParty => {
+                    let cid = ur!(self, self.cid, "cid is needed");
+                    if let Some(new_cid) = super::party::join_party(gd, cid) {
+                        // Following talks use the new id
+                        self.cid = Some(new_cid);
+                    }
+                }
+                Instruction::SetWeather(weather, hours) => {
+                    let hours = as_int!(hours.eval(gd));
+                    super::weather::set_weather(gd, *weather, hours.max(0) as u64);
+                }
+                Instruction::SpawnChara(chara_id) => {
+                    let idx: CharaTemplateIdx = ur!(self, gobj::id_to_idx_checked(chara_id), "unknown chara id");
+                    if !self.headless {
+                        let map = gd.get_current_map();
+                        let center = ur!(self, map.chara_pos(CharaId::Player), "the player is not on the map");
+                        let pos = ur!(self, super::map::empty_tile_near(map, center), "no empty tile");
+                        let mid = gd.get_current_mapid();
+                        let chara = super::chara::gen::create_monster_chara(idx);
+                        let cid = gd.add_chara_to_map(chara, mid);
+                        gd.get_current_map_mut().locate_chara(cid, pos);
+                    }
+                }
+                Instruction::Teleport(dest) => {
+                    let rid = gd.get_current_mapid().rid();
+                    let mid = if let Some((kind, n, floor)) = *dest {
+                        MapId::SiteMap { sid: SiteId { rid, kind, n }, floor }
+                    } else {
+                        MapId::RegionMap { rid }
+                    };
+                    if !gd.region.map_exist(mid) {
+                        warn!("script error: unknown map {:?}", mid);
+                        self.error = Some("unknown map");
+                        return ExecResult::Quit;
+                    }
+                    if !self.headless {
+                        break ExecResult::Teleport(mid);
+                    }
                 }
                 Instruction::Special(SpecialInstruction::ShopBuy) => {
-                    break ExecResult::ShopBuy(ur!(self.cid, "cid is needed"));
+                    break ExecResult::ShopBuy(ur!(self, self.cid, "cid is needed"));
                 }
                 Instruction::Special(SpecialInstruction::ShopSell) => {
                     break ExecResult::ShopSell;
                 }
                 Instruction::Special(SpecialInstruction::GetDungeonLocation) => {
-                    let mid = gd.get_current_mapid();
-                    super::region::gen_dungeon_max(gd, mid.rid());
+                    if !self.headless {
+                        let mid = gd.get_current_mapid();
+                        super::region::gen_dungeon_max(gd, mid.rid());
+                    }
                 }
                 Instruction::Special(SpecialInstruction::QuestWindow) => {
-                    super::quest::update_town_quest(gd);
+                    if !self.headless {
+                        super::quest::update_town_quest(gd);
+                    }
                     break ExecResult::Quest;
                 }
                 Instruction::Special(SpecialInstruction::ReceiveQuestRewards) => {
                     let result = super::quest::receive_rewards(gd);
                     gd.vars.set_last_result(Value::Bool(result))
                 }
+                Instruction::Special(SpecialInstruction::RevealMap) => {
+                    super::map::observe_all_tiles(gd.get_current_map_mut());
+                }
             }
             self.pos.advance();
         };
@@ -165,11 +419,16 @@ impl ScriptEngine {
     }
 
     pub fn continue_talk(&mut self, gd: &mut GameData, choice: Option<u32>) -> ExecResult {
-        match self.script.get(&self.pos).expect("instruction not found") {
+        let script = self.script.clone();
+        match script.get(&self.pos).expect("instruction not found") {
             Instruction::Talk(_, choices) => {
                 if let Some(c) = choice {
                     let next_section = &choices[c as usize].1;
                     if next_section == QUIT_SECTION {
+                        if self.return_to_caller() {
+                            return self.exec(gd);
+                        }
+                        gd.vars.clear_local_vars();
                         return ExecResult::Quit;
                     }
                     if next_section == CONTINUE_SECTION {
@@ -183,8 +442,486 @@ impl ScriptEngine {
                 }
                 self.exec(gd)
             }
+            Instruction::TalkRandom(_) => {
+                self.pos.advance();
+                self.exec(gd)
+            }
             _ => unreachable!(),
         }
     }
 }
 
+fn get_script(id: &str) -> Option<ScriptRef> {
+    if let Some(script_obj) = gobj::get_by_id_checked::<ScriptObject>(id) {
+        Some(ScriptRef::Pak(&script_obj.script))
+    } else {
+        warn!("script \"{}\" is not found", id);
+        None
+    }
+}
+
+/// Replace variables that reflect the world state in talk text.
+/// $(time), $(weather), and $(location) are available.
+pub fn replace_talk_vars(s: &str, gd: &GameData) -> String {
+    let date = gd.time.current_date();
+    let time = format!("{:02}:{:02}", date.hour, date.minute);
+    let location = match gd.get_current_mapid() {
+        MapId::SiteMap { sid, .. } => gd.region.get_site(sid).to_text(),
+        MapId::RegionMap { rid } => gd.region.get(rid).name.as_str().into(),
+    };
+    let weather = super::weather::local_weather(gd);
+    let weather = weather.to_text();
+
+    let table: [(&str, &str); 3] = [
+        ("time", &time),
+        ("weather", &weather),
+        ("location", &location),
+    ];
+    crate::util::replace_str(s, &table)
+}
+
+#[cfg(test)]
+fn test_game_data() -> GameData {
+    let mut gd = GameData::empty();
+    let rid = gd.region.add_region(Region::new("test-region", Map::new(1, 1), 0));
+    gd.set_initial_mapid(MapId::RegionMap { rid });
+    gd
+}
+
+#[test]
+fn talk_weather_test() {
+    let mut gd = test_game_data();
+    gd.get_current_region_mut().weather = Weather::Rain;
+    gd.time = GameTime::new(1, 1, 1, 9);
+
+    let s = replace_talk_vars("$(time) at $(location), it's $(weather) today.", &gd);
+    assert_eq!(s, "09:00 at test-region, it's !weather.rain today.");
+}
+
+#[test]
+fn talk_is_night_test() {
+    use common::hashmap::HashMap;
+
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::JumpIf("night".to_owned(), Expr::IsNight),
+        Instruction::Talk("greeting-day".to_owned(), vec![]),
+    ]);
+    map.insert("night".to_owned(), vec![
+        Instruction::Talk("greeting-night".to_owned(), vec![]),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    for &(hour, text_id) in &[(12, "greeting-day"), (22, "greeting-night")] {
+        gd.time = GameTime::new(1, 1, 1, hour);
+        let mut engine = ScriptEngine::with_script(script.clone(), Some(CharaId::Player));
+        let talk_text = TalkText { text_id: text_id.to_owned(), choices: None };
+        assert_eq!(engine.exec(&mut gd), ExecResult::Talk(CharaId::Player, talk_text, true));
+    }
+}
+
+/// Initialize objects and rules used by tests. All tests share the same objects.
+#[cfg(test)]
+pub(crate) fn init_test_objs() {
+    use std::sync::Once;
+    use common::hashmap::HashMap;
+    use common::obj::{AnimImgObject, CharaTemplateObject, EffectObject, Img, Movement, TileKind, TileObject};
+    use common::objholder::ObjectHolder;
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        let img = || Img {
+            data: Vec::new(), w: 1, h: 1, grid_nx: 1, grid_ny: 1,
+            n_frame: 1, n_pattern: 1, n_anim_frame: 1, duration: 0, n_direction: 1,
+        };
+        let item = |id: &str| ItemObject {
+            id: id.to_owned(),
+            img: img(),
+            kind: ItemKind::Object,
+            default_flags: ItemFlags::empty(),
+            basic_price: 0,
+            w: 0,
+            gen_weight: 0.0,
+            shop_weight: 0.0,
+            gen_level: 0,
+            dice_n: 0,
+            dice_x: 0,
+            def: ElementArray::default(),
+            eff: 0,
+            medical_effect: MedicalEffect::None,
+            nutrition: 0,
+            light: 0,
+            container: None,
+            charge: None,
+            has_quality: false,
+            materials: Vec::new(),
+            mount: None,
+            dig_power: 0,
+            overlay: None,
+            learn_skill: None,
+        };
+        let mut objholder = ObjectHolder::new();
+        objholder.item.push(item("test-item"));
+        let mount = MountParams {
+            travel_time: 50, movements: vec![Movement::Swim], rider_img: "test-swimmer".to_owned(),
+        };
+        objholder.item.push(ItemObject { mount: Some(mount), ..item("test-boat") });
+        objholder.item.push(ItemObject {
+            medical_effect: MedicalEffect::Heal, eff: 10, ..item("test-potion")
+        });
+        objholder.item.push(ItemObject { kind: ItemKind::Food, ..item("test-food") });
+        objholder.item.push(ItemObject {
+            container: Some(ContainerParams { max_w: 1000, max_n: 3 }), ..item("test-chest")
+        });
+        objholder.item.push(ItemObject {
+            basic_price: 100, w: 400, charge: Some((3, 3)), materials: vec![MaterialKind::Steel],
+            ..item("test-wand")
+        });
+        objholder.item.push(ItemObject {
+            default_flags: ItemFlags::READABLE, learn_skill: Some(SkillKind::Crafting), ..item("test-book")
+        });
+        let group = |chara: &str, floor_range, n| SpawnGroup {
+            chara: vec![chara.to_owned()], weight: 1.0, floor_range, n,
+        };
+        objholder.spawn_table.push(SpawnTableObject {
+            id: "test-spawn".to_owned(),
+            groups: vec![group("test-chara", [0, 2], [2, 2]), group("test-swimmer", [3, 5], [1, 1])],
+        });
+        objholder.recipe.push(RecipeObject {
+            id: "test-recipe".to_owned(),
+            inputs: vec![RecipeInput { id: "test-item".to_owned(), n: 2 }],
+            skill: SkillKind::Crafting,
+            skill_level: 1,
+            output: "test-potion".to_owned(),
+            n_output: 1,
+        });
+
+        // Characters use the first template by default
+        for &(id, movement) in &[("test-chara", Movement::Walk), ("test-swimmer", Movement::Swim)] {
+            objholder.chara_template.push(CharaTemplateObject {
+                id: id.to_owned(),
+                img: img(),
+                race: Race::Human,
+                gen_weight: 0.0,
+                gen_level: 0,
+                default_ai_kind: NpcAIKind::None,
+                base_attr: CharaBaseAttr {
+                    base_hp: 1, str: 1, vit: 1, dex: 1, int: 1, wil: 1, cha: 1, spd: 1,
+                },
+                skill_levels: Vec::new(),
+                movements: vec![movement],
+            });
+        }
+
+        // Maps are filled by the first tile
+        for &(id, kind) in &[("test-ground", TileKind::Ground), ("test-water", TileKind::Water)] {
+            objholder.tile.push(TileObject {
+                id: id.to_owned(), img: img(), kind, symbol_color: (0, 0, 0),
+            });
+        }
+
+        // Game::empty() needs overlays
+        for id in &["overlay-fog", "overlay-fog-dark", "overlay-night",
+                    "overlay-twilight0", "overlay-twilight1", "overlay-twilight2"] {
+            objholder.effect.push(EffectObject { id: id.to_string(), img: img() });
+        }
+        // Animations of attacks
+        for id in &["!arrow", "!damage-blunt", "!throw"] {
+            objholder.anim_img.push(AnimImgObject { id: id.to_string(), img: img() });
+        }
+        objholder.ability.push(AbilityObject {
+            id: "test-heal".to_owned(),
+            cost_mp: 3,
+            range: 2,
+            effect: AbilityEffect::Heal,
+            element: Element::None,
+            dice_n: 1,
+            dice_x: 1,
+        });
+
+        let mut map = HashMap::default();
+        map.insert("start".to_owned(), vec![
+            Instruction::ReceiveItem("test-item".to_owned(), Expr::Value(Value::Int(2))),
+            Instruction::ReceiveMoney(Expr::Value(Value::Int(500))),
+        ]);
+        objholder.script.push(ScriptObject {
+            id: "test-quest-reward".to_owned(),
+            script: Script::from_map(map),
+        });
+        let mut map = HashMap::default();
+        map.insert("start".to_owned(), vec![Instruction::Talk("test-event-text".to_owned(), vec![])]);
+        objholder.script.push(ScriptObject {
+            id: "test-event-talk".to_owned(),
+            script: Script::from_map(map),
+        });
+        common::gobj::init_with_objholder(objholder);
+        // Rules are loaded from the repository
+        rules::init(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../res"));
+    });
+}
+
+/// A game on a region map of the size. The player is at (0, 0).
+#[cfg(test)]
+pub(crate) fn test_game(w: u32, h: u32) -> super::Game {
+    init_test_objs();
+    let mut game = super::Game::empty();
+    let rid = game.gd.region.add_region(Region::new("test-region", Map::new(w, h), 0));
+    game.gd.set_initial_mapid(MapId::RegionMap { rid });
+    let player = Chara { name: Some("player".to_owned()), ..Chara::default() };
+    game.gd.add_chara(player, CharaKind::Player);
+    game.gd.get_current_map_mut().locate_chara(CharaId::Player, Vec2d(0, 0));
+    game
+}
+
+/// Locate a character that the player is neutral to, with attributes usable in combat
+#[cfg(test)]
+pub(crate) fn add_test_npc(game: &mut super::Game, pos: Vec2d) -> CharaId {
+    let attr = CharaAttributes {
+        max_hp: 100, str: 10, vit: 10, dex: 10, int: 10, wil: 10, cha: 10, spd: 100, view_range: 5,
+        ..CharaAttributes::default()
+    };
+    let npc = Chara { name: Some("npc".to_owned()), attr, ..Chara::default() };
+    let mid = game.gd.get_current_mapid();
+    let cid = game.gd.add_chara_to_map(npc, mid);
+    game.gd.get_current_map_mut().locate_chara(cid, pos);
+    cid
+}
+
+/// The text id of the talk that the game requests to open
+#[cfg(test)]
+pub(crate) fn requested_talk(game: &mut super::Game) -> Option<(CharaId, String)> {
+    match game.pop_dialog_open_request() {
+        Some(super::DialogOpenRequest::Talk { cid, talk_text }) => Some((cid, talk_text.text_id)),
+        _ => None,
+    }
+}
+
+#[test]
+fn quest_reward_script_test() {
+    use common::hashmap::HashMap;
+    init_test_objs();
+
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::QuestStart("rescue".to_owned(), None, None, Some("test-quest-reward".to_owned())),
+        Instruction::QuestComplete("rescue".to_owned()),
+        // Continue after the reward script
+        Instruction::GSet("finished".to_owned(), Expr::Value(Value::Bool(true))),
+        // Already completed quest doesn't give rewards again
+        Instruction::QuestComplete("rescue".to_owned()),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    gd.add_chara(Chara::default(), CharaKind::Player);
+    let mut engine = ScriptEngine::with_script(script.clone(), None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+
+    assert_eq!(gd.quest.script_quest_state("rescue"), ScriptQuestState::Complete);
+    assert_eq!(gd.player.money(), 500);
+    let item_list = gd.get_item_list(ItemListLocation::Chara { cid: CharaId::Player });
+    let idx: ItemIdx = gobj::id_to_idx("test-item");
+    assert_eq!(item_list.iter().filter(|(item, _)| item.idx == idx).map(|(_, n)| n).sum::<u32>(), 2);
+    assert_eq!(gd.vars.global_var("finished"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn quest_stage_test() {
+    use common::hashmap::HashMap;
+
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        // Not started quests don't have stages
+        Instruction::QuestSet("delivery".to_owned(), Expr::Value(Value::Int(1))),
+        Instruction::LSet("before".to_owned(), Expr::QuestStage("delivery".to_owned())),
+        Instruction::QuestStart("delivery".to_owned(), None, None, None),
+        Instruction::QuestSet("delivery".to_owned(), Expr::Value(Value::Int(2))),
+        Instruction::GSet("stage".to_owned(), Expr::QuestStage("delivery".to_owned())),
+        Instruction::GSet("before".to_owned(), Expr::LVar("before".to_owned())),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    let mut engine = ScriptEngine::with_script(script.clone(), None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+    assert_eq!(gd.vars.global_var("before"), Some(&Value::Int(0)));
+    assert_eq!(gd.vars.global_var("stage"), Some(&Value::Int(2)));
+}
+
+#[test]
+fn local_vars_test() {
+    use common::hashmap::HashMap;
+
+    // Talk "first" only if lvar(tmp) is not set, and set it
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::JumpIf("second".to_owned(), Expr::Term(vec![
+            (Operator::None, Expr::LVar("tmp".to_owned())),
+            (Operator::Eq, Expr::Value(Value::Int(1)))])),
+        Instruction::LSet("tmp".to_owned(), Expr::Value(Value::Int(1))),
+        Instruction::Talk("first".to_owned(), vec![]),
+    ]);
+    map.insert("second".to_owned(), vec![
+        Instruction::Talk("second".to_owned(), vec![]),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+    let first = TalkText { text_id: "first".to_owned(), choices: None };
+
+    let mut gd = test_game_data();
+    for _ in 0..2 {
+        let mut engine = ScriptEngine::with_script(script.clone(), Some(CharaId::Player));
+        assert_eq!(engine.exec(&mut gd), ExecResult::Talk(CharaId::Player, first.clone(), true));
+        assert_eq!(gd.vars.local_var("tmp"), Some(&Value::Int(1)));
+        assert_eq!(engine.continue_talk(&mut gd, None), ExecResult::Quit);
+        // Locals are discarded and not written to globals
+        assert_eq!(gd.vars.local_var("tmp"), None);
+        assert_eq!(gd.vars.global_var("tmp"), None);
+    }
+}
+
+#[test]
+fn talk_random_test() {
+    use common::hashmap::HashMap;
+
+    let text_ids = vec!["greet_a".to_owned(), "greet_b".to_owned(), "greet_c".to_owned()];
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![Instruction::TalkRandom(text_ids.clone())]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    let mut talk = |seed| {
+        rng::reseed_with(seed);
+        (0..10).map(|_| {
+            let mut engine = ScriptEngine::with_script(script.clone(), Some(CharaId::Player));
+            match engine.exec(&mut gd) {
+                ExecResult::Talk(_, talk_text, _) => talk_text.text_id,
+                _ => panic!(),
+            }
+        }).collect::<Vec<_>>()
+    };
+
+    let texts = talk(1);
+    assert!(texts.iter().all(|t| text_ids.iter().any(|id| id == t)));
+    // The same seed gives the same selection
+    assert_eq!(texts, talk(1));
+}
+
+#[test]
+fn remove_item_test() {
+    use common::hashmap::HashMap;
+    init_test_objs();
+
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::RemoveItem("test-item".to_owned(), Expr::Value(Value::Int(3))),
+        Instruction::JumpIf("failed".to_owned(), Expr::Term(vec![
+            (Operator::None, Expr::GVar("?".to_owned())),
+            (Operator::Eq, Expr::Value(Value::Bool(false)))])),
+        Instruction::GSet("removed".to_owned(), Expr::Value(Value::Bool(true))),
+    ]);
+    map.insert("failed".to_owned(), vec![
+        Instruction::GSet("removed".to_owned(), Expr::Value(Value::Bool(false))),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    gd.add_chara(Chara::default(), CharaKind::Player);
+    let idx: ItemIdx = gobj::id_to_idx("test-item");
+    let ill = ItemListLocation::Chara { cid: CharaId::Player };
+    gd.get_item_list_mut(ill).append(crate::game::item::gen::gen_item_from_idx(idx), 2);
+
+    // Insufficient items are not removed partially
+    let mut engine = ScriptEngine::with_script(script.clone(), None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+    assert_eq!(gd.vars.global_var("removed"), Some(&Value::Bool(false)));
+    assert_eq!(gd.get_item_list(ill).count(idx), 2);
+
+    gd.get_item_list_mut(ill).append(crate::game::item::gen::gen_item_from_idx(idx), 2);
+    let mut engine = ScriptEngine::with_script(script.clone(), None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+    assert_eq!(gd.vars.global_var("removed"), Some(&Value::Bool(true)));
+    assert_eq!(gd.get_item_list(ill).count(idx), 1);
+}
+
+#[test]
+fn spawn_chara_test() {
+    use common::hashmap::HashMap;
+    init_test_objs();
+
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::SpawnChara("test-chara".to_owned()),
+        Instruction::SpawnChara("test-chara".to_owned()),
+    ]);
+    let script = Rc::new(Script::from_map(map));
+
+    let mut gd = test_game_data();
+    let rid = gd.region.add_region(Region::new("test-region2", Map::new(3, 3), 0));
+    gd.set_current_mapid(MapId::RegionMap { rid });
+    gd.add_chara(Chara::default(), CharaKind::Player);
+    gd.get_current_map_mut().locate_chara(CharaId::Player, Vec2d(1, 1));
+    let mut engine = ScriptEngine::with_script(script, None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+    assert_eq!(engine.error(), None);
+
+    let idx: CharaTemplateIdx = gobj::id_to_idx("test-chara");
+    let spawned: Vec<CharaId> = gd.get_current_map().iter_charaid()
+        .filter(|&&cid| cid != CharaId::Player).cloned().collect();
+    assert_eq!(spawned.len(), 2);
+    for cid in spawned {
+        assert_eq!(gd.chara.get(cid).template, idx);
+        assert_eq!(gd.chara.get(cid).faction, Faction::Monster);
+        assert!(gd.get_current_map().chara_pos(cid).is_some());
+    }
+
+    // Unknown templates stop the script
+    let mut map = HashMap::default();
+    map.insert("start".to_owned(), vec![Instruction::SpawnChara("unknown".to_owned())]);
+    let mut engine = ScriptEngine::with_script(Rc::new(Script::from_map(map)), None);
+    assert_eq!(engine.exec(&mut gd), ExecResult::Quit);
+    assert_eq!(engine.error(), Some("unknown chara id"));
+}
+
+#[test]
+fn teleport_test() {
+    use common::hashmap::HashMap;
+
+    let mut gd = test_game_data();
+    let rid = gd.get_current_mapid().rid();
+    let sid = gd.add_site(Site::new(1), SiteKind::Town, rid, Vec2d(0, 0)).unwrap();
+    let mid = gd.add_map(Map::new(1, 1), sid, 0);
+    let teleport = |gd: &mut GameData, dest| {
+        let mut map = HashMap::default();
+        map.insert("start".to_owned(), vec![Instruction::Teleport(dest)]);
+        let mut engine = ScriptEngine::with_script(Rc::new(Script::from_map(map)), None);
+        (engine.exec(gd), engine.error())
+    };
+
+    assert_eq!(teleport(&mut gd, None), (ExecResult::Teleport(MapId::RegionMap { rid }), None));
+    assert_eq!(teleport(&mut gd, Some((SiteKind::Town, sid.n, 0))), (ExecResult::Teleport(mid), None));
+    // Floors that don't exist are not created
+    assert_eq!(teleport(&mut gd, Some((SiteKind::Town, sid.n, 1))), (ExecResult::Quit, Some("unknown map")));
+}
+
+#[test]
+fn modify_stat_clamp_test() {
+    use common::hashmap::HashMap;
+    let mut game = test_game(1, 1);
+    let npc = add_test_npc(&mut game, Vec2d(0, 0));
+    let player = game.gd.chara.get_mut(CharaId::Player);
+    player.attr.max_hp = 50;
+    player.hp = 20;
+
+    for &(v, hp) in &[(10, 30), (1000, 50), (-1000, 1)] {
+        let mut map = HashMap::default();
+        map.insert("start".to_owned(), vec![
+            Instruction::ModifyStat(StatKind::Hp, Expr::Value(Value::Int(v))),
+        ]);
+        // The talking character is not changed
+        let mut engine = ScriptEngine::with_script(Rc::new(Script::from_map(map)), Some(npc));
+        assert_eq!(engine.exec(&mut game.gd), ExecResult::Quit);
+        assert_eq!(game.gd.chara.get(CharaId::Player).hp, hp);
+        assert_eq!(game.gd.chara.get(npc).hp, 100);
+    }
+}
@@ -0,0 +1,211 @@
+
+//! Verifies talk scripts in loaded paks without SDL. Used by `rusted-ruins --test-scripts`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use common::gobj;
+use common::gamedata::*;
+use common::script::*;
+use super::script::{ScriptEngine, ExecResult};
+
+/// Choice paths executed for each script. Talks with many choices can make too many paths.
+const MAX_RUNS: usize = 1000;
+/// Results of executions waiting for the player, like talks and shop windows, in one run
+const MAX_WAITS: usize = 64;
+
+/// Check all loaded scripts and print found problems. Returns the number of problems.
+pub fn check_all() -> usize {
+    let scripts = &gobj::get_objholder().script;
+    let mut n_problem = 0;
+    for script_obj in scripts {
+        for problem in check_script(&script_obj.script) {
+            println!("{}: {}", script_obj.id, problem);
+            n_problem += 1;
+        }
+    }
+    println!("{} scripts checked, {} problems found", scripts.len(), n_problem);
+    n_problem
+}
+
+fn check_script(script: &Script) -> Vec<String> {
+    let mut problems = Vec::new();
+    let sections: HashMap<&str, &[Instruction]> = script.iter_sections().collect();
+    if !sections.contains_key("start") {
+        problems.push("no start section".to_owned());
+        return problems;
+    }
+
+    let mut missing_sections = Vec::new();
+    let mut missing_texts = Vec::new();
+    for instruction in sections.values().flat_map(|section| section.iter()) {
+        for dest in destinations(instruction) {
+            if !sections.contains_key(dest) && !missing_sections.contains(&dest) {
+                missing_sections.push(dest);
+            }
+        }
+        for text_id in text_ids(instruction) {
+            // Inline texts are registered as talk texts when loading
+            if crate::text::talk_txt_checked(text_id).is_none() && !missing_texts.contains(&text_id) {
+                missing_texts.push(text_id);
+            }
+        }
+    }
+    missing_sections.sort();
+    missing_texts.sort();
+    problems.extend(missing_sections.iter().map(|s| format!("unknown section \"{}\"", s)));
+    problems.extend(missing_texts.iter().map(|t| format!("unknown text id \"{}\"", t)));
+
+    let reachable = reachable_sections(&sections);
+    let mut unreachable: Vec<&str> =
+        sections.keys().cloned().filter(|s| !reachable.contains(s)).collect();
+    unreachable.sort();
+    problems.extend(unreachable.iter().map(|s| format!("unreachable section \"{}\"", s)));
+
+    problems.extend(run_all_choices(&Rc::new(script.clone())));
+    problems
+}
+
+/// Sections that can be jumped to by the instruction
+fn destinations(instruction: &Instruction) -> Vec<&str> {
+    let dests: Vec<&str> = match instruction {
+        Instruction::Jump(dest) | Instruction::JumpIf(dest, _) => vec![dest.as_str()],
+        Instruction::Talk(_, choices) => choices.iter().map(|(_, dest)| dest.as_str()).collect(),
+        _ => vec![],
+    };
+    dests.into_iter().filter(|&dest| dest != QUIT_SECTION && dest != CONTINUE_SECTION).collect()
+}
+
+fn text_ids(instruction: &Instruction) -> Vec<&str> {
+    match instruction {
+        Instruction::Talk(text_id, choices) => {
+            let mut ids = vec![text_id.as_str()];
+            ids.extend(choices.iter().map(|(text_id, _)| text_id.as_str()));
+            ids
+        }
+        Instruction::TalkRandom(text_ids) => text_ids.iter().map(|t| t.as_str()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Sections reachable from the start section by jumps and choices in any conditions
+fn reachable_sections<'a>(sections: &HashMap<&'a str, &'a [Instruction]>) -> HashSet<&'a str> {
+    let mut reachable = HashSet::new();
+    let mut stack = vec!["start"];
+    while let Some(name) = stack.pop() {
+        if !reachable.insert(name) {
+            continue;
+        }
+        if let Some(section) = sections.get(name) {
+            for instruction in section.iter() {
+                stack.extend(destinations(instruction).into_iter().filter(|d| sections.contains_key(d)));
+            }
+        }
+    }
+    reachable
+}
+
+/// Run the script with every sequence of choices on a stubbed game state
+fn run_all_choices(script: &Rc<Script>) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut queue: VecDeque<Vec<u32>> = VecDeque::new();
+    queue.push_back(Vec::new());
+    let mut n_run = 0;
+
+    while let Some(choices) = queue.pop_front() {
+        n_run += 1;
+        if n_run > MAX_RUNS {
+            break;
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| run(script, &choices)));
+        match result {
+            Ok(Ok(Some(n_choice))) => {
+                queue.extend((0..n_choice).map(|c| {
+                    let mut choices = choices.clone();
+                    choices.push(c);
+                    choices
+                }));
+            }
+            Ok(Ok(None)) => (),
+            Ok(Err(e)) => {
+                let problem = format!("{} (choices {:?})", e, choices);
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+            }
+            Err(_) => {
+                problems.push(format!("the interpreter panicked (choices {:?})", choices));
+            }
+        }
+    }
+    problems
+}
+
+/// Run the script giving the choices to talks in order. Returns the number of choices
+/// of the next talk after using all given choices, or None if the script quits before it.
+fn run(script: &Rc<Script>, choices: &[u32]) -> Result<Option<u32>, String> {
+    let mut gd = stub_game_data();
+    let mut engine = ScriptEngine::with_script(script.clone(), Some(CharaId::Player)).headless();
+    let mut choices = choices.iter();
+    let mut result = engine.exec(&mut gd);
+
+    for _ in 0..MAX_WAITS {
+        if let Some(e) = engine.error() {
+            return Err(e.to_owned());
+        }
+        result = match result {
+            ExecResult::Quit => { return Ok(None); }
+            ExecResult::Talk(_, talk_text, _) => {
+                let n_choice = talk_text.choices.as_ref().map_or(0, |c| c.len() as u32);
+                if n_choice == 0 {
+                    engine.continue_talk(&mut gd, None)
+                } else if let Some(&c) = choices.next() {
+                    engine.continue_talk(&mut gd, Some(c))
+                } else {
+                    return Ok(Some(n_choice));
+                }
+            }
+            // Windows opened by scripts return to the script when closed
+            _ => engine.exec(&mut gd),
+        };
+    }
+    Err("too many talks without quitting".to_owned())
+}
+
+fn stub_game_data() -> GameData {
+    let mut gd = GameData::empty();
+    let rid = gd.region.add_region(Region::new("test-region", Map::new(1, 1), 0));
+    gd.set_initial_mapid(MapId::RegionMap { rid });
+    gd.add_chara(Chara::default(), CharaKind::Player);
+    gd
+}
+
+#[test]
+fn check_script_test() {
+    // Inline texts of loaded scripts are registered when talk texts are loaded
+    super::script::init_test_objs();
+    let mut map = common::hashmap::HashMap::default();
+    map.insert("start".to_owned(), vec![
+        Instruction::Talk("greeting".to_owned(), vec![
+            ("yes".to_owned(), "sub".to_owned()), ("no".to_owned(), QUIT_SECTION.to_owned())]),
+    ]);
+    map.insert("sub".to_owned(), vec![
+        Instruction::JumpIf("missing".to_owned(), Expr::Value(Value::Bool(false))),
+    ]);
+    map.insert("orphan".to_owned(), vec![]);
+    let script = Script::from_map(map);
+
+    // Texts are not loaded in unit tests
+    assert_eq!(check_script(&script), vec![
+        "unknown section \"missing\"",
+        "unknown text id \"greeting\"",
+        "unknown text id \"no\"",
+        "unknown text id \"yes\"",
+        "unreachable section \"orphan\"",
+    ]);
+
+    let mut map = common::hashmap::HashMap::default();
+    map.insert("start".to_owned(), vec![Instruction::Jump("start".to_owned())]);
+    let script = Script::from_map(map);
+    assert_eq!(check_script(&script), vec!["too many instructions without talking (choices [])"]);
+}
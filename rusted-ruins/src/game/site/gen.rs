@@ -10,6 +10,14 @@ pub fn add_unique_citizens(gd: &mut GameData, sid: SiteId, sg: &SiteGenObject) {
         let mut chara = game::chara::gen::create_chara(gobj::id_to_idx(&uc.chara_template_id), 1);
         let mid = MapId::SiteMap { sid, floor: uc.floor };
         chara.rel = common::gamedata::chara::Relationship::FRIENDLY;
+        chara.faction = Faction::Citizen;
+        chara.ai.needs = Some(CharaNeeds::default());
+        if sg.shops.iter().any(|shop| shop.chara_n == uc.n) {
+            chara.ai.kind = NpcAIKind::Shopkeeper;
+        }
+        let mut schedule = uc.schedule.clone();
+        schedule.sort_by_key(|entry| entry.hour);
+        chara.ai.schedule = schedule;
         
         if let Some(talk_script_id) = uc.talk_script_id.as_ref() { // Talk script setting
             chara.trigger_talk = Some(talk_script_id.to_owned());
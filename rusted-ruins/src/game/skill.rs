@@ -4,6 +4,11 @@ use common::basic::SKILL_EXP_LVUP;
 use common::gamedata::*;
 use rules::RULES;
 
+/// A skill check succeeds if the skill level and the roll exceed the difficulty
+pub fn skill_check(skill_level: u32, difficulty: u32, roll: u32) -> bool {
+    skill_level + roll > difficulty
+}
+
 pub trait SkillListEx {
     fn add_exp(&mut self, kind: SkillKind, add_exp: u32, base_level: u32) -> (bool, u32);
     fn learn_new_skill(&mut self, kind: SkillKind);
@@ -117,3 +122,9 @@ fn search_adjust_coeff(base_level: u32, skill_level: u32) -> f32 {
     }
 }
 
+#[test]
+fn skill_check_test() {
+    assert!(skill_check(0, 0, 1));
+    assert!(!skill_check(3, 8, 5));
+    assert!(skill_check(3, 8, 6));
+}
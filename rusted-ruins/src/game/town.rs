@@ -1,8 +1,9 @@
 
 use array2d::Vec2d;
-use common::obj::SiteGenObject;
+use common::obj::{SiteGenObject, MapTemplateObject};
 use common::gamedata::*;
 use common::gobj;
+use rules::RULES;
 use super::saveload::gen_box_id;
 
 /// Create town from SiteGenObect and add it to region map
@@ -12,6 +13,7 @@ pub fn add_town(gd: &mut GameData, rid: RegionId, pos: Vec2d, town_id: &str) {
     let mut site = Site::new(sg.map_template_id.len() as u32);
     let site_content = SiteContent::Town { town: Box::new(town) };
     site.content = site_content;
+    site.weather = sg.weather;
     let sid = if let Some(sid) = gd.add_site(site, SiteKind::Town, rid, pos) {
         sid
     } else {
@@ -20,11 +22,13 @@ pub fn add_town(gd: &mut GameData, rid: RegionId, pos: Vec2d, town_id: &str) {
     };
 
     for map_template_id in &sg.map_template_id {
-        let map = super::map::from_template::from_template_id(map_template_id)
+        let t: &MapTemplateObject = gobj::get_by_id_checked(map_template_id)
             .expect(&format!("Map template not found: {}", map_template_id));
+        let map = super::map::from_template::from_template(t);
 
         let map_random_id = gen_box_id(gd);
-        gd.add_map(map, sid, map_random_id);
+        let mid = gd.add_map(map, sid, map_random_id);
+        super::map::from_template::gen_charas(gd, mid, t);
     }
 
     super::site::gen::add_unique_citizens(gd, sid, sg);
@@ -71,5 +75,28 @@ pub fn update_shops(gd: &mut GameData, sid: SiteId) {
     for shop in town.iter_shops_mut() {
         update_items_on_shop(shop)
     }
+    town.set_shops_updated(gd.time.current_time());
+}
+
+/// Restock shops if enough days have passed since the last update
+pub fn restock_shops(gd: &mut GameData, sid: SiteId) {
+    let shops_updated = match &gd.region.get_site(sid).content {
+        SiteContent::Town { ref town } => town.shops_updated(),
+        _ => { return; }
+    };
+    if is_restock_needed(shops_updated, gd.time.current_time(), RULES.town.shop_restock_days) {
+        update_shops(gd, sid);
+    }
+}
+
+fn is_restock_needed(shops_updated: Time, now: Time, restock_days: u64) -> bool {
+    now >= shops_updated && now.duration_from(shops_updated) >= Duration::from_days(restock_days)
+}
+
+#[test]
+fn is_restock_needed_test() {
+    let updated = Time::from_seconds(SECS_PER_DAY);
+    assert!(!is_restock_needed(updated, Time::from_seconds(SECS_PER_DAY * 3), 7));
+    assert!(is_restock_needed(updated, Time::from_seconds(SECS_PER_DAY * 8), 7));
 }
 
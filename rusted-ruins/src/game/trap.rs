@@ -0,0 +1,121 @@
+//! Detecting, triggering and disarming traps
+
+use array2d::*;
+use common::gamedata::*;
+use common::gobj;
+use common::obj::{TrapParams, TrapEffect};
+use common::objholder::SpecialTileIdx;
+use rng;
+use rules::RULES;
+use super::{Game, InfoGetter};
+use super::chara::CharaEx;
+use super::combat::DamageKind;
+use super::skill::skill_check;
+
+fn roll_trap_dice() -> u32 {
+    rng::gen_range(1, RULES.params.trap_dice + 1)
+}
+
+fn trap_params(idx: SpecialTileIdx) -> Option<&'static TrapParams> {
+    let params = gobj::get_obj(idx).trap.as_ref();
+    if params.is_none() {
+        warn!("special tile \"{}\" is not a trap", gobj::idx_to_id(idx));
+    }
+    params
+}
+
+/// The player tries to detect hidden traps around by Perception skill
+pub fn detect_traps(gd: &mut GameData) {
+    let center = gd.player_pos();
+    let range = RULES.params.trap_detect_range;
+    let skill_level = gd.chara.get(CharaId::Player).skills.get(SkillKind::Perception);
+
+    for (_, pos) in MDistRangeIter::new(center, range) {
+        let map = gd.get_current_map_mut();
+        if !map.is_inside(pos) {
+            continue;
+        }
+        let idx = match map.tile[pos].special {
+            SpecialTileKind::Trap { idx, detected: false } => idx,
+            _ => { continue; }
+        };
+        let params = if let Some(params) = trap_params(idx) { params } else { continue; };
+        if skill_check(skill_level, params.difficulty, roll_trap_dice()) {
+            map.tile[pos].special = SpecialTileKind::Trap { idx, detected: true };
+            let player = gd.chara.get_mut(CharaId::Player);
+            game_log_i!("trap-detect"; chara=player);
+            player.add_skill_exp(SkillKind::Perception, RULES.exp.detect_trap, params.difficulty);
+        }
+    }
+}
+
+/// Trigger the trap at the position by the character stepping on it
+pub fn trigger(game: &mut Game, cid: CharaId, pos: Vec2d) {
+    let idx = match game.gd.get_current_map().tile[pos].special {
+        SpecialTileKind::Trap { idx, .. } => idx,
+        _ => { return; }
+    };
+    let params = if let Some(params) = trap_params(idx) { params } else { return; };
+    // Triggered traps are revealed
+    game.gd.get_current_map_mut().tile[pos].special = SpecialTileKind::Trap { idx, detected: true };
+
+    match params.effect {
+        TrapEffect::Damage { dice_n, dice_x } => {
+            let damage = rng::dice(dice_n as i32, dice_x as i32);
+            game_log_i!("trap-damage"; chara=game.gd.chara.get(cid), damage=damage);
+            super::chara::damage(game, cid, damage, DamageKind::Trap);
+        }
+        TrapEffect::Teleport => {
+            let map = game.gd.get_current_map_mut();
+            if let Some(dest) = super::map::choose_empty_tile(map) {
+                map.locate_chara(cid, dest);
+            }
+            game_log_i!("trap-teleport"; chara=game.gd.chara.get(cid));
+        }
+        TrapEffect::Alarm { n_npc } => {
+            game_log_i!("trap-alarm"; chara=game.gd.chara.get(cid));
+            let mid = game.gd.get_current_mapid();
+            if !mid.is_region_map() {
+                super::map::gen_npcs(&mut game.gd, mid, n_npc, mid.floor());
+            }
+        }
+    }
+}
+
+/// The player tries to disarm a detected trap on or around the player's tile.
+/// Returns true if the player spends the turn.
+pub fn disarm(game: &mut Game) -> bool {
+    let center = game.gd.player_pos();
+    let map = game.gd.get_current_map();
+    let pos = std::iter::once(center)
+        .chain(Direction::EIGHT_DIRS.iter().map(|dir| center + dir.as_vec()))
+        .find(|&p| map.is_inside(p) && match map.tile[p].special {
+            SpecialTileKind::Trap { detected: true, .. } => true,
+            _ => false,
+        });
+    let pos = if let Some(pos) = pos { pos } else {
+        game_log_i!("trap-not-found");
+        return false;
+    };
+    let idx = match map.tile[pos].special {
+        SpecialTileKind::Trap { idx, .. } => idx,
+        _ => unreachable!(),
+    };
+    let params = if let Some(params) = trap_params(idx) { params } else { return false; };
+
+    let roll = roll_trap_dice();
+    let player = game.gd.chara.get_mut(CharaId::Player);
+    let skill_level = player.skills.get(SkillKind::Lockpicking);
+    if skill_check(skill_level, params.difficulty, roll) {
+        game_log_i!("trap-disarm"; chara=player);
+        player.add_skill_exp(SkillKind::Lockpicking, RULES.exp.disarm_trap, params.difficulty);
+        game.gd.get_current_map_mut().tile[pos].special = SpecialTileKind::None;
+    } else if roll == 1 {
+        // Fumbled disarming sets the trap off
+        game_log_i!("trap-disarm-failure"; chara=player);
+        trigger(game, CharaId::Player, pos);
+    } else {
+        game_log_i!("trap-disarm-failure"; chara=player);
+    }
+    true
+}
@@ -3,7 +3,7 @@ use common::basic::WAIT_TIME_NUMERATOR;
 use common::gamedata::*;
 use rules::RULES;
 use crate::text::ToText;
-use super::{Game, GameState};
+use super::{Game, GameState, InfoGetter};
 use super::chara::CharaEx;
 use super::chara::preturn::preturn;
 use super::npc::process_npc_turn;
@@ -21,6 +21,7 @@ pub fn turn_loop(game: &mut Game) {
 
         if preturn(game, cid) {
             if cid == CharaId::Player {
+                super::map::respawn_npcs(game);
                 game.state = GameState::PlayerTurn;
                 game.update_before_player_turn();
                 return;
@@ -63,6 +64,7 @@ fn remove_dying_charas(game: &mut Game) {
                 game.request_dialog_open(DialogOpenRequest::GameOver);
                 return;
             }
+            drop_items_on_death(&mut game.gd, cid);
             // Remove dying chara
             game.gd.remove_chara(cid);
             // If the current target is cid, remove it
@@ -73,15 +75,33 @@ fn remove_dying_charas(game: &mut Game) {
     }
 }
 
+/// Dying characters drop their belongings and equipments on their tile
+fn drop_items_on_death(gd: &mut GameData, cid: CharaId) {
+    let pos = if let Some(pos) = gd.chara_pos(cid) { pos } else { return; };
+    let items: Vec<(Item, u32)> = {
+        let chara = gd.chara.get_mut(cid);
+        let mut items = std::mem::replace(&mut chara.item_list, ItemList::new()).items;
+        items.extend(chara.equip.item_iter().map(|(_, _, item)| (item.clone(), 1)));
+        items
+    };
+    let map = gd.get_current_map_mut();
+    for (item, n) in items {
+        map.locate_item(item, pos, n);
+    }
+}
+
 fn advance_game_time(game: &mut Game, advanced_clock: u32) {
     let mid = game.gd.get_current_mapid();
     let minutes_per_turn = if mid.is_region_map() {
-        RULES.params.minutes_per_turn_region
+        // Mounts change the travel speed
+        let travel_time = super::mount::travel_time(game.gd.chara.get(CharaId::Player));
+        RULES.params.minutes_per_turn_region * travel_time as f32 / 100.0
     } else {
         RULES.params.minutes_per_turn_normal
     };
     const AVERAGE_CLOCK_PER_TURN: u32 = WAIT_TIME_NUMERATOR / 100;
     let advanced_secs = minutes_per_turn * 60.0 * advanced_clock as f32 / AVERAGE_CLOCK_PER_TURN as f32;
-    game.gd.time.advance(advanced_secs as u64)
+    game.gd.time.advance(advanced_secs as u64);
+    super::weather::update(&mut game.gd);
 }
 
@@ -57,7 +57,8 @@ pub fn update_view_map(game: &mut Game) {
     view_map.fill(w, h, false); // Fill by false
 
     let player_pos = game.gd.player_pos();
-    let player_view_range = game.gd.chara.get(CharaId::Player).attr.view_range;
+    let player_view_range = crate::game::weather::view_range(
+        &game.gd, game.gd.chara.get(CharaId::Player).attr.view_range);
 
     view_map.visible[player_pos] = true;
 
@@ -66,7 +67,7 @@ pub fn update_view_map(game: &mut Game) {
 
         for p in LineIter::new(player_pos, pos).skip(1) {
             view_map.visible[p] = true;
-            if !map.tile[p].wall.is_empty() {
+            if !map.tile[p].wall.is_empty() || map.tile[p].special.is_closed_door() {
                 break;
             }
         }
@@ -75,7 +76,7 @@ pub fn update_view_map(game: &mut Game) {
 
 pub fn calc_visual_distance(map: &Map, orig: Vec2d, dist: Vec2d) -> Option<i32> {
     for pos in LineIter::new(orig, dist) {
-        if !map.tile[pos].wall.is_empty() {
+        if !map.tile[pos].wall.is_empty() || map.tile[pos].special.is_closed_door() {
             return None;
         }
     }
@@ -0,0 +1,99 @@
+
+//! Weather changes of regions and their effects
+
+use std::cmp::min;
+use common::gamedata::*;
+use rules::RULES;
+
+/// Change the weather of the current region if its time has come
+pub fn update(gd: &mut GameData) {
+    let now = gd.time.current_time();
+    let month = gd.time.current_date().month;
+    let region = gd.get_current_region_mut();
+    if now < region.next_weather_change {
+        return;
+    }
+
+    let r = &RULES.weather;
+    let weather = seasonal(choose_weather(&r.weights), month, &r.snow_months);
+    let hours = rng::gen_range(r.min_hours, r.max_hours + 1);
+    let is_changed = region.weather != weather;
+    set_weather(gd, weather, hours);
+    if is_changed && visible_weather(gd).is_some() {
+        game_log_i!("weather-change"; weather=weather);
+    }
+}
+
+/// Set the weather of the current region. The weather lasts for given hours.
+pub fn set_weather(gd: &mut GameData, weather: Weather, hours: u64) {
+    let mut next_change = gd.time.current_time();
+    next_change.advance(hours * SECS_PER_HOUR);
+    let region = gd.get_current_region_mut();
+    region.weather = weather;
+    region.next_weather_change = next_change;
+}
+
+/// The weather at the current location. Sites can have their own weathers.
+pub fn local_weather(gd: &GameData) -> Weather {
+    let mid = gd.get_current_mapid();
+    let region_weather = gd.get_current_region().weather;
+    if mid.is_region_map() {
+        return region_weather;
+    }
+    gd.region.get_site(mid.sid()).weather.unwrap_or(region_weather)
+}
+
+/// The weather seen by the player. Returns None in dungeons.
+pub fn visible_weather(gd: &GameData) -> Option<Weather> {
+    let mid = gd.get_current_mapid();
+    if !mid.is_region_map() {
+        if let SiteContent::AutoGenDungeon { .. } = gd.region.get_site(mid.sid()).content {
+            return None;
+        }
+    }
+    Some(local_weather(gd))
+}
+
+/// Sight radius narrowed by fog
+pub fn view_range(gd: &GameData, view_range: i32) -> i32 {
+    if visible_weather(gd) == Some(Weather::Fog) {
+        min(view_range, RULES.weather.fog_view_range)
+    } else {
+        view_range
+    }
+}
+
+fn choose_weather(weights: &[(Weather, f32)]) -> Weather {
+    let sum: f32 = weights.iter().map(|&(_, w)| w).sum();
+    if sum <= 0.0 {
+        return Weather::Fine;
+    }
+    let r = rng::gen_range(0.0, sum);
+    let mut sum = 0.0;
+    for &(weather, w) in weights {
+        sum += w;
+        if r < sum {
+            return weather;
+        }
+    }
+    Weather::Fine
+}
+
+/// Snow falls instead of rain in snow months, and rain instead of snow in other months
+fn seasonal(weather: Weather, month: u16, snow_months: &[u16]) -> Weather {
+    let is_snow_month = snow_months.contains(&month);
+    match weather {
+        Weather::Rain if is_snow_month => Weather::Snow,
+        Weather::Snow if !is_snow_month => Weather::Rain,
+        _ => weather,
+    }
+}
+
+#[test]
+fn seasonal_test() {
+    let snow_months = [1, 2, 12];
+    assert_eq!(seasonal(Weather::Rain, 1, &snow_months), Weather::Snow);
+    assert_eq!(seasonal(Weather::Rain, 6, &snow_months), Weather::Rain);
+    assert_eq!(seasonal(Weather::Snow, 6, &snow_months), Weather::Rain);
+    assert_eq!(seasonal(Weather::Fog, 12, &snow_months), Weather::Fog);
+}
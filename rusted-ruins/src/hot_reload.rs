@@ -0,0 +1,94 @@
+
+//! Watches pak and text files during development, and reloads them if changed
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+use crate::config::{self, CONFIG};
+
+/// Files are checked at this interval
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Kinds of files changed since the last check
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Changed {
+    pub pak: bool,
+    pub text: bool,
+}
+
+pub struct HotReloader {
+    last_check: Instant,
+    paks: HashMap<PathBuf, SystemTime>,
+    texts: HashMap<PathBuf, SystemTime>,
+}
+
+impl HotReloader {
+    /// Returns None if hot reloading is disabled in config
+    pub fn new() -> Option<HotReloader> {
+        if !CONFIG.hot_reload {
+            return None;
+        }
+        info!("Hot reloading of pak and text files is enabled");
+        Some(HotReloader {
+            last_check: Instant::now(),
+            paks: modified_times(&config::PAK_DIRS, "pak"),
+            texts: modified_times(&text_dirs(), "txt"),
+        })
+    }
+
+    /// Check modified times of files if the interval has passed
+    pub fn check(&mut self) -> Changed {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return Changed::default();
+        }
+        self.last_check = Instant::now();
+
+        let paks = modified_times(&config::PAK_DIRS, "pak");
+        let texts = modified_times(&text_dirs(), "txt");
+        let changed = Changed {
+            pak: paks != self.paks,
+            text: texts != self.texts,
+        };
+        self.paks = paks;
+        self.texts = texts;
+        changed
+    }
+}
+
+fn text_dirs() -> Vec<PathBuf> {
+    config::get_data_dirs().into_iter().map(|dir| dir.join("text")).collect()
+}
+
+/// Modified times of files that have the extension in the directories
+fn modified_times<P: AsRef<Path>>(dirs: &[P], extension: &str) -> HashMap<PathBuf, SystemTime> {
+    let mut map = HashMap::new();
+    for dir in dirs {
+        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() ||
+                entry.path().extension().map_or(true, |e| e != extension) {
+                continue;
+            }
+            if let Some(time) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                map.insert(entry.path().to_owned(), time);
+            }
+        }
+    }
+    map
+}
+
+#[test]
+fn modified_times_test() {
+    let dir = std::env::temp_dir().join("rusted-ruins-hot-reload-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.pak"), b"a").unwrap();
+    std::fs::write(dir.join("b.txt"), b"b").unwrap();
+
+    let times = modified_times(&[&dir], "pak");
+    assert_eq!(times.len(), 1);
+    assert!(times.contains_key(&dir.join("a.pak")));
+    std::fs::remove_file(dir.join("a.pak")).unwrap();
+    assert_ne!(modified_times(&[&dir], "pak"), times);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -8,7 +8,7 @@ pub fn init() {
 
 lazy_static! {
     static ref GAME_LOG: Mutex<GameLog> = Mutex::new(GameLog::new());
-    static ref LOG_MAX_LINE: usize = 30;
+    static ref LOG_MAX_LINE: usize = 500;
 }
 
 pub struct GameLog {
@@ -8,6 +8,7 @@ extern crate rusted_ruins_map_generator as map_generator;
 #[macro_use]
 extern crate lazy_static;
 extern crate failure;
+extern crate serde_cbor;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
@@ -26,21 +27,116 @@ mod game;
 mod draw;
 mod window;
 mod screen;
+mod screenshot;
+mod hot_reload;
 mod eventhandler;
+mod replay;
 mod sdltypeconv;
 
 fn main() {
     setup_logger();
-    init_lazy_statics();
+    // Objects are loaded before texts because inline texts in scripts are registered as talk texts
     init_obj();
+    init_lazy_statics();
     init_rules();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Verify scripts without SDL, and exit with failure if problems are found
+    if args.test_scripts {
+        let n_problem = game::script_check::check_all();
+        std::process::exit(if n_problem == 0 { 0 } else { 1 });
+    }
+
+    let replay = match init_replay(&args) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     
     let sdl_context = SdlContext::init();
     let mut screen = screen::Screen::new(&sdl_context.sdl_context);
+    if let Some(replay) = replay {
+        screen.set_replay(replay);
+    }
 
     screen.main_loop(&sdl_context);
 }
 
+#[derive(Default, PartialEq, Eq, Debug)]
+struct Args {
+    test_scripts: bool,
+    /// Seed for deterministic mode
+    seed: Option<u64>,
+    /// File to record input commands
+    record: Option<String>,
+    /// File to play back recorded commands
+    replay: Option<String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value", name));
+        match arg.as_str() {
+            "--test-scripts" => { parsed.test_scripts = true; }
+            "--seed" => {
+                let seed = value("--seed")?;
+                parsed.seed = Some(seed.parse().map_err(|_| format!("invalid seed \"{}\"", seed))?);
+            }
+            "--record" => { parsed.record = Some(value("--record")?); }
+            "--replay" => { parsed.replay = Some(value("--replay")?); }
+            _ => { return Err(format!("unknown argument \"{}\"", arg)); }
+        }
+    }
+    if parsed.record.is_some() && parsed.replay.is_some() {
+        return Err("--record and --replay cannot be used together".to_owned());
+    }
+    Ok(parsed)
+}
+
+/// Seed the random number generator, and prepare recording or playing back of inputs.
+/// Recording always uses deterministic mode to make the record reproducible.
+fn init_replay(args: &Args) -> Result<Option<replay::Replay>, String> {
+    use rng::Rng;
+
+    if let Some(ref path) = args.replay {
+        let (player, seed) = replay::Player::load(path)?;
+        match args.seed.or(seed) {
+            Some(seed) => rng::set_deterministic(seed),
+            None => warn!("The replay has no seed, so the game may not be reproduced"),
+        }
+        info!("Replaying inputs from \"{}\"", path);
+        return Ok(Some(replay::Replay::Play(player)));
+    }
+
+    let seed = match (args.seed, &args.record) {
+        (Some(seed), _) => seed,
+        (None, Some(_)) => rng::thread_rng().gen(),
+        (None, None) => {
+            rng::reseed();
+            return Ok(None);
+        }
+    };
+    rng::set_deterministic(seed);
+    info!("Deterministic mode with seed {}", seed);
+
+    if let Some(ref path) = args.record {
+        let recorder = replay::Recorder::create(path, seed)?;
+        info!("Recording inputs to \"{}\"", path);
+        return Ok(Some(replay::Replay::Record(recorder)));
+    }
+    Ok(None)
+}
+
 pub struct SdlContext {
     pub sdl_context: sdl2::Sdl,
     pub ttf_context: sdl2::ttf::Sdl2TtfContext,
@@ -54,7 +150,8 @@ impl SdlContext {
             sdl_context: sdl2::init().expect("Init Failed : SDL Context"),
             ttf_context: sdl2::ttf::init().expect("Init Failed : SDL_ttf Context"),
             _image: sdl2::image::init(sdl2::image::InitFlag::PNG).expect("Init Failed : SDL_Image"),
-            _audio_context: audio::init(&config::get_data_dirs()),
+            _audio_context: audio::init(
+                &config::get_data_dirs(), config::CONFIG.music_volume, config::CONFIG.sound_volume),
         }
     }
 }
@@ -67,12 +164,11 @@ fn init_lazy_statics() {
 }
 
 fn init_obj() {
-    let mut data_dirs = crate::config::get_data_dirs();
-    for d in data_dirs.iter_mut() {
+    let pak_dirs = crate::config::PAK_DIRS.clone();
+    for d in &pak_dirs {
         info!("Loading objects from \"{}\"", d.to_string_lossy());
-        d.push("paks");
     }
-    common::gobj::init(data_dirs);
+    common::gobj::init(pak_dirs);
 }
 
 fn init_rules() {
@@ -93,3 +189,17 @@ fn setup_logger() {
     }
     builder.init();
 }
+
+#[test]
+fn parse_args_test() {
+    let args = |a: &[&str]| parse_args(a.iter().map(|s| s.to_string()));
+
+    assert_eq!(args(&[]), Ok(Args::default()));
+    assert_eq!(args(&["--seed", "42", "--record", "a.toml"]), Ok(Args {
+        seed: Some(42), record: Some("a.toml".to_owned()), .. Args::default()
+    }));
+    assert!(args(&["--seed"]).is_err());
+    assert!(args(&["--seed", "x"]).is_err());
+    assert!(args(&["--record", "a.toml", "--replay", "b.toml"]).is_err());
+    assert!(args(&["--unknown"]).is_err());
+}
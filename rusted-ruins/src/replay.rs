@@ -0,0 +1,164 @@
+
+//! Records input commands to a file and plays them back, to reproduce games.
+//! Used by `rusted-ruins --record <file>` and `rusted-ruins --replay <file>`.
+//! Replay files are a header and recorded commands as a sequence of CBOR values.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use crate::game::Command;
+
+#[derive(Serialize, Deserialize)]
+struct ReplayHeader {
+    seed: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct RecordedCommand {
+    /// The number of polls without commands before this command.
+    /// Needed because traveling continues while there are no inputs.
+    skip: u32,
+    command: Command,
+}
+
+pub enum Replay {
+    Record(Recorder),
+    Play(Player),
+}
+
+impl Replay {
+    /// Get the next command from the replay file, or record the command got from inputs
+    pub fn command<F: FnOnce() -> Option<Command>>(&mut self, input: F) -> Option<Command> {
+        match self {
+            Replay::Record(recorder) => {
+                let command = input();
+                recorder.record(command.as_ref());
+                command
+            }
+            Replay::Play(player) => {
+                match player.next() {
+                    Some(command) => command,
+                    None => input(),
+                }
+            }
+        }
+    }
+}
+
+/// Appends commands to the file as they are input
+pub struct Recorder {
+    file: File,
+    skip: u32,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(path: P, seed: u64) -> Result<Recorder, String> {
+        let path = path.as_ref();
+        let mut file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_cbor::to_writer(&mut file, &ReplayHeader { seed: Some(seed) })
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Recorder { file, skip: 0 })
+    }
+
+    fn record(&mut self, command: Option<&Command>) {
+        let command = if let Some(command) = command {
+            command.clone()
+        } else {
+            self.skip += 1;
+            return;
+        };
+        let recorded = RecordedCommand { skip: self.skip, command };
+        self.skip = 0;
+
+        // Written for each command to keep the record if the game crashes
+        let result = serde_cbor::to_vec(&recorded).map_err(|e| e.to_string()).and_then(|data| {
+            self.file.write_all(&data).map_err(|e| e.to_string())
+        });
+        if let Err(e) = result {
+            warn!("Cannot record command: {}", e);
+        }
+    }
+}
+
+/// Plays back recorded commands
+pub struct Player {
+    commands: VecDeque<RecordedCommand>,
+    skip: u32,
+    finished: bool,
+}
+
+impl Player {
+    /// Load the replay file. Returns the player and the seed used in recording.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Player, Option<u64>), String> {
+        let path = path.as_ref();
+        let error = |e: &std::fmt::Display| format!("{}: {}", path.display(), e);
+        let mut data = Vec::new();
+        File::open(path).and_then(|mut file| file.read_to_end(&mut data)).map_err(|e| error(&e))?;
+
+        let mut de = serde_cbor::Deserializer::from_slice(&data);
+        let header: ReplayHeader = serde::Deserialize::deserialize(&mut de).map_err(|e| error(&e))?;
+        let mut commands = Vec::new();
+        for recorded in de.into_iter::<RecordedCommand>() {
+            match recorded {
+                Ok(recorded) => commands.push(recorded),
+                Err(e) => {
+                    // The last command may be broken if the game crashed while recording
+                    warn!("{}", error(&e));
+                    break;
+                }
+            }
+        }
+        Ok((Player::new(commands), header.seed))
+    }
+
+    fn new(commands: Vec<RecordedCommand>) -> Player {
+        Player { commands: commands.into(), skip: 0, finished: false }
+    }
+
+    /// Returns None after all commands are played back
+    fn next(&mut self) -> Option<Option<Command>> {
+        let recorded = if let Some(recorded) = self.commands.front() {
+            recorded
+        } else {
+            if !self.finished {
+                self.finished = true;
+                info!("Replay finished");
+            }
+            return None;
+        };
+        if self.skip < recorded.skip {
+            self.skip += 1;
+            return Some(None);
+        }
+        self.skip = 0;
+        Some(self.commands.pop_front().map(|recorded| recorded.command))
+    }
+}
+
+#[test]
+fn replay_test() {
+    use array2d::Direction;
+
+    let path = std::env::temp_dir().join("rusted-ruins-replay-test");
+    let commands = vec![
+        None, Some(Command::Move { dir: Direction::N }), Some(Command::Enter),
+        None, None, Some(Command::TextInput { text: "a".to_owned() }),
+    ];
+    {
+        let mut replay = Replay::Record(Recorder::create(&path, 42).unwrap());
+        for command in &commands {
+            assert_eq!(replay.command(|| command.clone()), *command);
+        }
+    }
+
+    let (player, seed) = Player::load(&path).unwrap();
+    assert_eq!(seed, Some(42));
+    let mut replay = Replay::Play(player);
+    for command in &commands {
+        assert_eq!(replay.command(|| panic!("inputs are used while replaying")), *command);
+    }
+    assert_eq!(replay.command(|| Some(Command::Cancel)), Some(Command::Cancel));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -1,14 +1,29 @@
 
+use std::cell::Cell;
 use std::time::{Duration, Instant};
 use std::thread::sleep;
 use sdl2;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::render::WindowCanvas;
 use sdl2::pixels::Color;
 use crate::config::{CONFIG, SCREEN_CFG};
 
 use crate::eventhandler::EventHandler;
+use crate::hot_reload::HotReloader;
 use crate::window::WindowManager;
 
+thread_local!(static SCREEN_SIZE: Cell<(u32, u32)> = Cell::new((SCREEN_CFG.screen_w, SCREEN_CFG.screen_h)));
+
+/// Current screen size in unscaled pixels.
+/// It can be larger than the size in screen config after the window is resized.
+pub fn screen_size() -> (u32, u32) {
+    SCREEN_SIZE.with(|screen_size| screen_size.get())
+}
+
+fn ui_scale() -> u32 {
+    std::cmp::max(CONFIG.ui_scale, 1)
+}
+
 pub struct Screen {
     canvas: WindowCanvas,
     event_handler: EventHandler,
@@ -18,11 +33,17 @@ impl Screen {
     pub fn new(sdl_context: &sdl2::Sdl) -> Screen {
         let video_subsystem = sdl_context.video().expect("Init Failed : SDL Video Subsystem");
 
-        let window = video_subsystem.window(
-            "Rusted Ruins", SCREEN_CFG.screen_w, SCREEN_CFG.screen_h)
+        let scale = ui_scale();
+        let mut window = video_subsystem.window(
+            "Rusted Ruins", SCREEN_CFG.screen_w * scale, SCREEN_CFG.screen_h * scale)
             .position_centered()
+            .resizable()
             .build()
             .unwrap();
+        // Layouts in screen config are not designed for smaller screens
+        if let Err(e) = window.set_minimum_size(SCREEN_CFG.screen_w * scale, SCREEN_CFG.screen_h * scale) {
+            warn!("Cannot set minimum window size: {}", e);
+        }
 
         let canvas_builder = window.into_canvas();
         let canvas_builder = if CONFIG.hardware_acceleration {
@@ -30,7 +51,10 @@ impl Screen {
         }else{
             canvas_builder.software()
         };
-        let canvas = canvas_builder.build().unwrap();
+        let mut canvas = canvas_builder.build().unwrap();
+        if let Err(e) = canvas.set_scale(scale as f32, scale as f32) {
+            warn!("Cannot set ui scale: {}", e);
+        }
 
         Screen {
             canvas,
@@ -38,6 +62,10 @@ impl Screen {
         }
     }
 
+    pub fn set_replay(&mut self, replay: crate::replay::Replay) {
+        self.event_handler.set_replay(replay);
+    }
+
     pub fn main_loop(&mut self, sdl_context: &crate::SdlContext) {
         let fps_duration = Duration::from_millis(1000 / 30);
         let mut event_pump = sdl_context.sdl_context.event_pump().unwrap();
@@ -46,15 +74,27 @@ impl Screen {
         let mut is_skip_next_frame = false;
         let texture_creator = self.canvas.texture_creator();
         let mut window_manager = WindowManager::new(sdl_context, &texture_creator);
-        
+        let mut hot_reloader = HotReloader::new();
+
         'mainloop: loop {
             self.event_handler.update_dir(&event_pump);
             for event in event_pump.poll_iter() {
+                if let Event::Window { win_event: WindowEvent::SizeChanged(w, h), .. } = event {
+                    resize(w as u32, h as u32, &mut window_manager);
+                    continue;
+                }
                 if !self.event_handler.process_event(event) {
                     break 'mainloop;
                 }
             }
             
+            if let Some(hot_reloader) = hot_reloader.as_mut() {
+                let changed = hot_reloader.check();
+                if changed.pak || changed.text {
+                    window_manager.reload(changed);
+                }
+            }
+
             if !window_manager.animation_now() {
                 if !window_manager.advance_turn(&mut self.event_handler) { break 'mainloop }
             }
@@ -85,3 +125,17 @@ impl Screen {
     }
 }
 
+
+/// Update the screen size by the new window size, and recreate windows to fit it
+fn resize(window_w: u32, window_h: u32, window_manager: &mut WindowManager) {
+    let scale = ui_scale();
+    let size = (
+        std::cmp::max(window_w / scale, SCREEN_CFG.screen_w),
+        std::cmp::max(window_h / scale, SCREEN_CFG.screen_h));
+    if size == screen_size() {
+        return;
+    }
+    info!("Screen resized to {}x{}", size.0, size.1);
+    SCREEN_SIZE.with(|screen_size| screen_size.set(size));
+    window_manager.relayout();
+}
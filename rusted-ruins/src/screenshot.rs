@@ -0,0 +1,133 @@
+
+//! Saving the screen and the whole current map to PNG files
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sdl2::image::SaveSurface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::surface::Surface;
+use sdl2::pixels::Color;
+use common::basic::TILE_SIZE_I;
+use crate::config::USER_DIR;
+use crate::context::Context;
+use crate::draw::mainwin::MainWinDrawer;
+use crate::game::Game;
+use crate::screen::screen_size;
+
+/// Images are written in this directory under the user directory
+const SCREENSHOT_DIR_NAME: &str = "screenshots";
+/// Map images larger than this are scaled down
+const MAX_MAP_IMAGE_SIZE: i32 = 4096;
+const PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CaptureRequest {
+    Screen,
+    /// The whole current map
+    Map,
+}
+
+/// Save the current frame. Must be called after drawing all windows.
+pub fn save_screen(canvas: &mut WindowCanvas) {
+    canvas.set_viewport(None);
+    let (w, h) = match canvas.output_size() {
+        Ok(size) => size,
+        Err(e) => { warn!("Failed to take a screenshot: {}", e); return; }
+    };
+    let result = read_surface(canvas, Rect::new(0, 0, w, h))
+        .and_then(|surface| save(&surface, "screenshot"));
+    if let Err(e) = result {
+        warn!("Failed to take a screenshot: {}", e);
+    }
+}
+
+/// Render the whole current map page by page, and save it.
+/// All tiles are drawn as observed, and the observed state is restored after that.
+pub fn export_map(context: &mut Context, game: &mut Game) {
+    let map = game.gd.get_current_map_mut();
+    let observed_tile = map.observed_tile.clone();
+    crate::game::map::observe_all_tiles(map);
+
+    if let Err(e) = render_map(context, game) {
+        warn!("Failed to export the map: {}", e);
+    }
+
+    game.gd.get_current_map_mut().observed_tile = observed_tile;
+    context.set_viewport(None);
+    context.canvas.set_draw_color(Color::RGB(0, 0, 0));
+    context.canvas.clear();
+}
+
+fn render_map(context: &mut Context, game: &Game) -> Result<(), String> {
+    let map = game.gd.get_current_map();
+    let (map_w, map_h) = (map.w as i32 * TILE_SIZE_I, map.h as i32 * TILE_SIZE_I);
+    let scale = (MAX_MAP_IMAGE_SIZE as f32 / std::cmp::max(map_w, map_h) as f32).min(1.0);
+    let scaled = |a: i32| (a as f32 * scale).ceil() as u32;
+    let mut image = Surface::new(scaled(map_w), scaled(map_h), PIXEL_FORMAT)?;
+
+    let (page_w, page_h) = screen_size();
+    let (page_w, page_h) = (page_w as i32, page_h as i32);
+    let (sx, sy) = context.canvas.scale();
+    let mut drawer = MainWinDrawer::new(Rect::new(0, 0, page_w as u32, page_h as u32));
+
+    for top in (0..map_h).step_by(page_h as usize) {
+        for left in (0..map_w).step_by(page_w as usize) {
+            context.set_viewport(None);
+            context.canvas.set_draw_color(Color::RGB(0, 0, 0));
+            context.canvas.clear();
+            drawer.draw_map_page(context, game, left, top);
+            context.set_viewport(None);
+
+            let w = std::cmp::min(page_w, map_w - left);
+            let h = std::cmp::min(page_h, map_h - top);
+            let src = Rect::new(0, 0, (w as f32 * sx) as u32, (h as f32 * sy) as u32);
+            let page = read_surface(context.canvas, src)?;
+            let dest = Rect::new(
+                (left as f32 * scale) as i32, (top as f32 * scale) as i32, scaled(w), scaled(h));
+            page.blit_scaled(None, &mut image, dest)?;
+        }
+    }
+    save(&image, "map")
+}
+
+fn read_surface(canvas: &WindowCanvas, rect: Rect) -> Result<Surface<'static>, String> {
+    let pixels = canvas.read_pixels(rect, PIXEL_FORMAT)?;
+    let mut surface = Surface::new(rect.width(), rect.height(), PIXEL_FORMAT)?;
+    let pitch = surface.pitch() as usize;
+    let row_len = rect.width() as usize * 4;
+    surface.with_lock_mut(|buf| {
+        for (y, row) in pixels.chunks(row_len).enumerate() {
+            buf[y * pitch..y * pitch + row_len].copy_from_slice(row);
+        }
+    });
+    Ok(surface)
+}
+
+/// Save the image as "<prefix>-<unix time in milliseconds>.png"
+fn save(surface: &Surface, prefix: &str) -> Result<(), String> {
+    let dir = USER_DIR.join(SCREENSHOT_DIR_NAME);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let path: PathBuf = dir.join(file_name(prefix, millis, |name| dir.join(name).exists()));
+    surface.save(&path)?;
+    info!("Saved an image to {:?}", path.to_string_lossy());
+    Ok(())
+}
+
+/// The file name of the time, or a name with a counter if it is used
+fn file_name<F: Fn(&str) -> bool>(prefix: &str, millis: u128, exists: F) -> String {
+    let name = format!("{}-{}.png", prefix, millis);
+    if !exists(&name) {
+        return name;
+    }
+    (1..).map(|i| format!("{}-{}-{}.png", prefix, millis, i)).find(|name| !exists(name)).unwrap()
+}
+
+#[test]
+fn file_name_test() {
+    let used = ["screenshot-1000.png", "screenshot-1000-1.png"];
+    assert_eq!(file_name("map", 1000, |name| used.contains(&name)), "map-1000.png");
+    assert_eq!(file_name("screenshot", 1000, |name| used.contains(&name)), "screenshot-1000-2.png");
+}
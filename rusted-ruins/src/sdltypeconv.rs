@@ -2,6 +2,7 @@
 use sdl2::rect::Rect;
 use sdl2::pixels::Color;
 use crate::config::{CfgRect, CfgPos, CfgColor, SCREEN_CFG};
+use crate::screen::screen_size;
 
 /// Centering for main window
 pub const CENTERING_POS: i32 = -999;
@@ -10,24 +11,41 @@ pub const CENTERING_POS_FOR_SCREEN: i32 = -1000;
 
 impl Into<Rect> for CfgRect {
     fn into(self) -> Rect {
+        let (screen_w, screen_h) = screen_size();
+        let (dx, dy) = screen_size_diff();
+        let w = if self.stretch_w { (self.w as i32 + dx) as u32 } else { self.w };
+        let h = if self.stretch_h { (self.h as i32 + dy) as u32 } else { self.h };
+
         let x = if self.x == CENTERING_POS {
-            SCREEN_CFG.main_window.x + (SCREEN_CFG.main_window.w - self.w) as i32 / 2
+            let main_window: Rect = SCREEN_CFG.main_window.into();
+            main_window.x + (main_window.w - w as i32) / 2
         }else if self.x == CENTERING_POS_FOR_SCREEN {
-            (SCREEN_CFG.screen_w - self.w) as i32 / 2
+            (screen_w - w) as i32 / 2
+        }else if self.anchor_right {
+            self.x + dx
         }else{
             self.x
         };
         let y = if self.y == CENTERING_POS {
-            SCREEN_CFG.main_window.y + (SCREEN_CFG.main_window.h - self.h) as i32 / 2
+            let main_window: Rect = SCREEN_CFG.main_window.into();
+            main_window.y + (main_window.h - h as i32) / 2
         }else if self.y == CENTERING_POS_FOR_SCREEN {
-            (SCREEN_CFG.screen_h - self.h) as i32 / 2
+            (screen_h - h) as i32 / 2
+        }else if self.anchor_bottom {
+            self.y + dy
         }else{
             self.y
         };
-        Rect::new(x, y, self.w, self.h)
+        Rect::new(x, y, w, h)
     }
 }
 
+/// Difference between the current screen size and the size screen config is written for
+pub fn screen_size_diff() -> (i32, i32) {
+    let (screen_w, screen_h) = screen_size();
+    (screen_w as i32 - SCREEN_CFG.screen_w as i32, screen_h as i32 - SCREEN_CFG.screen_h as i32)
+}
+
 impl Into<(i32, i32)> for CfgPos {
     fn into(self) -> (i32, i32) {
         (self.x, self.y)
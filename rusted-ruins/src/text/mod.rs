@@ -5,72 +5,99 @@ mod to_text;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::RwLock;
 use std::io::{BufRead, BufReader};
 use walkdir::WalkDir;
 use crate::error::*;
 use crate::config;
 use common::basic;
 
-/// Initialize lazy static
+/// Load text files
 pub fn init() {
-    ::lazy_static::initialize(&OBJ_TXT_MAP);
-    ::lazy_static::initialize(&LOG_TXT_MAP);
-    ::lazy_static::initialize(&UI_TXT_MAP);
-    ::lazy_static::initialize(&TALK_TXT_MAP);
-    ::lazy_static::initialize(&MISC_TXT_MAP);
+    replace(&OBJ_TXT_MAP, load_trans_txt(basic::OBJ_TXT_DIR));
+    replace(&LOG_TXT_MAP, load_trans_txt(basic::LOG_TXT_DIR));
+    replace(&UI_TXT_MAP, load_trans_txt(basic::UI_TXT_DIR));
+    replace(&MISC_TXT_MAP, load_trans_txt(basic::MISC_TXT_DIR));
+    reload_talk();
 }
 
+type TextMap = RwLock<&'static HashMap<String, String>>;
+
+// Maps are empty until init() is called, e.g. in unit tests. Ids are used as texts then.
 lazy_static! {
-    static ref OBJ_TXT_MAP:  HashMap<String, String> = load_trans_txt(basic::OBJ_TXT_DIR);
-    static ref LOG_TXT_MAP:  HashMap<String, String> = load_trans_txt(basic::LOG_TXT_DIR);
-    static ref UI_TXT_MAP:   HashMap<String, String> = load_trans_txt(basic::UI_TXT_DIR);
-    static ref TALK_TXT_MAP: HashMap<String, String> = load_trans_txt(basic::TALK_TXT_DIR);
-    static ref MISC_TXT_MAP: HashMap<String, String> = load_trans_txt(basic::MISC_TXT_DIR);
+    static ref OBJ_TXT_MAP:  TextMap = new_map(HashMap::new());
+    static ref LOG_TXT_MAP:  TextMap = new_map(HashMap::new());
+    static ref UI_TXT_MAP:   TextMap = new_map(HashMap::new());
+    static ref TALK_TXT_MAP: TextMap = new_map(HashMap::new());
+    static ref MISC_TXT_MAP: TextMap = new_map(HashMap::new());
+}
+
+// Maps are leaked when replaced by reloading, because returned texts may remain
+fn new_map(map: HashMap<String, String>) -> TextMap {
+    RwLock::new(Box::leak(Box::new(map)))
+}
+
+fn get(map: &TextMap) -> &'static HashMap<String, String> {
+    *map.read().unwrap()
+}
+
+fn replace(map: &TextMap, new_map: HashMap<String, String>) {
+    *map.write().unwrap() = Box::leak(Box::new(new_map));
+}
+
+/// Reload text files for development
+pub fn reload() {
+    info!("Reloading text files");
+    init();
+}
+
+/// Reload talk texts. It is needed after reloading paks to update inline texts in scripts.
+pub fn reload_talk() {
+    replace(&TALK_TXT_MAP, load_talk_txt());
 }
 
 pub fn obj_txt<'a>(id: &'a str) -> &'a str {
-    if let Some(txt) = OBJ_TXT_MAP.get(id) { txt }else{ id }
+    if let Some(txt) = get(&OBJ_TXT_MAP).get(id) { txt }else{ id }
 }
 
 #[allow(unused)]
 pub fn obj_txt_checked(id: &str) -> Option<&'static str> {
-    OBJ_TXT_MAP.get(id).map(|txt| txt.as_ref())
+    get(&OBJ_TXT_MAP).get(id).map(|txt| txt.as_ref())
 }
 
 pub fn log_txt<'a>(id: &'a str) -> &'a str {
-    if let Some(txt) = LOG_TXT_MAP.get(id) { txt }else{ id }
+    if let Some(txt) = get(&LOG_TXT_MAP).get(id) { txt }else{ id }
 }
 
 #[allow(unused)]
 pub fn log_txt_checked(id: &str) -> Option<&'static str> {
-    LOG_TXT_MAP.get(id).map(|txt| txt.as_ref())
+    get(&LOG_TXT_MAP).get(id).map(|txt| txt.as_ref())
 }
 
 pub fn ui_txt<'a>(id: &'a str) -> &'a str {
-    if let Some(txt) = UI_TXT_MAP.get(id) { txt }else{ id }
+    if let Some(txt) = get(&UI_TXT_MAP).get(id) { txt }else{ id }
 }
 
 #[allow(unused)]
 pub fn ui_txt_checked(id: &str) -> Option<&'static str> {
-    UI_TXT_MAP.get(id).map(|txt| txt.as_ref())
+    get(&UI_TXT_MAP).get(id).map(|txt| txt.as_ref())
 }
 
 pub fn talk_txt<'a>(id: &'a str) -> &'a str {
-    if let Some(txt) = TALK_TXT_MAP.get(id) { txt }else{ id }
+    if let Some(txt) = get(&TALK_TXT_MAP).get(id) { txt }else{ id }
 }
 
 #[allow(unused)]
 pub fn talk_txt_checked(id: &str) -> Option<&'static str> {
-    TALK_TXT_MAP.get(id).map(|txt| txt.as_ref())
+    get(&TALK_TXT_MAP).get(id).map(|txt| txt.as_ref())
 }
 
 pub fn misc_txt<'a>(id: &'a str) -> &'a str {
-    if let Some(txt) = MISC_TXT_MAP.get(id) { txt }else{ id }
+    if let Some(txt) = get(&MISC_TXT_MAP).get(id) { txt }else{ id }
 }
 
-#[allow(unused)]
 pub fn misc_txt_checked(id: &str) -> Option<&'static str> {
-    MISC_TXT_MAP.get(id).map(|txt| txt.as_ref())
+    get(&MISC_TXT_MAP).get(id).map(|txt| txt.as_ref())
 }
 
 /// This is helper trait for some data objects that need to be printed in game.
@@ -89,13 +116,25 @@ pub fn to_txt<T: ToTextId>(a: &T) -> &'static str {
     misc_txt(a.to_textid())
 }
 
+/// Load talk texts. Inline texts in scripts are also registered.
+fn load_talk_txt() -> HashMap<String, String> {
+    let mut map = load_trans_txt(basic::TALK_TXT_DIR);
+    for script_obj in &common::gobj::get_objholder().script {
+        for (text_id, text) in script_obj.script.iter_texts() {
+            // Texts in translation files have priority
+            map.entry(text_id.to_owned()).or_insert_with(|| text.to_owned());
+        }
+    }
+    map
+}
+
 fn load_trans_txt(kind: &str) -> HashMap<String, String> {
     let mut map = HashMap::new();
     let mut textdirs: Vec<PathBuf> = Vec::new();
 
-    // If the second language is specified, search (data_dir)/text/(second_lang)/(kind)/*.txt
-    if config::CONFIG.second_lang != "" {
-        let second_lang = &config::CONFIG.second_lang;
+    // Texts of the second language are used if the first language lacks them.
+    // Search (data_dir)/text/(second_lang)/(kind)/*.txt
+    if let Some(second_lang) = fallback_lang() {
         let v = config::get_data_dirs();
         for mut dir in v.into_iter() {
             dir.push("text");
@@ -135,6 +174,41 @@ fn load_trans_txt(kind: &str) -> HashMap<String, String> {
     map
 }
 
+/// Most texts are written in English first
+const FALLBACK_LANG: &str = "en";
+
+/// The second language, or English if it is not specified
+fn fallback_lang() -> Option<&'static str> {
+    let config = &config::CONFIG;
+    if config.second_lang != "" {
+        Some(&config.second_lang)
+    } else if config.lang != FALLBACK_LANG {
+        Some(FALLBACK_LANG)
+    } else {
+        None
+    }
+}
+
+/// Languages that have a text directory in data directories
+pub fn available_langs() -> Vec<String> {
+    let mut langs = Vec::new();
+    for mut dir in config::get_data_dirs().into_iter() {
+        dir.push("text");
+        let entries = if let Ok(entries) = fs::read_dir(&dir) { entries } else { continue; };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let lang = entry.file_name().to_string_lossy().into_owned();
+            if !langs.contains(&lang) {
+                langs.push(lang);
+            }
+        }
+    }
+    langs.sort();
+    langs
+}
+
 fn add_file<P: AsRef<Path>>(p: P, map: &mut HashMap<String, String>) -> Result<(), Error> {
     let p = p.as_ref();
     let file = fs::File::open(p)?;
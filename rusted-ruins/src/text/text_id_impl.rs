@@ -2,6 +2,7 @@
 use super::ToTextId;
     
 use common::gamedata::*;
+use crate::game::Command;
 
 impl ToTextId for DungeonKind {
     fn to_textid(&self) -> &'static str {
@@ -21,6 +22,7 @@ impl ToTextId for CharaStatus {
             CharaStatus::Starving      => "!chara_status.starving",
             CharaStatus::Asleep { .. } => "!chara_status.asleep",
             CharaStatus::Poisoned      => "!chara_status.poisoned",
+            CharaStatus::Hasted { .. } => "!chara_status.hasted",
         }
     }
 }
@@ -33,6 +35,11 @@ impl ToTextId for SkillKind {
            SkillKind::MartialArts   => "!skill_kind.martial_arts",
            SkillKind::Defence       => "!skill_kind.defence",
            SkillKind::Evasion       => "!skill_kind.evasion",
+           SkillKind::Crafting      => "!skill_kind.crafting",
+           SkillKind::Gathering     => "!skill_kind.gathering",
+           SkillKind::Lockpicking   => "!skill_kind.lockpicking",
+           SkillKind::Perception    => "!skill_kind.perception",
+           SkillKind::Stealth       => "!skill_kind.stealth",
            SkillKind::Weapon(weapon_kind) => {
                weapon_kind.to_textid()
            }
@@ -53,3 +60,87 @@ impl ToTextId for WeaponKind {
         }
     }
 }
+
+impl ToTextId for Weather {
+    fn to_textid(&self) -> &'static str {
+        match self {
+            Weather::Fine   => "!weather.fine",
+            Weather::Cloudy => "!weather.cloudy",
+            Weather::Rain   => "!weather.rain",
+            Weather::Snow   => "!weather.snow",
+            Weather::Fog    => "!weather.fog",
+        }
+    }
+}
+
+impl ToTextId for Faction {
+    fn to_textid(&self) -> &'static str {
+        match self {
+            Faction::Player  => "!faction.player",
+            Faction::Citizen => "!faction.citizen",
+            Faction::Monster => "!faction.monster",
+            Faction::Unknown => "!faction.unknown",
+        }
+    }
+}
+
+impl ToTextId for Command {
+    fn to_textid(&self) -> &'static str {
+        match self {
+            Command::Enter           => "!command.enter",
+            Command::Shot            => "!command.shot",
+            Command::OpenExitWin     => "!command.open_exit_win",
+            Command::OpenItemMenu    => "!command.open_item_menu",
+            Command::OpenEquipWin    => "!command.open_equip_win",
+            Command::OpenStatusWin   => "!command.open_status_win",
+            Command::OpenGameInfoWin => "!command.open_game_info_win",
+            Command::OpenJournalWin  => "!command.open_journal_win",
+            Command::OpenAbilityWin  => "!command.open_ability_win",
+            Command::OpenCraftWin    => "!command.open_craft_win",
+            Command::OpenLogWin      => "!command.open_log_win",
+            Command::OpenMapWin      => "!command.open_map_win",
+            Command::OpenPartyWin    => "!command.open_party_win",
+            Command::ToggleMinimap   => "!command.toggle_minimap",
+            Command::PickUpItem      => "!command.pick_up_item",
+            Command::DropItem        => "!command.drop_item",
+            Command::DrinkItem       => "!command.drink_item",
+            Command::EatItem         => "!command.eat_item",
+            Command::ReadItem        => "!command.read_item",
+            Command::ThrowItem       => "!command.throw_item",
+            Command::PushChara       => "!command.push_chara",
+            Command::Dismount        => "!command.dismount",
+            Command::Dig             => "!command.dig",
+            Command::CloseDoor       => "!command.close_door",
+            Command::DisarmTrap      => "!command.disarm_trap",
+            Command::TargetingMode   => "!command.targeting_mode",
+            Command::Screenshot      => "!command.screenshot",
+            Command::ExportMap       => "!command.export_map",
+            Command::OpenConsole     => "!command.open_console",
+            _ => "!command.other",
+        }
+    }
+}
+
+impl ToTextId for ItemQuality {
+    fn to_textid(&self) -> &'static str {
+        match self {
+            ItemQuality::Poor      => "!item_quality.poor",
+            ItemQuality::Normal    => "!item_quality.normal",
+            ItemQuality::Good      => "!item_quality.good",
+            ItemQuality::Excellent => "!item_quality.excellent",
+        }
+    }
+}
+
+impl ToTextId for MaterialKind {
+    fn to_textid(&self) -> &'static str {
+        match self {
+            MaterialKind::Wood    => "!material.wood",
+            MaterialKind::Bronze  => "!material.bronze",
+            MaterialKind::Iron    => "!material.iron",
+            MaterialKind::Steel   => "!material.steel",
+            MaterialKind::Silver  => "!material.silver",
+            MaterialKind::Mithril => "!material.mithril",
+        }
+    }
+}
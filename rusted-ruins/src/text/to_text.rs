@@ -23,6 +23,9 @@ impl ToText for Site {
                 text::to_txt(&dungeon_kind).into()
             }
             SiteContent::Town { ref town } => {
+                if let Some(name) = town.name() {
+                    return name.into();
+                }
                 text::obj_txt(town.id()).into()
             }
             SiteContent::Other => {
@@ -35,7 +38,27 @@ impl ToText for Site {
 
 impl ToText for Item {
     fn to_text(&self) -> Cow<str> {
-        crate::text::obj_txt(gobj::idx_to_id(self.idx)).into()
+        let name = crate::text::obj_txt(gobj::idx_to_id(self.idx));
+        let quality = self.quality();
+        let material = self.material();
+        let charge = self.charge();
+        if quality == ItemQuality::Normal && material.is_none() && charge.is_none() {
+            return name.into();
+        }
+
+        let mut words = Vec::new();
+        if quality != ItemQuality::Normal {
+            words.push(text::to_txt(&quality));
+        }
+        if let Some(material) = material {
+            words.push(text::to_txt(&material));
+        }
+        words.push(name);
+        let mut s = words.join(" ");
+        if let Some(n) = charge {
+            s.push_str(&format!(" ({})", n));
+        }
+        s.into()
     }
 }
 
@@ -45,6 +68,18 @@ impl ToText for CharaTemplateIdx {
     }
 }
 
+impl ToText for AbilityIdx {
+    fn to_text(&self) -> Cow<str> {
+        crate::text::obj_txt(gobj::idx_to_id(*self)).into()
+    }
+}
+
+impl ToText for ItemIdx {
+    fn to_text(&self) -> Cow<str> {
+        crate::text::obj_txt(gobj::idx_to_id(*self)).into()
+    }
+}
+
 impl ToText for Chara {
     fn to_text(&self) -> Cow<str> {
         if let Some(ref name) = self.name {
@@ -61,6 +96,12 @@ impl ToText for Quest {
             Quest::SlayMonsters { idx, .. } => {
                 replace_str!(text::misc_txt("!quest.slay_monsters"); monster=idx).into()
             }
+            Quest::CollectItems { idx, .. } => {
+                replace_str!(text::misc_txt("!quest.collect_items"); item=idx).into()
+            }
+            Quest::ReachSite { .. } => {
+                text::misc_txt("!quest.reach_site").into()
+            }
         }
     }
 }
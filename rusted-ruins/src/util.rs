@@ -43,10 +43,18 @@ pub fn replace_str<S0: AsRef<str>, S1: AsRef<str>>(s: &str, table: &[(S0, S1)])
             State::Var => {
                 if c == ')' {
                     {
+                        // "$(n|singular|plural)" selects a form by the number
+                        let mut parts = var_name.split('|');
+                        let name = parts.next().unwrap();
                         let a =
                             table.iter()
-                            .find(|t| t.0.as_ref() == var_name).map(|t| t.1.as_ref()).unwrap_or(&var_name);
-                        rst.push_str(a);
+                            .find(|t| t.0.as_ref() == name).map(|t| t.1.as_ref()).unwrap_or(name);
+                        let forms: Vec<&str> = parts.collect();
+                        if forms.is_empty() {
+                            rst.push_str(a);
+                        } else {
+                            rst.push_str(plural_form(a, &forms));
+                        }
                     }
                     var_name.clear();
                     state = State::Normal;
@@ -68,6 +76,15 @@ pub fn replace_str<S0: AsRef<str>, S1: AsRef<str>>(s: &str, table: &[(S0, S1)])
     rst
 }
 
+/// Choose the singular form if the value is 1, and the last form otherwise
+fn plural_form<'a>(value: &str, forms: &[&'a str]) -> &'a str {
+    if value.trim() == "1" {
+        forms[0]
+    } else {
+        forms[forms.len() - 1]
+    }
+}
+
 macro_rules! replace_str {
     ($original_text:expr; $($target:ident = $value:expr),*) => {{
         use std::borrow::Cow;
@@ -82,3 +99,11 @@ macro_rules! replace_str {
     }}
 }
 
+#[test]
+fn replace_str_test() {
+    let table = [("chara", "Goblin"), ("n", "1"), ("m", "3")];
+    assert_eq!(replace_str("$(chara) is damaged.", &table), "Goblin is damaged.");
+    assert_eq!(replace_str("$(n) $(n|arrow|arrows)", &table), "1 arrow");
+    assert_eq!(replace_str("$(m) $(m|arrow|arrows)", &table), "3 arrows");
+    assert_eq!(replace_str("$(unknown) costs $5", &table), "unknown costs $5");
+}
@@ -0,0 +1,72 @@
+
+use common::gamedata::*;
+use common::gobj;
+use common::objholder::AbilityIdx;
+use crate::text::ToText;
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+
+/// Choose an ability of the player to use
+pub struct AbilityWindow {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache)>,
+    abilities: Vec<AbilityIdx>,
+}
+
+impl AbilityWindow {
+    pub fn new(game: &Game) -> AbilityWindow {
+        let cfg = &UI_CFG.ability_window;
+        let rect: Rect = cfg.rect.into();
+        let abilities = game.gd.chara.get(CharaId::Player).abilities.clone();
+        let rows = abilities.iter().map(|&idx| {
+            let ability: &AbilityObject = gobj::get_obj(idx);
+            let cost = format!("{} MP", ability.cost_mp);
+            (TextCache::one(idx.to_text(), FontKind::M, UI_CFG.color.normal_font.into()),
+             TextCache::one(cost, FontKind::M, UI_CFG.color.normal_font.into()))
+        }).collect();
+
+        let mut list = ListWidget::new(
+            (0i32, 0i32, rect.w as u32, rect.h as u32),
+            cfg.column_pos.clone(),
+            cfg.n_row,
+            26,
+            true,
+            false);
+        list.set_items(rows);
+
+        AbilityWindow {
+            rect,
+            list,
+            abilities,
+        }
+    }
+}
+
+impl Window for AbilityWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+    }
+}
+
+impl DialogWindow for AbilityWindow {
+    fn process_command(&mut self, command: &Command, pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                pa.use_ability(self.abilities[i as usize]);
+                return DialogResult::Close;
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
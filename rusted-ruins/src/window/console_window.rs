@@ -0,0 +1,90 @@
+
+use crate::config::UI_CFG;
+use crate::context::textrenderer::FontKind;
+use crate::game::console::HELP;
+use super::commonuse::*;
+use super::text_input;
+use super::widget::*;
+
+/// Height of the input line at the bottom
+const INPUT_H: u32 = 26;
+
+/// Console for developers to execute commands. Opened only if debug_console is enabled in config.
+pub struct ConsoleWindow {
+    rect: Rect,
+    input: TextInputWidget,
+    history: LineSpecifiedLabelWidget,
+    lines: Vec<String>,
+}
+
+impl ConsoleWindow {
+    pub fn new() -> ConsoleWindow {
+        text_input::start();
+
+        let rect: Rect = UI_CFG.console_window.rect.into();
+        let history_rect = Rect::new(0, 0, rect.width(), rect.height() - INPUT_H);
+        let lines: Vec<String> = vec!["type \"help\" to show commands".to_owned()];
+
+        ConsoleWindow {
+            rect,
+            input: TextInputWidget::new(input_rect(rect), ""),
+            history: LineSpecifiedLabelWidget::new(
+                history_rect, &lines, FontKind::M, UI_CFG.console_window.n_row as usize),
+            lines,
+        }
+    }
+
+    fn exec(&mut self, pa: &mut DoPlayerAction) {
+        let line = self.input.get_text().trim().to_owned();
+        self.input = TextInputWidget::new(input_rect(self.rect), "");
+        if line.is_empty() {
+            return;
+        }
+        self.lines.push(format!("> {}", line));
+
+        if line == "help" {
+            self.lines.extend(HELP.iter().map(|s| s.to_string()));
+        } else if let Err(e) = pa.exec_console_command(&line) {
+            self.lines.push(e);
+        }
+
+        let n_row = UI_CFG.console_window.n_row as usize;
+        let start = self.lines.len().saturating_sub(n_row);
+        self.history.set_text(&self.lines[start..]);
+    }
+}
+
+fn input_rect(rect: Rect) -> Rect {
+    Rect::new(0, (rect.height() - INPUT_H) as i32, rect.width(), INPUT_H)
+}
+
+impl Window for ConsoleWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.history.draw(context);
+        self.input.draw(context);
+    }
+}
+
+impl DialogWindow for ConsoleWindow {
+    fn process_command(&mut self, command: &Command, pa: &mut DoPlayerAction) -> DialogResult {
+        if self.input.process_command(command).is_some() {
+            return DialogResult::Continue;
+        }
+        match command {
+            &Command::Enter => {
+                self.exec(pa);
+                DialogResult::Continue
+            },
+            &Command::Cancel => {
+                text_input::end();
+                DialogResult::Close
+            },
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::TextInput
+    }
+}
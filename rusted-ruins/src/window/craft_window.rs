@@ -0,0 +1,82 @@
+
+use common::gamedata::*;
+use common::gobj;
+use common::objholder::{ItemIdx, RecipeIdx};
+use crate::game::craft;
+use crate::text::ToText;
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+
+/// Choose a recipe to craft. Only recipes that the player has all inputs for are listed.
+pub struct CraftWindow {
+    rect: Rect,
+    list: ListWidget<(IconIdx, TextCache, TextCache)>,
+    recipes: Vec<RecipeIdx>,
+}
+
+impl CraftWindow {
+    pub fn new(game: &Game) -> CraftWindow {
+        let cfg = &UI_CFG.craft_window;
+        let rect: Rect = cfg.rect.into();
+
+        let mut w = CraftWindow {
+            rect,
+            list: ListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                cfg.column_pos.clone(),
+                cfg.n_row,
+                26,
+                true,
+                false),
+            recipes: Vec::new(),
+        };
+        w.update(game);
+        w
+    }
+
+    fn update(&mut self, game: &Game) {
+        self.recipes = craft::available_recipes(&game.gd);
+        let rows = self.recipes.iter().map(|&idx| {
+            let recipe: &RecipeObject = gobj::get_obj(idx);
+            let output: ItemIdx = gobj::id_to_idx(&recipe.output);
+            let name = format!("{} x {}", output.to_text(), recipe.n_output);
+            let inputs = recipe.inputs.iter().map(|input| {
+                let idx: ItemIdx = gobj::id_to_idx(&input.id);
+                format!("{} x {}", idx.to_text(), input.n)
+            }).collect::<Vec<_>>().join(", ");
+            (IconIdx::Item(output),
+             TextCache::one(name, FontKind::M, UI_CFG.color.normal_font.into()),
+             TextCache::one(inputs, FontKind::M, UI_CFG.color.normal_font.into()))
+        }).collect();
+        self.list.set_items(rows);
+    }
+}
+
+impl Window for CraftWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+    }
+}
+
+impl DialogWindow for CraftWindow {
+    fn process_command(&mut self, command: &Command, pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                pa.craft(self.recipes[i as usize]);
+                self.update(pa.game());
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
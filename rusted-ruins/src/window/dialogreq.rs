@@ -43,7 +43,7 @@ pub fn create_talk_dialog(talk_text: TalkText, cid: CharaId, game: &mut Game)
                           -> Option<Box<dyn DialogWindow>> {
     let chara_template_idx = game.gd.chara.get(cid).template;
     
-    let talk_window = talk_window::TalkWindow::new(talk_text, chara_template_idx);
+    let talk_window = talk_window::TalkWindow::new(talk_text, chara_template_idx, &game.gd);
     Some(Box::new(talk_window))
 }
 
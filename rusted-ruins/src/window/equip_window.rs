@@ -17,6 +17,8 @@ pub struct EquipWindow {
     rect: Rect,
     list: ListWidget<(IconIdx, IconIdx, TextCache)>,
     cid: CharaId,
+    /// The slot selected to change or remove its equipment
+    selected_slot: Option<(EquipSlotKind, u8)>,
 }
 
 impl EquipWindow {
@@ -33,6 +35,7 @@ impl EquipWindow {
                 true,
                 true),
             cid,
+            selected_slot: None,
         };
         equip_window.update_list(pa);
         equip_window
@@ -55,6 +58,27 @@ impl EquipWindow {
             }
         });
     }
+
+    /// Open the window to choose an item for given slot
+    fn select_item_to_equip(
+        &mut self, pa: &mut DoPlayerAction, slot: (EquipSlotKind, u8)) -> DialogResult {
+        use super::item_window::ItemWindow;
+
+        // Callback function for selected item equipment
+        let cid = self.cid;
+        let equip_selected_item = move |pa: &mut DoPlayerAction, il: ItemLocation| {
+            pa.change_equipment(cid, slot, il);
+            DialogResult::Close
+        };
+
+        let select_window = ItemWindow::new_select(
+            ItemListLocation::Chara { cid: CharaId::Player },
+            ItemFilter::new().equip_slot_kind(slot.0),
+            Box::new(equip_selected_item),
+            pa
+        );
+        DialogResult::OpenChildDialog(Box::new(select_window))
+    }
 }
 
 impl Window for EquipWindow {
@@ -73,25 +97,24 @@ impl DialogWindow for EquipWindow {
         if let Some(response) = self.list.process_command(&command) {
             match response {
                 ListWidgetResponse::Select(i) => { // Any item is selected
-                    use super::item_window::ItemWindow;
-
-                    // Callback function for selected item equipment
-                    let cid = self.cid;
-                    let (esk, esk_i, _) =
-                        pa.gd().get_equip_list(cid).slot_iter().nth(i as usize).unwrap();
+                    let (esk, esk_i, item) =
+                        pa.gd().get_equip_list(self.cid).slot_iter().nth(i as usize).unwrap();
                     let slot = (esk, esk_i);
-                    let equip_selected_item = move |pa: &mut DoPlayerAction, il: ItemLocation| {
-                        pa.change_equipment(cid, slot, il);
-                        DialogResult::Close
-                    };
-                    
-                    let select_window = ItemWindow::new_select(
-                        ItemListLocation::Chara { cid: CharaId::Player },
-                        ItemFilter::new().equip_slot_kind(slot.0),
-                        Box::new(equip_selected_item),
-                        pa
-                    );
-                    return DialogResult::OpenChildDialog(Box::new(select_window));
+
+                    // If the slot has an item, ask to change or remove it
+                    if let Some(item) = item {
+                        use super::msg_dialog::MsgDialog;
+                        let choices = vec![
+                            text::ui_txt("dialog.choice.change_equipment").to_owned(),
+                            text::ui_txt("dialog.choice.remove_equipment").to_owned(),
+                        ];
+                        let msg = text::obj_txt(&gobj::get_obj(item.idx).id);
+                        let dialog = MsgDialog::new(
+                            msg, choices, |_, n| DialogResult::CloseWithValue(Box::new(n)));
+                        self.selected_slot = Some(slot);
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
+                    }
+                    return self.select_item_to_equip(pa, slot);
                 }
                 ListWidgetResponse::PageChanged => {
                     self.update_list(pa);
@@ -114,8 +137,15 @@ impl DialogWindow for EquipWindow {
     }
 
     fn callback_child_closed(
-        &mut self, _result: Option<Box<dyn Any>>, pa: &mut DoPlayerAction) -> DialogResult {
-        
+        &mut self, result: Option<Box<dyn Any>>, pa: &mut DoPlayerAction) -> DialogResult {
+
+        let choice = result.and_then(|result| result.downcast::<u32>().ok());
+        if let (Some(choice), Some(slot)) = (choice, self.selected_slot.take()) {
+            match *choice {
+                0 => { return self.select_item_to_equip(pa, slot); }
+                _ => { pa.remove_equipment(self.cid, slot); }
+            }
+        }
         self.update_list(pa);
         DialogResult::Continue
     }
@@ -1,9 +1,11 @@
 
+use std::any::Any;
 use sdl2::rect::Rect;
 use crate::config::UI_CFG;
 use super::commonuse::*;
 use super::text_window::TextWindow;
 use super::choose_window::ChooseWindow;
+use super::text_input_dialog::TextInputDialog;
 use super::winpos::{WindowPos, WindowHPos, WindowVPos};
 use crate::text;
 
@@ -24,6 +26,7 @@ impl ExitWindow {
             vec![
                 text::ui_txt("dialog.choice.save_game").to_owned(),
                 text::ui_txt("dialog.choice.exit_game").to_owned(),
+                text::ui_txt("dialog.choice.key_bindings").to_owned(),
                 text::ui_txt("dialog.choice.close").to_owned(),
             ],
             Some(0));
@@ -63,11 +66,16 @@ impl DialogWindow for ExitWindow {
                 let n = *v.downcast::<u32>().unwrap();
                 match n {
                     0 => {
-                        pa.game().save_file();
-                        return DialogResult::Close;
+                        let description = pa.gd().meta.description();
+                        let dialog = TextInputDialog::with_text(description);
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
                     }
                     1 => { return DialogResult::Quit }
-                    2 => { return DialogResult::Close }
+                    2 => {
+                        let dialog = super::key_binding_window::KeyBindingWindow::new();
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
+                    }
+                    3 => { return DialogResult::Close }
                     _ => panic!(),
                 }
             }
@@ -79,9 +87,20 @@ impl DialogWindow for ExitWindow {
     fn mode(&self) -> InputMode {
         InputMode::Dialog
     }
+
+    fn callback_child_closed(
+        &mut self, result: Option<Box<dyn Any>>, pa: &mut DoPlayerAction) -> DialogResult {
+
+        // Save with the description from TextInputDialog
+        if let Some(description) = result.and_then(|result| result.downcast::<String>().ok()) {
+            pa.save_game(&description);
+            return DialogResult::Close;
+        }
+        DialogResult::Continue
+    }
 }
 
-/// Ask to return start screen or quit
+/// Ask to return start screen, load a save file or quit
 pub struct GameOverWindow {
     text_win: TextWindow,
     choose_win: ChooseWindow,
@@ -94,7 +113,8 @@ impl GameOverWindow {
         let winpos = WindowPos::new(
             WindowHPos::RightX(rect.right()),
             WindowVPos::TopMargin(rect.bottom() + UI_CFG.gap_len_between_dialogs));
-        let choices = vec!["Return to start screen".to_owned(), "Quit".to_owned()];
+        let choices = vec![
+            "Return to start screen".to_owned(), "Load game".to_owned(), "Quit".to_owned()];
         GameOverWindow {
             text_win,
             choose_win: ChooseWindow::new(winpos, choices, None),
@@ -132,7 +152,11 @@ impl DialogWindow for GameOverWindow {
                 let n = *v.downcast::<u32>().unwrap();
                 match n {
                     0 => { return DialogResult::Special(ReturnToStartScreen) }
-                    1 => { return DialogResult::Quit }
+                    1 => {
+                        let dialog = super::start_window::ChooseSaveFileDialog::new();
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
+                    }
+                    2 => { return DialogResult::Quit }
                     _ => panic!(),
                 }
             }
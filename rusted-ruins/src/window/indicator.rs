@@ -1,6 +1,7 @@
 
 use crate::config::{SCREEN_CFG, UI_CFG};
 use crate::game::InfoGetter;
+use crate::game::chara::status::CharaStatusEx;
 use crate::text::ToText;
 use super::commonuse::*;
 use super::widget::*;
@@ -17,7 +18,7 @@ pub struct HPIndicator {
 
 impl HPIndicator {
     pub fn new() -> HPIndicator {
-        let rect: Rect = SCREEN_CFG.hp_indicator.into();
+        let rect: Rect = UI_CFG.hud.hp_bar.rect.into();
 
         // Label is drawed over the guage
         let label_id = "!label-hp";
@@ -47,6 +48,32 @@ impl Window for HPIndicator {
     }
 }
 
+pub struct MPIndicator {
+    rect: Rect,
+    guage: GaugeWidget,
+}
+
+impl MPIndicator {
+    pub fn new() -> MPIndicator {
+        let rect: Rect = UI_CFG.hud.mp_bar.rect.into();
+        MPIndicator {
+            rect,
+            guage: GaugeWidget::with_label(
+                Rect::new(0, 0, rect.width(), rect.height()), 0.0, 1.0, GaugeColorMode::Mp, "MP"),
+        }
+    }
+}
+
+impl Window for MPIndicator {
+    fn draw(&mut self, context: &mut Context, game: &Game, _anim: Option<(&Animation, u32)>) {
+        let player = game.gd.chara.get(CharaId::Player);
+        self.guage.set_params(0.0, player.attr.max_mp as f32, player.mp as f32);
+
+        context.set_viewport(self.rect);
+        self.guage.draw(context);
+    }
+}
+
 pub struct FloorInfo {
     rect: Rect,
     label: LabelWidget,
@@ -96,10 +123,8 @@ pub struct TimeInfo {
 
 impl TimeInfo {
     pub fn new() -> TimeInfo {
-        let rect: Rect = SCREEN_CFG.date_info.into();
-        let date_label = LabelWidget::bordered(rect, "", FontKind::S);
-        let rect: Rect = SCREEN_CFG.time_info.into();
-        let time_label = LabelWidget::bordered(rect, "", FontKind::M);
+        let date_label = LabelWidget::bordered(UI_CFG.hud.date.rect, "", FontKind::S);
+        let time_label = LabelWidget::bordered(UI_CFG.hud.time.rect, "", FontKind::M);
         TimeInfo {
             date_label,
             time_label,
@@ -141,39 +166,84 @@ impl Window for TimeInfo {
         if time_changed {
             self.time_label.set_text(&format!("{:02}:{:02}", self.hour, minute10))
         }
-        self.date_label.draw(context);
-        self.time_label.draw(context);
+        context.set_viewport(None);
+        if UI_CFG.hud.date.show {
+            self.date_label.draw(context);
+        }
+        if UI_CFG.hud.time.show {
+            self.time_label.draw(context);
+        }
     }
 }
 
+/// Shows the player's hunger status
+pub struct HungerInfo {
+    label: LabelWidget,
+    status: Option<CharaStatus>,
+}
+
+impl HungerInfo {
+    pub fn new() -> HungerInfo {
+        HungerInfo {
+            label: LabelWidget::bordered(UI_CFG.hud.hunger.rect, "", FontKind::S),
+            status: None,
+        }
+    }
+}
+
+impl Window for HungerInfo {
+    fn draw(&mut self, context: &mut Context, game: &Game, _anim: Option<(&Animation, u32)>) {
+        let player_chara = game.gd.chara.get(CharaId::Player);
+        let status = player_chara.status.iter().find(|s| s.about_sp()).copied();
+        if self.status != status {
+            self.status = status;
+            self.label.set_text(status.as_ref().map_or("", |s| crate::text::to_txt(s)));
+        }
+
+        if self.status.is_some() {
+            context.set_viewport(None);
+            self.label.draw(context);
+        }
+    }
+}
+
+/// Shows icons of the player's status effects, and whether hostile npcs have noticed the player
 pub struct StatusInfo {
-    labels: Vec<LabelWidget>,
+    icons: Vec<IconWidget>,
     status: Vec<CharaStatus>,
+    noticed: bool,
 }
 
 impl StatusInfo {
     pub fn new() -> StatusInfo {
         StatusInfo {
-            labels: Vec::new(),
+            icons: Vec::new(),
             status: Vec::new(),
+            noticed: false,
         }
     }
 
     fn update(&mut self, game: &Game) {
         let player_chara = game.gd.chara.get(CharaId::Player);
-        let rect: Rect = SCREEN_CFG.status_info.into();
+        let rect: Rect = UI_CFG.hud.status_icons.rect.into();
         
-        if self.status != player_chara.status {
+        if self.status != player_chara.status || self.noticed != game.player_noticed {
             self.status.clone_from(&player_chara.status);
+            self.noticed = game.player_noticed;
 
-            self.labels.clear();
-            for (i, status) in self.status.iter().enumerate() {
-                let label = LabelWidget::bordered(
-                    Rect::new(rect.x, rect.y - rect.h * i as i32, 1, 1),
-                    crate::text::to_txt(status),
-                    FontKind::S);
-                self.labels.push(label);
+            let mut icons: Vec<(&str, &str)> = self.status.iter()
+                .filter(|s| !(s.about_sp() && UI_CFG.hud.hunger.show)) // Shown by HungerInfo
+                .map(|s| (status_icon_id(s), crate::text::to_txt(s)))
+                .collect();
+            if self.noticed {
+                icons.push(("!icon-noticed", crate::text::ui_txt("indicator.noticed")));
             }
+
+            self.icons = icons.into_iter().enumerate().map(|(i, (id, text))| {
+                IconWidget::new(
+                    Rect::new(rect.x, rect.y - rect.h * i as i32, rect.width(), rect.height()),
+                    id, text)
+            }).collect();
         }
     }
 }
@@ -184,9 +254,19 @@ impl Window for StatusInfo {
         self.update(game);
         
         context.set_viewport(None);
-        for label in self.labels.iter_mut() {
-            label.draw(context);
+        for icon in self.icons.iter_mut() {
+            icon.draw(context);
         }
     }
 }
 
+fn status_icon_id(status: &CharaStatus) -> &'static str {
+    match *status {
+        CharaStatus::Hungry        => "!icon-hungry",
+        CharaStatus::Weak          => "!icon-weak",
+        CharaStatus::Starving      => "!icon-starving",
+        CharaStatus::Asleep { .. } => "!icon-asleep",
+        CharaStatus::Poisoned      => "!icon-poisoned",
+        CharaStatus::Hasted { .. } => "!icon-hasted",
+    }
+}
@@ -9,15 +9,24 @@ use crate::config::UI_CFG;
 use crate::draw::border::draw_rect_border;
 use crate::eventhandler::InputMode;
 use super::widget::*;
+use super::msg_dialog::MsgDialog;
 use common::gamedata::*;
 use crate::game::item::filter::*;
 
 pub type ActionCallback = FnMut(&mut DoPlayerAction, ItemLocation) -> DialogResult;
 pub enum ItemWindowMode {
-    List, PickUp, Drop, Drink, Eat, ShopSell,
+    List, PickUp, Drop, Drink, Eat, Read, Throw, ShopSell,
     ShopBuy {
         cid: CharaId,
     },
+    /// Take items out of the container at the location
+    TakeOut {
+        container: (ContainerLocation, u32),
+    },
+    /// Put the player's items into the container at the location
+    PutIn {
+        container: (ContainerLocation, u32),
+    },
     Select {
         ill: ItemListLocation,
         filter: ItemFilter,
@@ -94,6 +103,17 @@ impl ItemWindow {
                     .get_filtered_item_list(ill, ItemFilter::new().flags(ItemFlags::EATABLE));
                 self.update_list(filtered_list);
             }
+            ItemWindowMode::Read => {
+                let ill = ItemListLocation::Chara { cid: CharaId::Player };
+                let filtered_list = gd
+                    .get_filtered_item_list(ill, ItemFilter::new().flags(ItemFlags::READABLE));
+                self.update_list(filtered_list);
+            }
+            ItemWindowMode::Throw => {
+                let ill = ItemListLocation::Chara { cid: CharaId::Player };
+                let filtered_list = gd.get_filtered_item_list(ill, ItemFilter::all());
+                self.update_list(filtered_list);
+            }
             ItemWindowMode::ShopBuy { cid } => {
                 let ill = ItemListLocation::Shop { cid };
                 let filtered_list = gd.get_filtered_item_list(ill, ItemFilter::new());
@@ -104,6 +124,16 @@ impl ItemWindow {
                 let filtered_list = gd.get_filtered_item_list(ill, ItemFilter::new());
                 self.update_list(filtered_list);
             }
+            ItemWindowMode::TakeOut { container: (parent, i) } => {
+                let ill = ItemListLocation::Container { parent, i };
+                let filtered_list = gd.get_filtered_item_list(ill, ItemFilter::all());
+                self.update_list(filtered_list);
+            }
+            ItemWindowMode::PutIn { .. } => {
+                let ill = ItemListLocation::Chara { cid: CharaId::Player };
+                let filtered_list = gd.get_filtered_item_list(ill, ItemFilter::all());
+                self.update_list(filtered_list);
+            }
             ItemWindowMode::Select { ill, filter, ..} => {
                 let filtered_list = gd.get_filtered_item_list(ill, filter);
                 self.update_list(filtered_list);
@@ -151,10 +181,22 @@ impl ItemWindow {
 
     fn do_action_for_item(&mut self, pa: &mut DoPlayerAction, il: ItemLocation) -> DialogResult {
         match self.mode {
-            ItemWindowMode::List => {
-                DialogResult::Continue
+            ItemWindowMode::List => { // Examine the selected item
+                if let Some(dialog) = container_dialog(pa, il) {
+                    return DialogResult::OpenChildDialog(Box::new(dialog));
+                }
+                if let Some(dialog) = mount_dialog(pa, il) {
+                    return DialogResult::OpenChildDialog(Box::new(dialog));
+                }
+                let msg = item_info_text(pa.gd().get_item(il).0);
+                let choices = vec![crate::text::ui_txt("dialog.choice.close").to_owned()];
+                let dialog = MsgDialog::new(&msg, choices, |_, _| DialogResult::Close);
+                DialogResult::OpenChildDialog(Box::new(dialog))
             }
             ItemWindowMode::PickUp => {
+                if let Some(dialog) = container_dialog(pa, il) { // Open the container
+                    return DialogResult::OpenChildDialog(Box::new(dialog));
+                }
                 pa.pick_up_item(il, 1);
                 if pa.gd().is_item_on_player_tile() {
                     self.update_by_mode(pa);
@@ -178,6 +220,15 @@ impl ItemWindow {
                 self.update_by_mode(pa);
                 DialogResult::CloseAll
             }
+            ItemWindowMode::Read => {
+                pa.read_item(il);
+                self.update_by_mode(pa);
+                DialogResult::CloseAll
+            }
+            ItemWindowMode::Throw => {
+                pa.throw_item(il);
+                DialogResult::CloseAll
+            }
             ItemWindowMode::ShopBuy { .. } => {
                 pa.buy_item(il);
                 self.update_by_mode(pa);
@@ -188,6 +239,16 @@ impl ItemWindow {
                 self.update_by_mode(pa);
                 DialogResult::Continue
             }
+            ItemWindowMode::TakeOut { .. } => {
+                pa.take_out_from_container(il, 1);
+                self.update_by_mode(pa);
+                DialogResult::Continue
+            }
+            ItemWindowMode::PutIn { container } => {
+                pa.put_in_container(il, container, 1);
+                self.update_by_mode(pa);
+                DialogResult::Continue
+            }
             ItemWindowMode::Select { ref mut action, .. } => {
                 action(pa, il)
             }
@@ -195,6 +256,72 @@ impl ItemWindow {
     }
 }
 
+/// If the item is a container, create a dialog to take out or put in items
+fn container_dialog(pa: &mut DoPlayerAction, il: ItemLocation) -> Option<MsgDialog> {
+    let item = pa.gd().get_item(il).0;
+    if !item.is_container() {
+        return None;
+    }
+    let (parent, on_map) = match il.0 {
+        ItemListLocation::OnMap { mid, pos } => (ContainerLocation::OnMap { mid, pos }, true),
+        ItemListLocation::Chara { cid } => (ContainerLocation::Chara { cid }, false),
+        _ => { return None; }
+    };
+    let container = (parent, il.1);
+
+    let msg = item_info_text(item);
+    let mut choices = vec![
+        crate::text::ui_txt("dialog.choice.take_out").to_owned(),
+        crate::text::ui_txt("dialog.choice.put_in").to_owned()];
+    if on_map {
+        choices.push(crate::text::ui_txt("dialog.choice.pick_up").to_owned());
+    }
+    choices.push(crate::text::ui_txt("dialog.choice.close").to_owned());
+
+    Some(MsgDialog::new(&msg, choices, move |pa, n| {
+        let mode = match n {
+            0 => ItemWindowMode::TakeOut { container },
+            1 => ItemWindowMode::PutIn { container },
+            2 if on_map => {
+                pa.pick_up_item(il, 1);
+                return DialogResult::CloseAll;
+            }
+            _ => { return DialogResult::Close; }
+        };
+        DialogResult::OpenChildDialog(Box::new(ItemWindow::new(mode, pa)))
+    }))
+}
+
+/// If the item is a horse or boat, create a dialog to ride it
+fn mount_dialog(pa: &mut DoPlayerAction, il: ItemLocation) -> Option<MsgDialog> {
+    let item = pa.gd().get_item(il).0;
+    if !crate::game::mount::is_mount(item) {
+        return None;
+    }
+
+    let msg = item_info_text(item);
+    let choices = vec![
+        crate::text::ui_txt("dialog.choice.ride").to_owned(),
+        crate::text::ui_txt("dialog.choice.close").to_owned()];
+
+    Some(MsgDialog::new(&msg, choices, move |pa, n| {
+        if n == 0 {
+            pa.ride(il);
+            return DialogResult::CloseAll;
+        }
+        DialogResult::Close
+    }))
+}
+
+/// Text to describe the item
+fn item_info_text(item: &Item) -> String {
+    let weight = format!("{:.2}", item.w() as f32 / 1000.0);
+    let price = item.price();
+    replace_str!(
+        crate::text::ui_txt("dialog.item_info");
+        item=item, weight=&weight, price=&price)
+}
+
 impl Window for ItemWindow {
     
     fn draw(
@@ -233,6 +360,13 @@ impl DialogWindow for ItemWindow {
         InputMode::Dialog
     }
 
+    fn callback_child_closed(
+        &mut self, _result: Option<Box<dyn std::any::Any>>, pa: &mut DoPlayerAction) -> DialogResult {
+        // Items may be moved by a container dialog
+        self.update_by_mode(pa);
+        DialogResult::Continue
+    }
+
     fn draw_mode(&self) -> WindowDrawMode {
         WindowDrawMode::SkipUnderWindows
     }
@@ -0,0 +1,120 @@
+
+use common::gamedata::*;
+use crate::text::{self, ToText};
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+use super::quest_window::quest_decription_text;
+
+/// Lists active and completed quests with their descriptions
+pub struct JournalWindow {
+    rect: Rect,
+    list: TextListWidget,
+    description: LabelWidget,
+    entries: Vec<JournalEntry>,
+}
+
+struct JournalEntry {
+    title: String,
+    description: String,
+}
+
+impl JournalWindow {
+    pub fn new(game: &Game) -> JournalWindow {
+        let rect: Rect = UI_CFG.quest_window.rect.into();
+        let mut w = JournalWindow {
+            rect,
+            list: TextListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                vec![6],
+                UI_CFG.quest_window.n_row,
+                26,
+                true,
+                false),
+            description: LabelWidget::wrapped(
+                (0i32, (UI_CFG.quest_window.n_row as i32 + 1) * 26, rect.width(), 0),
+                "",
+                FontKind::M, rect.width()),
+            entries: journal_entries(&game.gd),
+        };
+        let rows: Vec<TextCache> = w.entries.iter()
+            .map(|entry| TextCache::one(&entry.title, FontKind::M, UI_CFG.color.normal_font.into()))
+            .collect();
+        w.list.set_items(rows);
+        w.update_description();
+        w
+    }
+
+    fn update_description(&mut self) {
+        let text = self.entries.get(self.list.get_current_choice() as usize)
+            .map_or("", |entry| entry.description.as_str());
+        self.description.set_text(text);
+    }
+}
+
+impl Window for JournalWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+        self.description.draw(context);
+    }
+}
+
+impl DialogWindow for JournalWindow {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            match response {
+                ListWidgetResponse::SelectionChanged | ListWidgetResponse::PageChanged => {
+                    self.update_description();
+                }
+                _ => (),
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
+
+/// Town quests come first, then script quests sorted by id
+fn journal_entries(gd: &GameData) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+
+    for (state, quest) in gd.quest.iter() {
+        let state_id = match state {
+            QuestState::Active => "!quest.state.active",
+            QuestState::Completed | QuestState::RewardReceived => "!quest.state.complete",
+        };
+        let (current, goal) = quest.progress();
+        let progress = replace_str!(text::misc_txt("!quest.progress"); current=&current, goal=&goal);
+        entries.push(JournalEntry {
+            title: format!("{} {} {}", text::misc_txt(state_id), quest.to_text(), progress),
+            description: quest_decription_text(quest, gd),
+        });
+    }
+
+    for (id, quest) in gd.quest.script_quests() {
+        let state_id = match quest.state {
+            ScriptQuestState::Inactive => continue,
+            ScriptQuestState::Active => "!quest.state.active",
+            ScriptQuestState::Complete => "!quest.state.complete",
+            ScriptQuestState::Failed => "!quest.state.failed",
+        };
+        // Descriptions can be written for each stage
+        let desc_id = format!("!desc.quest.{}", id);
+        let description = text::misc_txt_checked(&format!("{}.{}", desc_id, quest.stage))
+            .unwrap_or_else(|| text::misc_txt(&desc_id));
+        entries.push(JournalEntry {
+            title: format!("{} {}", text::misc_txt(state_id), text::misc_txt(&format!("!quest.{}", id))),
+            description: description.to_owned(),
+        });
+    }
+    entries
+}
@@ -0,0 +1,188 @@
+
+use std::collections::HashMap;
+use crate::config::{self, INPUT_CFG};
+use crate::config::input::{self, InputConfig};
+use crate::text;
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+use super::SpecialDialogResult;
+
+/// Commands of normal mode that can be bound to keys
+const BINDABLE_COMMANDS: &[Command] = &[
+    Command::Enter,
+    Command::Shot,
+    Command::OpenExitWin,
+    Command::OpenItemMenu,
+    Command::OpenEquipWin,
+    Command::OpenStatusWin,
+    Command::OpenGameInfoWin,
+    Command::OpenJournalWin,
+    Command::OpenAbilityWin,
+    Command::OpenCraftWin,
+    Command::OpenLogWin,
+    Command::OpenMapWin,
+    Command::OpenPartyWin,
+    Command::ToggleMinimap,
+    Command::PickUpItem,
+    Command::DropItem,
+    Command::DrinkItem,
+    Command::EatItem,
+    Command::ReadItem,
+    Command::ThrowItem,
+    Command::PushChara,
+    Command::Dismount,
+    Command::Dig,
+    Command::CloseDoor,
+    Command::DisarmTrap,
+    Command::TargetingMode,
+    Command::Screenshot,
+    Command::ExportMap,
+    Command::OpenConsole,
+];
+
+/// Review and change key bindings of normal mode
+pub struct KeyBindingWindow {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache)>,
+    message: LabelWidget,
+    bindings: HashMap<String, Command>,
+    /// The index of the command waiting for a new key
+    waiting: Option<usize>,
+}
+
+impl KeyBindingWindow {
+    pub fn new() -> KeyBindingWindow {
+        let cfg = &UI_CFG.key_binding_window;
+        let rect: Rect = cfg.rect.into();
+        let bindings = config::load_input_config().normal;
+
+        let mut w = KeyBindingWindow {
+            rect,
+            list: ListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                cfg.column_pos.clone(),
+                cfg.n_row,
+                26,
+                true,
+                false),
+            message: LabelWidget::new(
+                (0i32, (cfg.n_row as i32 + 1) * 26, rect.width(), 0), "", FontKind::M),
+            bindings,
+            waiting: None,
+        };
+        w.update_rows();
+        w
+    }
+
+    fn update_rows(&mut self) {
+        let rows = BINDABLE_COMMANDS.iter().map(|command| {
+            let keys = bound_keys(&self.bindings, command).join(", ");
+            (TextCache::one(text::to_txt(command), FontKind::M, UI_CFG.color.normal_font.into()),
+             TextCache::one(keys, FontKind::M, UI_CFG.color.normal_font.into()))
+        }).collect();
+        self.list.set_items(rows);
+    }
+
+    /// Save the changed bindings and apply them to the event handler
+    fn apply(&self) -> DialogResult {
+        let config = InputConfig {
+            normal: self.bindings.clone(),
+            dialog: INPUT_CFG.dialog.clone(),
+            targeting: INPUT_CFG.targeting.clone(),
+            known: INPUT_CFG.known.clone(),
+        };
+        if let Err(e) = input::save_user_input_config(&config) {
+            warn!("Failed to save key bindings: {}", e);
+        }
+        DialogResult::Special(SpecialDialogResult::SetKeyBindings(self.bindings.clone()))
+    }
+}
+
+impl Window for KeyBindingWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+        self.message.draw(context);
+    }
+}
+
+impl DialogWindow for KeyBindingWindow {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(i) = self.waiting {
+            match command {
+                Command::KeyInput { key } => {
+                    toggle_binding(&mut self.bindings, key, &BINDABLE_COMMANDS[i]);
+                    self.waiting = None;
+                    self.message.set_text("");
+                    self.update_rows();
+                    return self.apply();
+                }
+                Command::Cancel => {
+                    self.waiting = None;
+                    self.message.set_text("");
+                }
+                _ => (),
+            }
+            return DialogResult::Continue;
+        }
+
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                self.waiting = Some(i as usize);
+                self.message.set_text(text::ui_txt("dialog.press_key"));
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        if self.waiting.is_some() {
+            InputMode::KeyInput
+        } else {
+            InputMode::Dialog
+        }
+    }
+}
+
+/// Sorted keys bound to the command
+fn bound_keys<'a>(bindings: &'a HashMap<String, Command>, command: &Command) -> Vec<&'a str> {
+    let mut keys: Vec<&str> = bindings.iter()
+        .filter(|&(_, c)| c == command)
+        .map(|(k, _)| k.as_str())
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Bind the key to the command. If the key is already bound to the command, it is unbound.
+/// One key is bound to one command, but a command can have multiple keys.
+fn toggle_binding(bindings: &mut HashMap<String, Command>, key: &str, command: &Command) {
+    if bindings.get(key) == Some(command) {
+        bindings.remove(key);
+    } else {
+        bindings.insert(key.to_owned(), command.clone());
+    }
+}
+
+#[test]
+fn toggle_binding_test() {
+    let mut bindings = HashMap::new();
+    bindings.insert("i".to_owned(), Command::OpenItemMenu);
+    bindings.insert("e".to_owned(), Command::EatItem);
+
+    toggle_binding(&mut bindings, "b", &Command::OpenItemMenu);
+    assert_eq!(bound_keys(&bindings, &Command::OpenItemMenu), vec!["b", "i"]);
+
+    toggle_binding(&mut bindings, "e", &Command::OpenItemMenu);
+    assert_eq!(bound_keys(&bindings, &Command::OpenItemMenu), vec!["b", "e", "i"]);
+    assert!(bound_keys(&bindings, &Command::EatItem).is_empty());
+
+    toggle_binding(&mut bindings, "i", &Command::OpenItemMenu);
+    assert_eq!(bound_keys(&bindings, &Command::OpenItemMenu), vec!["b", "e"]);
+}
@@ -0,0 +1,71 @@
+
+use crate::context::textrenderer::FontKind;
+use crate::log;
+use super::commonuse::*;
+use super::widget::*;
+
+/// Displays past log messages. The latest page is shown first.
+pub struct LogHistoryWindow {
+    rect: Rect,
+    list: TextListWidget,
+    lines: Vec<String>,
+}
+
+impl LogHistoryWindow {
+    pub fn new() -> LogHistoryWindow {
+        let rect: Rect = UI_CFG.log_history_window.rect.into();
+        let mut lines = Vec::new();
+        log::with_lines(0, |line| lines.push(line.concat()));
+
+        let mut w = LogHistoryWindow {
+            rect,
+            list: TextListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                vec![6],
+                UI_CFG.log_history_window.n_row,
+                UI_CFG.log_window.h as u32,
+                true,
+                true),
+            lines,
+        };
+        w.list.set_n_item(w.lines.len() as u32);
+        let max_page = w.list.get_max_page();
+        w.list.set_page(max_page);
+        w.update_rows();
+        w
+    }
+
+    fn update_rows(&mut self) {
+        let lines = &self.lines;
+        self.list.update_rows_by_func(|i| {
+            TextCache::one(&lines[i as usize], FontKind::Log, UI_CFG.color.log_font.into())
+        });
+    }
+}
+
+impl Window for LogHistoryWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+    }
+}
+
+impl DialogWindow for LogHistoryWindow {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::PageChanged = response {
+                self.update_rows();
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
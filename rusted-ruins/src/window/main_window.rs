@@ -1,14 +1,20 @@
 
 use array2d::*;
+use sdl2::rect::Rect;
 use crate::game::{Game, Animation, InfoGetter};
 use crate::context::*;
 use crate::window::Window;
+use crate::window::widget::{LabelWidget, WidgetTrait};
 use crate::draw::mainwin::MainWinDrawer;
 use crate::config::SCREEN_CFG;
+use crate::text::ToText;
 
 pub struct MainWindow {
     drawer: MainWinDrawer,
     centering_tile: Option<Vec2d>,
+    mouse_pos: Option<(i32, i32)>,
+    /// Tooltip of the tile under the mouse cursor
+    tooltip: Option<(Vec2d, LabelWidget)>,
 }
 
 impl MainWindow {
@@ -16,9 +22,35 @@ impl MainWindow {
         MainWindow {
             drawer: MainWinDrawer::new(SCREEN_CFG.main_window.into()),
             centering_tile: None,
+            mouse_pos: None,
+            tooltip: None,
         }
     }
 
+    pub fn set_mouse_pos(&mut self, mouse_pos: Option<(i32, i32)>) {
+        self.mouse_pos = mouse_pos;
+    }
+
+    /// Convert a position on the screen to the tile position on the current map
+    pub fn pos_to_tile(&self, game: &Game, x: i32, y: i32) -> Option<Vec2d> {
+        let tile = self.drawer.pos_to_tile(x, y)?;
+        if game.gd.get_current_map().is_inside(tile) { Some(tile) } else { None }
+    }
+
+    fn update_tooltip(&mut self, game: &Game) {
+        let tile = self.mouse_pos.and_then(|(x, y)| self.pos_to_tile(game, x, y));
+        let tile = if let Some(tile) = tile { tile } else {
+            self.tooltip = None;
+            return;
+        };
+        if self.tooltip.as_ref().map_or(false, |&(t, _)| t == tile) {
+            return;
+        }
+        self.tooltip = tile_description(game, tile).map(|text| {
+            (tile, LabelWidget::bordered((0, 0, 0, 0), &text, FontKind::M))
+        });
+    }
+
     pub fn start_targeting_mode(&mut self, game: &Game) {
         info!("Start targeting mode");
         self.centering_tile = Some(game.gd.player_pos());
@@ -74,6 +106,28 @@ impl Window for MainWindow {
         &mut self, context: &mut Context, game: &Game, anim: Option<(&Animation, u32)>) {
         
         self.drawer.draw(context, game, anim, self.centering_tile);
+
+        self.update_tooltip(game);
+        if let (Some((x, y)), Some((_, label))) = (self.mouse_pos, self.tooltip.as_mut()) {
+            const CURSOR_OFFSET: i32 = 16;
+            let (w, h) = label.adjust_widget_size(context.sv);
+            label.set_rect(Rect::new(x + CURSOR_OFFSET, y + CURSOR_OFFSET, w, h));
+            context.set_viewport(None);
+            label.draw(context);
+        }
+    }
+}
+
+/// Describe the character or items on the tile if the player can see it
+pub fn tile_description(game: &Game, tile: Vec2d) -> Option<String> {
+    if !game.view_map.get_tile_visible(tile) {
+        return None;
+    }
+    let t = &game.gd.get_current_map().tile[tile];
+    if let Some(cid) = t.chara {
+        return Some(game.gd.chara.get(cid).to_text().into_owned());
     }
+    let (item, _) = t.item_list.as_ref()?.iter().next()?;
+    Some(item.to_text().into_owned())
 }
 
@@ -0,0 +1,113 @@
+
+use sdl2::pixels::Color;
+use array2d::*;
+use crate::game::InfoGetter;
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+use super::minimap::symbol_color;
+use super::main_window::tile_description;
+
+/// Displays the whole current map. The cursor inspects the tile under it.
+pub struct MapWindow {
+    rect: Rect,
+    /// Size of one tile in pixels
+    tile_size: i32,
+    /// Top left position of the map in the window
+    offset: (i32, i32),
+    cursor: Vec2d,
+    label: LabelWidget,
+}
+
+impl MapWindow {
+    pub fn new(game: &Game) -> MapWindow {
+        let rect: Rect = UI_CFG.map_window.rect.into();
+        let label_h = UI_CFG.label_widget.h;
+        let (map_w, map_h) = game.gd.map_size();
+        let tile_size = std::cmp::min(rect.w / map_w as i32, (rect.h - label_h) / map_h as i32);
+        let tile_size = std::cmp::max(1, std::cmp::min(tile_size, UI_CFG.map_window.max_tile_size));
+        let offset = (
+            (rect.w - tile_size * map_w as i32) / 2,
+            (rect.h - label_h - tile_size * map_h as i32) / 2);
+
+        let mut w = MapWindow {
+            rect,
+            tile_size,
+            offset,
+            cursor: game.gd.player_pos(),
+            label: LabelWidget::new(
+                (0, rect.h - label_h, rect.w as u32, label_h as u32), "", FontKind::M),
+        };
+        w.update_label(game);
+        w
+    }
+
+    fn update_label(&mut self, game: &Game) {
+        let text = tile_description(game, self.cursor).unwrap_or_default();
+        self.label.set_text(&format!("({}, {}) {}", self.cursor.0, self.cursor.1, text));
+    }
+
+    fn move_cursor(&mut self, dir: Direction, game: &Game) {
+        let c = self.cursor + dir.as_vec();
+        if game.gd.get_current_map().is_inside(c) {
+            self.cursor = c;
+        }
+    }
+
+    /// Convert a position on the screen to a tile
+    fn pos_to_tile(&self, x: i32, y: i32) -> Vec2d {
+        let x = x - self.rect.x - self.offset.0;
+        let y = y - self.rect.y - self.offset.1;
+        Vec2d(x.div_euclid(self.tile_size), y.div_euclid(self.tile_size))
+    }
+}
+
+impl Window for MapWindow {
+    fn draw(&mut self, context: &mut Context, game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+
+        let s = self.tile_size;
+        let map = game.gd.get_current_map();
+        for p in map.tile.iter_idx() {
+            let color = if let Some(color) = symbol_color(game, p) { color } else { continue; };
+            let r = Rect::new(self.offset.0 + p.0 * s, self.offset.1 + p.1 * s, s as u32, s as u32);
+            context.canvas.set_draw_color(Color::RGB(color.0, color.1, color.2));
+            check_draw!(context.canvas.fill_rect(r));
+        }
+
+        // Draw cursor
+        let c = self.cursor;
+        let r = Rect::new(self.offset.0 + c.0 * s - 1, self.offset.1 + c.1 * s - 1,
+                          s as u32 + 2, s as u32 + 2);
+        context.canvas.set_draw_color(UI_CFG.color.border_highlight_light);
+        check_draw!(context.canvas.draw_rect(r));
+
+        self.label.draw(context);
+    }
+}
+
+impl DialogWindow for MapWindow {
+    fn process_command(&mut self, command: &Command, pa: &mut DoPlayerAction) -> DialogResult {
+        match *command {
+            Command::Move { dir } => {
+                self.move_cursor(dir, pa.game());
+                self.update_label(pa.game());
+                DialogResult::Continue
+            }
+            Command::MouseButtonUp { x, y } => {
+                let tile = self.pos_to_tile(x, y);
+                if pa.gd().get_current_map().is_inside(tile) {
+                    self.cursor = tile;
+                    self.update_label(pa.game());
+                }
+                DialogResult::Continue
+            }
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
@@ -6,34 +6,81 @@ use array2d::*;
 use common::gobj;
 use crate::game::Game;
 use crate::context::*;
+use common::gamedata::*;
 use crate::game::{Animation, InfoGetter};
+use crate::game::chara::relation_between;
 use crate::window::Window;
 use crate::config::SCREEN_CFG;
 
 pub struct MiniMapWindow {
     rect: Rect,
+    pub visible: bool,
+    terrain: Option<TerrainColors>,
+}
+
+/// Terrain colors of the current map.
+/// Only the chunks changed after `revision` are recalculated.
+struct TerrainColors {
+    mapid: MapId,
+    revision: u64,
+    colors: Array2d<Option<(u8, u8, u8)>>,
 }
 
 impl MiniMapWindow {
     pub fn new() -> MiniMapWindow {
         MiniMapWindow {
             rect: SCREEN_CFG.minimap_window.into(),
+            visible: true,
+            terrain: None,
+        }
+    }
+
+    fn update_terrain(&mut self, game: &Game) {
+        let mapid = game.gd.get_current_mapid();
+        let map = game.gd.get_current_map();
+        let revision = map.tile.revision();
+
+        match self.terrain {
+            Some(ref mut terrain) if terrain.mapid == mapid && terrain.colors.size() == map.size() => {
+                let mut chunks = map.tile.dirty_chunks(terrain.revision);
+                chunks.extend(map.observed_tile.dirty_chunks(terrain.revision));
+                for c in chunks {
+                    for p in map.tile.iter_chunk_idx(c) {
+                        terrain.colors[p] = terrain_color(map, p);
+                    }
+                }
+                terrain.revision = revision;
+            }
+            _ => {
+                let (w, h) = map.size();
+                let mut colors = Array2d::new(w, h, None);
+                for p in map.tile.iter_idx() {
+                    colors[p] = terrain_color(map, p);
+                }
+                self.terrain = Some(TerrainColors { mapid, revision, colors });
+            }
         }
     }
 }
 
 impl Window for MiniMapWindow {
     fn draw(&mut self, context: &mut Context, game: &Game, _anim: Option<(&Animation, u32)>) {
+        if !self.visible {
+            return;
+        }
 
+        self.update_terrain(game);
+        let terrain = &self.terrain.as_ref().unwrap().colors;
         context.set_viewport(self.rect);
-        draw_minimap(context.canvas, self.rect, game, context.sv);
+        draw_minimap(context.canvas, self.rect, game, terrain);
     }
 }
 
 const RECT_SIZE: u32 = 3;
 const RECT_SIZE_I: i32 = RECT_SIZE as i32;
 
-fn draw_minimap(canvas: &mut WindowCanvas, rect: Rect, game: &Game, _sv: &mut SdlValues) {
+fn draw_minimap(canvas: &mut WindowCanvas, rect: Rect, game: &Game,
+                terrain: &Array2d<Option<(u8, u8, u8)>>) {
     use std::cmp::{max, min};
     let map = game.gd.get_current_map();
     let map_size = map.size();
@@ -47,15 +94,8 @@ fn draw_minimap(canvas: &mut WindowCanvas, rect: Rect, game: &Game, _sv: &mut Sd
     let top_left = (max(0, top_left.0), max(0, top_left.1));
                              
     for p in RectIter::new(top_left, bottom_right) {
-        let color = if p == center_p {
-            (255, 255, 0)
-        } else if let Some(wall_idx) = map.observed_tile[p].wall.idx() {
-            gobj::get_obj(wall_idx).symbol_color
-        } else if map.observed_tile[p].tile {
-            gobj::get_obj(map.tile[p].main_tile()).symbol_color
-        } else {
-            continue;
-        };
+        let color = chara_color(game, p).or(terrain[p]);
+        let color = if let Some(color) = color { color } else { continue; };
         let color = Color::RGB(color.0, color.1, color.2);
         
         let draw_rect = Rect::new(p.0 * RECT_SIZE_I - dx, p.1 * RECT_SIZE_I - dy, RECT_SIZE, RECT_SIZE);
@@ -64,3 +104,41 @@ fn draw_minimap(canvas: &mut WindowCanvas, rect: Rect, game: &Game, _sv: &mut Sd
     }
 }
 
+
+/// Color of the tile on maps. Returns None if the tile is not observed yet.
+/// Characters are shown only while the player can see them.
+pub fn symbol_color(game: &Game, p: Vec2d) -> Option<(u8, u8, u8)> {
+    chara_color(game, p).or_else(|| terrain_color(game.gd.get_current_map(), p))
+}
+
+/// Color of the character on the tile if the player can see it
+fn chara_color(game: &Game, p: Vec2d) -> Option<(u8, u8, u8)> {
+    let gd = &game.gd;
+    let cid = gd.get_current_map().tile[p].chara?;
+    if cid == CharaId::Player {
+        return Some((255, 255, 0));
+    }
+    if game.view_map.get_tile_visible(p) {
+        match relation_between(gd, CharaId::Player, cid) {
+            Relationship::HOSTILE => Some((255, 40, 40)),
+            _ => Some((80, 200, 255)),
+        }
+    } else {
+        None
+    }
+}
+
+/// Color of the observed terrain
+fn terrain_color(map: &Map, p: Vec2d) -> Option<(u8, u8, u8)> {
+    let observed = &map.observed_tile[p];
+
+    if let SpecialTileKind::Stairs { .. } = observed.special {
+        Some((255, 255, 255))
+    } else if let Some(wall_idx) = observed.wall.idx() {
+        Some(gobj::get_obj(wall_idx).symbol_color)
+    } else if observed.tile {
+        Some(gobj::get_obj(map.tile[p].main_tile()).symbol_color)
+    } else {
+        None
+    }
+}
@@ -18,7 +18,17 @@ mod newgame_window;
 mod start_window;
 mod status_window;
 mod game_info_window;
+mod ability_window;
+mod craft_window;
+mod journal_window;
+mod log_history_window;
+mod map_window;
+mod key_binding_window;
+mod settings_window;
+mod mod_window;
+mod party_window;
 mod text_input_dialog;
+mod console_window;
 mod indicator;
 mod minimap;
 
@@ -30,6 +40,7 @@ use sdl2::render::TextureCreator;
 use sdl2::video::WindowContext;
 use sdl2::keyboard::TextInputUtil;
 use crate::SdlContext;
+use crate::screenshot::{self, CaptureRequest};
 use self::main_window::MainWindow;
 use self::log_window::LogWindow;
 use self::widget::WidgetTrait;
@@ -57,6 +68,8 @@ pub enum SpecialDialogResult {
     StartDialogNewGame, StartDialogLoadGame,
     NewGameStart(GameData),
     ReturnToStartScreen,
+    /// Replace the key bindings of normal mode
+    SetKeyBindings(std::collections::HashMap<String, Command>),
 }
 
 pub trait Window {
@@ -111,6 +124,14 @@ pub struct WindowManager<'sdl, 't> {
     passed_frame: u32,
     window_stack: Vec<Box<dyn DialogWindow>>,
     targeting_mode: bool,
+    /// The next direction input is used to push a character
+    push_mode: bool,
+    /// The next direction input is used to dig, and enter is used to dig down
+    dig_mode: bool,
+    /// The tile the player is travelling to by mouse click
+    travel_destination: Option<Vec2d>,
+    /// An image is saved at the next drawing
+    capture_request: Option<CaptureRequest>,
 }
 
 impl<'sdl, 't> WindowManager<'sdl, 't> {
@@ -132,6 +153,10 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
             passed_frame: 0,
             window_stack,
             targeting_mode: false,
+            push_mode: false,
+            dig_mode: false,
+            travel_destination: None,
+            capture_request: None,
         }
     }
 
@@ -152,6 +177,10 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
             }
         }
 
+        if let WindowManageMode::OnGame(ref mut game_windows) = self.mode {
+            game_windows.main_window.set_mouse_pos(event_handler.mouse_pos());
+        }
+
         if self.game.get_state() == GameState::PlayerTurn {
             if !self.process_command(event_handler) { return false; }
         }
@@ -179,6 +208,11 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
         let anim = self.anim.as_ref().map(|a| (a, self.passed_frame));
         let mut context = Context::new(canvas, &mut self.sdl_values);
 
+        if self.capture_request == Some(CaptureRequest::Map) && self.mode.is_on_game() {
+            self.capture_request = None;
+            screenshot::export_map(&mut context, &mut self.game);
+        }
+
         // Draw windows
         match self.mode {
             WindowManageMode::OnGame(ref mut game_windows) => {
@@ -209,11 +243,53 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
             self.window_stack[*i].draw(&mut context, &self.game, anim);
         }
 
+        if self.capture_request == Some(CaptureRequest::Screen) {
+            self.capture_request = None;
+            screenshot::save_screen(context.canvas);
+        }
+
         if anim.is_some() {
             self.passed_frame += 1;
         }
     }
 
+    /// Recreate the screen windows after the screen is resized.
+    /// Opened dialogs keep their positions.
+    pub fn relayout(&mut self) {
+        match self.mode {
+            WindowManageMode::Start(ref mut start_window) => {
+                *start_window = start_window::StartWindow::new();
+            }
+            WindowManageMode::NewGame(ref mut newgame_window) => {
+                *newgame_window = newgame_window::NewGameWindow::new();
+            }
+            WindowManageMode::OnGame(ref mut game_windows) => {
+                let minimap_visible = game_windows.minimap_window.visible;
+                *game_windows = GameWindows::new();
+                game_windows.minimap_window.visible = minimap_visible;
+                if self.targeting_mode {
+                    game_windows.main_window.start_targeting_mode(&self.game);
+                }
+            }
+        }
+    }
+
+    /// Reload changed data for development, and recreate windows to show them
+    pub fn reload(&mut self, changed: crate::hot_reload::Changed) {
+        if changed.pak && common::gobj::reload() {
+            info!("Paks are reloaded");
+            self.sdl_values.texture_holder =
+                crate::context::texture::TextureHolder::new(common::gobj::get_objholder(), self.sdl_values.tc);
+            if !changed.text {
+                crate::text::reload_talk();
+            }
+        }
+        if changed.text {
+            crate::text::reload();
+        }
+        self.relayout();
+    }
+
     pub fn animation_now(&self) -> bool {
         self.anim.is_some()
     }
@@ -233,16 +309,64 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
         };
         
         let command = event_handler.get_command(mode);
-        if command.is_none() { return true; }
+        if command.is_none() {
+            // Continue traveling if there is no input
+            if mode == InputMode::Normal && self.mode.is_on_game() {
+                if let Some(dest) = self.travel_destination {
+                    let mut pa = DoPlayerAction::new(&mut self.game);
+                    if !pa.travel_step(dest) {
+                        self.travel_destination = None;
+                    }
+                }
+            }
+            return true;
+        }
         let command = command.unwrap();
+        match command {
+            Command::MouseButtonUp { .. } | Command::MouseWheel { .. } => (),
+            _ => { self.travel_destination = None; }
+        }
+
+        match command {
+            Command::Screenshot => {
+                self.capture_request = Some(CaptureRequest::Screen);
+                return true;
+            }
+            Command::ExportMap => {
+                // Exporting the whole map is a debug feature like the console
+                if self.mode.is_on_game() && crate::config::CONFIG.debug_console {
+                    self.capture_request = Some(CaptureRequest::Map);
+                }
+                return true;
+            }
+            _ => (),
+        }
 
         if self.targeting_mode {
             self.process_command_targeting_mode(command);
             return true;
         }
-        
-        use crate::game::playeract::DoPlayerAction;
 
+        // Other commands than moving cancel pushing
+        if self.push_mode && self.window_stack.is_empty() {
+            self.push_mode = false;
+            if let Command::Move { dir } = command {
+                DoPlayerAction::new(&mut self.game).push_chara(dir);
+            }
+            return true;
+        }
+
+        // Other commands than moving and enter cancel digging
+        if self.dig_mode && self.window_stack.is_empty() {
+            self.dig_mode = false;
+            match command {
+                Command::Move { dir } => DoPlayerAction::new(&mut self.game).dig(dir),
+                Command::Enter => DoPlayerAction::new(&mut self.game).dig_down(),
+                _ => (),
+            }
+            return true;
+        }
+        
         if !self.window_stack.is_empty() {
             let mut tail = self.window_stack.len() - 1;
             let mut dialog_result = {
@@ -279,6 +403,9 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
                     DialogResult::OpenChildDialog(child) => {
                         self.window_stack.push(child);
                     }
+                    DialogResult::Special(SpecialDialogResult::SetKeyBindings(bindings)) => {
+                        event_handler.set_normal_bindings(&bindings);
+                    }
                     DialogResult::Special(result) => { self.process_special_result(result); }
                 }
                 return true;
@@ -296,6 +423,10 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
                 // If player is on stairs, move from this map
                 if pa.gd().on_map_entrance() {
                     pa.goto_next_floor(Direction::none());
+                } else if pa.gd().on_resource() {
+                    pa.harvest();
+                } else {
+                    pa.examine();
                 }
             }
             Command::Shot => {
@@ -316,6 +447,33 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
             Command::OpenGameInfoWin => {
                 self.window_stack.push(Box::new(game_info_window::GameInfoWindow::new(pa.game())));
             }
+            Command::OpenAbilityWin => {
+                self.window_stack.push(Box::new(ability_window::AbilityWindow::new(pa.game())));
+            }
+            Command::OpenCraftWin => {
+                self.window_stack.push(Box::new(craft_window::CraftWindow::new(pa.game())));
+            }
+            Command::OpenJournalWin => {
+                self.window_stack.push(Box::new(journal_window::JournalWindow::new(pa.game())));
+            }
+            Command::OpenLogWin => {
+                self.window_stack.push(Box::new(log_history_window::LogHistoryWindow::new()));
+            }
+            Command::OpenMapWin => {
+                self.window_stack.push(Box::new(map_window::MapWindow::new(pa.game())));
+            }
+            Command::OpenPartyWin => {
+                self.window_stack.push(Box::new(party_window::PartyWindow::new(pa.game())));
+            }
+            Command::ToggleMinimap => {
+                match self.mode {
+                    WindowManageMode::OnGame(ref mut game_windows) => {
+                        let minimap = &mut game_windows.minimap_window;
+                        minimap.visible = !minimap.visible;
+                    }
+                    _ => unreachable!(),
+                }
+            }
             Command::PickUpItem => {
                 if pa.gd().item_on_player_tile().is_some() {
                     let item_window = ItemWindow::new(ItemWindowMode::PickUp, &mut pa);
@@ -331,6 +489,47 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
             Command::EatItem => {
                 self.window_stack.push(Box::new(ItemWindow::new(ItemWindowMode::Eat, &mut pa)));
             }
+            Command::ReadItem => {
+                self.window_stack.push(Box::new(ItemWindow::new(ItemWindowMode::Read, &mut pa)));
+            }
+            Command::ThrowItem => {
+                self.window_stack.push(Box::new(ItemWindow::new(ItemWindowMode::Throw, &mut pa)));
+            }
+            Command::PushChara => {
+                game_log_i!("push-which-dir");
+                self.push_mode = true;
+            }
+            Command::CloseDoor => {
+                pa.close_door();
+            }
+            Command::Dismount => {
+                pa.dismount();
+            }
+            Command::Dig => {
+                game_log_i!("dig-which-dir");
+                self.dig_mode = true;
+            }
+            Command::DisarmTrap => {
+                pa.disarm_trap();
+            }
+            Command::MouseButtonUp { x, y } => {
+                let tile = match self.mode {
+                    WindowManageMode::OnGame(ref game_windows) => {
+                        game_windows.main_window.pos_to_tile(pa.game(), x, y)
+                    }
+                    _ => unreachable!(),
+                };
+                if let Some(tile) = tile {
+                    if pa.travel_step(tile) {
+                        self.travel_destination = Some(tile);
+                    }
+                }
+            }
+            Command::OpenConsole => {
+                if crate::config::CONFIG.debug_console {
+                    self.window_stack.push(Box::new(console_window::ConsoleWindow::new()));
+                }
+            }
             Command::TargetingMode => {
                 self.targeting_mode = true;
                 match self.mode {
@@ -364,13 +563,7 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
                     // Load from file
                     SpecialDialogResult::NewGameStart(gd) => {
                         info!("Load game from file");
-                        self.window_stack.clear();
-                        self.mode = WindowManageMode::OnGame(GameWindows::new());
-
-                        let game = Game::new(gd);
-                        self.game = game;
-                        self.game.update_before_player_turn();
-                        game_log_i!("start"; version=env!("CARGO_PKG_VERSION"));
+                        self.start_game(gd);
                     }
                     _ => unreachable!(),
                 }
@@ -379,13 +572,7 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
                 match result {
                     SpecialDialogResult::NewGameStart(gd) => {
                         info!("Create newgame from dialog result");
-                        self.window_stack.clear();
-                        self.mode = WindowManageMode::OnGame(GameWindows::new());
-
-                        let game = Game::new(gd);
-                        self.game = game;
-                        self.game.update_before_player_turn();
-                        game_log_i!("start"; version=env!("CARGO_PKG_VERSION"));
+                        self.start_game(gd);
                     }
                     _ => unreachable!(),
                 }
@@ -399,12 +586,29 @@ impl<'sdl, 't> WindowManager<'sdl, 't> {
                         self.window_stack.push(Box::new(start_window::StartDialog::new()));
                         self.mode = WindowManageMode::Start(start_window::StartWindow::new());
                     }
+                    // Load from file after game over
+                    SpecialDialogResult::NewGameStart(gd) => {
+                        info!("Load game from file");
+                        crate::log::clear();
+                        self.start_game(gd);
+                    }
                     _ => unreachable!(),
                 }
             }
         }
     }
 
+    /// Start playing the new or loaded game
+    fn start_game(&mut self, gd: GameData) {
+        self.window_stack.clear();
+        self.mode = WindowManageMode::OnGame(GameWindows::new());
+
+        self.game = Game::new(gd);
+        self.game.update_before_player_turn();
+        self.game.play_map_music();
+        game_log_i!("start"; version=env!("CARGO_PKG_VERSION"));
+    }
+
     fn process_command_targeting_mode(&mut self, command: Command) {
         let main_window = match self.mode {
             WindowManageMode::OnGame(ref mut game_windows) => {
@@ -473,9 +677,11 @@ struct GameWindows {
     main_window: MainWindow,
     log_window: LogWindow,
     minimap_window: minimap::MiniMapWindow,
-    indicator: indicator::HPIndicator,
+    hp_indicator: Option<indicator::HPIndicator>,
+    mp_indicator: Option<indicator::MPIndicator>,
     floor_info: indicator::FloorInfo,
-    status_info: indicator::StatusInfo,
+    hunger_info: Option<indicator::HungerInfo>,
+    status_info: Option<indicator::StatusInfo>,
     time_info: indicator::TimeInfo,
     hborders: Vec<self::widget::HBorder>,
     vborders: Vec<self::widget::VBorder>,
@@ -483,26 +689,35 @@ struct GameWindows {
 
 impl GameWindows {
     fn new() -> GameWindows {
-        use crate::config::SCREEN_CFG;
+        use crate::config::{SCREEN_CFG, UI_CFG};
+        use crate::sdltypeconv::screen_size_diff;
         use self::widget::{HBorder, VBorder};
+        let (dx, dy) = screen_size_diff();
+        let hud = &UI_CFG.hud;
         let mut hborders = Vec::new();
         for hborder in &SCREEN_CFG.hborders {
-            hborders.push(HBorder::new(
-                (hborder.x, hborder.y), hborder.len));
+            let x = if hborder.anchor_right { hborder.x + dx } else { hborder.x };
+            let y = if hborder.anchor_bottom { hborder.y + dy } else { hborder.y };
+            let len = if hborder.stretch { (hborder.len as i32 + dx) as u32 } else { hborder.len };
+            hborders.push(HBorder::new((x, y), len));
         }
         let mut vborders = Vec::new();
         for vborder in &SCREEN_CFG.vborders {
-            vborders.push(VBorder::new(
-                (vborder.x, vborder.y), vborder.len));
+            let x = if vborder.anchor_right { vborder.x + dx } else { vborder.x };
+            let y = if vborder.anchor_bottom { vborder.y + dy } else { vborder.y };
+            let len = if vborder.stretch { (vborder.len as i32 + dy) as u32 } else { vborder.len };
+            vborders.push(VBorder::new((x, y), len));
         }
         
         GameWindows {
             main_window: MainWindow::new(),
             log_window:  LogWindow ::new(),
             minimap_window: minimap::MiniMapWindow::new(),
-            indicator: indicator::HPIndicator::new(),
+            hp_indicator: if hud.hp_bar.show { Some(indicator::HPIndicator::new()) } else { None },
+            mp_indicator: if hud.mp_bar.show { Some(indicator::MPIndicator::new()) } else { None },
             floor_info: indicator::FloorInfo::new(),
-            status_info: indicator::StatusInfo::new(),
+            hunger_info: if hud.hunger.show { Some(indicator::HungerInfo::new()) } else { None },
+            status_info: if hud.status_icons.show { Some(indicator::StatusInfo::new()) } else { None },
             time_info: indicator::TimeInfo::new(),
             hborders,
             vborders,
@@ -514,9 +729,19 @@ impl GameWindows {
         self.main_window.draw(context, game, anim);
         self.log_window.draw(context, game, anim);
         self.minimap_window.draw(context, game, anim);
-        self.indicator.draw(context, game, anim);
+        if let Some(hp_indicator) = self.hp_indicator.as_mut() {
+            hp_indicator.draw(context, game, anim);
+        }
+        if let Some(mp_indicator) = self.mp_indicator.as_mut() {
+            mp_indicator.draw(context, game, anim);
+        }
         self.floor_info.draw(context, game, anim);
-        self.status_info.draw(context, game, anim);
+        if let Some(hunger_info) = self.hunger_info.as_mut() {
+            hunger_info.draw(context, game, anim);
+        }
+        if let Some(status_info) = self.status_info.as_mut() {
+            status_info.draw(context, game, anim);
+        }
         self.time_info.draw(context, game, anim);
 
         for hborder in self.hborders.iter_mut() {
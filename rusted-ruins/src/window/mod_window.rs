@@ -0,0 +1,82 @@
+
+use crate::config::ModConfig;
+use crate::text;
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+
+/// Enable or disable mods. Changed mods are returned to the settings window when closed.
+pub struct ModWindow {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache)>,
+    message: LabelWidget,
+    mods: Vec<ModConfig>,
+}
+
+impl ModWindow {
+    pub fn new(mods: Vec<ModConfig>) -> ModWindow {
+        let cfg = &UI_CFG.mod_window;
+        let rect: Rect = cfg.rect.into();
+
+        let mut w = ModWindow {
+            rect,
+            list: ListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                cfg.column_pos.clone(),
+                cfg.n_row,
+                26,
+                true,
+                false),
+            message: LabelWidget::new(
+                (0i32, (cfg.n_row as i32 + 1) * 26, rect.width(), 0), "", FontKind::M),
+            mods,
+        };
+        if w.mods.is_empty() {
+            w.message.set_text(text::ui_txt("settings.no_mods"));
+        }
+        w.update_rows();
+        w
+    }
+
+    fn update_rows(&mut self) {
+        let color = UI_CFG.color.normal_font.into();
+        let rows = self.mods.iter().map(|m| {
+            let state = if m.disabled { "settings.mod_disabled" } else { "settings.mod_enabled" };
+            (TextCache::one(m.dir.clone(), FontKind::M, color),
+             TextCache::one(text::ui_txt(state), FontKind::M, color))
+        }).collect();
+        self.list.set_items(rows);
+    }
+}
+
+impl Window for ModWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+        self.message.draw(context);
+    }
+}
+
+impl DialogWindow for ModWindow {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                if let Some(m) = self.mods.get_mut(i as usize) {
+                    m.disabled = !m.disabled;
+                    self.message.set_text(text::ui_txt("settings.mod_restart_required"));
+                    self.update_rows();
+                }
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::CloseWithValue(Box::new(self.mods.clone())),
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
@@ -1,21 +1,28 @@
 
 use crate::config::SCREEN_CFG;
+use array2d::*;
 use super::commonuse::*;
 use super::widget::*;
 use crate::text;
 use common::gamedata::*;
-use crate::game::newgame::NewGameBuilder;
+use common::gobj;
+use common::objholder::CharaTemplateIdx;
+use crate::game::newgame::{NewGameBuilder, StatAllocation};
+use crate::context::textrenderer::FontKind;
 use super::text_window::{TextWindow, ScrollingTextWindow};
 use super::text_input_dialog::TextInputDialog;
 use super::SpecialDialogResult;
 use rules::RULES;
+use rules::newgame::StartScenario;
 
 /// Newgame processes with next order
-/// PlayerNameinput -> ChooseClass
+/// PlayerNameinput -> ChooseClass -> AllocateStats -> ChooseScenario
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum NewGameBuildStage {
     PlayerNameInput,
     ChooseClass,
+    AllocateStats,
+    ChooseScenario,
     OpeningText,
 }
 
@@ -25,7 +32,8 @@ pub struct NewGameWindow {
 
 impl NewGameWindow {
     pub fn new() -> NewGameWindow {
-        let rect = Rect::new(0, 0, SCREEN_CFG.screen_w, SCREEN_CFG.screen_h);
+        let (screen_w, screen_h) = crate::screen::screen_size();
+        let rect = Rect::new(0, 0, screen_w, screen_h);
         
         NewGameWindow {
             back_image: ImageWidget::ui_img(rect, "!title-screen"),
@@ -43,6 +51,8 @@ pub struct DummyNewGameDialog {
     explanation_text: TextWindow,
     name_input_dialog: Option<TextInputDialog>,
     choose_class_dialog: ChooseClassDialog,
+    allocate_stats_dialog: Option<AllocateStatsDialog>,
+    choose_scenario_dialog: ChooseScenarioDialog,
     opening_text: ScrollingTextWindow,
 }
 
@@ -54,6 +64,8 @@ impl DummyNewGameDialog {
             stage: NewGameBuildStage::PlayerNameInput,
             name_input_dialog: Some(TextInputDialog::new()),
             choose_class_dialog: ChooseClassDialog::new(),
+            allocate_stats_dialog: None,
+            choose_scenario_dialog: ChooseScenarioDialog::new(),
             opening_text: opening_text_window(),
         }
     }
@@ -71,6 +83,14 @@ impl Window for DummyNewGameDialog {
                 self.explanation_text.draw(context, game, anim);
                 self.choose_class_dialog.draw(context, game, anim);
             }
+            NewGameBuildStage::AllocateStats => {
+                self.explanation_text.draw(context, game, anim);
+                self.allocate_stats_dialog.as_mut().unwrap().draw(context, game, anim);
+            }
+            NewGameBuildStage::ChooseScenario => {
+                self.explanation_text.draw(context, game, anim);
+                self.choose_scenario_dialog.draw(context, game, anim);
+            }
             NewGameBuildStage::OpeningText => {
                 self.opening_text.draw(context, game, anim);
             }
@@ -84,7 +104,7 @@ impl DialogWindow for DummyNewGameDialog {
             NewGameBuildStage::PlayerNameInput => {
                 let name_input_dialog = self.name_input_dialog.as_mut().unwrap();
                 match name_input_dialog.process_command(command, pa) {
-                    DialogResult::Close => {
+                    DialogResult::Close | DialogResult::CloseWithValue(_) => {
                         let player_name = name_input_dialog.get_text();
                         if player_name != "" { // If input text is invalid for character name
                             self.builder.as_mut().unwrap().set_player_name(player_name);
@@ -102,6 +122,35 @@ impl DialogWindow for DummyNewGameDialog {
                     DialogResult::CloseWithValue(chara_class) => {
                         let chara_class = chara_class.downcast::<CharaClass>().unwrap();
                         self.builder.as_mut().unwrap().set_chara_class(*chara_class);
+                        self.allocate_stats_dialog = Some(AllocateStatsDialog::new(*chara_class));
+                        self.explanation_text = explanation_text_window("newgame.allocatestats");
+                        self.stage = NewGameBuildStage::AllocateStats;
+                    }
+                    _ => (),
+                }
+                return DialogResult::Continue;
+            }
+            NewGameBuildStage::AllocateStats => {
+                match self.allocate_stats_dialog.as_mut().unwrap().process_command(command, pa) {
+                    DialogResult::CloseWithValue(bonus_attr) => {
+                        let bonus_attr = bonus_attr.downcast::<CharaAttrRevision>().unwrap();
+                        self.builder.as_mut().unwrap().set_bonus_attr(*bonus_attr);
+                        self.explanation_text = explanation_text_window("newgame.choosescenario");
+                        self.stage = NewGameBuildStage::ChooseScenario;
+                    }
+                    DialogResult::Close => { // Back to class selection
+                        self.explanation_text = explanation_text_window("newgame.chooseclass");
+                        self.stage = NewGameBuildStage::ChooseClass;
+                    }
+                    _ => (),
+                }
+                return DialogResult::Continue;
+            }
+            NewGameBuildStage::ChooseScenario => {
+                match self.choose_scenario_dialog.process_command(command, pa) {
+                    DialogResult::CloseWithValue(scenario) => {
+                        let scenario = scenario.downcast::<StartScenario>().unwrap();
+                        self.builder.as_mut().unwrap().set_scenario(*scenario);
                         self.stage = NewGameBuildStage::OpeningText;
                         { // Skip OP text
                             let builder = self.builder.take().unwrap();
@@ -109,6 +158,10 @@ impl DialogWindow for DummyNewGameDialog {
                             return DialogResult::Special(SpecialDialogResult::NewGameStart(gd));
                         }
                     }
+                    DialogResult::Close => { // Back to stat allocation
+                        self.explanation_text = explanation_text_window("newgame.allocatestats");
+                        self.stage = NewGameBuildStage::AllocateStats;
+                    }
                     _ => (),
                 }
                 return DialogResult::Continue;
@@ -194,6 +247,154 @@ impl DialogWindow for ChooseClassDialog {
     }
 }
 
+const ATTR_NAMES: [&str; 6] = ["STR", "VIT", "DEX", "INT", "WIL", "CHA"];
+
+/// Allocate bonus points to the attributes of the chosen class
+pub struct AllocateStatsDialog {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache)>,
+    remaining_label: LabelWidget,
+    /// Attributes before allocation
+    base: [i16; 6],
+    allocation: StatAllocation,
+}
+
+impl AllocateStatsDialog {
+    pub fn new(chara_class: CharaClass) -> AllocateStatsDialog {
+        let rect: Rect = UI_CFG.allocate_stats_dialog.rect.into();
+        let label_h = UI_CFG.label_widget.h;
+        let ct_idx: CharaTemplateIdx =
+            gobj::id_to_idx(&RULES.newgame.chara_template_table[&chara_class]);
+        let ct = gobj::get_obj(ct_idx);
+        let a = if let Some(&r) = RULES.chara.class_revision.get(&chara_class) {
+            ct.base_attr.revise(r)
+        } else {
+            ct.base_attr
+        };
+
+        let mut dialog = AllocateStatsDialog {
+            rect,
+            list: ListWidget::new(
+                (0, label_h, rect.width(), rect.height() - label_h as u32),
+                vec![UI_CFG.list_widget.left_margin, UI_CFG.allocate_stats_dialog.column_pos],
+                ATTR_NAMES.len() as u32,
+                UI_CFG.list_widget.h_row_with_text as u32,
+                false,
+                false),
+            remaining_label: LabelWidget::new(
+                (0, 0, rect.width(), label_h as u32), "", FontKind::M),
+            base: [a.str, a.vit, a.dex, a.int, a.wil, a.cha],
+            allocation: StatAllocation::default(),
+        };
+        dialog.update();
+        dialog
+    }
+
+    fn update(&mut self) {
+        let color = UI_CFG.color.normal_font.into();
+        let rows = ATTR_NAMES.iter().enumerate().map(|(i, name)| {
+            let points = self.allocation.points[i];
+            let value = format!("{:>3} (+{})", self.base[i] + points as i16, points);
+            (TextCache::one(*name, FontKind::MonoM, color), TextCache::one(value, FontKind::MonoM, color))
+        }).collect();
+        self.list.set_items(rows);
+
+        let remaining = self.allocation.remaining(RULES.newgame.stat_points);
+        self.remaining_label.set_text(&replace_str!(
+            text::ui_txt("newgame.remaining_points"); points=&remaining));
+    }
+}
+
+impl Window for AllocateStatsDialog {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.remaining_label.draw(context);
+        self.list.draw(context);
+    }
+}
+
+impl DialogWindow for AllocateStatsDialog {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(_) = response {
+                return DialogResult::CloseWithValue(Box::new(self.allocation.to_revision()));
+            }
+            return DialogResult::Continue;
+        }
+        match *command {
+            Command::Move { dir } => {
+                let increase = match dir.hdir {
+                    HDirection::Left => false,
+                    HDirection::Right => true,
+                    HDirection::None => { return DialogResult::Continue; }
+                };
+                let i = self.list.get_current_choice() as usize;
+                if self.allocation.change(
+                    i, increase, RULES.newgame.stat_points, RULES.newgame.max_stat_points_per_attr) {
+                    self.update();
+                }
+                DialogResult::Continue
+            }
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
+
+pub struct ChooseScenarioDialog {
+    rect: Rect,
+    list: TextListWidget,
+}
+
+impl ChooseScenarioDialog {
+    pub fn new() -> ChooseScenarioDialog {
+        let rect: Rect = UI_CFG.choose_scenario_dialog.rect.into();
+        let choices: Vec<String> = RULES
+            .newgame
+            .scenarios
+            .iter()
+            .map(|s| text::misc_txt(&format!("!scenario.{}", s.id)).to_owned())
+            .collect();
+
+        ChooseScenarioDialog {
+            rect,
+            list: TextListWidget::text_choices(
+                (0, 0, rect.width(), rect.height()), choices),
+        }
+    }
+}
+
+impl Window for ChooseScenarioDialog {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+    }
+}
+
+impl DialogWindow for ChooseScenarioDialog {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                let scenario = RULES.newgame.scenarios[i as usize].clone();
+                return DialogResult::CloseWithValue(Box::new(scenario));
+            }
+            return DialogResult::Continue;
+        }
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+}
+
 fn explanation_text_window(s: &str) -> TextWindow {
     TextWindow::new(
         UI_CFG.newgame_dialog.explanation_text_rect.into(),
@@ -0,0 +1,175 @@
+
+use std::any::Any;
+use common::gamedata::*;
+use crate::game::item::filter::ItemFilter;
+use crate::game::party;
+use crate::text::{self, ToText};
+use crate::context::textrenderer::FontKind;
+use super::commonuse::*;
+use super::widget::*;
+use super::msg_dialog::MsgDialog;
+use super::equip_window::EquipWindow;
+use super::item_window::ItemWindow;
+use super::text_input_dialog::TextInputDialog;
+
+/// List party members on the current map, and give orders to them
+pub struct PartyWindow {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache, TextCache)>,
+    message: LabelWidget,
+    members: Vec<CharaId>,
+    /// The member selected to give an order
+    selected: Option<CharaId>,
+    /// The member whose new name is being input
+    renaming: Option<CharaId>,
+}
+
+impl PartyWindow {
+    pub fn new(game: &Game) -> PartyWindow {
+        let cfg = &UI_CFG.party_window;
+        let rect: Rect = cfg.rect.into();
+
+        let mut w = PartyWindow {
+            rect,
+            list: ListWidget::new(
+                (0i32, 0i32, rect.w as u32, rect.h as u32),
+                cfg.column_pos.clone(),
+                cfg.n_row,
+                26,
+                true,
+                false),
+            message: LabelWidget::new(
+                (0i32, (cfg.n_row as i32 + 1) * 26, rect.width(), 0), "", FontKind::M),
+            members: Vec::new(),
+            selected: None,
+            renaming: None,
+        };
+        w.update_rows(&game.gd);
+        w
+    }
+
+    fn update_rows(&mut self, gd: &GameData) {
+        self.members = party::members_on_map(gd);
+        if self.members.is_empty() {
+            self.message.set_text(text::ui_txt("party.no_members"));
+        }
+
+        let color = UI_CFG.color.normal_font.into();
+        let rows = self.members.iter().map(|&cid| {
+            let chara = gd.chara.get(cid);
+            let hp = format!("HP {}/{}", chara.hp, chara.attr.max_hp);
+            let order = match chara.ai.order {
+                PartyOrder::Follow => "party.order_follow",
+                PartyOrder::Wait => "party.order_wait",
+                PartyOrder::Attack(_) => "party.order_attack",
+            };
+            (TextCache::one(chara.to_text(), FontKind::M, color),
+             TextCache::one(hp, FontKind::M, color),
+             TextCache::one(text::ui_txt(order), FontKind::M, color))
+        }).collect();
+        self.list.set_items(rows);
+    }
+}
+
+impl Window for PartyWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+        self.message.draw(context);
+    }
+}
+
+impl DialogWindow for PartyWindow {
+    fn process_command(&mut self, command: &Command, pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                if let Some(&cid) = self.members.get(i as usize) {
+                    let choices = [
+                        "dialog.choice.party_follow",
+                        "dialog.choice.party_wait",
+                        "dialog.choice.party_attack",
+                        "dialog.choice.party_equipment",
+                        "dialog.choice.party_give_item",
+                        "dialog.choice.party_take_item",
+                        "dialog.choice.party_rename",
+                        "dialog.choice.close",
+                    ].iter().map(|id| text::ui_txt(id).to_owned()).collect();
+                    let msg = pa.gd().chara.get(cid).to_text();
+                    let dialog = MsgDialog::new(
+                        &msg, choices, |_, n| DialogResult::CloseWithValue(Box::new(n)));
+                    self.selected = Some(cid);
+                    return DialogResult::OpenChildDialog(Box::new(dialog));
+                }
+            }
+            return DialogResult::Continue;
+        }
+
+        match *command {
+            Command::Cancel => DialogResult::Close,
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+
+    fn callback_child_closed(
+        &mut self, result: Option<Box<dyn Any>>, pa: &mut DoPlayerAction) -> DialogResult {
+
+        let result = match result.map(|result| result.downcast::<String>()) {
+            Some(Ok(name)) => { // New name from TextInputDialog
+                if let Some(cid) = self.renaming.take() {
+                    pa.rename_chara(cid, &name);
+                }
+                self.update_rows(pa.gd());
+                return DialogResult::Continue;
+            }
+            Some(Err(result)) => Some(result),
+            None => None,
+        };
+        let choice = result.and_then(|result| result.downcast::<u32>().ok());
+        if let (Some(choice), Some(cid)) = (choice, self.selected.take()) {
+            match *choice {
+                0 => pa.order_party_member(cid, PartyOrder::Follow),
+                1 => pa.order_party_member(cid, PartyOrder::Wait),
+                2 => pa.order_attack_target(cid),
+                3 => {
+                    return DialogResult::OpenChildDialog(Box::new(EquipWindow::new(pa, cid)));
+                }
+                4 => {
+                    let give_selected_item = move |pa: &mut DoPlayerAction, il: ItemLocation| {
+                        pa.give_item(il, cid);
+                        DialogResult::Close
+                    };
+                    let select_window = ItemWindow::new_select(
+                        ItemListLocation::Chara { cid: CharaId::Player },
+                        ItemFilter::all(),
+                        Box::new(give_selected_item),
+                        pa);
+                    return DialogResult::OpenChildDialog(Box::new(select_window));
+                }
+                5 => {
+                    let take_selected_item = move |pa: &mut DoPlayerAction, il: ItemLocation| {
+                        pa.take_item(il, cid);
+                        DialogResult::Close
+                    };
+                    let select_window = ItemWindow::new_select(
+                        ItemListLocation::Chara { cid },
+                        ItemFilter::all(),
+                        Box::new(take_selected_item),
+                        pa);
+                    return DialogResult::OpenChildDialog(Box::new(select_window));
+                }
+                6 => {
+                    let name = pa.gd().chara.get(cid).to_text();
+                    self.renaming = Some(cid);
+                    return DialogResult::OpenChildDialog(Box::new(TextInputDialog::with_text(&name)));
+                }
+                _ => (),
+            }
+        }
+        self.update_rows(pa.gd());
+        DialogResult::Continue
+    }
+}
@@ -1,6 +1,6 @@
 
 use sdl2::rect::Rect;
-use common::gamedata::Quest;
+use common::gamedata::{GameData, Quest};
 use crate::text::ToText;
 use crate::context::*;
 use crate::window::{Window, DialogWindow, DialogResult, WindowDrawMode};
@@ -57,7 +57,7 @@ impl QuestWindow {
             self.description.set_text("");
         } else {
             let q = &available_quests(&game.gd)[self.list.get_current_choice() as usize];
-            self.description.set_text(&quest_decription_text(q));
+            self.description.set_text(&quest_decription_text(q, &game.gd));
         }
     }
 }
@@ -128,12 +128,23 @@ impl DialogWindow for QuestWindow {
     }
 }
 
-fn quest_decription_text(quest: &Quest) -> String {
-    match quest {
+pub fn quest_decription_text(quest: &Quest, gd: &GameData) -> String {
+    let desc = match quest {
         Quest::SlayMonsters { idx, goal, .. } => {
             let t = crate::text::misc_txt("!desc.quest.slay_monsters");
             replace_str!(t; monster=idx, n=goal)
         }
-    }
+        Quest::CollectItems { idx, goal, .. } => {
+            let t = crate::text::misc_txt("!desc.quest.collect_items");
+            replace_str!(t; item=idx, n=goal)
+        }
+        Quest::ReachSite { sid, .. } => {
+            let t = crate::text::misc_txt("!desc.quest.reach_site");
+            replace_str!(t; site=gd.region.get_site(*sid))
+        }
+    };
+    let (current, goal) = quest.progress();
+    let progress = replace_str!(crate::text::misc_txt("!quest.progress"); current=&current, goal=&goal);
+    format!("{} {}", desc, progress)
 }
 
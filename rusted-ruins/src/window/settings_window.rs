@@ -0,0 +1,194 @@
+
+use array2d::*;
+use std::any::Any;
+use crate::config::{self, Config, ModConfig};
+use crate::context::textrenderer::FontKind;
+use crate::text;
+use super::commonuse::*;
+use super::widget::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsRow {
+    KeyBindings,
+    MusicVolume,
+    SoundVolume,
+    Language,
+    Mods,
+}
+
+const ROWS: [SettingsRow; 5] = [
+    SettingsRow::KeyBindings,
+    SettingsRow::MusicVolume,
+    SettingsRow::SoundVolume,
+    SettingsRow::Language,
+    SettingsRow::Mods,
+];
+
+const VOLUME_STEP: u32 = 10;
+
+/// Change key bindings, volumes, the language and mods.
+/// Changed settings are saved when this window is closed.
+pub struct SettingsWindow {
+    rect: Rect,
+    list: ListWidget<(TextCache, TextCache)>,
+    message: LabelWidget,
+    config: Config,
+    langs: Vec<String>,
+}
+
+impl SettingsWindow {
+    pub fn new() -> SettingsWindow {
+        let cfg = &UI_CFG.settings_window;
+        let rect: Rect = cfg.rect.into();
+        let h_row = UI_CFG.list_widget.h_row_with_text;
+        let config = config::current_config();
+        let mut langs = text::available_langs();
+        if !langs.contains(&config.lang) {
+            langs.push(config.lang.clone());
+        }
+
+        let mut w = SettingsWindow {
+            rect,
+            list: ListWidget::new(
+                (0, 0, rect.width(), h_row as u32 * ROWS.len() as u32),
+                cfg.column_pos.clone(),
+                ROWS.len() as u32,
+                h_row as u32,
+                false,
+                false),
+            message: LabelWidget::new(
+                (0, h_row * ROWS.len() as i32, rect.width(), 0), "", FontKind::M),
+            config,
+            langs,
+        };
+        w.update_rows();
+        w
+    }
+
+    fn update_rows(&mut self) {
+        let color = UI_CFG.color.normal_font.into();
+        let rows = ROWS.iter().map(|row| {
+            let (name, value) = match row {
+                SettingsRow::KeyBindings => ("settings.key_bindings", "".to_owned()),
+                SettingsRow::MusicVolume => ("settings.music_volume", format!("{}", self.config.music_volume)),
+                SettingsRow::SoundVolume => ("settings.sound_volume", format!("{}", self.config.sound_volume)),
+                SettingsRow::Language => ("settings.language", self.config.lang.clone()),
+                SettingsRow::Mods => ("settings.mods", format!(
+                    "{}/{}", self.config.mods.iter().filter(|m| !m.disabled).count(),
+                    self.config.mods.len())),
+            };
+            (TextCache::one(text::ui_txt(name), FontKind::M, color),
+             TextCache::one(value, FontKind::M, color))
+        }).collect();
+        self.list.set_items(rows);
+    }
+
+    fn change(&mut self, row: SettingsRow, increase: bool) {
+        match row {
+            SettingsRow::KeyBindings | SettingsRow::Mods => { return; }
+            SettingsRow::MusicVolume => {
+                change_volume(&mut self.config.music_volume, increase);
+            }
+            SettingsRow::SoundVolume => {
+                change_volume(&mut self.config.sound_volume, increase);
+            }
+            SettingsRow::Language => {
+                let n = self.langs.len();
+                let i = self.langs.iter().position(|l| *l == self.config.lang).unwrap_or(0);
+                let i = if increase { (i + 1) % n } else { (i + n - 1) % n };
+                self.config.lang = self.langs[i].clone();
+                self.message.set_text(text::ui_txt("settings.restart_required"));
+            }
+        }
+        crate::audio::set_volume(self.config.music_volume, self.config.sound_volume);
+        self.update_rows();
+    }
+
+    /// Save changed settings
+    fn save(&self) {
+        if let Err(e) = config::save_user_config(&self.config) {
+            warn!("Failed to save settings: {}", e);
+        }
+    }
+}
+
+fn change_volume(volume: &mut u32, increase: bool) {
+    *volume = if increase {
+        std::cmp::min(*volume + VOLUME_STEP, 100)
+    } else {
+        volume.saturating_sub(VOLUME_STEP)
+    };
+}
+
+impl Window for SettingsWindow {
+    fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
+        draw_rect_border(context, self.rect);
+        self.list.draw(context);
+        self.message.draw(context);
+    }
+}
+
+impl DialogWindow for SettingsWindow {
+    fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(response) = self.list.process_command(&command) {
+            if let ListWidgetResponse::Select(i) = response {
+                match ROWS[i as usize] {
+                    SettingsRow::KeyBindings => {
+                        let dialog = super::key_binding_window::KeyBindingWindow::new();
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
+                    }
+                    SettingsRow::Mods => {
+                        let dialog = super::mod_window::ModWindow::new(self.config.mods.clone());
+                        return DialogResult::OpenChildDialog(Box::new(dialog));
+                    }
+                    _ => (),
+                }
+            }
+            return DialogResult::Continue;
+        }
+        match *command {
+            Command::Move { dir } => {
+                let row = ROWS[self.list.get_current_choice() as usize];
+                match dir.hdir {
+                    HDirection::Left => self.change(row, false),
+                    HDirection::Right => self.change(row, true),
+                    HDirection::None => (),
+                }
+                DialogResult::Continue
+            }
+            Command::Cancel => {
+                self.save();
+                DialogResult::Close
+            }
+            _ => DialogResult::Continue,
+        }
+    }
+
+    fn mode(&self) -> InputMode {
+        InputMode::Dialog
+    }
+
+    fn callback_child_closed(
+        &mut self, result: Option<Box<dyn Any>>, _pa: &mut DoPlayerAction) -> DialogResult {
+        if let Some(mods) = result.and_then(|r| r.downcast::<Vec<ModConfig>>().ok()) {
+            if *mods != self.config.mods {
+                self.config.mods = *mods;
+                self.message.set_text(text::ui_txt("settings.mod_restart_required"));
+            }
+            self.update_rows();
+        }
+        DialogResult::Continue
+    }
+}
+
+#[test]
+fn change_volume_test() {
+    let mut v = 95;
+    change_volume(&mut v, true);
+    assert_eq!(v, 100);
+    change_volume(&mut v, false);
+    assert_eq!(v, 90);
+    let mut v = 5;
+    change_volume(&mut v, false);
+    assert_eq!(v, 0);
+}
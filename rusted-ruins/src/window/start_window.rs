@@ -2,7 +2,7 @@
 use std::ffi::OsStr;
 use std::path::PathBuf;
 use common::gamedata::GameData;
-use crate::config::{SCREEN_CFG, UI_CFG};
+use crate::config::UI_CFG;
 use super::commonuse::*;
 use super::widget::*;
 use super::SpecialDialogResult;
@@ -14,7 +14,8 @@ pub struct StartWindow {
 
 impl StartWindow {
     pub fn new() -> StartWindow {
-        let rect = Rect::new(0, 0, SCREEN_CFG.screen_w, SCREEN_CFG.screen_h);
+        let (screen_w, screen_h) = crate::screen::screen_size();
+        let rect = Rect::new(0, 0, screen_w, screen_h);
         
         StartWindow {
             title_screen: ImageWidget::ui_img(rect, "!title-screen"),
@@ -37,6 +38,7 @@ impl StartDialog {
         let choices = vec![
             text::ui_txt("dialog.choice.newgame").to_owned(),
             text::ui_txt("dialog.choice.loadgame").to_owned(),
+            text::ui_txt("dialog.choice.settings").to_owned(),
             text::ui_txt("dialog.choice.exit").to_owned()];
         let rect = UI_CFG.start_dialog.rect.into();
         StartDialog {
@@ -63,7 +65,11 @@ impl DialogWindow for StartDialog {
                 ListWidgetResponse::Select(1) => { // Load Game
                     return DialogResult::Special(SpecialDialogResult::StartDialogLoadGame);
                 }
-                ListWidgetResponse::Select(2) => { // Exit
+                ListWidgetResponse::Select(2) => { // Settings
+                    let dialog = super::settings_window::SettingsWindow::new();
+                    return DialogResult::OpenChildDialog(Box::new(dialog));
+                }
+                ListWidgetResponse::Select(3) => { // Exit
                     return DialogResult::Quit;
                 }
                 _ => (),
@@ -83,15 +89,32 @@ pub struct ChooseSaveFileDialog {
     rect: Rect,
     list: TextListWidget,
     save_files: Vec<PathBuf>,
+    /// Auto-saves are listed after manual saves
+    n_manual_save: usize,
 }
 
 impl ChooseSaveFileDialog {
     pub fn new() -> ChooseSaveFileDialog {
-        let save_files = crate::game::saveload::save_file_list().expect("Error at reading save file directory");
+        let mut save_files = crate::game::saveload::save_file_list().expect("Error at reading save file directory");
+        let n_manual_save = save_files.len();
+        save_files.extend(crate::game::saveload::autosave_file_list());
         
         let file_name_list: Vec<String> = save_files
             .iter()
-            .map(|path| path.file_stem().unwrap_or_else(|| OsStr::new("")).to_string_lossy().into_owned())
+            .enumerate()
+            .map(|(i, path)| {
+                let mut name = path.file_stem().unwrap_or_else(|| OsStr::new("")).to_string_lossy().into_owned();
+                if let Ok(meta) = GameData::load_metadata(path) {
+                    if !meta.description().is_empty() {
+                        name = format!("{} - {}", name, meta.description());
+                    }
+                }
+                if i < n_manual_save {
+                    name
+                } else {
+                    format!("{} ({})", name, crate::text::ui_txt("save.autosave"))
+                }
+            })
             .collect();
         let rect = UI_CFG.choose_save_file_dialog.rect.into();
         
@@ -100,6 +123,7 @@ impl ChooseSaveFileDialog {
             list: TextListWidget::text_choices(
                 (0, 0, rect.width(), rect.height()), file_name_list),
             save_files,
+            n_manual_save,
         }
     }
 }
@@ -116,8 +140,18 @@ impl DialogWindow for ChooseSaveFileDialog {
         if let Some(response) = self.list.process_command(&command) {
             match response {
                 ListWidgetResponse::Select(i) => { // Any item is selected
-                    match GameData::load(&self.save_files[i as usize]) {
-                        Ok(o) => {
+                    let i = i as usize;
+                    match GameData::load(&self.save_files[i]) {
+                        Ok(mut o) => {
+                            if i >= self.n_manual_save {
+                                // Continue from the auto-save in a new save of the game
+                                let result = crate::game::saveload::restore_autosave(&self.save_files[i], &mut o);
+                                if let Err(e) = result {
+                                    warn!("Failed to restore an auto-save: {}", e);
+                                    return DialogResult::Continue;
+                                }
+                            }
+                            crate::game::saveload::restore_rng_state(&mut o);
                             return DialogResult::Special(SpecialDialogResult::NewGameStart(o));
                         }
                         Err(e) => {
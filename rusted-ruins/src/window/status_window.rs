@@ -35,6 +35,7 @@ pub struct StatusWindow {
     image: ImageWidget,
     name_label: LabelWidget,
     hp_label: LabelWidget,
+    mp_label: LabelWidget,
     str_label: LabelWidget,
     vit_label: LabelWidget,
     dex_label: LabelWidget,
@@ -52,6 +53,8 @@ impl StatusWindow {
         let name_label = LabelWidget::new(cfg.name_label_rect, &chara.to_text(), FontKind::M);
         let hp_label = LabelWidget::new(
             cfg.hp_label_rect, &format!("HP  {} / {}", chara.hp, chara.attr.max_hp), FontKind::MonoM);
+        let mp_label = LabelWidget::new(
+            cfg.mp_label_rect, &format!("MP  {} / {}", chara.mp, chara.attr.max_mp), FontKind::MonoM);
         let str_label = LabelWidget::new(
             cfg.str_label_rect, &format!("STR  {}", chara.attr.str), FontKind::MonoM);
         let vit_label = LabelWidget::new(
@@ -66,7 +69,7 @@ impl StatusWindow {
             cfg.cha_label_rect, &format!("CHA  {}", chara.attr.cha), FontKind::MonoM);
         StatusWindow {
             rect,
-            image, name_label, hp_label,
+            image, name_label, hp_label, mp_label,
             str_label, vit_label, dex_label, int_label, wil_label, cha_label,
         }
     }
@@ -80,6 +83,7 @@ impl Window for StatusWindow {
         self.image.draw(context);
         self.name_label.draw(context);
         self.hp_label.draw(context);
+        self.mp_label.draw(context);
         self.str_label.draw(context);
         self.vit_label.draw(context);
         self.dex_label.draw(context);
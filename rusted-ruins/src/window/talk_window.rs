@@ -5,7 +5,8 @@ use std::borrow::Cow;
 use std::any::Any;
 use common::objholder::CharaTemplateIdx;
 use common::basic::TILE_SIZE;
-use crate::game::{TalkText, AdvanceScriptResult};
+use crate::game::{TalkText, AdvanceScriptResult, replace_talk_vars};
+use common::gamedata::GameData;
 use crate::context::textrenderer::FontKind;
 use crate::config::UI_CFG;
 use super::misc_window::ImageWindow;
@@ -17,18 +18,21 @@ pub struct TalkWindow {
     rect: Rect,
     talk_text: TalkText,
     label: LineSpecifiedLabelWidget,
+    more_indicator: LabelWidget,
     image_window: ImageWindow,
     msg_text: MsgText,
     choose_win: Option<ChooseWindow>,
 }
 
 impl TalkWindow {
-    pub fn new(talk_text: TalkText, chara_template_idx: CharaTemplateIdx) -> TalkWindow {
+    pub fn new(talk_text: TalkText, chara_template_idx: CharaTemplateIdx, gd: &GameData) -> TalkWindow {
         
         let rect: Rect = UI_CFG.talk_window.rect.into();
         let label = LineSpecifiedLabelWidget::new(
             Rect::new(0, 0, rect.width(), rect.height()),
             &[""], FontKind::M, UI_CFG.talk_window.n_default_line);
+        let more_indicator = LabelWidget::new(
+            UI_CFG.talk_window.more_indicator_rect, text::ui_txt("talk.more"), FontKind::M);
         let rect_image_window = Rect::new(
             rect.x + UI_CFG.talk_window.image_window_pos_x,
             rect.y + UI_CFG.talk_window.image_window_pos_y,
@@ -36,26 +40,27 @@ impl TalkWindow {
             TILE_SIZE * 2);
         let mut talk_window = TalkWindow {
             rect,
-            talk_text,
+            talk_text: talk_text.clone(),
             label,
+            more_indicator,
             image_window: ImageWindow::chara(rect_image_window, chara_template_idx),
             msg_text: MsgText::default(),
             choose_win: None,
         };
-        talk_window.update_page(Some(talk_text));
+        talk_window.update_page(Some(talk_text), gd);
         talk_window
     }
 
-    fn update_page(&mut self, talk_text: Option<TalkText>) {
+    fn update_page(&mut self, talk_text: Option<TalkText>, gd: &GameData) {
         if let Some(talk_text) = talk_text {
+            self.msg_text = MsgText::new(&talk_text.text_id, gd);
             self.talk_text = talk_text;
-            self.msg_text = MsgText::new(&*talk_text.text_id);
             self.choose_win = None;
         }
         
         // Create answers
         if self.msg_text.is_final_page() {
-            if let Some(choices) = self.talk_text.choices {
+            if let Some(choices) = &self.talk_text.choices {
                 let winpos = WindowPos::new(
                     WindowHPos::RightX(self.rect.right()),
                     WindowVPos::TopMargin(self.rect.bottom() + UI_CFG.gap_len_between_dialogs));
@@ -76,6 +81,9 @@ impl Window for TalkWindow {
         self.image_window.draw(context, game, anim);
         draw_rect_border(context, self.rect);
         self.label.draw(context);
+        if !self.msg_text.is_final_page() {
+            self.more_indicator.draw(context);
+        }
         if let Some(ref mut choose_win) = self.choose_win {
             choose_win.draw(context, game, anim);
         }
@@ -92,7 +100,7 @@ impl DialogWindow for TalkWindow {
                     if let Ok(choosed_answer) = choosed_answer.downcast::<u32>() {
                         match pa.advance_talk(Some(*choosed_answer)) {
                             AdvanceScriptResult::UpdateTalkText(talk_text) => {
-                                self.update_page(Some(talk_text));
+                                self.update_page(Some(talk_text), pa.gd());
                                 return DialogResult::Continue;
                             }
                             AdvanceScriptResult::Continue => {
@@ -115,7 +123,7 @@ impl DialogWindow for TalkWindow {
                 if self.msg_text.is_final_page() {
                     match pa.advance_talk(None) {
                         AdvanceScriptResult::UpdateTalkText(talk_text) => {
-                            self.update_page(Some(talk_text));
+                            self.update_page(Some(talk_text), pa.gd());
                             DialogResult::Continue
                         }
                         AdvanceScriptResult::Continue => {
@@ -127,7 +135,7 @@ impl DialogWindow for TalkWindow {
                     }
                 } else {
                     self.msg_text.next_page();
-                    self.update_page(None);
+                    self.update_page(None, pa.gd());
                     DialogResult::Continue
                 }
             },
@@ -145,7 +153,7 @@ impl DialogWindow for TalkWindow {
 
         match pa.advance_script() {
             AdvanceScriptResult::UpdateTalkText(talk_text) => {
-                self.update_page(Some(talk_text));
+                self.update_page(Some(talk_text), pa.gd());
                 DialogResult::Continue
             }
             AdvanceScriptResult::Continue => {
@@ -167,12 +175,13 @@ struct MsgText {
 }
 
 impl MsgText {
-    fn new(text_id: &str) -> MsgText {
+    fn new(text_id: &str, gd: &GameData) -> MsgText {
         let mut lines: Vec<Cow<'static, str>> = Vec::new();
 
         if let Some(s) = text::talk_txt_checked(text_id) {
+            let s = replace_talk_vars(s, gd);
             for line in s.lines() {
-                lines.push(line.into());
+                lines.push(line.to_owned().into());
             }
         } else {
             lines.push(text_id.to_owned().into());
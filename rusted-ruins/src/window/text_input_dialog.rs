@@ -1,32 +1,36 @@
 
 use crate::config::UI_CFG;
-use crate::context::textrenderer::FontKind;
 use super::commonuse::*;
 use super::text_input;
 use super::widget::*;
 
+/// Dialog to input one line text.
+/// Returns the text by CloseWithValue when enter is pressed, and Close when canceled.
 pub struct TextInputDialog {
-    label: LabelWidget,
+    input: TextInputWidget,
     rect: Rect,
-    text: String,
 }
 
 impl TextInputDialog {
     pub fn new() -> TextInputDialog {
+        TextInputDialog::with_text("")
+    }
+
+    /// Start input with the text, e.g. the current name for renaming
+    pub fn with_text(text: &str) -> TextInputDialog {
         text_input::start();
 
         let rect: Rect = UI_CFG.text_input_dialog.rect.into();
-        let label_rect = Rect::new(0, 0, rect.width(), rect.height());
+        let input_rect = Rect::new(0, 0, rect.width(), rect.height());
         
         TextInputDialog {
-            label: LabelWidget::new(label_rect, "", FontKind::M),
+            input: TextInputWidget::new(input_rect, text),
             rect,
-            text: String::new(),
         }
     }
 
     pub fn get_text(&self) -> &str {
-        &self.text
+        self.input.get_text()
     }
 
     /// This function is used when the result string is invalid,
@@ -39,26 +43,19 @@ impl TextInputDialog {
 impl Window for TextInputDialog {
     fn draw(&mut self, context: &mut Context, _game: &Game, _anim: Option<(&Animation, u32)>) {
         draw_rect_border(context, self.rect);
-        self.label.draw(context);
+        self.input.draw(context);
     }
 }
 
 impl DialogWindow for TextInputDialog {
     fn process_command(&mut self, command: &Command, _pa: &mut DoPlayerAction) -> DialogResult {
+        if self.input.process_command(command).is_some() {
+            return DialogResult::Continue;
+        }
         match command {
-            &Command::TextInput { ref text } => {
-                self.text.push_str(&text);
-                self.label.set_text(&self.text);
-                DialogResult::Continue
-            },
-            &Command::TextDelete => {
-                self.text.pop();
-                self.label.set_text(&self.text);
-                DialogResult::Continue
-            },
             &Command::Enter => {
                 text_input::end();
-                DialogResult::Close
+                DialogResult::CloseWithValue(Box::new(self.get_text().to_owned()))
             },
             &Command::Cancel => {
                 text_input::end();
@@ -72,4 +69,3 @@ impl DialogWindow for TextInputDialog {
         InputMode::TextInput
     }
 }
-
@@ -15,7 +15,7 @@ pub struct GaugeWidget {
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GaugeColorMode {
-    Hp, Exp,
+    Hp, Mp, Exp,
 }
 
 impl GaugeColorMode {
@@ -27,6 +27,12 @@ impl GaugeColorMode {
                 border_light: UI_CFG.color.border_light.into(),
                 border_dark: UI_CFG.color.border_dark.into(),
             },
+            GaugeColorMode::Mp => Colors {
+                bar: UI_CFG.color.gauge_mp.into(),
+                bg: UI_CFG.color.gauge_bg.into(),
+                border_light: UI_CFG.color.border_light.into(),
+                border_dark: UI_CFG.color.border_dark.into(),
+            },
             GaugeColorMode::Exp => Colors {
                 bar: UI_CFG.color.gauge_exp.into(),
                 bg: UI_CFG.color.gauge_bg.into(),
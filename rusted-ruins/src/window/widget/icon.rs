@@ -0,0 +1,44 @@
+
+use sdl2::rect::Rect;
+use crate::context::*;
+use common::gobj;
+use common::objholder::UIImgIdx;
+use super::{WidgetTrait, LabelWidget};
+
+/// Icon widget. Shows the text instead if the icon image is not found.
+pub struct IconWidget {
+    rect: Rect,
+    icon: Icon,
+}
+
+enum Icon {
+    Img(UIImgIdx),
+    Text(LabelWidget),
+}
+
+impl IconWidget {
+    pub fn new<R: Into<Rect>>(rect: R, id: &str, text: &str) -> IconWidget {
+        let rect = rect.into();
+        let icon = if let Some(idx) = gobj::id_to_idx_checked(id) {
+            Icon::Img(idx)
+        } else {
+            Icon::Text(LabelWidget::bordered(rect, text, FontKind::S))
+        };
+        IconWidget { rect, icon }
+    }
+}
+
+impl WidgetTrait for IconWidget {
+    type Response = ();
+
+    fn draw(&mut self, context: &mut Context) {
+        match self.icon {
+            Icon::Img(idx) => {
+                context.render_tex(idx, self.rect);
+            }
+            Icon::Text(ref mut label) => {
+                label.draw(context);
+            }
+        }
+    }
+}
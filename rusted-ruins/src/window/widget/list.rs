@@ -26,6 +26,8 @@ pub struct ListWidget<T> {
     update_by_user: bool,
     page_label: Option<TextCache>,
     draw_border: bool,
+    /// Position on the screen at last drawing. Used to handle mouse clicks.
+    screen_rect: Option<Rect>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -68,6 +70,7 @@ impl<T: ListWidgetRow> ListWidget<T> {
             update_by_user,
             page_label: None,
             draw_border: multiple_page,
+            screen_rect: None,
         };
         if multiple_page {
             w.update_page_label();
@@ -156,6 +159,16 @@ impl<T: ListWidgetRow> ListWidget<T> {
         }
     }
 
+    /// Returns the row index at the position on the screen
+    fn row_at(&self, x: i32, y: i32) -> Option<u32> {
+        let screen_rect = self.screen_rect?;
+        if !screen_rect.contains_point((x, y)) {
+            return None;
+        }
+        let i = (y - screen_rect.y) as u32 / self.h_row;
+        if i < self.n_row { Some(i) } else { None }
+    }
+
     fn update_page_label(&mut self) {
         let text = format!("{} {}/{}", "page:", self.current_page + 1, self.max_page + 1);
         if self.multiple_page {
@@ -280,11 +293,25 @@ impl<T: ListWidgetRow> WidgetTrait for ListWidget<T> {
                 }
                 None
             }
+            Command::MouseButtonUp { x, y } => {
+                let i = self.row_at(x, y)?;
+                self.current_choice = i;
+                Some(ListWidgetResponse::Select(i + self.current_page * self.page_size))
+            }
+            Command::MouseWheel { y } if y != 0 => {
+                let vdir = if y > 0 { VDirection::Up } else { VDirection::Down };
+                let dir = Direction::new(HDirection::None, vdir);
+                self.process_command(&Command::Move { dir })
+            }
             _ => None,
         }
     }
 
     fn draw(&mut self, context: &mut Context) {
+        let viewport = context.canvas.viewport();
+        self.screen_rect = Some(Rect::new(
+            viewport.x + self.rect.x, viewport.y + self.rect.y,
+            self.rect.width(), self.h_row * self.page_size));
 
         // Draw page label
         if self.multiple_page {
@@ -1,9 +1,11 @@
 
 mod border;
 mod gauge;
+mod icon;
 mod image;
 mod label;
 mod list;
+mod text_input;
 
 use crate::game::Command;
 use crate::context::*;
@@ -16,7 +18,9 @@ pub trait WidgetTrait {
 
 pub use self::border::*;
 pub use self::gauge::*;
+pub use self::icon::*;
 pub use self::image::*;
 pub use self::label::*;
 pub use self::list::*;
+pub use self::text_input::*;
 
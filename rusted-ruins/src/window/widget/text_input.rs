@@ -0,0 +1,177 @@
+
+use sdl2::rect::Rect;
+use array2d::HDirection;
+use crate::game::Command;
+use crate::context::*;
+use crate::config::UI_CFG;
+use super::{WidgetTrait, LabelWidget};
+
+/// Single line text input widget.
+/// Text input mode must be started by the owner window.
+pub struct TextInputWidget {
+    rect: Rect,
+    buf: TextBuffer,
+    label: LabelWidget,
+    /// Text before the cursor to calculate the cursor position
+    before_cursor: LabelWidget,
+}
+
+impl TextInputWidget {
+    pub fn new<R: Into<Rect>>(rect: R, text: &str) -> TextInputWidget {
+        let rect = rect.into();
+        let mut w = TextInputWidget {
+            rect,
+            buf: TextBuffer::new(text),
+            label: LabelWidget::new(rect, "", FontKind::M),
+            before_cursor: LabelWidget::new(rect, "", FontKind::M),
+        };
+        w.update_label();
+        w
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.buf.text
+    }
+
+    fn update_label(&mut self) {
+        let (before, after) = self.buf.text.split_at(self.buf.byte_pos());
+        let composition = &self.buf.composition;
+        let composition_before_cursor = &composition[..self.buf.composition_byte_pos()];
+        self.label.set_text(&format!("{}{}{}", before, composition, after));
+        self.before_cursor.set_text(&format!("{}{}", before, composition_before_cursor));
+    }
+}
+
+impl WidgetTrait for TextInputWidget {
+    type Response = ();
+
+    /// Returns Some if the command is used to edit the text
+    fn process_command(&mut self, command: &Command) -> Option<()> {
+        match command {
+            Command::TextInput { text } => self.buf.insert(text),
+            Command::TextEditing { text, start } => self.buf.set_composition(text, *start),
+            Command::TextDelete => self.buf.backspace(),
+            Command::TextDeleteForward => self.buf.delete(),
+            Command::Move { dir } if dir.hdir == HDirection::Left => self.buf.move_cursor(-1),
+            Command::Move { dir } if dir.hdir == HDirection::Right => self.buf.move_cursor(1),
+            _ => return None,
+        }
+        self.update_label();
+        Some(())
+    }
+
+    fn draw(&mut self, context: &mut Context) {
+        self.label.draw(context);
+
+        let x = if self.buf.cursor == 0 && self.buf.composition_cursor == 0 {
+            0
+        } else {
+            self.before_cursor.adjust_widget_size(&mut context.sv).0 as i32
+        };
+        let x = self.rect.x + UI_CFG.label_widget.left_margin + x;
+        let canvas = &mut context.canvas;
+        canvas.set_draw_color(UI_CFG.color.normal_font);
+        check_draw!(canvas.draw_line((x, self.rect.y + 2), (x, self.rect.bottom() - 2)));
+    }
+}
+
+/// Edited text. The cursor position is counted in chars.
+struct TextBuffer {
+    text: String,
+    cursor: usize,
+    /// Text being composed by IME. This is not included in the text until it is committed.
+    composition: String,
+    /// The cursor position in the composition given by IME, counted in chars
+    composition_cursor: usize,
+}
+
+impl TextBuffer {
+    fn new(text: &str) -> TextBuffer {
+        TextBuffer {
+            text: text.to_owned(),
+            cursor: text.chars().count(),
+            composition: String::new(),
+            composition_cursor: 0,
+        }
+    }
+
+    fn set_composition(&mut self, text: &str, start: i32) {
+        self.composition = text.to_owned();
+        let len = text.chars().count() as i32;
+        self.composition_cursor = if start < 0 { 0 } else if start > len { len as usize } else { start as usize };
+    }
+
+    fn composition_byte_pos(&self) -> usize {
+        self.composition.char_indices().nth(self.composition_cursor)
+            .map_or(self.composition.len(), |(i, _)| i)
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.text.char_indices().nth(self.cursor).map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn insert(&mut self, s: &str) {
+        let pos = self.byte_pos();
+        self.text.insert_str(pos, s);
+        self.cursor += s.chars().count();
+        self.composition.clear();
+        self.composition_cursor = 0;
+    }
+
+    /// Remove the char before the cursor
+    fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let pos = self.byte_pos();
+        self.text.remove(pos);
+    }
+
+    /// Remove the char after the cursor
+    fn delete(&mut self) {
+        let pos = self.byte_pos();
+        if pos < self.text.len() {
+            self.text.remove(pos);
+        }
+    }
+
+    fn move_cursor(&mut self, d: i32) {
+        let len = self.text.chars().count() as i32;
+        let cursor = self.cursor as i32 + d;
+        self.cursor = if cursor < 0 { 0 } else if cursor > len { len as usize } else { cursor as usize };
+    }
+}
+
+#[test]
+fn text_buffer_test() {
+    let mut buf = TextBuffer::new("ルイン");
+    assert_eq!(buf.cursor, 3);
+    buf.move_cursor(-1);
+    buf.insert("ab");
+    assert_eq!(buf.text, "ルイabン");
+    buf.backspace();
+    assert_eq!(buf.text, "ルイaン");
+    buf.delete();
+    assert_eq!(buf.text, "ルイa");
+    buf.delete();
+    assert_eq!(buf.text, "ルイa");
+    buf.move_cursor(-10);
+    buf.backspace();
+    assert_eq!(buf.text, "ルイa");
+    assert_eq!(buf.byte_pos(), 0);
+    buf.move_cursor(10);
+    assert_eq!(buf.cursor, 3);
+}
+
+#[test]
+fn composition_cursor_test() {
+    let mut buf = TextBuffer::new("a");
+    buf.set_composition("るいん", 2);
+    assert_eq!(&buf.composition[..buf.composition_byte_pos()], "るい");
+    buf.set_composition("るいん", 10);
+    assert_eq!(buf.composition_byte_pos(), "るいん".len());
+    buf.insert("ルイン");
+    assert_eq!(buf.text, "aルイン");
+    assert_eq!(buf.composition_byte_pos(), 0);
+}
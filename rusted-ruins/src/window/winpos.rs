@@ -1,7 +1,7 @@
 //! Helper functions to calculate Window position
 #![allow(unused)]
 
-use crate::config::SCREEN_CFG;
+use crate::screen::screen_size;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum WindowHPos {
@@ -25,8 +25,9 @@ impl WindowPos {
     }
 
     pub fn calc_left_top(&self, w: u32, h: u32) -> (i32, i32) {
-        let parent_w = SCREEN_CFG.screen_w as i32;
-        let parent_h = SCREEN_CFG.screen_h as i32;
+        let (parent_w, parent_h) = screen_size();
+        let parent_w = parent_w as i32;
+        let parent_h = parent_h as i32;
         let w = w as i32;
         let h = h as i32;
         